@@ -0,0 +1,52 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, Result};
+use std::time::Instant;
+
+use sym_spell_wasm::sym_spell::delete_generation::DeleteGeneration;
+use sym_spell_wasm::sym_spell::sym_spell::SymSpell;
+use sym_spell_wasm::sym_spell::verbosity::Verbosity;
+
+const RUNS: u32 = 3;
+
+/// Measures the time taken to build the index from the bundled English dictionary.
+/// Run with `cargo bench --bench dictionary_build` after decompressing the dictionaries.
+fn main() -> Result<()> {
+    let mut lines = vec![];
+    let mut reader = BufReader::new(File::open("lib/frequency_dictionary_en_82_765.txt")?);
+    let mut s = String::new();
+    while reader.read_line(&mut s)? != 0 {
+        lines.push(s.clone());
+        s.truncate(0);
+    }
+
+    let mut total = 0;
+    for run in 0..RUNS {
+        let start = Instant::now();
+        let mut sym_spell = SymSpell::new(Some(2), Some(7), None);
+        for line in &lines {
+            sym_spell.write_line_to_dictionary(line, " ").unwrap();
+        }
+        let elapsed = start.elapsed().as_millis();
+        total += elapsed;
+        println!("run {}: {} words, {} deletes in {}ms", run, sym_spell.word_count(), sym_spell.entry_count(), elapsed);
+        if run == 0 {
+            let mut inline = 0;
+            sym_spell.storage().for_each_delete(&mut |_, words| inline += (words.len() <= 2) as usize);
+            println!("{} of {} delete buckets small enough to need no allocation", inline, sym_spell.entry_count());
+            #[cfg(feature = "fst-dictionary")]
+            println!("{} bytes as an FstDictionary", sym_spell.to_fst_dictionary().as_bytes().len());
+        }
+    }
+    println!("average: {}ms", total / RUNS as u128);
+
+    // the same dictionary with only the prefixes precomputed
+    let mut sym_spell = SymSpell::new(Some(2), Some(7), None);
+    sym_spell.set_delete_generation(DeleteGeneration::OnDemand { precomputed_edits: 0, cache_capacity: 4096 });
+    for line in &lines {
+        sym_spell.write_line_to_dictionary(line, " ").unwrap();
+    }
+    let start = Instant::now();
+    let suggestions = sym_spell.lookup("mispelle", Verbosity::Closest, 2, false, false);
+    println!("on demand: {} deletes, first lookup of {} suggestions in {}ms", sym_spell.entry_count(), suggestions.len(), start.elapsed().as_millis());
+    Ok(())
+}
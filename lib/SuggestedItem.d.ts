@@ -3,10 +3,12 @@ export declare class SuggestedItem {
     private readonly cache;
     private readonly data;
     private readonly ptr;
-    constructor(data: Uint8Array, ptr: number);
+    private readonly length;
+    constructor(data: Uint8Array, ptr: number, length?: number);
     get count(): number;
     get distance(): number;
     get term(): string;
+    get probability(): number | null;
     toJSON(): Pick<SuggestedItem, 'count' | 'distance' | 'term'>;
 }
 export declare function readU32(buffer: Uint8Array, ptr: number): number;
@@ -1,10 +1,11 @@
 "use strict";
 Object.defineProperty(exports, "__esModule", { value: true });
 class SuggestedItem {
-    constructor(data, ptr) {
+    constructor(data, ptr, length = data.byteLength - ptr) {
         this.cache = {};
         this.data = data;
         this.ptr = ptr;
+        this.length = length;
     }
     get count() {
         return this.cache.count || (this.cache.count = readU32(this.data, this.ptr));
@@ -20,6 +21,17 @@ class SuggestedItem {
         const end = start + readU32(this.data, this.ptr + 8);
         return (this.cache.term = SuggestedItem.decodeString(this.data.slice(start, end)));
     }
+    get probability() {
+        if ('probability' in this.cache) {
+            return this.cache.probability;
+        }
+        const offset = 12 + readU32(this.data, this.ptr + 8);
+        // wasm builds before abi_version() 3 write no probability
+        const probability = offset + 4 <= this.length
+            ? new DataView(this.data.buffer, this.data.byteOffset).getFloat32(this.ptr + offset, true)
+            : NaN;
+        return (this.cache.probability = Number.isNaN(probability) ? null : probability);
+    }
     toJSON() {
         const { count, distance, term } = this;
         return { count, distance, term };
@@ -57,7 +69,7 @@ function deserializeSuggestedItems(buffer, ptr, length) {
         ptr += 4;
         // All suggested items share the same slice but the pointer
         // is updated to indicate where the property values start.
-        suggestedItems[i] = new SuggestedItem(rawSlice, ptr);
+        suggestedItems[i] = new SuggestedItem(rawSlice, ptr, itemLen);
         ptr += itemLen;
     }
     return suggestedItems;
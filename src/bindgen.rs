@@ -0,0 +1,124 @@
+use std::convert::TryFrom;
+
+use wasm_bindgen::prelude::*;
+
+use crate::sym_spell::suggested_item::SuggestItem;
+use crate::sym_spell::sym_spell::SymSpell;
+use crate::sym_spell::verbosity::Verbosity;
+use crate::sym_spell::Count;
+
+/// A typed wrapper around `SymSpell` for bundler based consumers that
+/// prefers automatic string marshalling over the raw pointer exports.
+#[wasm_bindgen(js_name = SymSpell)]
+pub struct WasmSymSpell {
+    sym_spell: SymSpell,
+}
+
+#[wasm_bindgen(js_class = SymSpell)]
+impl WasmSymSpell {
+    /// Throws the `SpellcheckerError` message for an invalid prefix length.
+    #[wasm_bindgen(constructor)]
+    pub fn new(max_dictionary_edit_distance: Option<usize>, prefix_length: Option<usize>, count_threshold: Option<usize>) -> Result<WasmSymSpell, JsError> {
+        let count_threshold = count_threshold.map(|count_threshold| Count::try_from(count_threshold).unwrap_or(Count::MAX));
+        let sym_spell = SymSpell::try_new(max_dictionary_edit_distance, prefix_length, count_threshold)?;
+        Ok(WasmSymSpell { sym_spell })
+    }
+
+    /// Returns false when the line does not hold a word and a count.
+    #[wasm_bindgen(js_name = writeLineToDictionary)]
+    pub fn write_line_to_dictionary(&mut self, line: &str, separator: &str) -> bool {
+        self.sym_spell.write_line_to_dictionary(line, separator).is_ok()
+    }
+
+    /// Returns false when the line does not hold two words and an optional count.
+    #[wasm_bindgen(js_name = writeLineToBigramDictionary)]
+    pub fn write_line_to_bigram_dictionary(&mut self, line: &str, separator: &str) -> bool {
+        self.sym_spell.write_line_to_bigram_dictionary(line, separator).is_ok()
+    }
+
+    /// Returns false when the line does not hold three words and an optional count.
+    #[wasm_bindgen(js_name = writeLineToTrigramDictionary)]
+    pub fn write_line_to_trigram_dictionary(&mut self, line: &str, separator: &str) -> bool {
+        self.sym_spell.write_line_to_trigram_dictionary(line, separator).is_ok()
+    }
+
+    #[wasm_bindgen(js_name = learnNgrams)]
+    pub fn learn_ngrams(&mut self, text: &str) {
+        self.sym_spell.learn_ngrams(text);
+    }
+
+    /// Throws when max_edit_distance exceeds the max dictionary edit distance.
+    pub fn lookup(&self, input: &str, verbosity: Verbosity, max_edit_distance: usize, include_unknown: bool, include_self: bool) -> Result<Vec<WasmSuggestItem>, JsError> {
+        Ok(wrap(self.sym_spell.try_lookup(input, verbosity, max_edit_distance, include_unknown, include_self)?))
+    }
+
+    /// Throws when max_edit_distance exceeds the max dictionary edit distance.
+    #[wasm_bindgen(js_name = lookupCompound)]
+    pub fn lookup_compound(&self, input: &str, max_edit_distance: usize) -> Result<Vec<WasmSuggestItem>, JsError> {
+        self.sym_spell.check_edit_distance(max_edit_distance)?;
+        Ok(wrap(self.sym_spell.lookup_compound(input, max_edit_distance)))
+    }
+
+    pub fn complete(&self, prefix: &str, max_results: usize) -> Vec<WasmSuggestItem> {
+        wrap(self.sym_spell.complete(prefix, max_results))
+    }
+
+    #[wasm_bindgen(js_name = completeFuzzy)]
+    pub fn complete_fuzzy(&self, prefix: &str, max_results: usize) -> Vec<WasmSuggestItem> {
+        wrap(self.sym_spell.complete_fuzzy(prefix, max_results))
+    }
+
+    #[wasm_bindgen(getter, js_name = wordCount)]
+    pub fn word_count(&self) -> usize {
+        self.sym_spell.word_count()
+    }
+}
+
+/// A single suggestion exposed to JS with read only properties.
+#[wasm_bindgen(js_name = SuggestItem)]
+pub struct WasmSuggestItem {
+    suggest_item: SuggestItem,
+}
+
+#[wasm_bindgen(js_class = SuggestItem)]
+impl WasmSuggestItem {
+    #[wasm_bindgen(getter)]
+    pub fn term(&self) -> String {
+        self.suggest_item.term.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn distance(&self) -> usize {
+        self.suggest_item.distance
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn count(&self) -> usize {
+        usize::try_from(self.suggest_item.count).unwrap_or(usize::MAX)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn probability(&self) -> Option<f64> {
+        self.suggest_item.probability
+    }
+
+    /// The language tag of the entry metadata, see `SymSpell::set_entry_metadata`.
+    #[wasm_bindgen(getter)]
+    pub fn language(&self) -> Option<String> {
+        self.suggest_item.metadata.as_ref()?.language.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn source(&self) -> Option<String> {
+        self.suggest_item.metadata.as_ref()?.source.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn canonical(&self) -> Option<String> {
+        self.suggest_item.metadata.as_ref()?.canonical.clone()
+    }
+}
+
+fn wrap(results: Vec<SuggestItem>) -> Vec<WasmSuggestItem> {
+    results.into_iter().map(|suggest_item| WasmSuggestItem { suggest_item }).collect()
+}
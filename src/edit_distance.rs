@@ -1,32 +1,64 @@
+use std::sync::Arc;
+
 use crate::soft_wx::{
+    damerau::Damerau,
     damerau_osa::DamaerauOSA,
     levensthtein::Levenshtein,
+    longest_common_subsequence::LongestCommonSubsequence,
+    myers::MyersPattern,
+    sift4::Sift4,
+    weighted_levenshtein::{WeightedCosts, WeightedLevenshtein},
 };
 use crate::soft_wx::Distance;
+use crate::soft_wx::helpers::PreparedQuery;
+
+/// <summary>Creates a user supplied edit distance algorithm. Called once per lookup so
+/// every call gets its own instance and concurrent lookups do not share state.</summary>
+pub type DistanceFactory = Arc<dyn Fn() -> Box<dyn Distance + Send> + Send + Sync>;
 
+/// <summary>Supported edit distance algorithms.</summary>
+#[derive(Clone)]
 pub enum DistanceAlgorithm {
     Levenshtein,
     DamaerauOSA,
+    Damerau,
+    /// <summary>Levenshtein with per edit costs, rounded up to whole edits.</summary>
+    Weighted(Arc<WeightedCosts>),
+    /// <summary>The fast approximate Sift4 distance using the given max offset.</summary>
+    Sift4(usize),
+    /// <summary>Insertions and deletions only, so a substitution counts as 2.</summary>
+    LongestCommonSubsequence,
+    Custom(DistanceFactory),
 }
 
 /// <summary>Wrapper for third party edit distance algorithms.</summary>
-
-/// <summary>Supported edit distance algorithms.</summary>
 pub struct EditDistance {
     distance_comparator: Box<dyn Distance>,
+    // the bit-parallel Levenshtein is tried first when Levenshtein is selected
+    bit_parallel: bool,
+    // the last string1 prepared for bit-parallel comparisons, with no pattern when it is too long
+    prepared: Option<(String, Option<MyersPattern>)>,
 }
 
 impl EditDistance {
     /// <summary>Create a new EditDistance object.</summary>
     /// <param name="algorithm">The desired edit distance algorithm.</param>
     pub fn new(distance_algorithm: DistanceAlgorithm) -> EditDistance {
+        let bit_parallel = matches!(distance_algorithm, DistanceAlgorithm::Levenshtein);
         let distance_comparator:Box<dyn Distance> = match distance_algorithm {
             DistanceAlgorithm::DamaerauOSA => Box::new(DamaerauOSA::new()),
             DistanceAlgorithm::Levenshtein => Box::new(Levenshtein::new()),
+            DistanceAlgorithm::Damerau => Box::new(Damerau::new()),
+            DistanceAlgorithm::Weighted(costs) => Box::new(WeightedLevenshtein::new(costs)),
+            DistanceAlgorithm::Sift4(max_offset) => Box::new(Sift4::with_max_offset(max_offset)),
+            DistanceAlgorithm::LongestCommonSubsequence => Box::new(LongestCommonSubsequence::new()),
+            DistanceAlgorithm::Custom(factory) => factory(),
         };
 
         EditDistance {
-            distance_comparator
+            distance_comparator,
+            bit_parallel,
+            prepared: None,
         }
     }
 
@@ -35,10 +67,123 @@ impl EditDistance {
     /// <param name="string2">The string to compare.</param>
     /// <param name="maxDistance">The maximum distance allowed.</param>
     /// <returns>The edit distance (or -1 if maxDistance exceeded).</returns>
+    /// <remarks>Levenshtein comparisons where string1 is up to 64 graphemes use the much
+    /// faster bit-parallel Myers algorithm. string1 is prepared for it once and reused for
+    /// as long as the following comparisons pass the same string1.</remarks>
     pub fn compare(&mut self, string1: &str, string2: &str, max_distance: Option<usize>) -> Option<usize> {
-        if max_distance.is_some() {
-            return self.distance_comparator.distance2(string1, string2, max_distance.unwrap())
+        if self.bit_parallel {
+            if let Some(pattern) = self.prepare(string1) {
+                return pattern.distance(string2, max_distance.unwrap_or(usize::MAX));
+            }
+        }
+        match max_distance {
+            Some(max_distance) => self.distance_comparator.distance2(string1, string2, max_distance),
+            None => self.distance_comparator.distance(string1, string2),
+        }
+    }
+
+    /// <summary>Compare the query to each of the candidates, using the previously selected
+    /// algorithm.</summary>
+    /// <returns>The edit distance to each candidate, in order, None where it exceeds
+    /// maxDistance.</returns>
+    ///
+    /// example:
+    /// let distances = edit_distance.compare_many("kitten", &["sitting", "mitten"], Some(2));
+    ///
+    /// assert_eq!(distances, vec![None, Some(1)]);
+    pub fn compare_many(&mut self, query: &str, candidates: &[&str], max_distance: Option<usize>) -> Vec<Option<usize>> {
+        let query = PreparedQuery::new(query);
+        candidates.iter().map(|candidate| self.compare_prepared(&query, candidate, max_distance)).collect()
+    }
+
+    /// <summary>Compare a string to a query split into its graphemes once, using the
+    /// previously selected algorithm.</summary>
+    /// <returns>The edit distance, None if it exceeds maxDistance.</returns>
+    pub fn compare_prepared(&mut self, query: &PreparedQuery, string2: &str, max_distance: Option<usize>) -> Option<usize> {
+        if self.bit_parallel {
+            if let Some(pattern) = self.prepare(query.text()) {
+                return pattern.distance(string2, max_distance.unwrap_or(usize::MAX));
+            }
+        }
+        self.distance_comparator.distance_prepared(query, string2, max_distance)
+    }
+
+    // the bit-parallel form of string1, kept while the same string1 is compared
+    fn prepare(&mut self, string1: &str) -> Option<&MyersPattern> {
+        if self.prepared.as_ref().map_or(true, |(prepared, _)| prepared != string1) {
+            self.prepared = Some((string1.to_string(), MyersPattern::new(string1)));
+        }
+        self.prepared.as_ref().and_then(|(_, pattern)| pattern.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod edit_distance_tests {
+    use std::sync::Arc;
+
+    use crate::edit_distance::{DistanceAlgorithm, EditDistance};
+    use crate::soft_wx::weighted_levenshtein::WeightedCosts;
+
+    #[test]
+    fn compare_test() {
+        let mut edit_distance = EditDistance::new(DistanceAlgorithm::Levenshtein);
+        assert_eq!(edit_distance.compare("kitten", "sitting", None), Some(3));
+        assert_eq!(edit_distance.compare("kitten", "sitting", Some(2)), None);
+        assert_eq!(edit_distance.compare("", "abc", Some(3)), Some(3));
+
+        // too long for the bit vectors so the Levenshtein implementation is used
+        let long1 = "ab".repeat(40);
+        let long2 = "ba".repeat(40);
+        assert_eq!(edit_distance.compare(&long1, &long2, None), Some(2));
+        assert_eq!(edit_distance.compare(&long1, &long2, Some(1)), None);
+
+        let mut osa = EditDistance::new(DistanceAlgorithm::DamaerauOSA);
+        assert_eq!(osa.compare("ab", "ba", None), Some(1));
+
+        let mut sift4 = EditDistance::new(DistanceAlgorithm::Sift4(5));
+        assert_eq!(sift4.compare("ab", "ba", None), Some(1));
+        let mut lcs = EditDistance::new(DistanceAlgorithm::LongestCommonSubsequence);
+        assert_eq!(lcs.compare("ab", "ba", None), Some(2));
+    }
+
+    #[test]
+    fn compare_many_test() {
+        let long = "ab".repeat(40);
+        let candidates = ["sitting", "mitten", "", "kitten", &long];
+        let mut levenshtein = EditDistance::new(DistanceAlgorithm::Levenshtein);
+        assert_eq!(levenshtein.compare_many("kitten", &candidates, Some(3)), vec![Some(3), Some(1), None, Some(0), None]);
+        assert_eq!(levenshtein.compare_many("kitten", &candidates, None), vec![Some(3), Some(1), Some(6), Some(0), Some(80)]);
+        // a new query replaces the prepared one
+        assert_eq!(levenshtein.compare_many("mitten", &candidates[..2], None), vec![Some(3), Some(0)]);
+        // too long to prepare so each candidate falls back to Levenshtein
+        let long_query = "ba".repeat(40);
+        assert_eq!(levenshtein.compare_many(&long_query, &candidates[3..], Some(2)), vec![None, Some(2)]);
+
+        let mut osa = EditDistance::new(DistanceAlgorithm::DamaerauOSA);
+        assert_eq!(osa.compare_many("ab", &["ba", "abc", "xyz"], Some(1)), vec![Some(1), Some(1), None]);
+    }
+
+    #[test]
+    fn compare_prepared_test() {
+        let costs = Arc::new(WeightedCosts::new().with_substitution("a", "e", 0.5));
+        let algorithms = vec![
+            DistanceAlgorithm::DamaerauOSA,
+            DistanceAlgorithm::Damerau,
+            DistanceAlgorithm::Levenshtein,
+            DistanceAlgorithm::Weighted(costs),
+            DistanceAlgorithm::Sift4(5),
+            DistanceAlgorithm::LongestCommonSubsequence,
+        ];
+        let strings = ["", "a", "ab", "ba", "héllo", "hello", "hlelo", "🚀hello", "kitten", "sitting", "cafe\u{301}", "café"];
+        for algorithm in algorithms {
+            let mut prepared = EditDistance::new(algorithm.clone());
+            let mut unprepared = EditDistance::new(algorithm);
+            for query in strings.iter() {
+                for max_distance in [None, Some(0), Some(1), Some(2)] {
+                    let expected: Vec<Option<usize>> = strings.iter().map(|string| unprepared.compare(query, string, max_distance)).collect();
+                    assert_eq!(prepared.compare_many(query, &strings, max_distance), expected, "{} {:?}", query, max_distance);
+                }
+            }
         }
-        return self.distance_comparator.distance(string1, string2);
     }
 }
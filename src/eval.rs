@@ -0,0 +1,139 @@
+use crate::sym_spell::sym_spell::SymSpell;
+use crate::sym_spell::verbosity::Verbosity;
+
+/// A single misspelling and the spelling it is expected to be corrected to.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Misspelling {
+    pub misspelled: String,
+    pub correct: String,
+}
+
+/// The outcome of running a SymSpell instance against a misspelling corpus.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EvalReport {
+    /// Number of misspellings evaluated
+    pub total: usize,
+    /// Misspellings for which a suggestion other than the input itself was offered
+    pub corrected: usize,
+    /// Misspellings whose top suggestion is the expected spelling
+    pub top1_correct: usize,
+    /// Misspellings whose expected spelling appears anywhere in the suggestions
+    pub found: usize,
+}
+
+impl EvalReport {
+    /// Share of offered corrections that were right.
+    pub fn precision(&self) -> f64 {
+        ratio(self.top1_correct, self.corrected)
+    }
+
+    /// Share of misspellings whose expected spelling was among the suggestions.
+    pub fn recall(&self) -> f64 {
+        ratio(self.found, self.total)
+    }
+
+    /// Share of misspellings whose top suggestion was the expected spelling.
+    pub fn top1_accuracy(&self) -> f64 {
+        ratio(self.top1_correct, self.total)
+    }
+}
+
+fn ratio(numerator: usize, denominator: usize) -> f64 {
+    if denominator == 0 { 0.0 } else { numerator as f64 / denominator as f64 }
+}
+
+/// Parses a corpus in the Birkbeck format where each correct spelling is
+/// prefixed with `$` and followed by its misspellings, one per line.
+///
+/// example:
+/// $because
+/// becuase
+/// beacuse
+pub fn parse_birkbeck(text: &str) -> Vec<Misspelling> {
+    let mut misspellings = vec![];
+    let mut correct: Option<&str> = None;
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(stripped) = line.strip_prefix('$') {
+            correct = Some(stripped);
+        } else if let Some(correct) = correct {
+            misspellings.push(Misspelling { misspelled: line.to_string(), correct: correct.to_string() });
+        }
+    }
+    misspellings
+}
+
+/// Parses a corpus in the Wikipedia "list of common misspellings" format
+/// where each line maps a misspelling to one or more comma separated
+/// corrections. Only the first correction is used.
+///
+/// example:
+/// abandonned->abandoned
+/// abondon->abandon, abundant
+pub fn parse_wikipedia(text: &str) -> Vec<Misspelling> {
+    text.lines()
+        .filter_map(|line| {
+            let mut parts = line.trim().splitn(2, "->");
+            let misspelled = parts.next()?.trim();
+            let correct = parts.next()?.split(',').next()?.trim();
+            if misspelled.is_empty() || correct.is_empty() {
+                return None;
+            }
+            Some(Misspelling { misspelled: misspelled.to_string(), correct: correct.to_string() })
+        })
+        .collect()
+}
+
+/// Runs `lookup` for every misspelling in the corpus and tallies the results.
+pub fn evaluate(sym_spell: &SymSpell, misspellings: &[Misspelling], verbosity: Verbosity, max_edit_distance: usize) -> EvalReport {
+    let mut report = EvalReport::default();
+    for misspelling in misspellings {
+        report.total += 1;
+        let suggestions = sym_spell.lookup(&misspelling.misspelled, verbosity, max_edit_distance, false, false);
+        if suggestions.iter().any(|s| s.term != misspelling.misspelled) {
+            report.corrected += 1;
+        }
+        if suggestions.first().is_some_and(|s| s.term == misspelling.correct) {
+            report.top1_correct += 1;
+        }
+        if suggestions.iter().any(|s| s.term == misspelling.correct) {
+            report.found += 1;
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod eval_tests {
+    use crate::eval::{evaluate, parse_birkbeck, parse_wikipedia};
+    use crate::sym_spell::sym_spell::SymSpell;
+    use crate::sym_spell::verbosity::Verbosity;
+
+    #[test]
+    fn parse_corpora_test() {
+        let birkbeck = parse_birkbeck("$because\nbecuase\nbeacuse\n$the\nteh\n");
+        assert_eq!(birkbeck.len(), 3);
+        assert_eq!(birkbeck[2].misspelled, "teh");
+        assert_eq!(birkbeck[2].correct, "the");
+
+        let wikipedia = parse_wikipedia("abandonned->abandoned\nabondon->abandon, abundant\n");
+        assert_eq!(wikipedia.len(), 2);
+        assert_eq!(wikipedia[1].correct, "abandon");
+    }
+
+    #[test]
+    fn evaluate_test() {
+        let sym_spell: SymSpell = vec![("because", 100), ("the", 1000), ("tea", 10)].into_iter().collect();
+        let corpus = parse_birkbeck("$because\nbecuase\n$the\nteh\n$zebra\nzebar\n");
+        let report = evaluate(&sym_spell, &corpus, Verbosity::Closest, 2);
+
+        assert_eq!(report.total, 3);
+        assert_eq!(report.corrected, 2);
+        assert_eq!(report.top1_correct, 2);
+        assert_eq!(report.found, 2);
+        assert_eq!(report.precision(), 1.0);
+    }
+}
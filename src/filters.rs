@@ -0,0 +1,294 @@
+use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
+
+/// A kind of token that is not prose and so is never a misspelling.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Filter {
+    /// "https://example.com/path", "ftp://host" or "www.example.com"
+    Url,
+    /// "jane.doe@example.com"
+    Email,
+    /// "42", "3.5GHz", "-12°C", "$9.99", "100%" or "1990s": digits with an optional unit of up to five letters
+    Number,
+    /// "0xff", "#1e90ff", "f9f4cea" or a UUID: at least seven hex digits mixing digits and letters
+    HexString,
+    /// "@octocat"
+    Mention,
+    /// "#rustlang"
+    Hashtag,
+    /// "parseWords", "max_edit_distance", "SymSpell::new" or "lookup()"
+    CodeIdentifier,
+}
+
+impl Filter {
+    /// True when the chunk, a run of text without whitespace stripped of
+    /// surrounding punctuation, is of this kind.
+    pub fn matches(self, chunk: &str) -> bool {
+        match self {
+            Filter::Url => is_url(chunk),
+            Filter::Email => is_email(chunk),
+            Filter::Number => is_number(chunk),
+            Filter::HexString => is_hex_string(chunk),
+            Filter::Mention => chunk.strip_prefix('@').is_some_and(is_handle),
+            Filter::Hashtag => chunk.strip_prefix('#').is_some_and(is_handle),
+            Filter::CodeIdentifier => is_code_identifier(chunk),
+        }
+    }
+}
+
+/// Spans of a text that `SymSpell::check_text` and `SymSpell::document_stats` skip,
+/// e.g. URLs and email addresses. The text is split at whitespace and each chunk,
+/// without the punctuation around it, is skipped when any filter, pattern or matcher
+/// matches it.
+///
+/// example:
+/// let filters = TextFilters::common().with_pattern("JIRA-*");
+///
+/// assert_eq!(filters.spans("see JIRA-42 or https://example.com."), vec![4..11, 15..34]);
+#[derive(Clone, Default)]
+pub struct TextFilters {
+    filters: Vec<Filter>,
+    // wildcard patterns, '*' for any run of chars and '?' for any one
+    patterns: Vec<String>,
+    matchers: Vec<Matcher>,
+}
+
+type Matcher = Arc<dyn Fn(&str) -> bool + Send + Sync>;
+
+impl TextFilters {
+    /// Skips nothing.
+    pub fn new() -> TextFilters {
+        TextFilters::default()
+    }
+
+    /// Every built in `Filter`.
+    pub fn common() -> TextFilters {
+        [Filter::Url, Filter::Email, Filter::Number, Filter::HexString, Filter::Mention, Filter::Hashtag, Filter::CodeIdentifier]
+            .iter()
+            .fold(TextFilters::new(), |filters, &filter| filters.with_filter(filter))
+    }
+
+    pub fn with_filter(mut self, filter: Filter) -> TextFilters {
+        if !self.filters.contains(&filter) {
+            self.filters.push(filter);
+        }
+        self
+    }
+
+    /// Skips the chunks matching a wildcard pattern, where '*' stands for any
+    /// run of chars and '?' for any one char, e.g. "JIRA-*".
+    pub fn with_pattern(mut self, pattern: &str) -> TextFilters {
+        self.patterns.push(pattern.to_string());
+        self
+    }
+
+    /// Skips the chunks the function returns true for.
+    pub fn with_matcher(mut self, matcher: impl Fn(&str) -> bool + Send + Sync + 'static) -> TextFilters {
+        self.matchers.push(Arc::new(matcher));
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty() && self.patterns.is_empty() && self.matchers.is_empty()
+    }
+
+    /// True when a filter, pattern or matcher matches the chunk.
+    pub fn matches(&self, chunk: &str) -> bool {
+        self.filters.iter().any(|filter| filter.matches(chunk))
+            || self.patterns.iter().any(|pattern| wildcard_match(pattern, chunk))
+            || self.matchers.iter().any(|matcher| matcher(chunk))
+    }
+
+    /// The byte ranges of the chunks of the text to skip, in order.
+    pub fn spans(&self, text: &str) -> Vec<Range<usize>> {
+        if self.is_empty() {
+            return vec![];
+        }
+        let mut spans = vec![];
+        let mut start = None;
+        for (idx, ch) in text.char_indices().chain(Some((text.len(), ' '))) {
+            match (start, ch.is_whitespace()) {
+                (None, false) => start = Some(idx),
+                (Some(chunk_start), true) => {
+                    let chunk = trim_punctuation(&text[chunk_start..idx]);
+                    if !chunk.is_empty() && self.matches(chunk) {
+                        let chunk_start = chunk.as_ptr() as usize - text.as_ptr() as usize;
+                        spans.push(chunk_start..chunk_start + chunk.len());
+                    }
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+        spans
+    }
+}
+
+impl fmt::Debug for TextFilters {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TextFilters")
+            .field("filters", &self.filters)
+            .field("patterns", &self.patterns)
+            .field("matchers", &self.matchers.len())
+            .finish()
+    }
+}
+
+// the chunk without the quotes, brackets and sentence punctuation around it
+fn trim_punctuation(chunk: &str) -> &str {
+    let mut chunk = chunk.trim_start_matches(|ch| "\"'“‘([{<".contains(ch));
+    loop {
+        let trimmed = chunk.trim_end_matches(|ch| "\"'”’.,;:!?".contains(ch));
+        // a bracket closing one opened within the chunk stays, e.g. in "lookup()"
+        chunk = match trimmed.strip_suffix(|ch| ")]}>".contains(ch)) {
+            Some(rest) if !rest.contains(|ch| "([{<".contains(ch)) => rest,
+            _ => return trimmed,
+        };
+    }
+}
+
+fn is_url(chunk: &str) -> bool {
+    let lowercase = chunk.to_lowercase();
+    if let Some(rest) = lowercase.strip_prefix("www.") {
+        return rest.contains('.');
+    }
+    match lowercase.find("://") {
+        Some(at) => at > 0 && at + 3 < lowercase.len() && lowercase[..at].chars().all(|ch| ch.is_ascii_alphabetic()),
+        None => false,
+    }
+}
+
+fn is_email(chunk: &str) -> bool {
+    let (local, domain) = match chunk.split_once('@') {
+        Some(parts) => parts,
+        None => return false,
+    };
+    !local.is_empty()
+        && !local.contains(char::is_whitespace)
+        && domain.contains('.')
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+        && domain.chars().all(|ch| ch.is_alphanumeric() || ch == '-' || ch == '.')
+}
+
+fn is_number(chunk: &str) -> bool {
+    let number = chunk.trim_start_matches(|ch| "+-−$€£¥".contains(ch));
+    if !number.starts_with(|ch: char| ch.is_ascii_digit()) {
+        return false;
+    }
+    let unit = number.trim_start_matches(|ch: char| ch.is_ascii_digit() || ".,:/-".contains(ch));
+    unit.chars().count() <= 5 && unit.chars().all(|ch| ch.is_alphabetic() || "%‰°/²³µ$€£¥".contains(ch))
+}
+
+fn is_hex_string(chunk: &str) -> bool {
+    let is_hex = |digits: &str| !digits.is_empty() && digits.chars().all(|ch| ch.is_ascii_hexdigit());
+    if let Some(digits) = chunk.strip_prefix("0x").or_else(|| chunk.strip_prefix("0X")) {
+        return is_hex(digits);
+    }
+    if let Some(digits) = chunk.strip_prefix('#') {
+        return [3, 4, 6, 8].contains(&digits.len()) && is_hex(digits) && digits.contains(|ch: char| ch.is_ascii_digit());
+    }
+    // commit hashes and UUIDs
+    let digits: String = chunk.chars().filter(|&ch| ch != '-').collect();
+    digits.len() >= 7
+        && is_hex(&digits)
+        && !chunk.starts_with('-')
+        && digits.contains(|ch: char| ch.is_ascii_digit())
+        && digits.contains(|ch: char| ch.is_ascii_alphabetic())
+}
+
+// the name after the '@' of a mention or the '#' of a hashtag
+fn is_handle(name: &str) -> bool {
+    name.starts_with(char::is_alphanumeric) && name.chars().all(|ch| ch.is_alphanumeric() || "_.-".contains(ch))
+}
+
+fn is_code_identifier(chunk: &str) -> bool {
+    if chunk.contains("::") || chunk.contains("->") || chunk.ends_with("()") {
+        return true;
+    }
+    let chars: Vec<char> = chunk.chars().collect();
+    chars.windows(2).any(|pair| pair[0].is_lowercase() && pair[1].is_uppercase())
+        || chars.windows(3).any(|triple| triple[1] == '_' && triple[0].is_alphanumeric() && triple[2].is_alphanumeric())
+}
+
+// matches the whole text against a pattern of literal chars, '*' and '?'
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // the position of the last '*' and the text position it was tried at
+    let mut backtrack = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&ch) if ch == '?' || ch == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, star_t)) => {
+                    backtrack = Some((star, star_t + 1));
+                    p = star + 1;
+                    t = star_t + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&ch| ch == '*')
+}
+
+#[cfg(test)]
+mod filters_tests {
+    use crate::filters::{Filter, TextFilters};
+
+    #[test]
+    fn filter_test() {
+        let cases = [
+            (Filter::Url, vec!["https://example.com/a?b=c", "ftp://host", "www.example.com"], vec!["https://", "www.", "not://", "a.b"]),
+            (Filter::Email, vec!["jane.doe@example.com", "a@b.co"], vec!["@example.com", "jane@localhost", "a@b."]),
+            (Filter::Number, vec!["42", "3.5GHz", "-12°C", "$9.99", "100%", "1990s", "5km/h", "12:30"], vec!["4everyone", "v2", "one"]),
+            (Filter::HexString, vec!["0xff", "#1e90ff", "f9f4cea", "550e8400-e29b-41d4-a716-446655440000"], vec!["0x", "#bad", "deadbeef", "1234567", "facade"]),
+            (Filter::Mention, vec!["@octocat", "@jane_doe"], vec!["@", "@-x", "octocat"]),
+            (Filter::Hashtag, vec!["#rustlang", "#100DaysOfCode"], vec!["#", "#!", "rustlang"]),
+            (Filter::CodeIdentifier, vec!["parseWords", "max_edit_distance", "SymSpell::new", "lookup()", "iPhone"], vec!["Hello", "word", "_private", "e-mail"]),
+        ];
+        for (filter, matching, other) in cases.iter() {
+            for chunk in matching {
+                assert!(filter.matches(chunk), "{:?} {}", filter, chunk);
+            }
+            for chunk in other {
+                assert!(!filter.matches(chunk), "{:?} {}", filter, chunk);
+            }
+        }
+    }
+
+    #[test]
+    fn spans_test() {
+        let text = "Mail (jane@example.com), see https://example.com. JIRA-42 costs 3€ and ABC-1! Call lookup().";
+        assert_eq!(TextFilters::new().spans(text), vec![]);
+        let filters = TextFilters::common();
+        let spans: Vec<&str> = filters.spans(text).into_iter().map(|span| &text[span]).collect();
+        assert_eq!(spans, vec!["jane@example.com", "https://example.com", "3€", "lookup()"]);
+
+        let filters = filters.with_pattern("JIRA-*").with_matcher(|chunk| chunk == "ABC-1");
+        let spans: Vec<&str> = filters.spans(text).into_iter().map(|span| &text[span]).collect();
+        assert_eq!(spans, vec!["jane@example.com", "https://example.com", "JIRA-42", "3€", "ABC-1", "lookup()"]);
+    }
+
+    #[test]
+    fn wildcard_test() {
+        let filters = |pattern: &str| TextFilters::new().with_pattern(pattern);
+        assert!(filters("JIRA-*").matches("JIRA-42"));
+        assert!(!filters("JIRA-*").matches("jira-42"));
+        assert!(filters("*.rs").matches("main.rs"));
+        assert!(filters("a*b*c").matches("aXbYbc"));
+        assert!(filters("v?.?").matches("v1.2"));
+        assert!(!filters("v?.?").matches("v10.2"));
+        assert!(filters("*").matches(""));
+    }
+}
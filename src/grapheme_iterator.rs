@@ -1,35 +1,103 @@
-use std::str;
+use std::borrow::Cow;
 use std::ops::{Range, Index};
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+use std::str::{self, Utf8Error};
+
+#[cfg(feature = "unicode-segmentation")]
+use unicode_segmentation::UnicodeSegmentation;
+
+#[cfg(not(feature = "unicode-segmentation"))]
+const ZERO_WIDTH_JOINER: char = '\u{200D}';
 
 pub struct GraphemeClusters<'a> {
-    bytes: &'a [u8],
+    string: &'a str,
+    // the byte offset and grapheme index of the front of the iteration
     cursor: usize,
+    front: usize,
+    // the grapheme index and byte offset of the back of the iteration
+    // once next_back has been called
+    back: Option<(usize, usize)>,
     // A vector of byte indices where the vec
     // index is the grapheme cluster index
     byte_indices: RefCell<Vec<usize>>,
+    // The grapheme count once known. A count equal to the
+    // byte length means every grapheme is a single byte
+    len: Cell<Option<usize>>,
 }
 
-impl GraphemeClusters<'_> {
-    pub fn new(s: &str) -> GraphemeClusters {
+impl<'a> GraphemeClusters<'a> {
+    pub fn new(s: &str) -> GraphemeClusters<'_> {
         GraphemeClusters {
-            bytes: s.as_bytes(),
+            string: s,
             cursor: 0,
+            front: 0,
+            back: None,
             byte_indices: RefCell::new(vec![0]),
+            len: Cell::new(None),
         }
     }
 
+    /// Validating alternative to `new` for bytes from an untrusted source such as
+    /// the host side of the wasm boundary. See `decode_utf8` to replace invalid
+    /// sequences rather than reject them.
+    pub fn try_new(bytes: &[u8]) -> Result<GraphemeClusters<'_>, Utf8Error> {
+        str::from_utf8(bytes).map(GraphemeClusters::new)
+    }
+
+    /// The number of grapheme clusters. The first call scans the string, recording
+    /// every grapheme's byte offset, so later calls and indexing are O(1).
     pub fn len(&self) -> usize {
-        let mut len = 0;
-        let mut idx = 0;
-        while idx != self.bytes.len() {
-            let byte = self.bytes[idx];
-            idx += GraphemeClusters::grapheme_len(&byte);
-            len += 1;
+        if let Some(len) = self.len.get() {
+            return len;
         }
+        let len = if GraphemeClusters::is_byte_per_grapheme(self.string) {
+            self.string.len()
+        } else {
+            let mut byte_indices = self.byte_indices.borrow_mut();
+            let mut idx = *byte_indices.last().unwrap();
+            while idx != self.string.len() {
+                idx += GraphemeClusters::grapheme_len(&self.string[idx..]);
+                byte_indices.push(idx);
+            }
+            byte_indices.len() - 1
+        };
+        self.len.set(Some(len));
         len
     }
 
+    /// The number of grapheme clusters if `len` has already been computed.
+    pub fn len_cached(&self) -> Option<usize> {
+        self.len.get()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.string.is_empty()
+    }
+
+    /// Every grapheme cluster of the string, in order.
+    ///
+    /// example:
+    /// let gc = GraphemeClusters::new("né🚀");
+    ///
+    /// assert_eq!(gc.as_slices(), vec!["n", "é", "🚀"]);
+    pub fn as_slices(&self) -> Vec<&'a str> {
+        let string = self.string;
+        if self.len() == string.len() {
+            return (0..string.len()).map(|idx| &string[idx..idx + 1]).collect();
+        }
+        self.byte_indices.borrow().windows(2).map(|range| &string[range[0]..range[1]]).collect()
+    }
+
+    /// The str of a grapheme cluster range.
+    ///
+    /// example:
+    /// let gc = GraphemeClusters::new("🐶 my dog");
+    ///
+    /// assert_eq!(gc.slice(2..4), "my");
+    pub fn slice(&self, range: Range<usize>) -> &'a str {
+        &self.string[self.get_slice_range(range)]
+    }
+
     /// Converts a grapheme cluster range to a slice range
     ///
     /// example:
@@ -39,14 +107,18 @@ impl GraphemeClusters<'_> {
     /// assert_eq!(s[gc.get_slice_range(0..8)], "🐶 my dog")
     ///
     pub fn get_slice_range(&self, range: Range<usize>) -> Range<usize> {
+        if self.len.get() == Some(self.string.len()) {
+            assert!(range.end <= self.string.len(), "grapheme index out of range");
+            return range;
+        }
         let mut byte_indices = self.byte_indices.borrow_mut();
         let mut largest_idx = byte_indices.len() - 1;
         let mut start_idx = if largest_idx >= range.start { byte_indices[range.start] } else { byte_indices[largest_idx] };
         let mut end_idx = if largest_idx >= range.end { byte_indices[range.end] } else { byte_indices[largest_idx] };
 
         while largest_idx < range.end {
-            let byte = self.bytes[end_idx];
-            end_idx += GraphemeClusters::grapheme_len(&byte);
+            assert!(end_idx < self.string.len(), "grapheme index out of range");
+            end_idx += GraphemeClusters::grapheme_len(&self.string[end_idx..]);
             largest_idx += 1;
             byte_indices.push(end_idx);
             if largest_idx == range.start {
@@ -56,18 +128,112 @@ impl GraphemeClusters<'_> {
         start_idx..end_idx
     }
 
-    fn grapheme_len(byte: &u8) -> usize {
-        let mut bytes = 1;
-        if ((byte & 0b10000000) >> 7) == 1 && ((byte & 0b1000000) >> 6) == 1 {
-            bytes += 1;
-        }
-        if bytes == 2 && ((byte & 0b100000) >> 5) == 1 {
-            bytes += 1;
+    // whether every byte of the str is a grapheme of its own, true of ASCII
+    // except for a "\r\n" pair which is a single extended grapheme cluster
+    #[cfg(feature = "unicode-segmentation")]
+    fn is_byte_per_grapheme(s: &str) -> bool {
+        s.is_ascii() && !s.contains("\r\n")
+    }
+
+    #[cfg(not(feature = "unicode-segmentation"))]
+    fn is_byte_per_grapheme(s: &str) -> bool {
+        s.is_ascii()
+    }
+
+    // the byte length of the extended grapheme cluster the str starts with
+    #[cfg(feature = "unicode-segmentation")]
+    fn grapheme_len(s: &str) -> usize {
+        s.graphemes(true).next().map_or(0, |grapheme| grapheme.len())
+    }
+
+    // the byte length of the grapheme cluster the str starts with. This covers the
+    // UAX #29 rules that matter for spelling: combining marks, variation selectors,
+    // emoji modifiers and tags extend a cluster, a zero width joiner joins the emoji
+    // after it and regional indicators pair up into flags. Hangul jamo and the rarer
+    // script specific rules need the unicode-segmentation feature.
+    #[cfg(not(feature = "unicode-segmentation"))]
+    fn grapheme_len(s: &str) -> usize {
+        let bytes = s.as_bytes();
+        // an ASCII char followed by another, or by nothing, is a cluster of its own
+        if bytes.first().is_some_and(|byte| byte.is_ascii()) && bytes.get(1).map_or(true, |byte| byte.is_ascii()) {
+            return bytes.len().min(1);
         }
-        if bytes == 3 && ((byte & 0b10000) >> 4) == 1 {
-            bytes += 1;
+        let mut chars = s.char_indices();
+        let first = match chars.next() {
+            Some((_, first)) => first,
+            None => return 0,
+        };
+        let mut previous = first;
+        let mut end = first.len_utf8();
+        for (idx, ch) in chars {
+            let joins = is_extend(ch)
+                || (previous == ZERO_WIDTH_JOINER && is_pictographic(ch))
+                || (idx == first.len_utf8() && is_regional_indicator(first) && is_regional_indicator(ch));
+            if !joins {
+                break;
+            }
+            previous = ch;
+            end = idx + ch.len_utf8();
         }
-        bytes
+        end
+    }
+}
+
+// true for the chars that never start a cluster of their own
+#[cfg(not(feature = "unicode-segmentation"))]
+fn is_extend(ch: char) -> bool {
+    matches!(ch as u32,
+        0x0300..=0x036F // combining diacritical marks
+        | 0x0483..=0x0489 // cyrillic
+        | 0x0591..=0x05BD | 0x05BF | 0x05C1..=0x05C2 | 0x05C4..=0x05C5 | 0x05C7 // hebrew points
+        | 0x0610..=0x061A | 0x064B..=0x065F | 0x0670 | 0x06D6..=0x06DC | 0x06DF..=0x06E4 // arabic
+        | 0x0900..=0x0903 | 0x093A..=0x093C | 0x093E..=0x094F | 0x0951..=0x0957 | 0x0962..=0x0963 // devanagari
+        | 0x0E31 | 0x0E34..=0x0E3A | 0x0E47..=0x0E4E // thai
+        | 0x1AB0..=0x1AFF | 0x1DC0..=0x1DFF | 0x20D0..=0x20FF | 0xFE20..=0xFE2F // combining marks
+        | 0x200C..=0x200D // zero width non-joiner and joiner
+        | 0x3099..=0x309A // kana voicing marks
+        | 0xFE00..=0xFE0F | 0xE0100..=0xE01EF // variation selectors
+        | 0x1F3FB..=0x1F3FF // emoji skin tone modifiers
+        | 0xE0020..=0xE007F // tags of subdivision flags
+    )
+}
+
+#[cfg(not(feature = "unicode-segmentation"))]
+fn is_pictographic(ch: char) -> bool {
+    matches!(ch as u32, 0x00A9 | 0x00AE | 0x2190..=0x21FF | 0x2300..=0x23FF | 0x25A0..=0x27BF | 0x2B00..=0x2BFF | 0x1F000..=0x1FAFF)
+}
+
+#[cfg(not(feature = "unicode-segmentation"))]
+fn is_regional_indicator(ch: char) -> bool {
+    matches!(ch as u32, 0x1F1E6..=0x1F1FF)
+}
+
+/// Decodes bytes from an untrusted source, borrowing them when they are valid utf-8.
+/// When `lossy` is true each invalid sequence is replaced with U+FFFD, otherwise the
+/// error reports how many leading bytes were valid.
+///
+/// example:
+/// assert_eq!(decode_utf8(b"caf\xE9", true).unwrap(), "caf\u{FFFD}");
+/// assert_eq!(decode_utf8(b"caf\xE9", false).unwrap_err().valid_up_to(), 3);
+pub fn decode_utf8(bytes: &[u8], lossy: bool) -> Result<Cow<'_, str>, Utf8Error> {
+    if lossy {
+        Ok(String::from_utf8_lossy(bytes))
+    } else {
+        str::from_utf8(bytes).map(Cow::Borrowed)
+    }
+}
+
+impl GraphemeClusters<'_> {
+    // the grapheme index and byte offset of the back of the iteration
+    fn back(&self) -> (usize, usize) {
+        self.back.unwrap_or_else(|| (self.len(), self.string.len()))
+    }
+
+    // starts the iteration over once it is exhausted
+    fn rewind(&mut self) {
+        self.cursor = 0;
+        self.front = 0;
+        self.back = None;
     }
 }
 
@@ -78,33 +244,69 @@ impl<'a> Iterator for GraphemeClusters<'a> {
     type Item = (&'a str, Range<usize>);
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.bytes.len() <= self.cursor {
-            self.cursor = 0;
+        let end = self.back.map_or(self.string.len(), |(_, end)| end);
+        if end <= self.cursor {
+            self.rewind();
             return None;
         }
-        let next_byte = &self.bytes[self.cursor];
-        let len = GraphemeClusters::grapheme_len(next_byte);
-        let end = self.cursor + len;
-        let s = unsafe { str::from_utf8_unchecked(&self.bytes[self.cursor..end]) };
-        let result = Some((s, self.cursor..end));
-        self.cursor = end;
+        // collect() asks for the exact size up front so the byte offsets are often recorded already
+        let grapheme_end = match self.len.get() {
+            Some(len) if len == self.string.len() => self.cursor + 1,
+            Some(_) => self.byte_indices.borrow()[self.front + 1],
+            None => self.cursor + GraphemeClusters::grapheme_len(&self.string[self.cursor..]),
+        };
+        let result = Some((&self.string[self.cursor..grapheme_end], self.cursor..grapheme_end));
+        self.cursor = grapheme_end;
+        self.front += 1;
 
         result
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.back().0 - self.front;
+        (remaining, Some(remaining))
+    }
+
+    // jumps straight to the grapheme using the recorded byte offsets
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        let index = self.front + n;
+        if index >= self.back().0 {
+            self.rewind();
+            return None;
+        }
+        let range = self.get_slice_range(index..index + 1);
+        self.cursor = range.end;
+        self.front = index + 1;
+        Some((&self.string[range.clone()], range))
+    }
 }
 
+impl<'a> DoubleEndedIterator for GraphemeClusters<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let (back, end) = self.back();
+        if end <= self.cursor {
+            self.rewind();
+            return None;
+        }
+        let range = self.get_slice_range(back - 1..back);
+        self.back = Some((back - 1, range.start));
+        Some((&self.string[range.clone()], range))
+    }
+}
+
+impl ExactSizeIterator for GraphemeClusters<'_> {}
+
 impl<'a> Index<usize> for GraphemeClusters<'a> {
     type Output = str;
 
     fn index(&self, index: usize) -> &Self::Output {
-        let range = self.get_slice_range(index..index + 1);
-        unsafe { str::from_utf8_unchecked(&self.bytes[range]) }
+        &self.string[self.get_slice_range(index..index + 1)]
     }
 }
 
 #[cfg(test)]
 mod grapheme_iterator_tests {
-    use crate::grapheme_iterator::GraphemeClusters;
+    use crate::grapheme_iterator::{decode_utf8, GraphemeClusters};
 
     #[test]
     fn iterator_test() {
@@ -118,10 +320,20 @@ mod grapheme_iterator_tests {
         let s = "🚀rocket ";
         let it: Vec<_> = GraphemeClusters::new(s).collect();
         for (grapheme, _) in it {
-            assert_eq!(grapheme.len() > 0, true)
+            assert!(!grapheme.is_empty())
         }
     }
 
+    #[test]
+    #[cfg(feature = "unicode-segmentation")]
+    fn crlf_test() {
+        let s = "a\r\nb";
+        assert_eq!(GraphemeClusters::new(s).len(), 3);
+        let it: Vec<_> = GraphemeClusters::new(s).map(|(grapheme, _)| grapheme).collect();
+        assert_eq!(it, vec!["a", "\r\n", "b"]);
+        assert_eq!(&GraphemeClusters::new(s)[2], "b");
+    }
+
     #[test]
     fn len_test() {
         let s = "🚀this is a test string🚀";
@@ -141,6 +353,78 @@ mod grapheme_iterator_tests {
         assert_eq!(byte_range1, 4..8);
     }
 
+    #[test]
+    fn extended_cluster_test() {
+        let s = "cafe\u{301} 👨\u{200D}👩\u{200D}👧 🇫🇷🇩🇪 👍🏽!";
+        let it: Vec<_> = GraphemeClusters::new(s).map(|(grapheme, _)| grapheme).collect();
+        assert_eq!(it, vec!["c", "a", "f", "e\u{301}", " ", "👨\u{200D}👩\u{200D}👧", " ", "🇫🇷", "🇩🇪", " ", "👍🏽", "!"]);
+        assert_eq!(GraphemeClusters::new(s).len(), 12);
+        assert_eq!(&GraphemeClusters::new(s)[8], "🇩🇪");
+    }
+
+    #[test]
+    fn invalid_utf8_test() {
+        assert_eq!(GraphemeClusters::try_new("héllo".as_bytes()).unwrap().len(), 5);
+        assert_eq!(GraphemeClusters::try_new(b"h\xC3llo").err().unwrap().valid_up_to(), 1);
+        // a truncated multi-byte sequence at the end
+        assert!(GraphemeClusters::try_new(b"h\xF0\x9F\x9A").is_err());
+
+        let decoded = decode_utf8(b"h\xC3llo \xF0\x9F\x9A", true).unwrap();
+        assert_eq!(decoded, "h\u{FFFD}llo \u{FFFD}");
+        assert_eq!(GraphemeClusters::new(&decoded).len(), 7);
+        assert_eq!(decode_utf8(b"hello", false).unwrap(), "hello");
+        assert_eq!(decode_utf8(b"h\xC3llo", false).unwrap_err().valid_up_to(), 1);
+    }
+
+    #[test]
+    fn cached_len_test() {
+        let s = "🚀this is a test string🚀";
+        let gc = GraphemeClusters::new(s);
+        assert_eq!(&gc[1], "t");
+        assert_eq!(gc.len_cached(), None);
+        assert_eq!(gc.len(), 23);
+        assert_eq!(gc.len_cached(), Some(23));
+        assert_eq!(gc.get_slice_range(22..23), 25..29);
+        let slices = gc.as_slices();
+        assert_eq!((slices.len(), slices[0], slices[1]), (23, "🚀", "t"));
+        assert_eq!(slices.concat(), s);
+
+        let ascii = GraphemeClusters::new("ascii");
+        assert_eq!(ascii.len(), 5);
+        assert_eq!(ascii.get_slice_range(1..3), 1..3);
+        assert_eq!(ascii.as_slices(), vec!["a", "s", "c", "i", "i"]);
+        assert!(GraphemeClusters::new("").as_slices().is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "grapheme index out of range")]
+    fn index_out_of_range_test() {
+        let gc = GraphemeClusters::new("né");
+        let _ = &gc[2];
+    }
+
+    #[test]
+    fn double_ended_test() {
+        let s = "a🇫🇷be\u{301}🚀";
+        let reversed: Vec<_> = GraphemeClusters::new(s).rev().map(|(grapheme, _)| grapheme).collect();
+        assert_eq!(reversed, vec!["🚀", "e\u{301}", "b", "🇫🇷", "a"]);
+
+        let mut gc = GraphemeClusters::new(s);
+        assert_eq!(gc.len(), 5);
+        assert_eq!(gc.next(), Some(("a", 0..1)));
+        assert_eq!(gc.next_back(), Some(("🚀", 13..17)));
+        assert_eq!(gc.size_hint(), (3, Some(3)));
+        assert_eq!(gc.nth(1), Some(("b", 9..10)));
+        assert_eq!(gc.next_back(), Some(("e\u{301}", 10..13)));
+        assert_eq!(gc.next(), None);
+        assert_eq!(gc.next_back(), Some(("🚀", 13..17)));
+
+        assert_eq!(GraphemeClusters::new(s).skip(2).map(|(grapheme, _)| grapheme).collect::<Vec<_>>(), vec!["b", "e\u{301}", "🚀"]);
+        assert_eq!(GraphemeClusters::new(s).nth(5), None);
+        assert_eq!(GraphemeClusters::new("abc").rev().nth(1), Some(("b", 1..2)));
+        assert_eq!(GraphemeClusters::new(s).slice(1..3), "🇫🇷b");
+    }
+
     #[test]
     fn index_test() {
         let s = "🚀this is a test string🚀";
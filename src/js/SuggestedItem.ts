@@ -5,10 +5,12 @@ export class SuggestedItem {
 
     private readonly data: Uint8Array;
     private readonly ptr: number;
+    private readonly length: number;
 
-    constructor(data: Uint8Array, ptr: number) {
+    constructor(data: Uint8Array, ptr: number, length = data.byteLength - ptr) {
         this.data = data;
         this.ptr = ptr;
+        this.length = length;
     }
 
     public get count(): number /* u32 */ {
@@ -28,6 +30,18 @@ export class SuggestedItem {
         return (this.cache.term = SuggestedItem.decodeString(this.data.slice(start, end)));
     }
 
+    public get probability(): number | null /* f32 after the term, NaN when there is none */ {
+        if ('probability' in this.cache) {
+            return this.cache.probability as number | null;
+        }
+        const offset = 12 + readU32(this.data, this.ptr + 8);
+        // wasm builds before abi_version() 3 write no probability
+        const probability = offset + 4 <= this.length
+            ? new DataView(this.data.buffer, this.data.byteOffset).getFloat32(this.ptr + offset, true)
+            : NaN;
+        return (this.cache.probability = Number.isNaN(probability) ? null : probability);
+    }
+
     public toJSON(): Pick<SuggestedItem, 'count' | 'distance' | 'term'> {
         const {count, distance, term} = this;
         return {count, distance, term};
@@ -65,7 +79,7 @@ export function deserializeSuggestedItems(buffer: ArrayBufferLike, ptr: number,
         ptr += 4;
         // All suggested items share the same slice but the pointer
         // is updated to indicate where the property values start.
-        suggestedItems[i] = new SuggestedItem(rawSlice, ptr);
+        suggestedItems[i] = new SuggestedItem(rawSlice, ptr, itemLen);
         ptr += itemLen;
     }
 
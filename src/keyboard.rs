@@ -0,0 +1,95 @@
+use crate::soft_wx::weighted_levenshtein::WeightedCosts;
+
+/// A physical keyboard layout used to judge how plausible a substitution is.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum KeyboardLayout {
+    Qwerty,
+    Azerty,
+    Qwertz,
+}
+
+impl KeyboardLayout {
+    // the unshifted rows from top to bottom, each row offset by about half a key to the right
+    fn rows(self) -> [&'static str; 4] {
+        match self {
+            KeyboardLayout::Qwerty => ["1234567890", "qwertyuiop", "asdfghjkl", "zxcvbnm"],
+            KeyboardLayout::Azerty => ["1234567890", "azertyuiop", "qsdfghjklm", "wxcvbn"],
+            KeyboardLayout::Qwertz => ["1234567890", "qwertzuiop", "asdfghjkl", "yxcvbnm"],
+        }
+    }
+
+    fn position(self, key: char) -> Option<(usize, usize)> {
+        let key = key.to_lowercase().next()?;
+        self.rows().iter().enumerate()
+            .find_map(|(row, keys)| keys.chars().position(|k| k == key).map(|col| (row, col)))
+    }
+
+    /// Returns true when the keys are next to each other on the same row or
+    /// touch diagonally on the row above or below. Case is ignored.
+    ///
+    /// example:
+    /// assert!(KeyboardLayout::Qwerty.is_adjacent('e', 'w'));
+    /// assert!(!KeyboardLayout::Qwerty.is_adjacent('e', 'u'));
+    pub fn is_adjacent(self, key1: char, key2: char) -> bool {
+        let (row1, col1) = match self.position(key1) { Some(position) => position, None => return false };
+        let (row2, col2) = match self.position(key2) { Some(position) => position, None => return false };
+        if row1 == row2 {
+            return col1 + 1 == col2 || col2 + 1 == col1;
+        }
+        // each row sits half a key right of the one above, so a key touches the
+        // key above it and the one after that, and the key below it and the one before
+        let ((upper_row, upper_col), (lower_row, lower_col)) = if row1 < row2 { ((row1, col1), (row2, col2)) } else { ((row2, col2), (row1, col1)) };
+        lower_row == upper_row + 1 && (lower_col == upper_col || lower_col + 1 == upper_col)
+    }
+
+    /// Edit costs where substituting a key with one next to it costs `adjacent_cost`
+    /// and every other edit costs 1, e.g. for `DistanceAlgorithm::Weighted`.
+    pub fn weighted_costs(self, adjacent_cost: f64) -> WeightedCosts {
+        let keys: Vec<char> = self.rows().iter().flat_map(|row| row.chars()).collect();
+        let mut costs = WeightedCosts::new();
+        let mut buffer1 = [0; 4];
+        let mut buffer2 = [0; 4];
+        for &key1 in &keys {
+            for &key2 in &keys {
+                if self.is_adjacent(key1, key2) {
+                    costs = costs.with_substitution(key1.encode_utf8(&mut buffer1), key2.encode_utf8(&mut buffer2), adjacent_cost);
+                }
+            }
+        }
+        costs
+    }
+}
+
+#[cfg(test)]
+mod keyboard_tests {
+    use std::sync::Arc;
+
+    use crate::keyboard::KeyboardLayout;
+    use crate::soft_wx::weighted_levenshtein::WeightedLevenshtein;
+
+    #[test]
+    fn is_adjacent_test() {
+        let qwerty = KeyboardLayout::Qwerty;
+        for neighbour in "wrsd34".chars() {
+            assert!(qwerty.is_adjacent('e', neighbour), "{}", neighbour);
+            assert!(qwerty.is_adjacent(neighbour, 'e'), "{}", neighbour);
+        }
+        for far in "eaxu5".chars() {
+            assert!(!qwerty.is_adjacent('e', far), "{}", far);
+        }
+        assert!(qwerty.is_adjacent('H', 'n'));
+        assert!(!qwerty.is_adjacent('é', 'e'));
+
+        assert!(KeyboardLayout::Azerty.is_adjacent('a', 'z'));
+        assert!(!KeyboardLayout::Azerty.is_adjacent('a', 's'));
+        assert!(KeyboardLayout::Qwertz.is_adjacent('z', 't'));
+        assert!(KeyboardLayout::Qwertz.is_adjacent('y', 'a'));
+    }
+
+    #[test]
+    fn weighted_costs_test() {
+        let mut weighted = WeightedLevenshtein::new(Arc::new(KeyboardLayout::Qwerty.weighted_costs(0.5)));
+        assert_eq!(weighted.weighted_distance("hwllo", "hello"), 0.5);
+        assert_eq!(weighted.weighted_distance("hwllo", "hullo"), 1.0);
+    }
+}
@@ -7,6 +7,7 @@ mod tests {
 
     use crate::sym_spell::sym_spell::SymSpell;
     use crate::sym_spell::verbosity::Verbosity;
+    use crate::sym_spell::word_segmenter::WordSegmenter;
 
     const MAX_EDIT_DISTANCE:usize = 2;
     const PREFIX_LENGTH:usize = 7;
@@ -25,9 +26,9 @@ mod tests {
                     break;
                 }
                 if is_bigram {
-                    sym_spell.write_line_to_bigram_dictionary(&s, " ");
+                    sym_spell.write_line_to_bigram_dictionary(&s, " ").unwrap();
                 } else {
-                    sym_spell.write_line_to_dictionary(&s, " ");
+                    sym_spell.write_line_to_dictionary(&s, " ").unwrap();
                 }
 
                 s.truncate(0);
@@ -54,7 +55,7 @@ mod tests {
             if len == 0 {
                 break;
             }
-            sym_spell.write_line_to_dictionary(&s, " ");
+            sym_spell.write_line_to_dictionary(&s, " ").unwrap();
             s.truncate(0);
         }
 
@@ -64,12 +65,74 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn write_to_word_segmentation_test() -> Result<()> {
+        let mut sym_spell = SymSpell::new(Some(0), Some(PREFIX_LENGTH), None);
+        let f = File::open("lib/frequency_dictionary_en_82_765.txt")?;
+        for line in BufReader::new(f).lines() {
+            sym_spell.write_line_to_dictionary(&line?, " ").unwrap();
+        }
+
+        // the outputs of the reference SymSpell
+        let cases = [
+            ("thequickbrownfoxjumpsoverthelazydog", "the quick brown fox jumps over the lazy dog"),
+            ("itwasabrightcolddayinaprilandtheclockswerestrikingthirteen", "it was a bright cold day in april and the clocks were striking thirteen"),
+            ("itwasthebestoftimesitwastheworstoftimesitwastheageofwisdomitwastheageoffoolishness", "it was the best of times it was the worst of times it was the age of wisdom it was the age of foolishness"),
+        ];
+        for (input, expected) in cases.iter() {
+            let (segmented, corrected, distance_sum, _) = sym_spell.word_segmentation(input, 0, None);
+            assert_eq!(segmented, *expected);
+            assert_eq!(corrected, *expected);
+            assert_eq!(distance_sum, expected.matches(' ').count());
+        }
+
+        // a long document segmented chunk by chunk
+        let document = cases.iter().map(|(input, _)| *input).collect::<Vec<&str>>().concat().repeat(20);
+        let mut segmenter = WordSegmenter::new(&sym_spell, 0, None);
+        let mut segmented = String::new();
+        for chunk in document.as_bytes().chunks(50) {
+            segmented += &segmenter.push(std::str::from_utf8(chunk).unwrap()).0;
+        }
+        segmented += &segmenter.finish().0;
+        let expected = cases.iter().map(|(_, expected)| *expected).collect::<Vec<&str>>().join(" ");
+        assert_eq!(segmented, vec![expected; 20].join(" "));
+
+        Ok(())
+    }
+
+    #[test]
+    fn write_to_estimate_memory_test() -> Result<()> {
+        for max_edit_distance in 1..=3 {
+            let mut sym_spell = SymSpell::new(Some(max_edit_distance), Some(PREFIX_LENGTH), None);
+            let f = File::open("lib/frequency_dictionary_en_82_765.txt")?;
+            for line in BufReader::new(f).lines() {
+                sym_spell.write_line_to_dictionary(&line?, " ").unwrap();
+            }
+
+            // the estimate is within a quarter of what the loaded index takes
+            let allocated = sym_spell.allocated_size().unwrap() as f64;
+            let estimate = SymSpell::estimate_memory(max_edit_distance, PREFIX_LENGTH, sym_spell.word_count()) as f64;
+            assert!((estimate - allocated).abs() < allocated / 4.0, "{} {} {}", max_edit_distance, estimate, allocated);
+        }
+        Ok(())
+    }
 }
 
 pub mod grapheme_iterator;
+pub mod keyboard;
+pub mod markup;
+pub mod small_string;
+pub mod small_vec;
+pub mod tokenizer;
 pub mod utils;
 pub mod soft_wx;
 pub mod sym_spell;
 pub mod edit_distance;
+pub mod string_similarity;
+pub mod eval;
+pub mod filters;
 //#[cfg(target_arch = "wasm32")]
-pub mod spellchecker_wasm;
\ No newline at end of file
+pub mod spellchecker_wasm;
+#[cfg(feature = "wasm-bindgen")]
+pub mod bindgen;
@@ -0,0 +1,266 @@
+use std::ops::Range;
+
+/// The markup language of the text given to `SymSpell::check_text` and
+/// `SymSpell::document_stats`. The markup is skipped rather than stripped, so
+/// the byte ranges of misspelled words are positions in the original document.
+///
+/// example:
+/// let text = "Use `lookup()` or [the docs](https://exmaple.com).";
+///
+/// assert_eq!(Markup::Markdown.spans(text), vec![4..14, 28..49]);
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Markup {
+    /// Every byte is prose.
+    #[default]
+    PlainText,
+    /// Skips fenced code blocks, inline code, link and image destinations, link
+    /// reference definitions, autolinks and inline HTML.
+    Markdown,
+    /// Skips tags, comments, character references and the content of script,
+    /// style, code and pre elements.
+    Html,
+}
+
+// elements whose content is not prose
+const RAW_ELEMENTS: [&str; 4] = ["script", "style", "code", "pre"];
+
+impl Markup {
+    /// The byte ranges of the text that are markup rather than prose, in order.
+    pub fn spans(self, text: &str) -> Vec<Range<usize>> {
+        let mut spans = vec![];
+        match self {
+            Markup::PlainText => {}
+            Markup::Markdown => markdown_spans(text, &mut spans),
+            Markup::Html => inline_spans(text, 0..text.len(), false, &mut spans),
+        }
+        spans
+    }
+}
+
+// the fenced code blocks and link reference definitions line by line, and the inline
+// markup of the lines between them
+fn markdown_spans(text: &str, spans: &mut Vec<Range<usize>>) {
+    // the char and length of the fence of the open code block
+    let mut fence: Option<(u8, usize)> = None;
+    let mut prose_start = 0;
+    let mut line_start = 0;
+    for line in text.split_inclusive('\n') {
+        let line_range = line_start..line_start + line.len();
+        line_start = line_range.end;
+        let indent = line.len() - line.trim_start_matches(' ').len();
+        let rest = &line[indent..];
+        let run = |ch: u8| rest.bytes().take_while(|&byte| byte == ch).count();
+
+        if let Some((ch, len)) = fence {
+            if indent < 4 && run(ch) >= len && rest[run(ch)..].trim().is_empty() {
+                fence = None;
+            }
+            spans.push(line_range);
+            prose_start = line_start;
+            continue;
+        }
+        if indent >= 4 {
+            continue;
+        }
+        let opens_fence = [b'`', b'~'].iter().find_map(|&ch| {
+            let len = run(ch);
+            // the info string of a backtick fence holds no backticks
+            if len >= 3 && (ch == b'~' || !rest[len..].contains('`')) { Some((ch, len)) } else { None }
+        });
+        let is_reference_definition = rest.starts_with('[')
+            && rest.find("]:").is_some_and(|at| at > 1 && !rest[1..at].contains(']'));
+        if opens_fence.is_some() || is_reference_definition {
+            inline_spans(text, prose_start..line_range.start, true, spans);
+            spans.push(line_range.start + indent..line_range.start + line.trim_end().len());
+            prose_start = line_start;
+            fence = opens_fence;
+        }
+    }
+    inline_spans(text, prose_start..text.len(), true, spans);
+}
+
+// the tags and character references in text[range], along with the code spans
+// and link destinations in markdown
+fn inline_spans(text: &str, range: Range<usize>, markdown: bool, spans: &mut Vec<Range<usize>>) {
+    let bytes = text.as_bytes();
+    let end = range.end;
+    let mut i = range.start;
+    while i < end {
+        let span = match bytes[i] {
+            // an escaped char is literal
+            b'\\' if markdown => {
+                i += 2;
+                continue;
+            }
+            b'`' if markdown => {
+                let len = bytes[i..end].iter().take_while(|&&byte| byte == b'`').count();
+                match code_span_end(text, i + len, end, len) {
+                    Some(span_end) => i..span_end,
+                    None => {
+                        // a backtick run without a closing run is literal
+                        i += len;
+                        continue;
+                    }
+                }
+            }
+            b']' if markdown && bytes.get(i + 1) == Some(&b'(') => match link_destination_end(bytes, i + 1, end) {
+                Some(destination_end) => i + 1..destination_end,
+                None => {
+                    i += 1;
+                    continue;
+                }
+            },
+            b'<' => match tag_end(text, i, end) {
+                Some(tag_end) => i..tag_end,
+                None => {
+                    i += 1;
+                    continue;
+                }
+            },
+            b'&' => match reference_end(bytes, i, end) {
+                Some(reference_end) => i..reference_end,
+                None => {
+                    i += 1;
+                    continue;
+                }
+            },
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+        i = span.end;
+        spans.push(span);
+    }
+}
+
+// the end of the backtick run of the same length closing a code span, within the paragraph
+fn code_span_end(text: &str, start: usize, end: usize, len: usize) -> Option<usize> {
+    let bytes = text.as_bytes();
+    let paragraph_end = text[start..end].find("\n\n").map_or(end, |at| start + at);
+    let mut i = start;
+    while i < paragraph_end {
+        if bytes[i] != b'`' {
+            i += 1;
+            continue;
+        }
+        let run = bytes[i..paragraph_end].iter().take_while(|&&byte| byte == b'`').count();
+        if run == len {
+            return Some(i + run);
+        }
+        i += run;
+    }
+    None
+}
+
+// the end of the parenthesized destination of a link, "(url "title")"
+fn link_destination_end(bytes: &[u8], open: usize, end: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (i, &byte) in bytes.iter().enumerate().take(end).skip(open) {
+        match byte {
+            b'(' => depth += 1,
+            b')' if depth == 1 => return Some(i + 1),
+            b')' => depth -= 1,
+            b'\n' => return None,
+            _ => {}
+        }
+    }
+    None
+}
+
+// the end of the tag or comment starting at start, and of the content of a raw element
+fn tag_end(text: &str, start: usize, end: usize) -> Option<usize> {
+    let bytes = text.as_bytes();
+    if text[start..end].starts_with("<!--") {
+        return Some(text[start + 4..end].find("-->").map_or(end, |at| start + 4 + at + 3));
+    }
+    // "a < b" is prose
+    let first = *bytes.get(start + 1)?;
+    if !(first.is_ascii_alphabetic() || first == b'/' || first == b'!' || first == b'?') {
+        return None;
+    }
+    let mut quote = None;
+    let mut close = None;
+    for (i, &byte) in bytes.iter().enumerate().take(end).skip(start + 1) {
+        match (quote, byte) {
+            (None, b'"') | (None, b'\'') => quote = Some(byte),
+            (Some(open), _) if open == byte => quote = None,
+            (None, b'>') => {
+                close = Some(i + 1);
+                break;
+            }
+            _ => {}
+        }
+    }
+    let tag_end = close?;
+    let name = text[start + 1..tag_end].split(|ch: char| !ch.is_ascii_alphanumeric()).next().unwrap_or("").to_ascii_lowercase();
+    if !RAW_ELEMENTS.contains(&name.as_str()) || text[..tag_end].ends_with("/>") {
+        return Some(tag_end);
+    }
+    // the content of a raw element up to and including its end tag
+    let closing = format!("</{}", name);
+    let content_end = text[tag_end..end].to_ascii_lowercase().find(&closing).map_or(end, |at| tag_end + at);
+    Some(text[content_end..end].find('>').map_or(end, |at| content_end + at + 1))
+}
+
+// the end of a character reference such as "&amp;" or "&#x27;"
+fn reference_end(bytes: &[u8], start: usize, end: usize) -> Option<usize> {
+    let mut i = start + 1;
+    if bytes.get(i) == Some(&b'#') {
+        i += 1;
+    }
+    let name_start = i;
+    while i < end && i - name_start < 32 && bytes[i].is_ascii_alphanumeric() {
+        i += 1;
+    }
+    if i > name_start && i < end && bytes[i] == b';' { Some(i + 1) } else { None }
+}
+
+#[cfg(test)]
+mod markup_tests {
+    use crate::markup::Markup;
+
+    fn skipped(markup: Markup, text: &str) -> Vec<&str> {
+        markup.spans(text).into_iter().map(|span| &text[span]).collect()
+    }
+
+    #[test]
+    fn markdown_test() {
+        let text = "# Title\n\nRun `cargo tset` or ``a ` b``, see [docs](https://exmaple.com \"the (docs)\") and ![logo](logo.png).\n\
+                    \n```rust\nlet x = mispelled;\n```\nAn <https://auto.link> \\`not code\\` and <b>bold</b> &amp; `unclosed\n\n\
+                    [docs]: https://exmaple.com\n    indented code is prose\n~~~\nnever closed";
+        assert_eq!(skipped(Markup::Markdown, text), vec![
+            "`cargo tset`",
+            "``a ` b``",
+            "(https://exmaple.com \"the (docs)\")",
+            "(logo.png)",
+            "```rust",
+            "let x = mispelled;\n",
+            "```\n",
+            "<https://auto.link>",
+            "<b>",
+            "</b>",
+            "&amp;",
+            "[docs]: https://exmaple.com",
+            "~~~",
+            "never closed",
+        ]);
+        assert!(Markup::PlainText.spans(text).is_empty());
+    }
+
+    #[test]
+    fn html_test() {
+        let text = "<p class=\"a>b\">Helo <!-- a comment --> wrld&nbsp;&#39;</p><PRE>let  x</pre>a < b <script src=x>var y;</script>";
+        assert_eq!(skipped(Markup::Html, text), vec![
+            "<p class=\"a>b\">",
+            "<!-- a comment -->",
+            "&nbsp;",
+            "&#39;",
+            "</p>",
+            "<PRE>let  x</pre>",
+            "<script src=x>var y;</script>",
+        ]);
+        // markdown inline code is prose in HTML
+        assert!(Markup::Html.spans("`code`").is_empty());
+    }
+}
@@ -0,0 +1,91 @@
+use std::ops::Deref;
+use std::str;
+
+/// A string that keeps up to `N` bytes on the stack and only
+/// moves to the heap once it grows beyond that.
+///
+/// Used while generating deletes where the overwhelming majority
+/// of terms are short enough to never need an allocation.
+pub struct SmallString<const N: usize> {
+    inline: [u8; N],
+    len: usize,
+    heap: Option<String>,
+}
+
+impl<const N: usize> SmallString<N> {
+    pub fn new() -> SmallString<N> {
+        SmallString {
+            inline: [0; N],
+            len: 0,
+            heap: None,
+        }
+    }
+
+    pub fn push_str(&mut self, s: &str) {
+        if let Some(heap) = self.heap.as_mut() {
+            heap.push_str(s);
+            return;
+        }
+        let new_len = self.len + s.len();
+        if new_len <= N {
+            self.inline[self.len..new_len].copy_from_slice(s.as_bytes());
+            self.len = new_len;
+        } else {
+            let mut heap = String::with_capacity(new_len);
+            heap.push_str(self.as_str());
+            heap.push_str(s);
+            self.heap = Some(heap);
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self.heap.as_ref() {
+            Some(heap) => heap,
+            // only whole &str values are ever copied in
+            None => unsafe { str::from_utf8_unchecked(&self.inline[..self.len]) },
+        }
+    }
+
+    pub fn is_inline(&self) -> bool {
+        self.heap.is_none()
+    }
+
+    pub fn into_string(self) -> String {
+        match self.heap {
+            Some(heap) => heap,
+            None => String::from(self.as_str()),
+        }
+    }
+}
+
+impl<const N: usize> Default for SmallString<N> {
+    fn default() -> SmallString<N> {
+        SmallString::new()
+    }
+}
+
+impl<const N: usize> Deref for SmallString<N> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+#[cfg(test)]
+mod small_string_tests {
+    use crate::small_string::SmallString;
+
+    #[test]
+    fn push_str_test() {
+        let mut s: SmallString<8> = SmallString::new();
+        s.push_str("héllo");
+        assert!(s.is_inline());
+        assert_eq!(s.as_str(), "héllo");
+
+        s.push_str(" wörld");
+        assert!(!s.is_inline());
+        assert_eq!(&*s, "héllo wörld");
+        assert_eq!(s.into_string(), "héllo wörld");
+    }
+}
@@ -0,0 +1,100 @@
+use std::ops::Deref;
+
+/// A vec that keeps up to `N` items inline and only moves to the heap
+/// once it grows beyond that.
+///
+/// Used for the buckets of the deletes map where the overwhelming
+/// majority hold one or two words, so most never need an allocation.
+#[derive(Clone, Debug)]
+pub enum SmallVec<T: Copy + Default, const N: usize> {
+    Inline { items: [T; N], len: u8 },
+    Heap(Vec<T>),
+}
+
+impl<T: Copy + Default, const N: usize> SmallVec<T, N> {
+    pub fn new() -> SmallVec<T, N> {
+        SmallVec::Inline {
+            items: [T::default(); N],
+            len: 0,
+        }
+    }
+
+    pub fn push(&mut self, item: T) {
+        match self {
+            SmallVec::Inline { items, len } if (*len as usize) < N => {
+                items[*len as usize] = item;
+                *len += 1;
+            }
+            SmallVec::Inline { items, .. } => {
+                let mut heap = Vec::with_capacity(N * 2);
+                heap.extend_from_slice(items);
+                heap.push(item);
+                *self = SmallVec::Heap(heap);
+            }
+            SmallVec::Heap(heap) => heap.push(item),
+        }
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        match self {
+            SmallVec::Inline { items, len } => &items[..*len as usize],
+            SmallVec::Heap(heap) => heap,
+        }
+    }
+
+    pub fn is_inline(&self) -> bool {
+        matches!(self, SmallVec::Inline { .. })
+    }
+
+    /// The items allocated on the heap, 0 while they are kept inline.
+    pub fn heap_capacity(&self) -> usize {
+        match self {
+            SmallVec::Inline { .. } => 0,
+            SmallVec::Heap(heap) => heap.capacity(),
+        }
+    }
+}
+
+impl<T: Copy + Default, const N: usize> Default for SmallVec<T, N> {
+    fn default() -> SmallVec<T, N> {
+        SmallVec::new()
+    }
+}
+
+impl<T: Copy + Default, const N: usize> Deref for SmallVec<T, N> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T: Copy + Default + PartialEq, const N: usize> PartialEq for SmallVec<T, N> {
+    fn eq(&self, other: &SmallVec<T, N>) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+#[cfg(test)]
+mod small_vec_tests {
+    use crate::small_vec::SmallVec;
+
+    #[test]
+    fn push_test() {
+        let mut v: SmallVec<u32, 2> = SmallVec::new();
+        assert!(v.is_empty());
+        v.push(1);
+        v.push(2);
+        assert!(v.is_inline());
+        assert_eq!(v.as_slice(), &[1, 2]);
+
+        v.push(3);
+        assert!(!v.is_inline());
+        assert_eq!(&*v, &[1, 2, 3]);
+
+        // equal items are equal whether inline or not
+        let mut inline: SmallVec<u32, 2> = SmallVec::new();
+        inline.push(1);
+        assert_eq!(inline, SmallVec::Heap(vec![1]));
+    }
+}
@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::soft_wx::{Distance, Similarity};
+use crate::soft_wx::helpers::{graphemes, null_distance_results, similarity_from_distance, trivial_distance, PreparedQuery};
+
+pub struct Damerau {
+    costs: Vec<usize>,
+}
+
+/// <summary>
+/// Class providing methods for computing the unrestricted Damerau-Levenshtein
+/// distance between two strings.
+/// </summary>
+/// <remarks>
+/// Unlike the optimal string alignment variant (DamaerauOSA), a substring may be
+/// edited again after it has been transposed, so "ca" and "abc" have a distance
+/// of 2 (ca -> ac -> abc) rather than 3. This is the Lowrance-Wagner algorithm which
+/// keeps the row each grapheme last occurred in to find the transposition to extend.
+/// See https://en.wikipedia.org/wiki/Damerau%E2%80%93Levenshtein_distance
+/// The methods in this class are not threadsafe.</remarks>
+impl Damerau {
+    /// <summary>Create a new instance of Damerau.</summary>
+    pub fn new() -> Damerau {
+        Damerau {
+            costs: vec![]
+        }
+    }
+
+    /// <summary>Internal implementation of the core Damerau-Levenshtein algorithm.</summary>
+    /// <remarks>The cost matrix has an extra leading row and column holding a value larger
+    /// than any real distance so transpositions never reach outside of the strings.</remarks>
+    fn core_damerau<T: Copy + Eq + Hash>(string1: &[T], string2: &[T], costs: &mut Vec<usize>) -> usize {
+        let len1 = string1.len();
+        let len2 = string2.len();
+        let width = len2 + 2;
+        let infinity = len1 + len2;
+
+        costs.clear();
+        costs.resize((len1 + 2) * width, 0);
+        costs[0] = infinity;
+        for i in 0..=len1 {
+            costs[(i + 1) * width] = infinity;
+            costs[(i + 1) * width + 1] = i;
+        }
+        for j in 0..=len2 {
+            costs[j + 1] = infinity;
+            costs[width + j + 1] = j;
+        }
+
+        // the last row (1 based) in which each grapheme of string1 was seen
+        let mut last_row: HashMap<T, usize> = HashMap::new();
+        for i in 1..=len1 {
+            let char1 = string1[i - 1];
+            // the last column (1 based) in this row where the graphemes matched
+            let mut last_match_col = 0;
+            for j in 1..=len2 {
+                let char2 = string2[j - 1];
+                let transposed_row = last_row.get(&char2).cloned().unwrap_or(0);
+                let transposed_col = last_match_col;
+                let cost = if char1 == char2 {
+                    last_match_col = j;
+                    0
+                } else {
+                    1
+                };
+                let substitution = costs[i * width + j] + cost;
+                let insertion = costs[(i + 1) * width + j] + 1;
+                let deletion = costs[i * width + j + 1] + 1;
+                let transposition = costs[transposed_row * width + transposed_col]
+                    + (i - transposed_row - 1) + 1 + (j - transposed_col - 1);
+                costs[(i + 1) * width + j + 1] = substitution.min(insertion).min(deletion).min(transposition);
+            }
+            last_row.insert(char1, i);
+        }
+        costs[(len1 + 1) * width + len2 + 1]
+    }
+
+    // distance over the bytes of ASCII strings or the graphemes of any others
+    fn damerau<T: Copy + Eq + Hash>(&mut self, string1: &[T], string2: &[T], max_distance: usize) -> Option<usize> {
+        if string1.is_empty() || string2.is_empty() {
+            let distance = string1.len().max(string2.len());
+            return if distance <= max_distance { Some(distance) } else { None };
+        }
+        let len_diff = if string1.len() > string2.len() { string1.len() - string2.len() } else { string2.len() - string1.len() };
+        if len_diff > max_distance {
+            return None;
+        }
+
+        let distance = Damerau::core_damerau(string1, string2, &mut self.costs);
+        if distance <= max_distance { Some(distance) } else { None }
+    }
+}
+
+impl Default for Damerau {
+    fn default() -> Damerau {
+        Damerau::new()
+    }
+}
+
+impl Similarity for Damerau {
+    /// <summary>Return Damerau-Levenshtein similarity between two strings
+    /// (1 - (damerau distance / len of longer string)).</summary>
+    /// <param name="string1">One of the strings to compare.</param>
+    /// <param name="string2">The other string to compare.</param>
+    /// <returns>The degree of similarity 0 to 1.0, where 0 represents a lack of any
+    /// notable similarity, and 1 represents equivalent strings.</returns>
+    fn similarity<'a>(&mut self, string1: &'a str, string2: &'a str) -> Option<f64> {
+        similarity_from_distance(self, string1, string2, None)
+    }
+
+    /// <summary>Return Damerau-Levenshtein similarity between two strings
+    /// (1 - (damerau distance / len of longer string)).</summary>
+    /// <param name="string1">One of the strings to compare.</param>
+    /// <param name="string2">The other string to compare.</param>
+    /// <param name="minSimilarity">The minimum similarity that is of interest.</param>
+    /// <returns>None if the similarity is lower than minSimilarity, otherwise, a
+    /// number between 0 and 1.0 where 0 represents a lack of any noteable
+    /// similarity, and 1 represents equivalent strings.</returns>
+    fn similarity2<'a>(&mut self, string1: &'a str, string2: &'a str, min_similarity: f64) -> Option<f64> {
+        similarity_from_distance(self, string1, string2, Some(min_similarity))
+    }
+}
+
+impl Distance for Damerau {
+    /// <summary>Compute and return the Damerau-Levenshtein edit distance between two strings.</summary>
+    /// <remarks>This method is not threadsafe.</remarks>
+    /// <param name="string1">One of the strings to compare.</param>
+    /// <param name="string2">The other string to compare.</param>
+    /// <returns>0 if the strings are equivalent, otherwise a positive number whose
+    /// magnitude increases as difference between the strings increases.</returns>
+    fn distance<'a>(&mut self, string1: &'a str, string2: &'a str) -> Option<usize> {
+        if string1.is_ascii() && string2.is_ascii() {
+            self.damerau(string1.as_bytes(), string2.as_bytes(), usize::MAX)
+        } else {
+            self.damerau(&graphemes(string1), &graphemes(string2), usize::MAX)
+        }
+    }
+
+    /// <summary>Compute and return the Damerau-Levenshtein edit distance between two strings.</summary>
+    /// <remarks>This method is not threadsafe.</remarks>
+    /// <param name="string1">One of the strings to compare.</param>
+    /// <param name="string2">The other string to compare.</param>
+    /// <param name="maxDistance">The maximum distance that is of interest.</param>
+    /// <returns>None if the distance is greater than the maxDistance, 0 if the strings
+    /// are equivalent, otherwise a positive number whose magnitude increases as
+    /// difference between the strings increases.</returns>
+    fn distance2<'a>(&mut self, string1: &'a str, string2: &'a str, max_distance: usize) -> Option<usize> {
+        if string1.is_empty() || string2.is_empty() {
+            return null_distance_results(string1, string2, max_distance);
+        }
+
+        if max_distance == 0 {
+            return if string1 == string2 { Some(0) } else { None };
+        }
+
+        if string1.is_ascii() && string2.is_ascii() {
+            self.damerau(string1.as_bytes(), string2.as_bytes(), max_distance)
+        } else {
+            self.damerau(&graphemes(string1), &graphemes(string2), max_distance)
+        }
+    }
+
+    /// <summary>Damerau-Levenshtein distance2 of a query split once.</summary>
+    fn distance_prepared(&mut self, query: &PreparedQuery, string2: &str, max_distance: Option<usize>) -> Option<usize> {
+        let max_distance = max_distance.unwrap_or(usize::MAX);
+        if let Some(distance) = trivial_distance(query.text(), string2, max_distance) {
+            return distance;
+        }
+        if query.text().is_ascii() && string2.is_ascii() {
+            self.damerau(query.text().as_bytes(), string2.as_bytes(), max_distance)
+        } else {
+            self.damerau(query.graphemes(), &graphemes(string2), max_distance)
+        }
+    }
+}
+
+#[cfg(test)]
+mod damerau_tests {
+    use crate::soft_wx::{Distance, Similarity};
+    use crate::soft_wx::damerau::Damerau;
+    use crate::soft_wx::damerau_osa::DamaerauOSA;
+    use crate::soft_wx::levensthtein::Levenshtein;
+    use crate::soft_wx::test_strings::random_strings;
+
+    #[test]
+    fn distance_test() {
+        let mut damerau = Damerau::new();
+        let mut osa = DamaerauOSA::new();
+        assert_eq!(damerau.distance("ca", "abc"), Some(2));
+        assert_eq!(osa.distance("ca", "abc"), Some(3));
+        assert_eq!(damerau.distance("abcdef", "abdcef"), Some(1));
+        assert_eq!(damerau.distance("🚀é", "é🚀x"), Some(2));
+        assert_eq!(damerau.distance("", "abc"), Some(3));
+        assert_eq!(damerau.distance2("ca", "abc", 1), None);
+        assert_eq!(damerau.distance2("ca", "abc", 2), Some(2));
+        assert_eq!(damerau.distance2("abc", "abc", 0), Some(0));
+    }
+
+    #[test]
+    fn distance_bounds_test() {
+        let mut damerau = Damerau::new();
+        let mut osa = DamaerauOSA::new();
+        let mut levenshtein = Levenshtein::new();
+        let strings = random_strings(50, 8);
+        for string1 in &strings {
+            for string2 in &strings {
+                let distance = damerau.distance(string1, string2).unwrap();
+                // never more than the restricted variants and always symmetric
+                assert!(distance <= osa.distance(string1, string2).unwrap(), "{} {}", string1, string2);
+                assert!(distance <= levenshtein.distance(string1, string2).unwrap(), "{} {}", string1, string2);
+                assert_eq!(damerau.distance(string2, string1), Some(distance), "{} {}", string1, string2);
+                for max_distance in 0..4 {
+                    let bounded = if distance <= max_distance { Some(distance) } else { None };
+                    assert_eq!(damerau.distance2(string1, string2, max_distance), bounded, "{} {} {}", string1, string2, max_distance);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn similarity_test() {
+        let mut damerau = Damerau::new();
+        assert_eq!(damerau.similarity("", ""), Some(1.0));
+        assert_eq!(damerau.similarity("abcd", "abdc"), Some(0.75));
+        assert_eq!(damerau.similarity2("abcd", "abdc", 0.8), None);
+    }
+}
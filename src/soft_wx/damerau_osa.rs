@@ -2,15 +2,14 @@
 // Released under the MIT License the text of which appears at the end of this file.
 // <authors> Steve Hatchett
 
-use std::collections::HashMap;
+use std::mem;
 
-use crate::grapheme_iterator::GraphemeClusters;
 use crate::soft_wx::{Distance, Similarity};
-use crate::soft_wx::helpers::{distance, null_distance_results, null_similarity_results, prefix_suffix_prep, similarity};
+use crate::soft_wx::helpers::{graphemes, null_distance_results, prefix_suffix_prep, similarity_from_distance, trivial_distance, PreparedQuery};
 
 pub struct DamaerauOSA {
-    base_char1_costs: HashMap<usize, usize>,
-    base_prev_char1_costs: HashMap<usize, usize>,
+    base_char1_costs: Vec<usize>,
+    base_prev_char1_costs: Vec<usize>,
 }
 
 /// <summary>
@@ -47,39 +46,43 @@ impl DamaerauOSA {
     /// <summary>Create a new instance of DamerauOSA.</summary>
     pub fn new() -> DamaerauOSA {
         DamaerauOSA {
-            base_char1_costs: HashMap::new(),
-            base_prev_char1_costs: HashMap::new(),
+            base_char1_costs: Vec::new(),
+            base_prev_char1_costs: Vec::new(),
         }
     }
 
     /// <summary>Internal implementation of the core Damerau-Levenshtein, optimal string alignment algorithm.</summary>
     /// <remarks>https://github.com/softwx/SoftWx.Match</remarks>
-    fn core_damerau_levenshtein(string1: &str, string2: &str, len1: usize, len2: usize, start: usize, char1_costs: &mut HashMap<usize, usize>, prev_char1_costs: &mut HashMap<usize, usize>) -> Option<usize> {
-        for j in 0..len2 {
-            char1_costs.insert(j, j + 1);
+    fn core_damerau_levenshtein<T: Copy + PartialEq>(string1: &[T], string2: &[T], len1: usize, len2: usize, start: usize, char1_costs: &mut Vec<usize>, prev_char1_costs: &mut Vec<usize>) -> Option<usize> {
+        // the rows are reused across calls, growing only when a longer string is seen
+        if char1_costs.len() < len2 {
+            char1_costs.resize(len2, 0);
+            prev_char1_costs.resize(len2, 0);
+        }
+        for (j, cost) in char1_costs[..len2].iter_mut().enumerate() {
+            *cost = j + 1;
         }
 
-        let mut char1 = " ";
+        // the previous graphemes start out as placeholders that are never compared
+        let mut char1 = string1[start];
         let mut current_cost = 0;
-        let string1_gc = GraphemeClusters::new(string1);
-        let string2_gc = GraphemeClusters::new(string2);
         for i in 0..len1 {
             let prev_char1 = char1;
-            char1 = &string1_gc[start + i];
-            let mut char2 = " ";
+            char1 = string1[start + i];
+            let mut char2 = string2[start];
             let mut left_char_cost = i;
             let mut above_char_cost = i;
             let mut next_trans_cost = 0;
 
             for j in 0..len2 {
                 let this_trans_cost = next_trans_cost;
-                next_trans_cost = *prev_char1_costs.entry(j).or_insert(0);
+                next_trans_cost = prev_char1_costs[j];
                 current_cost = left_char_cost;
-                prev_char1_costs.insert(j, current_cost); // cost of diagonal (substitution)
-                left_char_cost = char1_costs[&j]; // left now equals current cost (which will be diagonal at next iteration)
+                prev_char1_costs[j] = current_cost; // cost of diagonal (substitution)
+                left_char_cost = char1_costs[j]; // left now equals current cost (which will be diagonal at next iteration)
 
                 let prev_char2 = char2;
-                char2 = &string2_gc[start + j];
+                char2 = string2[start + j];
                 if char1 != char2 {
                     // substitution if neither of two conditions below
                     if above_char_cost < current_cost {
@@ -89,44 +92,47 @@ impl DamaerauOSA {
                         current_cost = left_char_cost; // insertion
                     }
 
-                    current_cost = current_cost + 1;
+                    current_cost += 1;
 
                     if i != 0 && j != 0 && char1 == prev_char2 && prev_char1 == char2 && this_trans_cost + 1 < current_cost {
                         current_cost = this_trans_cost + 1; // transposition
                     }
                 }
                 above_char_cost = current_cost;
-                char1_costs.insert(j, above_char_cost);
+                char1_costs[j] = above_char_cost;
             }
         }
 
         Some(current_cost)
     }
 
-    fn core_damerau_levenshtein2(string1: &str, string2: &str, len1: usize, len2: usize, start: usize, max_distance: usize, char1_costs: &mut HashMap<usize, usize>, prev_char1_costs: &mut HashMap<usize, usize>) -> Option<usize> {
-        for j in 0..max_distance {
-            char1_costs.insert(j, j + 1);
+    #[allow(clippy::too_many_arguments)]
+    fn core_damerau_levenshtein2<T: Copy + PartialEq>(string1: &[T], string2: &[T], len1: usize, len2: usize, start: usize, max_distance: usize, char1_costs: &mut Vec<usize>, prev_char1_costs: &mut Vec<usize>) -> Option<usize> {
+        // the rows are reused across calls, growing only when a longer string is seen
+        if char1_costs.len() < len2 {
+            char1_costs.resize(len2, 0);
+            prev_char1_costs.resize(len2, 0);
+        }
+        for (j, cost) in char1_costs[..max_distance].iter_mut().enumerate() {
+            *cost = j + 1;
         }
 
         if max_distance < len2 {
-            for k in max_distance..len2 {
-                char1_costs.insert(k, max_distance + 1);
-            }
+            char1_costs[max_distance..len2].fill(max_distance + 1);
         }
 
         let len_diff = len2 - len1;
         let j_offset = max_distance as i32 - len_diff as i32;
         let mut j_start = 0;
         let mut j_end = max_distance;
-        let mut char1 = " ";
+        // the previous graphemes start out as placeholders that are never compared
+        let mut char1 = string1[start];
         let mut current_cost = 0;
 
-        let string1_gc = GraphemeClusters::new(string1);
-        let string2_gc = GraphemeClusters::new(string2);
         for i in 0..len1 {
             let prev_char1 = char1;
-            char1 = &string1_gc[start + i];
-            let mut char2 = " ";
+            char1 = string1[start + i];
+            let mut char2 = string2[start];
             let mut left_char_cost = i;
             let mut above_char_cost = i;
             let mut next_trans_cost = 0;
@@ -142,12 +148,12 @@ impl DamaerauOSA {
 
             for j in j_start..j_end {
                 let this_trans_cost = next_trans_cost;
-                next_trans_cost = *prev_char1_costs.entry(j).or_insert(0);
+                next_trans_cost = prev_char1_costs[j];
                 current_cost = left_char_cost;
-                prev_char1_costs.insert(j, current_cost); // cost on diagonal (substitution)
-                left_char_cost = char1_costs[&j]; // left now equals current cost (which will be diagonal at next iteration)
+                prev_char1_costs[j] = current_cost; // cost on diagonal (substitution)
+                left_char_cost = char1_costs[j]; // left now equals current cost (which will be diagonal at next iteration)
                 let prev_char2 = char2;
-                char2 = &string2_gc[start + j];
+                char2 = string2[start + j];
                 if char1 != char2 {
                     // substitution if neither of two conditions below
                     if above_char_cost < current_cost {
@@ -157,105 +163,96 @@ impl DamaerauOSA {
                         current_cost = left_char_cost
                     }
                     current_cost += 1;
-                    if i != 0 && j != 0 && char1 == prev_char2 && prev_char1 == char2 && this_trans_cost + 1 < current_cost {
+                    if i != 0 && j != j_start && char1 == prev_char2 && prev_char1 == char2 && this_trans_cost + 1 < current_cost {
                         current_cost = this_trans_cost + 1;
                     }
                 }
                 above_char_cost = current_cost;
-                char1_costs.insert(j, above_char_cost);
+                char1_costs[j] = above_char_cost;
             }
-            if char1_costs[&(i + len_diff)] > max_distance {
+            if char1_costs[i + len_diff] > max_distance {
                 return None;
             }
         }
-        return if current_cost <= max_distance { Some(current_cost) } else { None };
+        if current_cost <= max_distance { Some(current_cost) } else { None }
     }
-}
 
-impl Similarity for DamaerauOSA {
-    /// <summary>Return Damerau-Levenshtein optimal string alignment similarity
-    /// between two strings (1 - (damerau distance / len of longer string)).</summary>
-    /// <param name="string1">One of the strings to compare.</param>
-    /// <param name="string2">The other string to compare.</param>
-    /// <returns>The degree of similarity 0 to 1.0, where 0 represents a lack of any
-    /// noteable similarity, and 1 represents equivalent strings.</returns>
-    fn similarity<'a>(&mut self, mut string1: &'a str, mut string2: &'a str) -> Option<f64> {
-        let str1_len = GraphemeClusters::new(string1).len();
+    // distance over the bytes of ASCII strings or the graphemes of any others
+    fn damerau_osa<'a, T: Copy + PartialEq>(&mut self, mut string1: &'a [T], mut string2: &'a [T]) -> Option<usize> {
         if string1.is_empty() {
-            return Some(str1_len as f64);
+            return Some(string2.len());
         }
-
-        let mut str2_len = GraphemeClusters::new(string2).len();
         if string2.is_empty() {
-            return Some(str2_len as f64);
+            return Some(string1.len());
         }
 
-        if str1_len > str2_len {
-            let s = string1;
-            string1 = string2;
-            string2 = s;
-
-            str2_len = str1_len;
+        // if strings of different lengths, ensure shorter string is in string1. This can result in a little
+        // faster speed by spending more time spinning just the inner loop during the main processing.
+        if string1.len() > string2.len() {
+            mem::swap(&mut string1, &mut string2);
         }
 
         let (len1, len2, start) = prefix_suffix_prep(string1, string2);
+
         if len1 == 0 {
-            return Some(1.0);
+            return Some(len2);
         }
 
-        let distance = DamaerauOSA::core_damerau_levenshtein(string1, string2, len1, len2, start, &mut self.base_char1_costs, &mut self.base_prev_char1_costs);
-        if distance.is_some() {
-            return similarity(distance.unwrap() as f64, str2_len as f64);
-        }
-        None
+        DamaerauOSA::core_damerau_levenshtein(string1, string2, len1, len2, start, &mut self.base_char1_costs, &mut self.base_prev_char1_costs)
     }
 
-    /// <summary>Return Damerau-Levenshtein optimal string alignment similarity
-    /// between two strings (1 - (damerau distance / len of longer string)).</summary>
-    /// <param name="string1">One of the strings to compare.</param>
-    /// <param name="string2">The other string to compare.</param>
-    /// <param name="minSimilarity">The minimum similarity that is of interest.</param>
-    /// <returns>The degree of similarity 0 to 1.0, where -1 represents a similarity
-    /// lower than minSimilarity, otherwise, a number between 0 and 1.0 where 0
-    /// represents a lack of any noteable similarity, and 1 represents equivalent
-    /// strings.</returns>
-    fn similarity2<'a>(&mut self, string1: &'a str, string2: &'a str, min_similarity: f64) -> Option<f64> {
-        assert_eq!((0.0..1.0).contains(&min_similarity), true);
-
-        if string1.is_empty() || string2.is_empty() {
-            return null_similarity_results(string1, string2, min_similarity);
-        }
-
+    fn damerau_osa2<'a, T: Copy + PartialEq>(&mut self, mut string1: &'a [T], mut string2: &'a [T], max_distance: usize) -> Option<usize> {
         // if strings of different lengths, ensure shorter string is in string1. This can result in a little
         // faster speed by spending more time spinning just the inner loop during the main processing.
-        let str1_len = GraphemeClusters::new(string1).len();
-        let str2_len = GraphemeClusters::new(string2).len();
+        let str1_len = string1.len();
+        let str2_len = string2.len();
 
-        let max_distance = distance(min_similarity, str2_len);
-        if str2_len - str1_len > max_distance {
-            return None;
+        if str1_len > str2_len {
+            mem::swap(&mut string1, &mut string2);
         }
 
-        if max_distance <= 0 {
-            return if string1 == string2 { Some(1.0) } else { None };
+        if str2_len > str1_len && str2_len - str1_len > max_distance {
+            return None;
         }
 
         let (len1, len2, start) = prefix_suffix_prep(string1, string2);
-
         if len1 == 0 {
-            return Some(1.0);
+            return if len2 <= max_distance { Some(len2) } else { None };
         }
-        let distance =
         if max_distance < len2 {
-            DamaerauOSA::core_damerau_levenshtein2(string1, string2, len1, len2, start, max_distance, &mut self.base_char1_costs, &mut self.base_prev_char1_costs)
-        } else {
-            DamaerauOSA::core_damerau_levenshtein(string1, string2, len1, len2, start, &mut self.base_char1_costs, &mut self.base_prev_char1_costs)
-        };
-
-        if distance.is_some() {
-            return similarity(distance.unwrap() as f64, str2_len as f64);
+            return DamaerauOSA::core_damerau_levenshtein2(string1, string2, len1, len2, start, max_distance, &mut self.base_char1_costs, &mut self.base_prev_char1_costs);
         }
-        None
+        DamaerauOSA::core_damerau_levenshtein(string1, string2, len1, len2, start, &mut self.base_char1_costs, &mut self.base_prev_char1_costs)
+    }
+}
+
+impl Default for DamaerauOSA {
+    fn default() -> DamaerauOSA {
+        DamaerauOSA::new()
+    }
+}
+
+impl Similarity for DamaerauOSA {
+    /// <summary>Return Damerau-Levenshtein optimal string alignment similarity
+    /// between two strings (1 - (damerau distance / len of longer string)).</summary>
+    /// <param name="string1">One of the strings to compare.</param>
+    /// <param name="string2">The other string to compare.</param>
+    /// <returns>The degree of similarity 0 to 1.0, where 0 represents a lack of any
+    /// noteable similarity, and 1 represents equivalent strings.</returns>
+    fn similarity<'a>(&mut self, string1: &'a str, string2: &'a str) -> Option<f64> {
+        similarity_from_distance(self, string1, string2, None)
+    }
+
+    /// <summary>Return Damerau-Levenshtein optimal string alignment similarity
+    /// between two strings (1 - (damerau distance / len of longer string)).</summary>
+    /// <param name="string1">One of the strings to compare.</param>
+    /// <param name="string2">The other string to compare.</param>
+    /// <param name="minSimilarity">The minimum similarity that is of interest.</param>
+    /// <returns>None if the similarity is lower than minSimilarity, otherwise, a
+    /// number between 0 and 1.0 where 0 represents a lack of any noteable
+    /// similarity, and 1 represents equivalent strings.</returns>
+    fn similarity2<'a>(&mut self, string1: &'a str, string2: &'a str, min_similarity: f64) -> Option<f64> {
+        similarity_from_distance(self, string1, string2, Some(min_similarity))
     }
 }
 
@@ -268,31 +265,12 @@ impl Distance for DamaerauOSA {
     /// <param name="string2">The other string to compare.</param>
     /// <returns>0 if the strings are equivalent, otherwise a positive number whose
     /// magnitude increases as difference between the strings increases.</returns>
-    fn distance<'a>(&mut self, mut string1: &'a str, mut string2: &'a str) -> Option<usize> {
-        let str2_len = GraphemeClusters::new(string2).len();
-        if string1.is_empty() {
-            return Some(str2_len);
-        }
-        let str1_len = GraphemeClusters::new(string1).len();
-        if string2.is_empty() {
-            return Some(str1_len);
-        }
-
-        // if strings of different lengths, ensure shorter string is in string1. This can result in a little
-        // faster speed by spending more time spinning just the inner loop during the main processing.
-        if str1_len > str2_len {
-            let s = string1;
-            string1 = string2;
-            string2 = s;
-        }
-
-        let (len1, len2, start) = prefix_suffix_prep(string1, string2);
-
-        if len1 == 0 {
-            return Some(len2);
+    fn distance<'a>(&mut self, string1: &'a str, string2: &'a str) -> Option<usize> {
+        if string1.is_ascii() && string2.is_ascii() {
+            self.damerau_osa(string1.as_bytes(), string2.as_bytes())
+        } else {
+            self.damerau_osa(&graphemes(string1), &graphemes(string2))
         }
-
-        return DamaerauOSA::core_damerau_levenshtein(string1, string2, len1, len2, start, &mut self.base_char1_costs, &mut self.base_prev_char1_costs);
     }
 
     /// <summary>Compute and return the Damerau-Levenshtein optimal string
@@ -305,36 +283,72 @@ impl Distance for DamaerauOSA {
     /// <returns>-1 if the distance is greater than the maxDistance, 0 if the strings
     /// are equivalent, otherwise a positive number whose magnitude increases as
     /// difference between the strings increases.</returns>
-    fn distance2<'a>(&mut self, mut string1: &'a str, mut string2: &'a str, max_distance: usize) -> Option<usize> {
+    fn distance2<'a>(&mut self, string1: &'a str, string2: &'a str, max_distance: usize) -> Option<usize> {
         if string1.is_empty() || string2.is_empty() {
             return null_distance_results(string1, string2, max_distance);
         }
-        if max_distance <= 0 {
+        if max_distance == 0 {
             return if string1 == string2 { Some(0) } else { None };
         }
 
-        // if strings of different lengths, ensure shorter string is in string1. This can result in a little
-        // faster speed by spending more time spinning just the inner loop during the main processing.
-        let str1_len = GraphemeClusters::new(string1).len();
-        let str2_len = GraphemeClusters::new(string2).len();
-
-        if str1_len > str2_len {
-            let s = string1;
-            string1 = string2;
-            string2 = s;
+        if string1.is_ascii() && string2.is_ascii() {
+            self.damerau_osa2(string1.as_bytes(), string2.as_bytes(), max_distance)
+        } else {
+            self.damerau_osa2(&graphemes(string1), &graphemes(string2), max_distance)
         }
+    }
 
-        if str2_len > str1_len && str2_len - str1_len > max_distance {
-            return None;
+    /// <summary>Damerau-Levenshtein optimal string alignment distance2 of a query split once.</summary>
+    fn distance_prepared(&mut self, query: &PreparedQuery, string2: &str, max_distance: Option<usize>) -> Option<usize> {
+        let max_distance = max_distance.unwrap_or(usize::MAX);
+        if let Some(distance) = trivial_distance(query.text(), string2, max_distance) {
+            return distance;
         }
-
-        let (len1, len2, start) = prefix_suffix_prep(string1, string2);
-        if len1 == 0 {
-            return if len2 <= max_distance { Some(len2) } else { None };
+        if query.text().is_ascii() && string2.is_ascii() {
+            self.damerau_osa2(query.text().as_bytes(), string2.as_bytes(), max_distance)
+        } else {
+            self.damerau_osa2(query.graphemes(), &graphemes(string2), max_distance)
         }
-        if max_distance < len2 {
-            return DamaerauOSA::core_damerau_levenshtein2(string1, string2, len1, len2, start, max_distance, &mut self.base_char1_costs, &mut self.base_prev_char1_costs);
+    }
+}
+#[cfg(test)]
+mod damerau_osa_tests {
+    use crate::soft_wx::Distance;
+    use crate::soft_wx::damerau_osa::DamaerauOSA;
+    use crate::soft_wx::test_strings::random_strings;
+
+    // textbook O(n*m) optimal string alignment used as the reference implementation
+    fn reference_distance(string1: &str, string2: &str) -> usize {
+        let a: Vec<char> = string1.chars().collect();
+        let b: Vec<char> = string2.chars().collect();
+        let mut d: Vec<Vec<usize>> = (0..=a.len()).map(|i| (0..=b.len()).map(|j| if i == 0 { j } else if j == 0 { i } else { 0 }).collect()).collect();
+        for i in 1..=a.len() {
+            for j in 1..=b.len() {
+                let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+                d[i][j] = (d[i - 1][j] + 1).min(d[i][j - 1] + 1).min(d[i - 1][j - 1] + cost);
+                if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                    d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+                }
+            }
         }
-        return DamaerauOSA::core_damerau_levenshtein(string1, string2, len1, len2, start, &mut self.base_char1_costs, &mut self.base_prev_char1_costs);
+        d[a.len()][b.len()]
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn distance_matches_reference_test() {
+        // a single instance so the reused cost rows carry over between comparisons
+        let mut damerau_osa = DamaerauOSA::new();
+        let strings = random_strings(60, 8);
+        for string1 in &strings {
+            for string2 in &strings {
+                let expected = reference_distance(string1, string2);
+                assert_eq!(damerau_osa.distance(string1, string2), Some(expected), "{} {}", string1, string2);
+                for max_distance in 0..4 {
+                    let bounded = if expected <= max_distance { Some(expected) } else { None };
+                    assert_eq!(damerau_osa.distance2(string1, string2, max_distance), bounded, "{} {} {}", string1, string2, max_distance);
+                }
+            }
+        }
+        assert_eq!(damerau_osa.distance("ca", "abc"), Some(3));
+    }
+}
@@ -3,14 +3,15 @@
 // <authors> Steve Hatchett
 
 use crate::grapheme_iterator::GraphemeClusters;
+use crate::soft_wx::Distance;
 
 /// <summary>Determines the proper return value of an edit distance function when one or
 /// both strings are null.</summary>
 pub fn null_distance_results(string1: &str, string2: &str, max_distance: usize) -> Option<usize> {
     let gc2 = GraphemeClusters::new(string2);
-    if string1 == "" {
+    if string1.is_empty() {
         let str2_len = gc2.len();
-        if string2 == "" {
+        if string2.is_empty() {
             return Some(0);
         } else if str2_len <= max_distance {
             return Some(str2_len);
@@ -19,7 +20,7 @@ pub fn null_distance_results(string1: &str, string2: &str, max_distance: usize)
     }
     let gc1 = GraphemeClusters::new(string1);
     let str1_len = gc1.len();
-    return if str1_len <= max_distance { Some(str1_len) } else { None };
+    if str1_len <= max_distance { Some(str1_len) } else { None }
 }
 
 /// <summary>Determines the proper return value of a similarity function when one or
@@ -28,27 +29,68 @@ pub fn null_similarity_results(string1: &str, string2: &str, min_similarity: f64
     if string1.is_empty() && string2.is_empty() {
         return Some(1.0);
     }
-    return if min_similarity >= 0.0 { Some(0.0) } else { None };
+    if min_similarity <= 0.0 { Some(0.0) } else { None }
+}
+
+/// <summary>Splits a string into its graphemes so the distance cores can index them
+/// directly rather than re-scanning the string for each cell.</summary>
+pub fn graphemes(string: &str) -> Vec<&str> {
+    GraphemeClusters::new(string).map(|(grapheme, _)| grapheme).collect()
+}
+
+/// <summary>A query compared to many strings, e.g. the input of a lookup to its candidates,
+/// split into its graphemes once for `Distance::distance_prepared`.</summary>
+pub struct PreparedQuery<'a> {
+    text: &'a str,
+    graphemes: Vec<&'a str>,
+}
+
+impl<'a> PreparedQuery<'a> {
+    pub fn new(text: &'a str) -> PreparedQuery<'a> {
+        PreparedQuery { text, graphemes: graphemes(text) }
+    }
+
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+
+    pub fn graphemes(&self) -> &[&'a str] {
+        &self.graphemes
+    }
+}
+
+/// <summary>The distance of two strings that need no comparison: when either is empty,
+/// when they are equal or when maxDistance is 0. None when they need comparing.</summary>
+pub fn trivial_distance(string1: &str, string2: &str, max_distance: usize) -> Option<Option<usize>> {
+    if string1.is_empty() || string2.is_empty() {
+        return Some(null_distance_results(string1, string2, max_distance));
+    }
+    if string1 == string2 {
+        return Some(Some(0));
+    }
+    if max_distance == 0 {
+        return Some(None);
+    }
+    None
 }
 
 /// <summary>Calculates starting position and lengths of two strings such that common
 /// prefix and suffix substrings are excluded.</summary>
-/// <remarks>Expects string1.Length to be less than or equal to string2.Length</remarks>
-pub fn prefix_suffix_prep(string1: &str, string2: &str) -> (usize, usize, usize) {
-    let string1_gc = GraphemeClusters::new(string1);
-    let string2_gc = GraphemeClusters::new(string2);
-    let mut len1 = string1_gc.len(); // this is also the minimum length of the two strings
-    let mut len2 = string2_gc.len();
+/// <remarks>Expects string1.Length to be less than or equal to string2.Length. The strings
+/// are given as the bytes of ASCII strings or the graphemes of any others.</remarks>
+pub fn prefix_suffix_prep<T: PartialEq>(string1: &[T], string2: &[T]) -> (usize, usize, usize) {
+    let mut len1 = string1.len(); // this is also the minimum length of the two strings
+    let mut len2 = string2.len();
 
     // suffix common to both strings can be ignored
-    while len1 != 0 && string1_gc[len1 - 1] == string2_gc[len2 - 1] {
+    while len1 != 0 && string1[len1 - 1] == string2[len2 - 1] {
         len1 -= 1;
         len2 -= 1;
     }
 
     // prefix common to both strings can be ignored
     let mut start = 0;
-    while start != len1 && string1_gc[start] == string2_gc[start] {
+    while start != len1 && string1[start] == string2[start] {
         start += 1;
     }
 
@@ -65,7 +107,7 @@ pub fn prefix_suffix_prep(string1: &str, string2: &str) -> (usize, usize, usize)
 /// <param name="distance">The edit distance between two strings.</param>
 /// <returns>A similarity value from 0 to 1.0 (1 - (length / distance)).</returns>
 pub fn similarity(distance: f64, length: f64) -> Option<f64> {
-    return if distance < 0.0 { None } else { Some(1.0 - (distance / length)) };
+    if distance < 0.0 { None } else { Some(1.0 - (distance / length)) }
 }
 
 /// <summary>Calculate an edit distance from a similarity measure.</summary>
@@ -73,20 +115,49 @@ pub fn similarity(distance: f64, length: f64) -> Option<f64> {
 /// <param name="similarity">The similarity measure between two strings.</param>
 /// <returns>An edit distance from 0 to length (length * (1 - similarity)).</returns>
 pub fn distance(similarity: f64, length: usize) -> usize {
-    length * (1.0 - similarity) as usize
+    // the epsilon keeps e.g. 5 * (1 - 0.8) from truncating to 0
+    (length as f64 * (1.0 - similarity) + 1e-9) as usize
+}
+
+/// <summary>Shared core of the similarity functions of edit distance algorithms.</summary>
+/// <param name="min_similarity">The minimum similarity that is of interest, if any. It is
+/// converted to a maximum distance so the bounded distance function can exit early.</param>
+/// <returns>The similarity from 0 to 1.0, or None when it is below min_similarity.</returns>
+pub fn similarity_from_distance(comparator: &mut dyn Distance, string1: &str, string2: &str, min_similarity: Option<f64>) -> Option<f64> {
+    if let Some(min_similarity) = min_similarity {
+        assert!((0.0..=1.0).contains(&min_similarity));
+    }
+    if string1.is_empty() || string2.is_empty() {
+        return null_similarity_results(string1, string2, min_similarity.unwrap_or(0.0));
+    }
+
+    let length = GraphemeClusters::new(string1).len().max(GraphemeClusters::new(string2).len());
+    let edit_distance = match min_similarity {
+        Some(min_similarity) => comparator.distance2(string1, string2, distance(min_similarity, length))?,
+        None => comparator.distance(string1, string2)?,
+    };
+    similarity(edit_distance as f64, length as f64)
 }
 
 #[cfg(test)]
 mod helpers_tests {
-    use crate::soft_wx::helpers::prefix_suffix_prep;
+    use crate::soft_wx::helpers::{distance, graphemes, prefix_suffix_prep};
 
     #[test]
     fn prefix_suffix_prep_test() {
-        let (len1, len2, start) = prefix_suffix_prep("hello", "heelo!");
+        let (len1, len2, start) = prefix_suffix_prep(&graphemes("hello"), &graphemes("heelo!"));
         assert_eq!(len1, 3);
         assert_eq!(len2, 4);
         assert_eq!(start, 2);
     }
+
+    #[test]
+    fn distance_test() {
+        assert_eq!(distance(0.8, 5), 1);
+        assert_eq!(distance(0.5, 5), 2);
+        assert_eq!(distance(1.0, 5), 0);
+        assert_eq!(distance(0.0, 5), 5);
+    }
 }
 /*
 Permission is hereby granted, free of charge, to any person obtaining a copy
@@ -0,0 +1,166 @@
+use crate::soft_wx::Similarity;
+use crate::soft_wx::helpers::graphemes;
+
+/// <summary>The prefix scale suggested by Winkler.</summary>
+pub const DEFAULT_PREFIX_SCALE: f64 = 0.1;
+
+/// <summary>Jaro similarity below which the common prefix earns no boost.</summary>
+const BOOST_THRESHOLD: f64 = 0.7;
+
+/// <summary>Longest common prefix that is rewarded.</summary>
+const MAX_PREFIX_LENGTH: usize = 4;
+
+pub struct JaroWinkler {
+    prefix_scale: f64,
+}
+
+/// <summary>
+/// Class providing methods for computing the Jaro-Winkler similarity between two strings.
+/// </summary>
+/// <remarks>
+/// The Jaro similarity counts the graphemes the strings share within a window of half
+/// the longer length, penalised by the shared graphemes that appear out of order. Winkler
+/// then rewards a common prefix of up to 4 graphemes, making it well suited to names and
+/// record matching where typos rarely occur at the start of a word.
+/// See https://en.wikipedia.org/wiki/Jaro%E2%80%93Winkler_distance
+/// Comparisons are case sensitive.</remarks>
+impl JaroWinkler {
+    /// <summary>Create a new instance of JaroWinkler using the default prefix scale of 0.1.</summary>
+    pub fn new() -> JaroWinkler {
+        JaroWinkler::with_prefix_scale(DEFAULT_PREFIX_SCALE)
+    }
+
+    /// <summary>Create a new instance of JaroWinkler using the specified prefix scale.</summary>
+    /// <param name="prefix_scale">How much a common prefix raises the similarity. Clamped to
+    /// 0 - 0.25 so the similarity never exceeds 1.</param>
+    pub fn with_prefix_scale(prefix_scale: f64) -> JaroWinkler {
+        JaroWinkler {
+            prefix_scale: prefix_scale.clamp(0.0, 1.0 / MAX_PREFIX_LENGTH as f64)
+        }
+    }
+
+    pub fn prefix_scale(&self) -> f64 {
+        self.prefix_scale
+    }
+
+    /// <summary>Internal implementation of the Jaro similarity.</summary>
+    fn jaro(string1: &[&str], string2: &[&str]) -> f64 {
+        let window = (string1.len().max(string2.len()) / 2).saturating_sub(1);
+        let mut matched1 = vec![false; string1.len()];
+        let mut matched2 = vec![false; string2.len()];
+        let mut matches = 0;
+        for (i, char1) in string1.iter().enumerate() {
+            let start = i.saturating_sub(window);
+            let end = (i + window + 1).min(string2.len());
+            for j in start..end {
+                if !matched2[j] && string2[j] == *char1 {
+                    matched1[i] = true;
+                    matched2[j] = true;
+                    matches += 1;
+                    break;
+                }
+            }
+        }
+        if matches == 0 {
+            return 0.0;
+        }
+
+        // half the matched graphemes that are out of order
+        let mut transpositions = 0;
+        let mut j = 0;
+        for (i, char1) in string1.iter().enumerate() {
+            if !matched1[i] {
+                continue;
+            }
+            while !matched2[j] {
+                j += 1;
+            }
+            if string2[j] != *char1 {
+                transpositions += 1;
+            }
+            j += 1;
+        }
+
+        let matches = matches as f64;
+        (matches / string1.len() as f64 + matches / string2.len() as f64 + (matches - (transpositions / 2) as f64) / matches) / 3.0
+    }
+}
+
+impl Default for JaroWinkler {
+    fn default() -> JaroWinkler {
+        JaroWinkler::new()
+    }
+}
+
+impl Similarity for JaroWinkler {
+    /// <summary>Return Jaro-Winkler similarity between two strings.</summary>
+    /// <param name="string1">One of the strings to compare.</param>
+    /// <param name="string2">The other string to compare.</param>
+    /// <returns>The degree of similarity 0 to 1.0, where 0 represents a lack of any
+    /// notable similarity, and 1 represents equivalent strings.</returns>
+    fn similarity<'a>(&mut self, string1: &'a str, string2: &'a str) -> Option<f64> {
+        if string1 == string2 {
+            return Some(1.0);
+        }
+        let string1_gc = graphemes(string1);
+        let string2_gc = graphemes(string2);
+        if string1_gc.is_empty() || string2_gc.is_empty() {
+            return Some(0.0);
+        }
+
+        let jaro = JaroWinkler::jaro(&string1_gc, &string2_gc);
+        if jaro < BOOST_THRESHOLD {
+            return Some(jaro);
+        }
+        let prefix = string1_gc.iter().zip(string2_gc.iter())
+            .take(MAX_PREFIX_LENGTH)
+            .take_while(|(char1, char2)| char1 == char2)
+            .count();
+        Some(jaro + prefix as f64 * self.prefix_scale * (1.0 - jaro))
+    }
+
+    /// <summary>Return Jaro-Winkler similarity between two strings.</summary>
+    /// <param name="string1">One of the strings to compare.</param>
+    /// <param name="string2">The other string to compare.</param>
+    /// <param name="minSimilarity">The minimum similarity that is of interest.</param>
+    /// <returns>None if the similarity is lower than minSimilarity, otherwise, a
+    /// number between 0 and 1.0 where 0 represents a lack of any noteable
+    /// similarity, and 1 represents equivalent strings.</returns>
+    fn similarity2<'a>(&mut self, string1: &'a str, string2: &'a str, min_similarity: f64) -> Option<f64> {
+        self.similarity(string1, string2).filter(|&similarity| similarity >= min_similarity)
+    }
+}
+
+#[cfg(test)]
+mod jaro_winkler_tests {
+    use crate::soft_wx::Similarity;
+    use crate::soft_wx::jaro_winkler::JaroWinkler;
+
+    fn rounded(similarity: Option<f64>) -> f64 {
+        (similarity.unwrap() * 1000.0).round() / 1000.0
+    }
+
+    #[test]
+    fn similarity_test() {
+        let mut jaro_winkler = JaroWinkler::new();
+        assert_eq!(rounded(jaro_winkler.similarity("MARTHA", "MARHTA")), 0.961);
+        assert_eq!(rounded(jaro_winkler.similarity("DIXON", "DICKSONX")), 0.813);
+        assert_eq!(rounded(jaro_winkler.similarity("DWAYNE", "DUANE")), 0.84);
+        assert_eq!(rounded(jaro_winkler.similarity("résumé", "résumè")), 0.933);
+        assert_eq!(jaro_winkler.similarity("", ""), Some(1.0));
+        assert_eq!(jaro_winkler.similarity("abc", ""), Some(0.0));
+        assert_eq!(jaro_winkler.similarity("abc", "xyz"), Some(0.0));
+        assert_eq!(jaro_winkler.similarity2("MARTHA", "MARHTA", 0.97), None);
+    }
+
+    #[test]
+    fn prefix_scale_test() {
+        let mut jaro = JaroWinkler::with_prefix_scale(0.0);
+        assert_eq!(rounded(jaro.similarity("MARTHA", "MARHTA")), 0.944);
+        assert_eq!(JaroWinkler::with_prefix_scale(1.0).prefix_scale(), 0.25);
+
+        // the prefix weighting favours the candidate sharing the start of the input
+        let mut jaro_winkler = JaroWinkler::new();
+        assert!(jaro_winkler.similarity("abcd", "abdc") > jaro_winkler.similarity("abcd", "bacd"));
+    }
+}
@@ -1,14 +1,13 @@
 // Copyright ©2015-2018 SoftWx, Inc.
 // Released under the MIT License the text of which appears at the end of this file.
 // <authors> Steve Hatchett
-use std::collections::HashMap;
+use std::mem;
 
-use crate::grapheme_iterator::GraphemeClusters;
 use crate::soft_wx::{Distance, Similarity};
-use crate::soft_wx::helpers::{distance, null_distance_results, null_similarity_results, prefix_suffix_prep, similarity};
+use crate::soft_wx::helpers::{graphemes, null_distance_results, prefix_suffix_prep, similarity_from_distance, trivial_distance, PreparedQuery};
 
 pub struct Levenshtein {
-    base_char1_costs: HashMap<usize, usize>
+    base_char1_costs: Vec<usize>
 }
 
 /// <summary>
@@ -37,67 +36,42 @@ impl Levenshtein {
     /// be passed to the Levenshtein methods.</param>
     pub fn new() -> Levenshtein {
         Levenshtein {
-            base_char1_costs: HashMap::new()
+            base_char1_costs: Vec::new()
         }
     }
 
     /// <summary>Internal implementation of the core Levenshtein algorithm.</summary>
     /// <remarks>https://github.com/softwx/SoftWx.Match</remarks>
-    fn core_levenshtein(string1: &str, string2: &str, len1: usize, len2: usize, start: usize, char1_costs: &mut HashMap<usize, usize>) -> Option<usize> {
-        for j in 0..len2 {
-            char1_costs.insert(j, j + 1);
+    fn core_levenshtein<T: Copy + PartialEq>(string1: &[T], string2: &[T], len1: usize, len2: usize, start: usize, char1_costs: &mut Vec<usize>) -> Option<usize> {
+        // the rows are reused across calls, growing only when a longer string is seen
+        if char1_costs.len() < len2 {
+            char1_costs.resize(len2, 0);
+        }
+        for (j, cost) in char1_costs[..len2].iter_mut().enumerate() {
+            *cost = j + 1;
         }
         let mut current_char_cost = 0;
-        let string1_gc = GraphemeClusters::new(string1);
-        let string2_gc = GraphemeClusters::new(string2);
-        if start == 0 {
-            for i in 0..len1 {
-                let mut left_char_cost = i;
-                let mut above_char_cost = i;
-
-                let char1 = &string1_gc[i];
-                for j in 0..len2 {
-                    current_char_cost = left_char_cost; // cost on diagonal (substitution)
-                    left_char_cost = char1_costs[&j];
-                    if &string2_gc[j] != char1 {
-
-                        // substitution if neither of two conditions below
-                        if above_char_cost < current_char_cost {
-                            current_char_cost = above_char_cost;
-                        }
-
-                        if left_char_cost < current_char_cost {
-                            current_char_cost = left_char_cost;
-                        }
-                        current_char_cost += 1;
+        let string1 = &string1[start..start + len1];
+        let string2 = &string2[start..start + len2];
+        for (i, &char1) in string1.iter().enumerate() {
+            let mut left_char_cost = i;
+            let mut above_char_cost = i;
+            for (j, &char2) in string2.iter().enumerate() {
+                current_char_cost = left_char_cost; // cost on diagonal (substitution)
+                left_char_cost = char1_costs[j];
+                if char2 != char1 {
+                    // substitution if neither of two conditions below
+                    if above_char_cost < current_char_cost {
+                        current_char_cost = above_char_cost;
                     }
-                    above_char_cost = current_char_cost;
-                    char1_costs.insert(j, above_char_cost);
-                }
-            }
-        } else {
-            for i in 0..len1 {
-                let mut left_char_cost = i;
-                let mut above_char_cost = i;
-                let char1 = &string1_gc[start + i];
-                for j in 0..len2 {
-                    current_char_cost = left_char_cost; // cost on diagonal (substitution)
-                    left_char_cost = char1_costs[&j];
-
-                    if &string2_gc[start + j] != char1 {
-                        // substitution if neither of two conditions below
-                        if above_char_cost < current_char_cost {
-                            current_char_cost = above_char_cost;
-                        }
-
-                        if left_char_cost < current_char_cost {
-                            current_char_cost = left_char_cost;
-                        }
-                        current_char_cost += 1;
+
+                    if left_char_cost < current_char_cost {
+                        current_char_cost = left_char_cost;
                     }
-                    above_char_cost = current_char_cost;
-                    char1_costs.insert(j, above_char_cost);
+                    current_char_cost += 1;
                 }
+                above_char_cost = current_char_cost;
+                char1_costs[j] = above_char_cost;
             }
         }
         Some(current_char_cost)
@@ -105,15 +79,17 @@ impl Levenshtein {
 
     /// <summary>Internal implementation of the core Levenshtein algorithm that accepts a maxDistance.</summary>
     /// <remarks>https://github.com/softwx/SoftWx.Match</remarks>
-    fn core_levenshtein2(string1: &str, string2: &str, len1: usize, len2: usize, start: usize, max_distance: usize, char1_costs: &mut HashMap<usize, usize>) -> Option<usize> {
-        for j in 0..max_distance {
-            char1_costs.insert(j, j + 1);
+    fn core_levenshtein2<T: Copy + PartialEq>(string1: &[T], string2: &[T], len1: usize, len2: usize, start: usize, max_distance: usize, char1_costs: &mut Vec<usize>) -> Option<usize> {
+        // the rows are reused across calls, growing only when a longer string is seen
+        if char1_costs.len() < len2 {
+            char1_costs.resize(len2, 0);
+        }
+        for (j, cost) in char1_costs[..max_distance].iter_mut().enumerate() {
+            *cost = j + 1;
         }
 
         if len2 > max_distance {
-            for k in max_distance..len2 {
-                char1_costs.insert(k + 1, max_distance + 1);
-            }
+            char1_costs[max_distance..len2].fill(max_distance + 1);
         }
 
         let len_diff = len2 - len1;
@@ -121,185 +97,125 @@ impl Levenshtein {
         let mut j_start = 0;
         let mut j_end = max_distance;
         let mut current_cost = 0;
-        let string1_gc = GraphemeClusters::new(string1);
-        let string2_gc = GraphemeClusters::new(string2);
-        if start == 0 {
-            for i in 0..len1 {
-                let char1 = &string1_gc[i];
-                let mut prev_char1_cost = i;
-                let mut above_char1_cost = i;
-
-                // no need to look beyond window of lower right diagonal - maxDistance cells (lower right diag is i - lenDiff)
-                // and the upper left diagonal + maxDistance cells (upper left is i)
-                if i > j_offset {
-                    j_start += 1;
-                }
-
-                if j_end < len2 {
-                    j_end += 1;
-                }
+        let string1 = &string1[start..start + len1];
+        let string2 = &string2[start..start + len2];
+        for (i, &char1) in string1.iter().enumerate() {
+            let mut prev_char1_cost = i;
+            let mut above_char1_cost = i;
+
+            // no need to look beyond window of lower right diagonal - maxDistance cells (lower right diag is i - lenDiff)
+            // and the upper left diagonal + maxDistance cells (upper left is i)
+            if i > j_offset {
+                j_start += 1;
+            }
 
-                for j in j_start..j_end {
-                    current_cost = prev_char1_cost;// cost on diagonal (substitution)
-                    prev_char1_cost = char1_costs[&j];
-                    if &string2_gc[j] != char1 {
-                        // substitution if neither of two conditions below
-                        if above_char1_cost < current_cost {
-                            current_cost = above_char1_cost; // deletion
-                        }
+            if j_end < len2 {
+                j_end += 1;
+            }
 
-                        if prev_char1_cost < current_cost {
-                            current_cost = prev_char1_cost; // insertion
-                        }
+            for j in j_start..j_end {
+                current_cost = prev_char1_cost;// cost on diagonal (substitution)
+                prev_char1_cost = char1_costs[j];
+                if string2[j] != char1 {
+                    // substitution if neither of two conditions below
+                    if above_char1_cost < current_cost {
+                        current_cost = above_char1_cost; // deletion
+                    }
 
-                        current_cost += 1;
+                    if prev_char1_cost < current_cost {
+                        current_cost = prev_char1_cost; // insertion
                     }
-                    above_char1_cost = current_cost;
-                    char1_costs.insert(j, above_char1_cost);
-                }
 
-                if char1_costs[&(i + len_diff)] > max_distance {
-                    return None;
+                    current_cost += 1;
                 }
+                above_char1_cost = current_cost;
+                char1_costs[j] = above_char1_cost;
             }
-        } else {
-            for i in 0..len1 {
-                let char1 = &string1_gc[start + i];
-                let mut prev_char1_cost = i;
-                let mut above_char_cost = i;
-
-                // no need to look beyond window of lower right diagonal - maxDistance cells (lower right diag is i - lenDiff)
-                // and the upper left diagonal + maxDistance cells (upper left is i)
-                if i < j_offset {
-                    j_start += 1;
-                }
 
-                if j_end < len2 {
-                    j_end += 1;
-                }
-
-                for j in j_start..j_end {
-                    current_cost = prev_char1_cost;
-                    prev_char1_cost = char1_costs[&j];
-
-                    if &string2_gc[start + j] != char1 {
-                        // substitution if neither of two conditions below
-                        if above_char_cost < current_cost {
-                            current_cost = above_char_cost; // deletion
-                        }
-
-                        if prev_char1_cost < current_cost {
-                            current_cost = prev_char1_cost // insertion
-                        }
-
-                        current_cost += 1;
-                    }
-                    above_char_cost = current_cost;
-                    char1_costs.insert(j, above_char_cost);
-                }
-                if char1_costs[&(i + len_diff)] > max_distance {
-                    return None;
-                }
+            if char1_costs[i + len_diff] > max_distance {
+                return None;
             }
         }
-        return if current_cost <= max_distance { Some(current_cost) } else { None };
+        if current_cost <= max_distance { Some(current_cost) } else { None }
     }
-}
 
-impl Similarity for Levenshtein {
-    /// <summary>Return Levenshtein similarity between two strings
-    /// (1 - (levenshtein distance / len of longer string)).</summary>
-    /// <param name="string1">One of the strings to compare.</param>
-    /// <param name="string2">The other string to compare.</param>
-    /// <returns>The degree of similarity 0 to 1.0, where 0 represents a lack of any
-    /// notable similarity, and 1 represents equivalent strings.</returns>
-    fn similarity<'a>(&mut self, mut string1: &'a str, mut string2: &'a str) -> Option<f64> {
+    // distance over the bytes of ASCII strings or the graphemes of any others
+    fn levenshtein<'a, T: Copy + PartialEq>(&mut self, mut string1: &'a [T], mut string2: &'a [T]) -> Option<usize> {
         if string1.is_empty() {
-            return if string2.is_empty() { Some(1.0) } else { Some(0.0) };
+            return Some(string2.len());
         }
 
         if string2.is_empty() {
-            return Some(0.0);
+            return Some(string1.len());
         }
-
         // if strings of different lengths, ensure shorter string is in string1. This can result in a little
         // faster speed by spending more time spinning just the inner loop during the main processing.
-        let str1_len = GraphemeClusters::new(string1).len();
-        let mut str2_len = GraphemeClusters::new(string2).len();
-        if str1_len > str2_len {
-            let s = string1;
-            string1 = string2;
-            string2 = s;
-
-            str2_len = str1_len;
+        if string1.len() > string2.len() {
+            mem::swap(&mut string1, &mut string2);
         }
-
+        // identify common suffix and/or prefix that can be ignored
         let (len1, len2, start) = prefix_suffix_prep(string1, string2);
         if len1 == 0 {
-            return Some(1.0);
+            return Some(len2);
         }
 
-        let distance = Levenshtein::core_levenshtein(string1, string2, len1, len2, start, &mut self.base_char1_costs);
-        if distance.is_some() {
-            return similarity(distance.unwrap() as f64, str2_len as f64);
-        }
-        None
+        Levenshtein::core_levenshtein(string1, string2, len1, len2, start, &mut self.base_char1_costs)
     }
 
-    /// <summary>Return Levenshtein similarity between two strings
-    /// (1 - (levenshtein distance / len of longer string)).</summary>
-    /// <param name="string1">One of the strings to compare.</param>
-    /// <param name="string2">The other string to compare.</param>
-    /// <param name="minSimilarity">The minimum similarity that is of interest.</param>
-    /// <returns>The degree of similarity 0 to 1.0, where -1 represents a similarity
-    /// lower than minSimilarity, otherwise, a number between 0 and 1.0 where 0
-    /// represents a lack of any noteable similarity, and 1 represents equivalent
-    /// strings.</returns>
-    fn similarity2<'a>(&mut self, mut string1: &'a str, mut string2: &'a str, min_similarity: f64) -> Option<f64> {
-        assert_eq!((0.0..1.0).contains(&min_similarity), true);
-
-        if string1.is_empty() && string2.is_empty() {
-            return null_similarity_results(string1, string2, min_similarity);
-        }
-
+    fn levenshtein2<'a, T: Copy + PartialEq>(&mut self, mut string1: &'a [T], mut string2: &'a [T], max_distance: usize) -> Option<usize> {
         // if strings of different lengths, ensure shorter string is in string1. This can result in a little
         // faster speed by spending more time spinning just the inner loop during the main processing.
-        let mut str1_len = GraphemeClusters::new(string1).len();
-        let mut str2_len = GraphemeClusters::new(string2).len();
-        if str1_len > str2_len {
-            let s = string1;
-            string1 = string2;
-            string2 = s;
-
-            let sl = str1_len;
-            str1_len = str2_len;
-            str2_len = sl;
+        if string1.len() > string2.len() {
+            mem::swap(&mut string1, &mut string2);
         }
-        let max_distance = distance(min_similarity, str2_len);
-        if str1_len > max_distance {
+
+        if string2.len() - string1.len() > max_distance {
             return None;
         }
 
-        if max_distance == 0 {
-            return if string1 == string2 { Some(1.0) } else { None };
-        }
+        let (len1, len2, start) = prefix_suffix_prep(string1, string2);
 
-        // identify common suffix and/or prefix that can be ignored
-        let (len1, len2, start) = prefix_suffix_prep(string2, string2);
         if len1 == 0 {
-            return Some(1.0);
+            if len2 <= max_distance {
+                return Some(len2);
+            } else {
+                return None;
+            }
+        }
+        if max_distance < len2 {
+            return Levenshtein::core_levenshtein2(string1, string2, len1, len2, start, max_distance, &mut self.base_char1_costs);
         }
+        Levenshtein::core_levenshtein(string1, string2, len1, len2, start, &mut self.base_char1_costs)
+    }
+}
 
-        let distance = if max_distance < len2 {
-            Levenshtein::core_levenshtein2(string1, string2, len1, len2, start, max_distance as usize, &mut self.base_char1_costs)
-        } else {
-            Levenshtein::core_levenshtein(string1, string2, len1, len2, start, &mut self.base_char1_costs)
-        };
+impl Default for Levenshtein {
+    fn default() -> Levenshtein {
+        Levenshtein::new()
+    }
+}
 
-        if distance.is_some() {
-            return similarity(distance.unwrap() as f64, str2_len as f64);
-        }
-        None
+impl Similarity for Levenshtein {
+    /// <summary>Return Levenshtein similarity between two strings
+    /// (1 - (levenshtein distance / len of longer string)).</summary>
+    /// <param name="string1">One of the strings to compare.</param>
+    /// <param name="string2">The other string to compare.</param>
+    /// <returns>The degree of similarity 0 to 1.0, where 0 represents a lack of any
+    /// notable similarity, and 1 represents equivalent strings.</returns>
+    fn similarity<'a>(&mut self, string1: &'a str, string2: &'a str) -> Option<f64> {
+        similarity_from_distance(self, string1, string2, None)
+    }
+
+    /// <summary>Return Levenshtein similarity between two strings
+    /// (1 - (levenshtein distance / len of longer string)).</summary>
+    /// <param name="string1">One of the strings to compare.</param>
+    /// <param name="string2">The other string to compare.</param>
+    /// <param name="minSimilarity">The minimum similarity that is of interest.</param>
+    /// <returns>None if the similarity is lower than minSimilarity, otherwise, a
+    /// number between 0 and 1.0 where 0 represents a lack of any noteable
+    /// similarity, and 1 represents equivalent strings.</returns>
+    fn similarity2<'a>(&mut self, string1: &'a str, string2: &'a str, min_similarity: f64) -> Option<f64> {
+        similarity_from_distance(self, string1, string2, Some(min_similarity))
     }
 }
 
@@ -311,30 +227,12 @@ impl Distance for Levenshtein {
     /// <param name="string2">The other string to compare.</param>
     /// <returns>0 if the strings are equivalent, otherwise a positive number whose
     /// magnitude increases as difference between the strings increases.</returns>
-    fn distance<'a>(&mut self, mut string1: &'a str, mut string2: &'a str) -> Option<usize> {
-        let str2_len = GraphemeClusters::new(string2).len();
-        if string1.is_empty() {
-            return Some(str2_len);
-        }
-
-        let str1_len = GraphemeClusters::new(string1).len();
-        if string2.is_empty() {
-            return Some(str1_len);
-        }
-        // if strings of different lengths, ensure shorter string is in string1. This can result in a little
-        // faster speed by spending more time spinning just the inner loop during the main processing.
-        if str1_len > str2_len {
-            let s = string1;
-            string1 = string2;
-            string2 = s;
-        }
-        // identify common suffix and/or prefix that can be ignored
-        let (len1, len2, start) = prefix_suffix_prep(string1, string2);
-        if len1 == 0 {
-            return Some(len2);
+    fn distance<'a>(&mut self, string1: &'a str, string2: &'a str) -> Option<usize> {
+        if string1.is_ascii() && string2.is_ascii() {
+            self.levenshtein(string1.as_bytes(), string2.as_bytes())
+        } else {
+            self.levenshtein(&graphemes(string1), &graphemes(string2))
         }
-
-        return Levenshtein::core_levenshtein(string1, string2, len1, len2, start, &mut self.base_char1_costs);
     }
 
     /// <summary>Compute and return the Levenshtein edit distance between two strings.</summary>
@@ -346,7 +244,7 @@ impl Distance for Levenshtein {
     /// <returns>None if the distance is greater than the maxDistance, 0 if the strings
     /// are equivalent, otherwise a positive number whose magnitude increases as
     /// difference between the strings increases.</returns>
-    fn distance2<'a>(&mut self, mut string1: &'a str, mut string2: &'a str, max_distance: usize) -> Option<usize> {
+    fn distance2<'a>(&mut self, string1: &'a str, string2: &'a str, max_distance: usize) -> Option<usize> {
         if string1.is_empty() || string2.is_empty() {
             return null_distance_results(string1, string2, max_distance);
         }
@@ -355,26 +253,111 @@ impl Distance for Levenshtein {
             return if string1 == string2 { Some(0) } else { None };
         }
 
-        // if strings of different lengths, ensure shorter string is in string1. This can result in a little
-        // faster speed by spending more time spinning just the inner loop during the main processing.
-        if string1.len() > string2.len() {
-            let s = string1;
-            string1 = string2;
-            string2 = s;
+        if string1.is_ascii() && string2.is_ascii() {
+            self.levenshtein2(string1.as_bytes(), string2.as_bytes(), max_distance)
+        } else {
+            self.levenshtein2(&graphemes(string1), &graphemes(string2), max_distance)
         }
+    }
 
-        let (len1, len2, start) = prefix_suffix_prep(string1, string2);
+    /// <summary>Levenshtein distance2 of a query split once.</summary>
+    fn distance_prepared(&mut self, query: &PreparedQuery, string2: &str, max_distance: Option<usize>) -> Option<usize> {
+        let max_distance = max_distance.unwrap_or(usize::MAX);
+        if let Some(distance) = trivial_distance(query.text(), string2, max_distance) {
+            return distance;
+        }
+        if query.text().is_ascii() && string2.is_ascii() {
+            self.levenshtein2(query.text().as_bytes(), string2.as_bytes(), max_distance)
+        } else {
+            self.levenshtein2(query.graphemes(), &graphemes(string2), max_distance)
+        }
+    }
+}
 
-        if len1 == 0 {
-            if len2 <= max_distance {
-                return Some(len2);
-            } else {
-                return None;
+#[cfg(test)]
+mod levenshtein_tests {
+    use crate::soft_wx::{Distance, Similarity};
+    use crate::soft_wx::levensthtein::Levenshtein;
+    use crate::soft_wx::test_strings::random_strings;
+
+    // textbook O(n*m) Levenshtein used as the reference implementation
+    fn reference_distance(string1: &str, string2: &str) -> usize {
+        let a: Vec<char> = string1.chars().collect();
+        let b: Vec<char> = string2.chars().collect();
+        let mut row: Vec<usize> = (0..=b.len()).collect();
+        for i in 1..=a.len() {
+            let mut diagonal = row[0];
+            row[0] = i;
+            for j in 1..=b.len() {
+                let above = row[j];
+                row[j] = if a[i - 1] == b[j - 1] { diagonal } else { 1 + diagonal.min(above).min(row[j - 1]) };
+                diagonal = above;
             }
         }
-        if max_distance < len2 {
-            return Levenshtein::core_levenshtein2(string1, string2, len1, len2, start, max_distance, &mut self.base_char1_costs);
+        row[b.len()]
+    }
+
+    #[test]
+    fn distance_matches_reference_test() {
+        let mut levenshtein = Levenshtein::new();
+        let strings = random_strings(60, 8);
+        for string1 in &strings {
+            for string2 in &strings {
+                let expected = reference_distance(string1, string2);
+                assert_eq!(levenshtein.distance(string1, string2), Some(expected), "{} {}", string1, string2);
+                for max_distance in 0..4 {
+                    let bounded = if expected <= max_distance { Some(expected) } else { None };
+                    assert_eq!(levenshtein.distance2(string1, string2, max_distance), bounded, "{} {} {}", string1, string2, max_distance);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn ascii_fast_path_test() {
+        // pairs of ASCII strings compare bytes, any other pair compares graphemes
+        let mut levenshtein = Levenshtein::new();
+        assert_eq!(levenshtein.distance("cafe", "cafes"), Some(1));
+        assert_eq!(levenshtein.distance("cafe", "café"), Some(1));
+        assert_eq!(levenshtein.distance("resume", "résumé"), Some(2));
+        assert_eq!(levenshtein.distance2("🚀resume", "resume🚀", 1), None);
+        assert_eq!(levenshtein.distance2("🚀resume", "resume🚀", 2), Some(2));
+    }
+
+    #[test]
+    fn grapheme_cluster_test() {
+        // combining marks, emoji sequences and flags are each a single edit
+        let mut levenshtein = Levenshtein::new();
+        assert_eq!(levenshtein.distance("cafe\u{301}", "cafe"), Some(1));
+        assert_eq!(levenshtein.distance("cafe\u{301}", "café"), Some(1));
+        assert_eq!(levenshtein.distance("🇫🇷", "🇩🇪"), Some(1));
+        assert_eq!(levenshtein.distance("👨\u{200D}👩\u{200D}👧", "👨"), Some(1));
+        assert_eq!(levenshtein.distance("👍🏽", "👍"), Some(1));
+    }
+
+    #[test]
+    fn similarity2_matches_similarity_test() {
+        let mut levenshtein = Levenshtein::new();
+        let strings = random_strings(40, 8);
+        for string1 in &strings {
+            for string2 in &strings {
+                let similarity = levenshtein.similarity(string1, string2).unwrap();
+                for &min_similarity in [0.0, 0.25, 0.5, 0.75, 1.0].iter() {
+                    let expected = if similarity >= min_similarity { Some(similarity) } else { None };
+                    assert_eq!(levenshtein.similarity2(string1, string2, min_similarity), expected, "{} {} {}", string1, string2, min_similarity);
+                }
+            }
         }
-        return Levenshtein::core_levenshtein(string1, string2, len1, len2, start, &mut self.base_char1_costs);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn similarity_test() {
+        let mut levenshtein = Levenshtein::new();
+        assert_eq!(levenshtein.similarity("", ""), Some(1.0));
+        assert_eq!(levenshtein.similarity("abc", ""), Some(0.0));
+        assert_eq!(levenshtein.similarity("abc", "abc"), Some(1.0));
+        assert_eq!(levenshtein.similarity("abc", "abcd"), Some(0.75));
+        assert_eq!(levenshtein.similarity2("abc", "abcd", 0.8), None);
+        assert_eq!(levenshtein.similarity2("abc", "", 0.5), None);
+    }
+}
@@ -0,0 +1,185 @@
+use std::mem;
+
+use crate::soft_wx::{Distance, Similarity};
+use crate::soft_wx::helpers::{graphemes, null_distance_results, prefix_suffix_prep, similarity_from_distance, trivial_distance, PreparedQuery};
+
+pub struct LongestCommonSubsequence {
+    row: Vec<usize>,
+}
+
+/// <summary>
+/// Class providing methods for computing the longest common subsequence (LCS) distance
+/// between two strings.
+/// </summary>
+/// <remarks>
+/// The LCS distance only allows insertions and deletions, so it is the number of
+/// graphemes of either string that are not part of their longest common subsequence,
+/// i.e. len1 + len2 - 2 * lcs. A substitution therefore counts as 2 and the distance
+/// is never less than the Levenshtein distance. It is the measure behind line based
+/// diff tools and is useful for near-duplicate detection where reordered or replaced
+/// text should weigh more than in Levenshtein.
+/// See https://en.wikipedia.org/wiki/Longest_common_subsequence_problem
+/// The methods in this class are not threadsafe.</remarks>
+impl LongestCommonSubsequence {
+    /// <summary>Create a new instance of LongestCommonSubsequence.</summary>
+    pub fn new() -> LongestCommonSubsequence {
+        LongestCommonSubsequence {
+            row: vec![]
+        }
+    }
+
+    /// <summary>Internal implementation of the LCS length, a single row of the dynamic
+    /// programming matrix is kept.</summary>
+    fn core_lcs<T: Copy + PartialEq>(string1: &[T], string2: &[T], row: &mut Vec<usize>) -> usize {
+        row.clear();
+        row.resize(string2.len() + 1, 0);
+        for &char1 in string1 {
+            let mut diagonal = 0;
+            for (j, &char2) in string2.iter().enumerate() {
+                let above = row[j + 1];
+                row[j + 1] = if char1 == char2 { diagonal + 1 } else { above.max(row[j]) };
+                diagonal = above;
+            }
+        }
+        row[string2.len()]
+    }
+
+    // distance over the bytes of ASCII strings or the graphemes of any others
+    fn lcs_distance<'a, T: Copy + PartialEq>(&mut self, mut string1: &'a [T], mut string2: &'a [T], max_distance: usize) -> Option<usize> {
+        if string1.len() > string2.len() {
+            mem::swap(&mut string1, &mut string2);
+        }
+        if string2.len() - string1.len() > max_distance {
+            return None;
+        }
+        // a common prefix and suffix are always part of a longest common subsequence
+        let (len1, len2, start) = prefix_suffix_prep(string1, string2);
+        let lcs = LongestCommonSubsequence::core_lcs(&string1[start..start + len1], &string2[start..start + len2], &mut self.row);
+        let distance = len1 + len2 - 2 * lcs;
+        if distance <= max_distance { Some(distance) } else { None }
+    }
+
+    fn distance_of(&mut self, string1: &str, string2: &str, max_distance: usize) -> Option<usize> {
+        if string1.is_ascii() && string2.is_ascii() {
+            self.lcs_distance(string1.as_bytes(), string2.as_bytes(), max_distance)
+        } else {
+            self.lcs_distance(&graphemes(string1), &graphemes(string2), max_distance)
+        }
+    }
+}
+
+impl Default for LongestCommonSubsequence {
+    fn default() -> LongestCommonSubsequence {
+        LongestCommonSubsequence::new()
+    }
+}
+
+impl Similarity for LongestCommonSubsequence {
+    /// <summary>Return LCS similarity between two strings
+    /// (1 - (lcs distance / len of longer string)), clamped to 0.</summary>
+    /// <param name="string1">One of the strings to compare.</param>
+    /// <param name="string2">The other string to compare.</param>
+    /// <returns>The degree of similarity 0 to 1.0, where 0 represents a lack of any
+    /// notable similarity, and 1 represents equivalent strings.</returns>
+    fn similarity<'a>(&mut self, string1: &'a str, string2: &'a str) -> Option<f64> {
+        similarity_from_distance(self, string1, string2, None).map(|similarity| similarity.max(0.0))
+    }
+
+    /// <summary>Return LCS similarity between two strings
+    /// (1 - (lcs distance / len of longer string)), clamped to 0.</summary>
+    /// <param name="string1">One of the strings to compare.</param>
+    /// <param name="string2">The other string to compare.</param>
+    /// <param name="minSimilarity">The minimum similarity that is of interest.</param>
+    /// <returns>None if the similarity is lower than minSimilarity, otherwise, a
+    /// number between 0 and 1.0 where 0 represents a lack of any noteable
+    /// similarity, and 1 represents equivalent strings.</returns>
+    fn similarity2<'a>(&mut self, string1: &'a str, string2: &'a str, min_similarity: f64) -> Option<f64> {
+        if min_similarity <= 0.0 {
+            return self.similarity(string1, string2);
+        }
+        similarity_from_distance(self, string1, string2, Some(min_similarity))
+    }
+}
+
+impl Distance for LongestCommonSubsequence {
+    /// <summary>Compute and return the LCS distance between two strings.</summary>
+    /// <remarks>This method is not threadsafe.</remarks>
+    /// <param name="string1">One of the strings to compare.</param>
+    /// <param name="string2">The other string to compare.</param>
+    /// <returns>0 if the strings are equivalent, otherwise a positive number whose
+    /// magnitude increases as difference between the strings increases.</returns>
+    fn distance<'a>(&mut self, string1: &'a str, string2: &'a str) -> Option<usize> {
+        self.distance_of(string1, string2, usize::MAX)
+    }
+
+    /// <summary>Compute and return the LCS distance between two strings.</summary>
+    /// <remarks>This method is not threadsafe.</remarks>
+    /// <param name="string1">One of the strings to compare.</param>
+    /// <param name="string2">The other string to compare.</param>
+    /// <param name="maxDistance">The maximum distance that is of interest.</param>
+    /// <returns>None if the distance is greater than the maxDistance, 0 if the strings
+    /// are equivalent, otherwise a positive number whose magnitude increases as
+    /// difference between the strings increases.</returns>
+    fn distance2<'a>(&mut self, string1: &'a str, string2: &'a str, max_distance: usize) -> Option<usize> {
+        if string1.is_empty() || string2.is_empty() {
+            return null_distance_results(string1, string2, max_distance);
+        }
+        self.distance_of(string1, string2, max_distance)
+    }
+
+    /// <summary>LCS distance2 of a query split once.</summary>
+    fn distance_prepared(&mut self, query: &PreparedQuery, string2: &str, max_distance: Option<usize>) -> Option<usize> {
+        let max_distance = max_distance.unwrap_or(usize::MAX);
+        if let Some(distance) = trivial_distance(query.text(), string2, max_distance) {
+            return distance;
+        }
+        if query.text().is_ascii() && string2.is_ascii() {
+            self.lcs_distance(query.text().as_bytes(), string2.as_bytes(), max_distance)
+        } else {
+            self.lcs_distance(query.graphemes(), &graphemes(string2), max_distance)
+        }
+    }
+}
+
+#[cfg(test)]
+mod longest_common_subsequence_tests {
+    use crate::soft_wx::{Distance, Similarity};
+    use crate::soft_wx::levensthtein::Levenshtein;
+    use crate::soft_wx::longest_common_subsequence::LongestCommonSubsequence;
+
+    #[test]
+    fn distance_test() {
+        let mut lcs = LongestCommonSubsequence::new();
+        // a substitution is a deletion and an insertion
+        assert_eq!(lcs.distance("kitten", "sitting"), Some(5));
+        assert_eq!(lcs.distance("ab", "ba"), Some(2));
+        assert_eq!(lcs.distance("abc", "abc"), Some(0));
+        assert_eq!(lcs.distance("", "abc"), Some(3));
+        assert_eq!(lcs.distance("résumé", "resume"), Some(4));
+        assert_eq!(lcs.distance2("kitten", "sitting", 4), None);
+        assert_eq!(lcs.distance2("kitten", "sitting", 5), Some(5));
+        assert_eq!(lcs.distance2("a", "abcd", 2), None);
+    }
+
+    #[test]
+    fn levenshtein_bounds_test() {
+        let mut lcs = LongestCommonSubsequence::new();
+        let mut levenshtein = Levenshtein::new();
+        let words = ["", "a", "abc", "acb", "kitten", "sitting", "héllo", "🚀hello"];
+        for string1 in words.iter() {
+            for string2 in words.iter() {
+                let distance = lcs.distance(string1, string2).unwrap();
+                let levenshtein_distance = levenshtein.distance(string1, string2).unwrap();
+                assert!(distance >= levenshtein_distance && distance <= 2 * levenshtein_distance, "{} {}", string1, string2);
+            }
+        }
+    }
+
+    #[test]
+    fn similarity_test() {
+        let mut lcs = LongestCommonSubsequence::new();
+        assert_eq!(lcs.similarity("abcd", "abce"), Some(0.5));
+        assert_eq!(lcs.similarity("abc", "xyz"), Some(0.0));
+        assert_eq!(lcs.similarity2("abcd", "abce", 0.6), None);
+    }
+}
@@ -1,10 +1,30 @@
 pub mod helpers;
 pub mod levensthtein;
 pub mod damerau_osa;
+pub mod damerau;
+pub mod jaro_winkler;
+pub mod longest_common_subsequence;
+pub mod myers;
+pub mod sift4;
+pub mod weighted_levenshtein;
+#[cfg(test)]
+pub(crate) mod test_strings;
+
+use crate::soft_wx::helpers::PreparedQuery;
 
 pub trait Distance {
     fn distance<'a>(&mut self, string1: &'a str, string2: &'a str) -> Option<usize>;
     fn distance2<'a>(&mut self, string1: &'a str, string2: &'a str, max_distance: usize) -> Option<usize>;
+
+    /// <summary>The distance of a query compared to many strings, split into its graphemes
+    /// once rather than for every comparison, to string2, None when it exceeds maxDistance.</summary>
+    /// <remarks>The default compares the query text with `distance` or `distance2`.</remarks>
+    fn distance_prepared(&mut self, query: &PreparedQuery, string2: &str, max_distance: Option<usize>) -> Option<usize> {
+        match max_distance {
+            Some(max_distance) => self.distance2(query.text(), string2, max_distance),
+            None => self.distance(query.text(), string2),
+        }
+    }
 }
 
 pub trait Similarity {
@@ -0,0 +1,270 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::mem;
+
+use crate::grapheme_iterator::GraphemeClusters;
+use crate::soft_wx::{Distance, Similarity};
+use crate::soft_wx::helpers::{null_distance_results, similarity_from_distance};
+
+/// <summary>The longest string, in graphemes, that fits the bit vectors.</summary>
+pub const MAX_LENGTH: usize = 64;
+
+pub struct Myers;
+
+/// <summary>
+/// Class providing a bit-parallel Levenshtein distance for strings of up to 64 graphemes.
+/// </summary>
+/// <remarks>
+/// Myers' algorithm ("A fast bit-vector algorithm for approximate string matching based
+/// on dynamic programming", 1999), in the formulation by Hyyrö, keeps a whole column of
+/// the Levenshtein matrix as vertical deltas packed in two u64 words, so each grapheme
+/// of the second string is processed with a handful of bitwise operations rather than
+/// a loop over the first string. Results are identical to Levenshtein. Strings longer
+/// than MAX_LENGTH graphemes are not supported and return None from `try_distance`.</remarks>
+impl Myers {
+    /// <summary>Create a new instance of Myers.</summary>
+    pub fn new() -> Myers {
+        Myers
+    }
+
+    /// <summary>Compute the Levenshtein distance when both strings fit in MAX_LENGTH graphemes.</summary>
+    /// <returns>None when either string is too long, otherwise the result `distance2`
+    /// would give, which is itself None when the distance exceeds maxDistance.</returns>
+    pub fn try_distance(&mut self, string1: &str, string2: &str, max_distance: usize) -> Option<Option<usize>> {
+        if string1.is_ascii() && string2.is_ascii() {
+            if string1.len() > MAX_LENGTH || string2.len() > MAX_LENGTH {
+                return None;
+            }
+            return Some(Myers::core_myers(string1.as_bytes(), string2.as_bytes(), max_distance));
+        }
+        let string1_gc: Vec<&str> = GraphemeClusters::new(string1).map(|(grapheme, _)| grapheme).take(MAX_LENGTH + 1).collect();
+        let string2_gc: Vec<&str> = GraphemeClusters::new(string2).map(|(grapheme, _)| grapheme).take(MAX_LENGTH + 1).collect();
+        if string1_gc.len() > MAX_LENGTH || string2_gc.len() > MAX_LENGTH {
+            return None;
+        }
+        Some(Myers::core_myers(&string1_gc, &string2_gc, max_distance))
+    }
+
+    /// <summary>Internal implementation of the bit-parallel algorithm.</summary>
+    fn core_myers<'a, T: Copy + Eq + Hash>(mut string1: &'a [T], mut string2: &'a [T], max_distance: usize) -> Option<usize> {
+        if string1.len() > string2.len() {
+            mem::swap(&mut string1, &mut string2);
+        }
+        if string2.len() - string1.len() > max_distance {
+            return None;
+        }
+        if string1.is_empty() {
+            return Some(string2.len());
+        }
+
+        // the positions in string1 of each of its graphemes
+        let mut peq: HashMap<T, u64> = HashMap::with_capacity(string1.len());
+        for (i, grapheme) in string1.iter().enumerate() {
+            *peq.entry(*grapheme).or_insert(0) |= 1 << i;
+        }
+        let eqs = string2.iter().map(|grapheme| peq.get(grapheme).cloned().unwrap_or(0));
+        Myers::core_columns(string1.len(), string2.len(), eqs, max_distance)
+    }
+
+    /// <summary>Processes the columns of the second string given, for each of its graphemes,
+    /// the bit mask of the positions in the first string where it occurs.</summary>
+    /// <remarks>len1 must be from 1 to MAX_LENGTH.</remarks>
+    fn core_columns(len1: usize, len2: usize, eqs: impl Iterator<Item=u64>, max_distance: usize) -> Option<usize> {
+        let last = 1u64 << (len1 - 1);
+        let mut positive_vertical = !0u64;
+        let mut negative_vertical = 0u64;
+        let mut score = len1;
+        for (j, eq) in eqs.enumerate() {
+            let x_vertical = eq | negative_vertical;
+            let x_horizontal = ((eq & positive_vertical).wrapping_add(positive_vertical) ^ positive_vertical) | eq;
+            let mut positive_horizontal = negative_vertical | !(x_horizontal | positive_vertical);
+            let mut negative_horizontal = positive_vertical & x_horizontal;
+            if positive_horizontal & last != 0 {
+                score += 1;
+            } else if negative_horizontal & last != 0 {
+                score -= 1;
+            }
+            // the score falls by at most one for each remaining grapheme
+            if score > max_distance.saturating_add(len2 - j - 1) {
+                return None;
+            }
+            // the first row of the matrix grows by one per column
+            positive_horizontal = (positive_horizontal << 1) | 1;
+            negative_horizontal <<= 1;
+            positive_vertical = negative_horizontal | !(x_vertical | positive_horizontal);
+            negative_vertical = positive_horizontal & x_vertical;
+        }
+        if score <= max_distance { Some(score) } else { None }
+    }
+}
+
+/// <summary>A string prepared once for bit-parallel Levenshtein comparisons against any
+/// number of other strings, e.g. a query against many candidates.</summary>
+/// <remarks>Only the prepared string is limited to MAX_LENGTH graphemes.</remarks>
+///
+/// example:
+/// let pattern = MyersPattern::new("kitten").unwrap();
+///
+/// assert_eq!(pattern.distance("sitting", 3), Some(3));
+pub struct MyersPattern {
+    len: usize,
+    // the positions of each grapheme, indexed by byte for ASCII graphemes
+    ascii: [u64; 128],
+    other: HashMap<String, u64>,
+}
+
+impl MyersPattern {
+    /// <summary>Prepare the string, None when it is longer than MAX_LENGTH graphemes.</summary>
+    pub fn new(string: &str) -> Option<MyersPattern> {
+        let mut pattern = MyersPattern {
+            len: 0,
+            ascii: [0; 128],
+            other: HashMap::new(),
+        };
+        for (grapheme, _) in GraphemeClusters::new(string) {
+            if pattern.len == MAX_LENGTH {
+                return None;
+            }
+            match grapheme.as_bytes() {
+                &[byte] if byte.is_ascii() => pattern.ascii[byte as usize] |= 1 << pattern.len,
+                _ => *pattern.other.entry(grapheme.to_string()).or_insert(0) |= 1 << pattern.len,
+            }
+            pattern.len += 1;
+        }
+        Some(pattern)
+    }
+
+    fn eq(&self, grapheme: &str) -> u64 {
+        match grapheme.as_bytes() {
+            &[byte] if byte.is_ascii() => self.ascii[byte as usize],
+            _ => self.other.get(grapheme).cloned().unwrap_or(0),
+        }
+    }
+
+    /// <summary>Compute the Levenshtein distance between the prepared string and another.</summary>
+    /// <returns>None if the distance is greater than maxDistance.</returns>
+    pub fn distance(&self, string: &str, max_distance: usize) -> Option<usize> {
+        let len = if string.is_ascii() { string.len() } else { GraphemeClusters::new(string).len() };
+        let len_diff = self.len.abs_diff(len);
+        if len_diff > max_distance {
+            return None;
+        }
+        if self.len == 0 {
+            return Some(len);
+        }
+        let eqs = GraphemeClusters::new(string).map(|(grapheme, _)| self.eq(grapheme));
+        Myers::core_columns(self.len, len, eqs, max_distance)
+    }
+}
+
+impl Default for Myers {
+    fn default() -> Myers {
+        Myers::new()
+    }
+}
+
+impl Similarity for Myers {
+    /// <summary>Return Levenshtein similarity between two strings
+    /// (1 - (levenshtein distance / len of longer string)).</summary>
+    /// <param name="string1">One of the strings to compare.</param>
+    /// <param name="string2">The other string to compare.</param>
+    /// <returns>The degree of similarity 0 to 1.0, where 0 represents a lack of any
+    /// notable similarity, and 1 represents equivalent strings. None when either
+    /// string is longer than MAX_LENGTH graphemes.</returns>
+    fn similarity<'a>(&mut self, string1: &'a str, string2: &'a str) -> Option<f64> {
+        similarity_from_distance(self, string1, string2, None)
+    }
+
+    /// <summary>Return Levenshtein similarity between two strings
+    /// (1 - (levenshtein distance / len of longer string)).</summary>
+    /// <param name="string1">One of the strings to compare.</param>
+    /// <param name="string2">The other string to compare.</param>
+    /// <param name="minSimilarity">The minimum similarity that is of interest.</param>
+    /// <returns>None if the similarity is lower than minSimilarity or either string is
+    /// longer than MAX_LENGTH graphemes, otherwise, a number between 0 and 1.0 where 0
+    /// represents a lack of any noteable similarity, and 1 represents equivalent strings.</returns>
+    fn similarity2<'a>(&mut self, string1: &'a str, string2: &'a str, min_similarity: f64) -> Option<f64> {
+        similarity_from_distance(self, string1, string2, Some(min_similarity))
+    }
+}
+
+impl Distance for Myers {
+    /// <summary>Compute and return the Levenshtein edit distance between two strings.</summary>
+    /// <param name="string1">One of the strings to compare.</param>
+    /// <param name="string2">The other string to compare.</param>
+    /// <returns>None when either string is longer than MAX_LENGTH graphemes, 0 if the
+    /// strings are equivalent, otherwise a positive number whose magnitude increases as
+    /// difference between the strings increases.</returns>
+    fn distance<'a>(&mut self, string1: &'a str, string2: &'a str) -> Option<usize> {
+        self.try_distance(string1, string2, usize::MAX).flatten()
+    }
+
+    /// <summary>Compute and return the Levenshtein edit distance between two strings.</summary>
+    /// <param name="string1">One of the strings to compare.</param>
+    /// <param name="string2">The other string to compare.</param>
+    /// <param name="maxDistance">The maximum distance that is of interest.</param>
+    /// <returns>None if the distance is greater than the maxDistance or either string is
+    /// longer than MAX_LENGTH graphemes, 0 if the strings are equivalent, otherwise a
+    /// positive number whose magnitude increases as difference between the strings increases.</returns>
+    fn distance2<'a>(&mut self, string1: &'a str, string2: &'a str, max_distance: usize) -> Option<usize> {
+        if string1.is_empty() || string2.is_empty() {
+            return null_distance_results(string1, string2, max_distance);
+        }
+        self.try_distance(string1, string2, max_distance).flatten()
+    }
+}
+
+#[cfg(test)]
+mod myers_tests {
+    use crate::soft_wx::Distance;
+    use crate::soft_wx::levensthtein::Levenshtein;
+    use crate::soft_wx::myers::{Myers, MyersPattern};
+    use crate::soft_wx::test_strings::random_strings;
+
+    #[test]
+    fn distance_matches_levenshtein_test() {
+        let mut myers = Myers::new();
+        let mut levenshtein = Levenshtein::new();
+        let mut strings = random_strings(60, 9);
+        strings.extend(random_strings(6, 64));
+        for string1 in &strings {
+            for string2 in &strings {
+                assert_eq!(myers.distance(string1, string2), levenshtein.distance(string1, string2), "{} {}", string1, string2);
+                for max_distance in 0..4 {
+                    assert_eq!(myers.distance2(string1, string2, max_distance), levenshtein.distance2(string1, string2, max_distance), "{} {} {}", string1, string2, max_distance);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn pattern_matches_levenshtein_test() {
+        let mut levenshtein = Levenshtein::new();
+        let mut strings = random_strings(40, 9);
+        strings.extend(random_strings(4, 64));
+        // only the prepared string is limited in length
+        let mut candidates = strings.clone();
+        candidates.push("a".repeat(100));
+        for string1 in &strings {
+            let pattern = MyersPattern::new(string1).unwrap();
+            for string2 in &candidates {
+                assert_eq!(pattern.distance(string2, usize::MAX), levenshtein.distance(string1, string2), "{} {}", string1, string2);
+                for max_distance in 0..4 {
+                    assert_eq!(pattern.distance(string2, max_distance), levenshtein.distance2(string1, string2, max_distance), "{} {} {}", string1, string2, max_distance);
+                }
+            }
+        }
+        assert!(MyersPattern::new(&"a".repeat(65)).is_none());
+    }
+
+    #[test]
+    fn max_length_test() {
+        let mut myers = Myers::new();
+        let fits = "a".repeat(64);
+        let too_long = "a".repeat(65);
+        assert_eq!(myers.try_distance(&fits, "b", usize::MAX), Some(Some(64)));
+        assert_eq!(myers.try_distance(&too_long, "a", usize::MAX), None);
+        assert_eq!(myers.try_distance("a", &too_long, usize::MAX), None);
+        assert_eq!(myers.distance(&too_long, "a"), None);
+    }
+}
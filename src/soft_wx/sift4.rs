@@ -0,0 +1,245 @@
+use crate::soft_wx::{Distance, Similarity};
+use crate::soft_wx::helpers::{graphemes, null_distance_results, similarity_from_distance, PreparedQuery};
+
+/// <summary>The max offset suggested by the author, enough for typical typos.</summary>
+pub const DEFAULT_MAX_OFFSET: usize = 5;
+
+pub struct Sift4 {
+    max_offset: usize,
+    // the matches that may still form transpositions with later ones
+    offsets: Vec<Offset>,
+}
+
+struct Offset {
+    c1: isize,
+    c2: isize,
+    transposition: bool,
+}
+
+/// <summary>
+/// Class providing methods for computing the Sift4 distance between two strings.
+/// </summary>
+/// <remarks>
+/// Sift4 walks both strings once, looking up to max offset graphemes ahead to realign them
+/// after a mismatch, and counts the common graphemes and transpositions it finds. It runs
+/// in close to linear time and is usually within one or two of the Damerau-Levenshtein
+/// distance, which suits large scale near-duplicate detection where an approximate
+/// distance is good enough. The running estimate is not a lower bound of the final
+/// distance, so the bounded distance always walks both strings to the end.
+/// See https://siderite.dev/blog/super-fast-and-accurate-string-distance.html
+/// The methods in this class are not threadsafe.</remarks>
+impl Sift4 {
+    /// <summary>Create a new instance of Sift4 using the default max offset of 5.</summary>
+    pub fn new() -> Sift4 {
+        Sift4::with_max_offset(DEFAULT_MAX_OFFSET)
+    }
+
+    /// <summary>Create a new instance of Sift4 using the specified max offset.</summary>
+    /// <param name="max_offset">How far ahead to search for a grapheme after a mismatch.
+    /// Larger offsets find more distant realignments at the cost of speed.</param>
+    pub fn with_max_offset(max_offset: usize) -> Sift4 {
+        Sift4 {
+            max_offset,
+            offsets: vec![],
+        }
+    }
+
+    pub fn max_offset(&self) -> usize {
+        self.max_offset
+    }
+
+    /// <summary>Internal implementation of the common Sift4 algorithm.</summary>
+    fn core_sift4<T: Copy + PartialEq>(&mut self, string1: &[T], string2: &[T], max_distance: usize) -> Option<usize> {
+        // at most the shorter length of graphemes are common so the distance is never less
+        if string1.len().abs_diff(string2.len()) > max_distance {
+            return None;
+        }
+        let len1 = string1.len() as isize;
+        let len2 = string2.len() as isize;
+        let max_offset = self.max_offset as isize;
+        // the cursors step back one before a realignment so they can briefly be -1
+        let mut c1: isize = 0;
+        let mut c2: isize = 0;
+        let mut lcss = 0;
+        let mut local_cs = 0;
+        let mut transpositions = 0;
+        self.offsets.clear();
+        while c1 < len1 && c2 < len2 {
+            if string1[c1 as usize] == string2[c2 as usize] {
+                local_cs += 1;
+                let mut is_transposition = false;
+                let mut i = 0;
+                while i < self.offsets.len() {
+                    let offset = &mut self.offsets[i];
+                    if c1 <= offset.c1 || c2 <= offset.c2 {
+                        is_transposition = (c2 - c1).abs() >= (offset.c2 - offset.c1).abs();
+                        if is_transposition {
+                            transpositions += 1;
+                        } else if !offset.transposition {
+                            offset.transposition = true;
+                            transpositions += 1;
+                        }
+                        break;
+                    } else if c1 > offset.c2 && c2 > offset.c1 {
+                        self.offsets.remove(i);
+                    } else {
+                        i += 1;
+                    }
+                }
+                self.offsets.push(Offset { c1, c2, transposition: is_transposition });
+            } else {
+                lcss += local_cs;
+                local_cs = 0;
+                if c1 != c2 {
+                    c1 = c1.min(c2);
+                    c2 = c1;
+                }
+                // look ahead in both strings for the grapheme the other is at
+                let mut i = 0;
+                while i < max_offset && (c1 + i < len1 || c2 + i < len2) {
+                    if c1 + i < len1 && string1[(c1 + i) as usize] == string2[c2 as usize] {
+                        c1 += i - 1;
+                        c2 -= 1;
+                        break;
+                    }
+                    if c2 + i < len2 && string1[c1 as usize] == string2[(c2 + i) as usize] {
+                        c1 -= 1;
+                        c2 += i - 1;
+                        break;
+                    }
+                    i += 1;
+                }
+            }
+            c1 += 1;
+            c2 += 1;
+            if c1 >= len1 || c2 >= len2 {
+                lcss += local_cs;
+                local_cs = 0;
+                c1 = c1.min(c2);
+                c2 = c1;
+            }
+        }
+        lcss += local_cs;
+        let distance = (string1.len().max(string2.len()) + transpositions).saturating_sub(lcss);
+        if distance <= max_distance { Some(distance) } else { None }
+    }
+
+    // distance over the bytes of ASCII strings or the graphemes of any others
+    fn sift4(&mut self, string1: &str, string2: &str, max_distance: usize) -> Option<usize> {
+        if string1.is_ascii() && string2.is_ascii() {
+            self.core_sift4(string1.as_bytes(), string2.as_bytes(), max_distance)
+        } else {
+            self.core_sift4(&graphemes(string1), &graphemes(string2), max_distance)
+        }
+    }
+}
+
+impl Default for Sift4 {
+    fn default() -> Sift4 {
+        Sift4::new()
+    }
+}
+
+impl Similarity for Sift4 {
+    /// <summary>Return Sift4 similarity between two strings
+    /// (1 - (sift4 distance / len of longer string)).</summary>
+    /// <param name="string1">One of the strings to compare.</param>
+    /// <param name="string2">The other string to compare.</param>
+    /// <returns>The degree of similarity 0 to 1.0, where 0 represents a lack of any
+    /// notable similarity, and 1 represents equivalent strings.</returns>
+    fn similarity<'a>(&mut self, string1: &'a str, string2: &'a str) -> Option<f64> {
+        similarity_from_distance(self, string1, string2, None)
+    }
+
+    /// <summary>Return Sift4 similarity between two strings
+    /// (1 - (sift4 distance / len of longer string)).</summary>
+    /// <param name="string1">One of the strings to compare.</param>
+    /// <param name="string2">The other string to compare.</param>
+    /// <param name="minSimilarity">The minimum similarity that is of interest.</param>
+    /// <returns>None if the similarity is lower than minSimilarity, otherwise, a
+    /// number between 0 and 1.0 where 0 represents a lack of any noteable
+    /// similarity, and 1 represents equivalent strings.</returns>
+    fn similarity2<'a>(&mut self, string1: &'a str, string2: &'a str, min_similarity: f64) -> Option<f64> {
+        similarity_from_distance(self, string1, string2, Some(min_similarity))
+    }
+}
+
+impl Distance for Sift4 {
+    /// <summary>Compute and return the Sift4 distance between two strings.</summary>
+    /// <remarks>This method is not threadsafe.</remarks>
+    /// <param name="string1">One of the strings to compare.</param>
+    /// <param name="string2">The other string to compare.</param>
+    /// <returns>0 if the strings are equivalent, otherwise a positive number whose
+    /// magnitude increases as difference between the strings increases.</returns>
+    fn distance<'a>(&mut self, string1: &'a str, string2: &'a str) -> Option<usize> {
+        self.distance2(string1, string2, usize::MAX)
+    }
+
+    /// <summary>Compute and return the Sift4 distance between two strings.</summary>
+    /// <remarks>This method is not threadsafe.</remarks>
+    /// <param name="string1">One of the strings to compare.</param>
+    /// <param name="string2">The other string to compare.</param>
+    /// <param name="maxDistance">The maximum distance that is of interest.</param>
+    /// <returns>None if the distance is greater than the maxDistance, 0 if the strings
+    /// are equivalent, otherwise a positive number whose magnitude increases as
+    /// difference between the strings increases.</returns>
+    fn distance2<'a>(&mut self, string1: &'a str, string2: &'a str, max_distance: usize) -> Option<usize> {
+        if string1.is_empty() || string2.is_empty() {
+            return null_distance_results(string1, string2, max_distance);
+        }
+        if string1 == string2 {
+            return Some(0);
+        }
+        self.sift4(string1, string2, max_distance)
+    }
+
+    /// <summary>Sift4 distance2 of a query split once.</summary>
+    fn distance_prepared(&mut self, query: &PreparedQuery, string2: &str, max_distance: Option<usize>) -> Option<usize> {
+        let max_distance = max_distance.unwrap_or(usize::MAX);
+        if query.text().is_empty() || string2.is_empty() {
+            return null_distance_results(query.text(), string2, max_distance);
+        }
+        if query.text() == string2 {
+            return Some(0);
+        }
+        if query.text().is_ascii() && string2.is_ascii() {
+            self.core_sift4(query.text().as_bytes(), string2.as_bytes(), max_distance)
+        } else {
+            self.core_sift4(query.graphemes(), &graphemes(string2), max_distance)
+        }
+    }
+}
+
+#[cfg(test)]
+mod sift4_tests {
+    use crate::soft_wx::{Distance, Similarity};
+    use crate::soft_wx::sift4::Sift4;
+
+    #[test]
+    fn distance_test() {
+        let mut sift4 = Sift4::new();
+        // expected values are from the author's JavaScript implementation
+        assert_eq!(sift4.distance("This is the first string", "And this is another string"), Some(11));
+        assert_eq!(sift4.distance("kitten", "sitting"), Some(3));
+        assert_eq!(sift4.distance("ab", "ba"), Some(1));
+        assert_eq!(sift4.distance("abcdef", "abdcef"), Some(1));
+        assert_eq!(sift4.distance("ca", "abc"), Some(2));
+        assert_eq!(sift4.distance("spelling", "speling"), Some(1));
+        assert_eq!(sift4.distance("abc", "abc"), Some(0));
+        assert_eq!(sift4.distance("", "abc"), Some(3));
+        assert_eq!(sift4.distance("🚀é", "é🚀"), Some(1));
+
+        let mut wide = Sift4::with_max_offset(10);
+        assert_eq!(wide.distance("Lorem ipsum dolor sit amet, consectetur adipiscing elit.", "Amet Lorm ispum dolor sit amet, consetetur adixxxpiscing elit."), Some(12));
+    }
+
+    #[test]
+    fn bounded_distance_test() {
+        let mut sift4 = Sift4::new();
+        assert_eq!(sift4.distance2("kitten", "sitting", 3), Some(3));
+        assert_eq!(sift4.distance2("kitten", "sitting", 2), None);
+        assert_eq!(sift4.distance2("abc", "", 2), None);
+        assert_eq!(sift4.distance2("abc", "abc", 0), Some(0));
+        assert_eq!(sift4.similarity("ab", "ba"), Some(0.5));
+    }
+}
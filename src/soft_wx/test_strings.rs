@@ -0,0 +1,15 @@
+/// Deterministic pseudo random strings of up to `max_len` graphemes, mixing
+/// ASCII, an accented letter and an emoji, so failures of the distance tests
+/// are reproducible.
+pub(crate) fn random_strings(count: usize, max_len: usize) -> Vec<String> {
+    let alphabet = ['a', 'b', 'c', 'é', '🚀'];
+    let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = move || {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (seed >> 33) as usize
+    };
+    (0..count).map(|_| {
+        let len = next() % (max_len + 1);
+        (0..len).map(|_| alphabet[next() % alphabet.len()]).collect()
+    }).collect()
+}
@@ -0,0 +1,264 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::grapheme_iterator::GraphemeClusters;
+use crate::soft_wx::{Distance, Similarity};
+use crate::soft_wx::helpers::{graphemes, similarity_from_distance, PreparedQuery};
+
+// absorbs rounding error in sums of fractional costs before rounding up
+const EPSILON: f64 = 1e-9;
+
+/// <summary>The cost of each edit used by WeightedLevenshtein.</summary>
+/// <remarks>Costs describe transforming the first string into the second, which for
+/// lookups is the input into a dictionary word, e.g. a substitution from "rn" to "m"
+/// is an OCR reading "m" as "rn". Edits without a specific cost use the defaults of 1.
+/// </remarks>
+///
+/// example:
+/// let costs = WeightedCosts::new()
+///     .with_substitution("rn", "m", 0.3)
+///     .with_deletion("h", 0.5);
+#[derive(Clone, Debug)]
+pub struct WeightedCosts {
+    insertion: f64,
+    deletion: f64,
+    substitution: f64,
+    insertions: HashMap<String, f64>,
+    deletions: HashMap<String, f64>,
+    substitutions: HashMap<String, HashMap<String, f64>>,
+    // substitutions where either side spans more than one grapheme
+    sequence_substitutions: Vec<(Vec<String>, Vec<String>, f64)>,
+}
+
+impl WeightedCosts {
+    /// <summary>Create costs where every edit costs 1, equivalent to Levenshtein.</summary>
+    pub fn new() -> WeightedCosts {
+        WeightedCosts {
+            insertion: 1.0,
+            deletion: 1.0,
+            substitution: 1.0,
+            insertions: HashMap::new(),
+            deletions: HashMap::new(),
+            substitutions: HashMap::new(),
+            sequence_substitutions: vec![],
+        }
+    }
+
+    /// <summary>Set the costs of the edits that have no specific cost.</summary>
+    pub fn with_defaults(mut self, insertion: f64, deletion: f64, substitution: f64) -> WeightedCosts {
+        self.insertion = insertion;
+        self.deletion = deletion;
+        self.substitution = substitution;
+        self
+    }
+
+    /// <summary>Set the cost of inserting a grapheme.</summary>
+    pub fn with_insertion(mut self, grapheme: &str, cost: f64) -> WeightedCosts {
+        self.insertions.insert(grapheme.to_string(), cost);
+        self
+    }
+
+    /// <summary>Set the cost of deleting a grapheme.</summary>
+    pub fn with_deletion(mut self, grapheme: &str, cost: f64) -> WeightedCosts {
+        self.deletions.insert(grapheme.to_string(), cost);
+        self
+    }
+
+    /// <summary>Set the cost of replacing `from` with `to`. Either may span several graphemes.</summary>
+    pub fn with_substitution(mut self, from: &str, to: &str, cost: f64) -> WeightedCosts {
+        let from_gc: Vec<String> = GraphemeClusters::new(from).map(|(grapheme, _)| grapheme.to_string()).collect();
+        let to_gc: Vec<String> = GraphemeClusters::new(to).map(|(grapheme, _)| grapheme.to_string()).collect();
+        if from_gc.len() == 1 && to_gc.len() == 1 {
+            self.substitutions.entry(from.to_string()).or_default().insert(to.to_string(), cost);
+        } else if !from_gc.is_empty() && !to_gc.is_empty() {
+            self.sequence_substitutions.push((from_gc, to_gc, cost));
+        }
+        self
+    }
+
+    fn insertion(&self, grapheme: &str) -> f64 {
+        self.insertions.get(grapheme).cloned().unwrap_or(self.insertion)
+    }
+
+    fn deletion(&self, grapheme: &str) -> f64 {
+        self.deletions.get(grapheme).cloned().unwrap_or(self.deletion)
+    }
+
+    fn substitution(&self, from: &str, to: &str) -> f64 {
+        if from == to {
+            return 0.0;
+        }
+        self.substitutions.get(from).and_then(|costs| costs.get(to)).cloned().unwrap_or(self.substitution)
+    }
+}
+
+impl Default for WeightedCosts {
+    fn default() -> WeightedCosts {
+        WeightedCosts::new()
+    }
+}
+
+pub struct WeightedLevenshtein {
+    costs: Arc<WeightedCosts>,
+    matrix: Vec<f64>,
+}
+
+/// <summary>
+/// Class providing methods for computing a Levenshtein distance where each insertion,
+/// deletion and substitution has its own cost.
+/// </summary>
+/// <remarks>
+/// Useful when errors are not equally likely, e.g. keyboard neighbours or OCR confusions
+/// such as "rn" for "m". The `Distance` methods round the weighted cost up so a change
+/// always has a distance of at least 1; use `weighted_distance` for the exact cost.
+/// SymSpell only considers dictionary words within its delete based edit distance, so
+/// lowering costs below 1 re-ranks candidates rather than finding more distant ones.
+/// The methods in this class are not threadsafe.</remarks>
+impl WeightedLevenshtein {
+    /// <summary>Create a new instance of WeightedLevenshtein using the specified costs.</summary>
+    pub fn new(costs: Arc<WeightedCosts>) -> WeightedLevenshtein {
+        WeightedLevenshtein {
+            costs,
+            matrix: vec![],
+        }
+    }
+
+    /// <summary>Compute the weighted cost of transforming string1 into string2.</summary>
+    pub fn weighted_distance(&mut self, string1: &str, string2: &str) -> f64 {
+        let string1_gc = graphemes(string1);
+        let string2_gc = graphemes(string2);
+        WeightedLevenshtein::core_weighted(&string1_gc, &string2_gc, &self.costs, &mut self.matrix)
+    }
+
+    /// <summary>Internal implementation of the weighted Wagner-Fischer algorithm.</summary>
+    fn core_weighted(string1: &[&str], string2: &[&str], costs: &WeightedCosts, matrix: &mut Vec<f64>) -> f64 {
+        let width = string2.len() + 1;
+        matrix.clear();
+        matrix.resize((string1.len() + 1) * width, 0.0);
+        for j in 1..=string2.len() {
+            matrix[j] = matrix[j - 1] + costs.insertion(string2[j - 1]);
+        }
+        for i in 1..=string1.len() {
+            matrix[i * width] = matrix[(i - 1) * width] + costs.deletion(string1[i - 1]);
+            for j in 1..=string2.len() {
+                let mut cost = (matrix[(i - 1) * width + j - 1] + costs.substitution(string1[i - 1], string2[j - 1]))
+                    .min(matrix[(i - 1) * width + j] + costs.deletion(string1[i - 1]))
+                    .min(matrix[i * width + j - 1] + costs.insertion(string2[j - 1]));
+                for (from, to, sequence_cost) in &costs.sequence_substitutions {
+                    if from.len() <= i && to.len() <= j
+                        && string1[i - from.len()..i].iter().zip(from).all(|(a, b)| a == b)
+                        && string2[j - to.len()..j].iter().zip(to).all(|(a, b)| a == b) {
+                        cost = cost.min(matrix[(i - from.len()) * width + j - to.len()] + sequence_cost);
+                    }
+                }
+                matrix[i * width + j] = cost;
+            }
+        }
+        matrix[string1.len() * width + string2.len()]
+    }
+}
+
+impl Similarity for WeightedLevenshtein {
+    /// <summary>Return weighted Levenshtein similarity between two strings
+    /// (1 - (rounded weighted distance / len of longer string)).</summary>
+    /// <param name="string1">One of the strings to compare.</param>
+    /// <param name="string2">The other string to compare.</param>
+    /// <returns>The degree of similarity 0 to 1.0, where 0 represents a lack of any
+    /// notable similarity, and 1 represents equivalent strings.</returns>
+    fn similarity<'a>(&mut self, string1: &'a str, string2: &'a str) -> Option<f64> {
+        similarity_from_distance(self, string1, string2, None)
+    }
+
+    /// <summary>Return weighted Levenshtein similarity between two strings
+    /// (1 - (rounded weighted distance / len of longer string)).</summary>
+    /// <param name="string1">One of the strings to compare.</param>
+    /// <param name="string2">The other string to compare.</param>
+    /// <param name="minSimilarity">The minimum similarity that is of interest.</param>
+    /// <returns>None if the similarity is lower than minSimilarity, otherwise, a
+    /// number between 0 and 1.0 where 0 represents a lack of any noteable
+    /// similarity, and 1 represents equivalent strings.</returns>
+    fn similarity2<'a>(&mut self, string1: &'a str, string2: &'a str, min_similarity: f64) -> Option<f64> {
+        similarity_from_distance(self, string1, string2, Some(min_similarity))
+    }
+}
+
+impl Distance for WeightedLevenshtein {
+    /// <summary>Compute the weighted cost of transforming string1 into string2, rounded up.</summary>
+    /// <remarks>This method is not threadsafe.</remarks>
+    /// <param name="string1">One of the strings to compare.</param>
+    /// <param name="string2">The other string to compare.</param>
+    /// <returns>0 if the strings are equivalent, otherwise a positive number whose
+    /// magnitude increases as difference between the strings increases.</returns>
+    fn distance<'a>(&mut self, string1: &'a str, string2: &'a str) -> Option<usize> {
+        if string1 == string2 {
+            return Some(0);
+        }
+        let weighted = self.weighted_distance(string1, string2);
+        Some(((weighted - EPSILON).ceil().max(1.0)) as usize)
+    }
+
+    /// <summary>Compute the weighted cost of transforming string1 into string2, rounded up.</summary>
+    /// <remarks>This method is not threadsafe.</remarks>
+    /// <param name="string1">One of the strings to compare.</param>
+    /// <param name="string2">The other string to compare.</param>
+    /// <param name="maxDistance">The maximum distance that is of interest.</param>
+    /// <returns>None if the distance is greater than the maxDistance, 0 if the strings
+    /// are equivalent, otherwise a positive number whose magnitude increases as
+    /// difference between the strings increases.</returns>
+    fn distance2<'a>(&mut self, string1: &'a str, string2: &'a str, max_distance: usize) -> Option<usize> {
+        self.distance(string1, string2).filter(|&distance| distance <= max_distance)
+    }
+
+    /// <summary>Weighted distance2 of a query split once.</summary>
+    fn distance_prepared(&mut self, query: &PreparedQuery, string2: &str, max_distance: Option<usize>) -> Option<usize> {
+        if query.text() == string2 {
+            return Some(0);
+        }
+        let weighted = WeightedLevenshtein::core_weighted(query.graphemes(), &graphemes(string2), &self.costs, &mut self.matrix);
+        Some(((weighted - EPSILON).ceil().max(1.0)) as usize).filter(|&distance| distance <= max_distance.unwrap_or(usize::MAX))
+    }
+}
+
+#[cfg(test)]
+mod weighted_levenshtein_tests {
+    use std::sync::Arc;
+
+    use crate::soft_wx::Distance;
+    use crate::soft_wx::levensthtein::Levenshtein;
+    use crate::soft_wx::weighted_levenshtein::{WeightedCosts, WeightedLevenshtein};
+
+    #[test]
+    fn default_costs_match_levenshtein_test() {
+        let mut weighted = WeightedLevenshtein::new(Arc::new(WeightedCosts::new()));
+        let mut levenshtein = Levenshtein::new();
+        let words = ["", "a", "abc", "acb", "kitten", "sitting", "héllo", "🚀hello"];
+        for string1 in words.iter() {
+            for string2 in words.iter() {
+                assert_eq!(weighted.distance(string1, string2), levenshtein.distance(string1, string2), "{} {}", string1, string2);
+                assert_eq!(weighted.distance2(string1, string2, 2), levenshtein.distance2(string1, string2, 2), "{} {}", string1, string2);
+            }
+        }
+    }
+
+    #[test]
+    fn weighted_distance_test() {
+        let costs = WeightedCosts::new()
+            .with_substitution("rn", "m", 0.3)
+            .with_substitution("e", "r", 0.4)
+            .with_deletion("h", 0.5)
+            .with_insertion("u", 2.0);
+        let mut weighted = WeightedLevenshtein::new(Arc::new(costs));
+
+        assert!((weighted.weighted_distance("rnodel", "model") - 0.3).abs() < 1e-9);
+        assert!((weighted.weighted_distance("fiee", "fier") - 0.4).abs() < 1e-9);
+        // substitutions are directional, from the first string to the second
+        assert_eq!(weighted.weighted_distance("fier", "fiee"), 1.0);
+        assert_eq!(weighted.weighted_distance("ab", "aub"), 2.0);
+        assert_eq!(weighted.weighted_distance("hat", "at"), 0.5);
+
+        assert_eq!(weighted.distance("rnodel", "model"), Some(1));
+        assert_eq!(weighted.distance("rnirnic", "mimic"), Some(1));
+        assert_eq!(weighted.distance("model", "model"), Some(0));
+        assert_eq!(weighted.distance2("ab", "aub", 1), None);
+    }
+}
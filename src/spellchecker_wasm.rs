@@ -1,90 +1,2202 @@
-use std::cell::RefCell;
-use std::mem::transmute;
+use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+#[cfg(all(feature = "small-binary", target_arch = "wasm32"))]
+use std::panic;
+use std::ptr;
 use std::slice;
-use std::str;
-use std::mem;
+use std::str::{self, Utf8Error};
+#[cfg(all(feature = "small-binary", target_arch = "wasm32"))]
+use std::sync::Once;
 
-use crate::sym_spell::Encode;
+use crate::edit_distance::DistanceAlgorithm;
+use crate::filters::TextFilters;
+use crate::grapheme_iterator::decode_utf8;
+use crate::markup::Markup;
+use crate::soft_wx::sift4::DEFAULT_MAX_OFFSET;
+use crate::sym_spell::{Count, Encode};
+use crate::sym_spell::capabilities::Capabilities;
+use crate::sym_spell::compound_options::CompoundOptions;
+use crate::sym_spell::corpus_trainer::CorpusTrainer;
+use crate::sym_spell::delete_generation::DeleteGeneration;
+use crate::sym_spell::check_delta::CheckDelta;
+use crate::sym_spell::equivalences::Equivalences;
+use crate::sym_spell::incremental_checker::IncrementalChecker;
+use crate::sym_spell::issue_kind::IssueKind;
+use crate::sym_spell::log_event::LogEvent;
+#[cfg(feature = "log-handler")]
+use crate::sym_spell::logger::Logger;
+use crate::sym_spell::lookup_limits::LookupLimits;
+use crate::sym_spell::misspelled_range::MisspelledRange;
+use crate::sym_spell::result_frame::ResultFrame;
+use crate::sym_spell::spellchecker_error::SpellcheckerError;
+use crate::sym_spell::suggest_ref::AsSuggestRef;
 use crate::sym_spell::suggested_item::SuggestItem;
 use crate::sym_spell::sym_spell::SymSpell;
+use crate::sym_spell::trainer_options::TrainerOptions;
 use crate::sym_spell::verbosity::Verbosity;
+use crate::utils::CaseLocale;
 
-static mut BUFFER: Option<RefCell<Vec<u8>>> = None;
-static mut SYM: Option<RefCell<SymSpell>> = None;
+thread_local! {
+    static BUFFER: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+    static SYM: RefCell<Option<SymSpell>> = const { RefCell::new(None) };
+    static RESULT: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+    static OUTPUT_FORMAT: Cell<OutputFormat> = const { Cell::new(OutputFormat::Binary) };
+    static INVALID_UTF8_POLICY: Cell<InvalidUtf8Policy> = const { Cell::new(InvalidUtf8Policy::Reject) };
+    static INVALID_UTF8_OFFSET: Cell<Option<usize>> = const { Cell::new(None) };
+    static LAST_ERROR: RefCell<Option<(u32, String)>> = const { RefCell::new(None) };
+    static DOCUMENTS: RefCell<HashMap<u32, IncrementalChecker>> = RefCell::new(HashMap::new());
+    static NEXT_DOCUMENT: Cell<u32> = const { Cell::new(1) };
+    static TRAINER: RefCell<Option<CorpusTrainer>> = const { RefCell::new(None) };
+    static LOAD_BUDGET: Cell<usize> = const { Cell::new(0) };
+    static LOAD_STOPPED: Cell<bool> = const { Cell::new(false) };
+    #[cfg(feature = "result-handler")]
+    static BATCH_SIZE: Cell<usize> = const { Cell::new(0) };
+    #[cfg(feature = "log-handler")]
+    static LOGGING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Incremented whenever an existing export or the payload layout changes
+/// in a way that is not backward compatible.
+pub const ABI_VERSION: u32 = 3;
+
+/// Bit flags reported by `capabilities()`.
+pub const CAPABILITY_BIGRAMS: u32 = 1;
+pub const CAPABILITY_COMPOUND: u32 = 1 << 1;
+pub const CAPABILITY_SEGMENTATION: u32 = 1 << 2;
+pub const CAPABILITY_JSON_OUTPUT: u32 = 1 << 3;
+pub const CAPABILITY_HANDLES: u32 = 1 << 4;
+pub const CAPABILITY_RESULT_HANDLER: u32 = 1 << 5;
+pub const CAPABILITY_UTF16: u32 = 1 << 6;
+pub const CAPABILITY_SMALL_BINARY: u32 = 1 << 7;
+pub const CAPABILITY_PARALLEL: u32 = 1 << 8;
+pub const CAPABILITY_FRAMED_OUTPUT: u32 = 1 << 9;
+pub const CAPABILITY_ERROR_CODES: u32 = 1 << 10;
+pub const CAPABILITY_LOG_HANDLER: u32 = 1 << 11;
+pub const CAPABILITY_METRICS: u32 = 1 << 12;
+
+/// Option flags accepted by `correct_text()`.
+pub const CORRECT_TEXT_PRESERVE_PROPER_NOUNS: u32 = 1;
+pub const CORRECT_TEXT_TRANSFER_CASING: u32 = 1 << 1;
+
+/// The encoding used for result payloads.
+#[derive(Clone, Copy, PartialEq)]
+#[repr(u32)]
+pub enum OutputFormat {
+    /// Compact little endian encoding produced by `Encode` (default).
+    Binary = 0,
+    /// UTF-8 JSON for hosts that would rather not decode the binary layout.
+    Json = 1,
+    /// The versioned, self describing `ResultFrame`, which native Rust hosts can parse
+    /// with `ResultFrame::decode`.
+    Framed = 2,
+}
+
+/// How the exports treat text input that is not valid utf-8.
+#[derive(Clone, Copy, PartialEq)]
+#[repr(u32)]
+pub enum InvalidUtf8Policy {
+    /// Skip the call, recording the offset reported by `invalid_utf8_offset()` (default).
+    Reject = 0,
+    /// Replace each invalid sequence with U+FFFD and carry on.
+    Replace = 1,
+}
 
+/// The encoding of the terms, or the whole text for JSON, within a result payload.
+#[derive(Clone, Copy, PartialEq)]
+pub enum TextEncoding {
+    Utf8,
+    /// UTF-16LE, matching JS strings so hosts can decode without transcoding.
+    Utf16,
+}
+
+/// Runs the supplied closure against the SymSpell instance
+/// created by the `symspell` export.
+fn with_sym<R>(f: impl FnOnce(&mut SymSpell) -> R) -> R {
+    SYM.with(|sym_cell| {
+        let mut sym = sym_cell.borrow_mut();
+        f(sym.as_mut().expect("symspell() must be called before any other export"))
+    })
+}
+
+/// The version of the export signatures and payload layout of this build.
 #[no_mangle]
-pub unsafe extern fn symspell(max_dictionary_edit_distance: usize, count_threshold: usize) {
-    let sym = SymSpell::new(Some(max_dictionary_edit_distance), Some(7), Some(count_threshold));
+pub extern "C" fn abi_version() -> u32 {
+    ABI_VERSION
+}
 
-    SYM = Some(RefCell::new(sym));
-    BUFFER = Some(RefCell::new(Vec::new()));
+/// The `CAPABILITY_*` flags supported by this build, including the optional
+/// features it was compiled with, so hosts can feature detect rather than
+/// probe for exports. Segmentation is reserved for exports this build does
+/// not provide yet.
+#[no_mangle]
+pub extern "C" fn capabilities() -> u32 {
+    let compiled = Capabilities::compiled();
+    let optional = [
+        (compiled.result_handler, CAPABILITY_RESULT_HANDLER),
+        (compiled.small_binary, CAPABILITY_SMALL_BINARY),
+        (compiled.parallel, CAPABILITY_PARALLEL),
+        (compiled.log_handler, CAPABILITY_LOG_HANDLER),
+        (compiled.metrics, CAPABILITY_METRICS),
+    ];
+    optional.iter()
+        .filter(|(enabled, _)| *enabled)
+        .fold(CAPABILITY_BIGRAMS | CAPABILITY_COMPOUND | CAPABILITY_JSON_OUTPUT | CAPABILITY_UTF16 | CAPABILITY_HANDLES | CAPABILITY_FRAMED_OUTPUT | CAPABILITY_ERROR_CODES, |capabilities, (_, flag)| capabilities | flag)
 }
 
+/// Creates the SymSpell instance with the default prefix length of 7, or one more
+/// than the edit distance for edit distances of 7 and more.
 #[no_mangle]
-pub unsafe extern fn write_to_dictionary(ptr: *const u8, length: usize, is_bigram: bool) {
-    let buffer_cell = BUFFER.as_ref().unwrap();
-    let sym_cell = SYM.as_ref().unwrap();
-    let mut sym = sym_cell.borrow_mut();
-    let mut buffer = buffer_cell.borrow_mut();
-    buffer.extend_from_slice(slice::from_raw_parts(ptr, length));
+pub extern "C" fn symspell(max_dictionary_edit_distance: usize, count_threshold: usize) {
+    let prefix_length = max_dictionary_edit_distance.saturating_add(1).max(7);
+    symspell_with_options(max_dictionary_edit_distance, count_threshold, prefix_length);
+}
 
-    let len = buffer.len();
-    let mut cursor: usize = 0;
-    for i in 0..len {
-        let ch = buffer[i];
-        if ch == b'\n' {
-            if i > 1 {
-                let chunk = str::from_utf8_unchecked(&buffer[cursor..i - 1]);  // do not write the '\n' char
-                if is_bigram {
-                    sym.write_line_to_bigram_dictionary(chunk, " ");
-                } else {
-                    sym.write_line_to_dictionary(chunk, " ");
-                }
-            }
-            cursor = i + 1; // skip the '\n' char for the next iteration
+/// The approximate bytes the index of `word_count` words takes with the edit
+/// distance and prefix length, saturating at the 4 GiB of wasm memory, see
+/// `SymSpell::estimate_memory`. Call it before `symspell_with_options` to choose
+/// settings that fit.
+#[no_mangle]
+pub extern "C" fn estimate_memory(max_dictionary_edit_distance: usize, prefix_length: usize, word_count: usize) -> usize {
+    SymSpell::estimate_memory(max_dictionary_edit_distance, prefix_length, word_count)
+}
+
+/// Creates the SymSpell instance with an explicit prefix length, the main
+/// memory/speed trade-off. Returns false, leaving any existing instance in
+/// place, when the prefix length is not greater than the edit distance.
+#[no_mangle]
+pub extern "C" fn symspell_with_options(max_dictionary_edit_distance: usize, count_threshold: usize, prefix_length: usize) -> bool {
+    #[cfg(all(feature = "small-binary", target_arch = "wasm32"))]
+    install_panic_hook();
+    let count_threshold = Count::try_from(count_threshold).unwrap_or(Count::MAX);
+    let sym = match SymSpell::try_new(Some(max_dictionary_edit_distance), Some(prefix_length), Some(count_threshold)) {
+        Ok(sym) => sym,
+        Err(err) => {
+            record_error(err);
+            return false;
+        }
+    };
+
+    SYM.with(|sym_cell| sym_cell.replace(Some(sym)));
+    LOAD_STOPPED.with(|stopped| stopped.set(false));
+    // the new instance keeps the host logging if it was enabled
+    #[cfg(feature = "log-handler")]
+    logging(LOGGING.with(Cell::get));
+    BUFFER.with(|buffer_cell| buffer_cell.replace(Vec::new()));
+    true
+}
+
+/// Selects the algorithm used to verify edit distances: 0 for Damerau OSA
+/// (the default), 1 for Levenshtein, 2 for unrestricted Damerau, 3 for Sift4
+/// and 4 for the longest common subsequence distance.
+/// Returns false for an unknown algorithm.
+#[no_mangle]
+pub extern "C" fn distance_algorithm(algorithm: u32) -> bool {
+    let algorithm = match algorithm {
+        0 => DistanceAlgorithm::DamaerauOSA,
+        1 => DistanceAlgorithm::Levenshtein,
+        2 => DistanceAlgorithm::Damerau,
+        3 => DistanceAlgorithm::Sift4(DEFAULT_MAX_OFFSET),
+        4 => DistanceAlgorithm::LongestCommonSubsequence,
+        _ => return false,
+    };
+    with_sym(|sym| sym.set_distance_algorithm(algorithm));
+    true
+}
+
+/// Selects the casing rules used when lowercasing words for lookup: 0 for
+/// the Unicode defaults (which also cover Greek) and 1 for Turkish and
+/// Azerbaijani. Returns false for an unknown locale.
+#[no_mangle]
+pub extern "C" fn case_locale(locale: u32) -> bool {
+    let locale = match locale {
+        0 => CaseLocale::Default,
+        1 => CaseLocale::Turkic,
+        _ => return false,
+    };
+    with_sym(|sym| sym.set_case_locale(locale));
+    true
+}
+
+/// Treats "ß" and "ss", "æ" and "ae", and "œ" and "oe" as interchangeable
+/// at no edit cost when enabled, see `Equivalences::common`.
+#[no_mangle]
+pub extern "C" fn interchangeable_spellings(enabled: bool) {
+    let equivalences = if enabled { Equivalences::common() } else { Equivalences::new() };
+    with_sym(|sym| sym.set_equivalences(equivalences));
+}
+
+/// Selects the markup skipped by `check_text`: 0 for plain text, 1 for
+/// markdown and 2 for HTML, see `Markup`. Returns false for an unknown markup.
+#[no_mangle]
+pub extern "C" fn markup(markup: u32) -> bool {
+    let markup = match markup {
+        0 => Markup::PlainText,
+        1 => Markup::Markdown,
+        2 => Markup::Html,
+        _ => return false,
+    };
+    with_sym(|sym| sym.set_markup(markup));
+    true
+}
+
+/// Skips URLs, email addresses, numbers, hex strings, mentions, hashtags and
+/// code identifiers in `check_text` when enabled, the default, see `TextFilters::common`.
+/// Drops any patterns added with `text_filter_pattern`.
+#[no_mangle]
+pub extern "C" fn text_filters(enabled: bool) {
+    let text_filters = if enabled { TextFilters::common() } else { TextFilters::new() };
+    with_sym(|sym| sym.set_text_filters(text_filters));
+}
+
+/// Also skips the text matching a wildcard pattern in `check_text`, e.g.
+/// "JIRA-*", see `TextFilters::with_pattern`. Returns false, adding nothing, when
+/// the pattern is not utf-8.
+///
+/// # Safety
+///
+/// `ptr` must point to `length` readable bytes of utf-8 encoded text.
+#[no_mangle]
+pub unsafe extern "C" fn text_filter_pattern(ptr: *const u8, length: usize) -> bool {
+    let pattern = match str::from_utf8(slice::from_raw_parts(ptr, length)) {
+        Ok(pattern) => pattern,
+        Err(_) => return false,
+    };
+    with_sym(|sym| sym.set_text_filters(sym.text_filters().clone().with_pattern(pattern)));
+    true
+}
+
+/// Accepts the word as correctly spelled for the session without adding it to
+/// the dictionary, e.g. for "Ignore All", see `SymSpell::ignore_word`. Returns
+/// false, ignoring nothing, when the word is not utf-8.
+///
+/// # Safety
+///
+/// `ptr` must point to `length` readable bytes of utf-8 encoded text.
+#[no_mangle]
+pub unsafe extern "C" fn ignore_word(ptr: *const u8, length: usize) -> bool {
+    match str::from_utf8(slice::from_raw_parts(ptr, length)) {
+        Ok(word) => with_sym(|sym| sym.ignore_word(word)),
+        Err(_) => return false,
+    }
+    true
+}
+
+/// Stops accepting a word passed to `ignore_word`. Returns false when it was
+/// not ignored.
+///
+/// # Safety
+///
+/// `ptr` must point to `length` readable bytes of utf-8 encoded text.
+#[no_mangle]
+pub unsafe extern "C" fn unignore_word(ptr: *const u8, length: usize) -> bool {
+    match str::from_utf8(slice::from_raw_parts(ptr, length)) {
+        Ok(word) => with_sym(|sym| sym.unignore_word(word)),
+        Err(_) => false,
+    }
+}
+
+/// Adds an occurrence of a word learned at runtime, e.g. from the user's "Add to
+/// dictionary", that `decay_counts` fades again unless it is learned again, see
+/// `SymSpell::learn_word`. Returns true when the word became a dictionary word and
+/// false for a word that is not utf-8.
+///
+/// # Safety
+///
+/// `ptr` must point to `length` readable bytes of utf-8 encoded text.
+#[no_mangle]
+pub unsafe extern "C" fn learn_word(ptr: *const u8, length: usize) -> bool {
+    match str::from_utf8(slice::from_raw_parts(ptr, length)) {
+        Ok(word) => with_sym(|sym| sym.learn_word(word)),
+        Err(_) => false,
+    }
+}
+
+/// Multiplies the counts added by `learn_word` by the factor, between 0 and 1,
+/// see `SymSpell::decay_counts`.
+#[no_mangle]
+pub extern "C" fn decay_counts(factor: f64) {
+    with_sym(|sym| sym.decay_counts(factor));
+}
+
+/// Drops the learned words whose count decayed to 0 from the index, returning how
+/// many, see `SymSpell::purge_faded`.
+#[no_mangle]
+pub extern "C" fn purge_faded() -> usize {
+    with_sym(SymSpell::purge_faded)
+}
+
+/// Stops accepting every word passed to `ignore_word`.
+#[no_mangle]
+pub extern "C" fn clear_ignored() {
+    with_sym(SymSpell::clear_ignored);
+}
+
+/// Precomputes only the deletes of up to `precomputed_edits` edits, generating
+/// the rest during lookups and caching `cache_capacity` of them, see
+/// `DeleteGeneration::OnDemand`. Call it before writing to the dictionary.
+#[no_mangle]
+pub extern "C" fn on_demand_deletes(precomputed_edits: usize, cache_capacity: usize) {
+    with_sym(|sym| sym.set_delete_generation(DeleteGeneration::OnDemand { precomputed_edits, cache_capacity }));
+}
+
+/// Caches the results of the last `capacity` distinct lookups, 0 to disable
+/// the cache, see `SymSpell::set_lookup_cache_capacity`. Resets the counters.
+#[no_mangle]
+pub extern "C" fn lookup_cache(capacity: usize) {
+    with_sym(|sym| sym.set_lookup_cache_capacity(capacity));
+}
+
+/// Sets the factor a suggestion's probability is multiplied by for every edit,
+/// see `SymSpell::set_edit_decay`.
+#[no_mangle]
+pub extern "C" fn edit_decay(decay: f64) {
+    with_sym(|sym| sym.set_edit_decay(decay));
+}
+
+/// Sets the number of words of the corpus the counts come from, 0 for the sum of
+/// the loaded counts, see `SymSpell::set_corpus_size`.
+#[no_mangle]
+pub extern "C" fn corpus_size(corpus_size: f64) {
+    with_sym(|sym| sym.set_corpus_size(Some(corpus_size)));
+}
+
+/// Checks the words of the documents against a Bloom filter of the dictionary
+/// with the false positive rate before the dictionary, 0 to disable it, see
+/// `SymSpell::set_bloom_filter`.
+#[no_mangle]
+pub extern "C" fn bloom_filter(false_positive_rate: f64) {
+    with_sym(|sym| sym.set_bloom_filter(Some(false_positive_rate)));
+}
+
+/// Sets how much the bigram dictionary counts against the probabilities of single
+/// words, see `SymSpell::set_bigram_weight`.
+#[no_mangle]
+pub extern "C" fn bigram_weight(weight: f64) {
+    with_sym(|sym| sym.set_bigram_weight(weight));
+}
+
+/// The number of lookups answered from the lookup cache.
+#[no_mangle]
+pub extern "C" fn lookup_cache_hits() -> usize {
+    with_sym(|sym| sym.lookup_cache_stats().hits)
+}
+
+/// The number of lookups the lookup cache did not hold.
+#[no_mangle]
+pub extern "C" fn lookup_cache_misses() -> usize {
+    with_sym(|sym| sym.lookup_cache_stats().misses)
+}
+
+/// Gives up on lookups of inputs longer than `max_input_len` graphemes or
+/// examining more than `max_candidates` candidates, 0 for no limit. See
+/// `LookupLimits`.
+#[no_mangle]
+pub extern "C" fn lookup_limits(max_input_len: usize, max_candidates: usize) {
+    let limit = |max: usize| if max == 0 { None } else { Some(max) };
+    with_sym(|sym| sym.set_lookup_limits(LookupLimits { max_input_len: limit(max_input_len), max_candidates: limit(max_candidates) }));
+}
+
+/// The number of lookups given up on for exceeding the lookup limits.
+#[no_mangle]
+pub extern "C" fn truncated_lookups() -> usize {
+    with_sym(|sym| sym.truncated_lookups())
+}
+
+/// The number of lookups that searched the dictionary. See `SymSpell::metrics`.
+#[cfg(feature = "metrics")]
+#[no_mangle]
+pub extern "C" fn metrics_lookups() -> usize {
+    with_sym(|sym| sym.metrics().lookups)
+}
+
+/// The number of candidates checked per lookup.
+#[cfg(feature = "metrics")]
+#[no_mangle]
+pub extern "C" fn metrics_average_candidates() -> f64 {
+    with_sym(|sym| sym.metrics().average_candidates())
+}
+
+/// The number of edit distances computed by the lookups.
+#[cfg(feature = "metrics")]
+#[no_mangle]
+pub extern "C" fn metrics_distance_computations() -> usize {
+    with_sym(|sym| sym.metrics().distance_computations)
+}
+
+/// The number of lookups answered from the lookup cache.
+#[cfg(feature = "metrics")]
+#[no_mangle]
+pub extern "C" fn metrics_cache_hits() -> usize {
+    with_sym(|sym| sym.metrics().cache_hits)
+}
+
+/// The milliseconds spent in the lookups, as measured by the imported `metrics_clock`.
+#[cfg(feature = "metrics")]
+#[no_mangle]
+pub extern "C" fn metrics_total_time() -> f64 {
+    with_sym(|sym| sym.metrics().total_time.as_secs_f64() * 1000.0)
+}
+
+/// Starts the metrics over, e.g. before measuring another prefix length.
+#[cfg(feature = "metrics")]
+#[no_mangle]
+pub extern "C" fn reset_metrics() {
+    with_sym(|sym| sym.reset_metrics());
+}
+
+/// Drops every dictionary and bigram entry along with any partially
+/// written line while keeping the current settings.
+#[no_mangle]
+pub extern "C" fn clear_dictionary() {
+    with_sym(SymSpell::clear);
+    BUFFER.with(|buffer_cell| buffer_cell.replace(Vec::new()));
+    LOAD_STOPPED.with(|stopped| stopped.set(false));
+}
+
+/// Replaces the SymSpell instance with an empty one using new settings.
+/// Returns false, leaving the existing instance in place, for invalid settings.
+#[no_mangle]
+pub extern "C" fn reset(max_dictionary_edit_distance: usize, prefix_length: usize, count_threshold: usize) -> bool {
+    symspell_with_options(max_dictionary_edit_distance, count_threshold, prefix_length)
+}
+
+/// Frees the SymSpell instance and every buffer owned by the module.
+/// `symspell()` must be called again before any other export.
+#[no_mangle]
+pub extern "C" fn destroy() {
+    SYM.with(|sym_cell| sym_cell.replace(None));
+    BUFFER.with(|buffer_cell| buffer_cell.replace(Vec::new()));
+    RESULT.with(|result_cell| result_cell.replace(Vec::new()));
+    DOCUMENTS.with(|documents| documents.borrow_mut().clear());
+    LOAD_STOPPED.with(|stopped| stopped.set(false));
+}
+
+/// Appends a chunk of dictionary data. Complete lines are written to the
+/// dictionary while a trailing partial line (which may end mid character)
+/// stays buffered until the next chunk or `flush_dictionary()`.
+///
+/// # Safety
+///
+/// `ptr` must point to `length` readable bytes of utf-8 encoded
+/// dictionary data within the module's linear memory.
+#[no_mangle]
+pub unsafe extern "C" fn write_to_dictionary(ptr: *const u8, length: usize, is_bigram: bool) {
+    BUFFER.with(|buffer_cell| with_sym(|sym| {
+        let mut buffer = buffer_cell.borrow_mut();
+        buffer.extend_from_slice(slice::from_raw_parts(ptr, length));
+        write_lines(sym, &mut buffer, LineKind::of(is_bigram), false);
+    }));
+}
+
+/// Identical to `write_to_dictionary` for trigram dictionary data, lines of
+/// three words and an optional count, see `SymSpell::write_line_to_trigram_dictionary`.
+///
+/// # Safety
+///
+/// `ptr` must point to `length` readable bytes of utf-8 encoded
+/// trigram dictionary data within the module's linear memory.
+#[no_mangle]
+pub unsafe extern "C" fn write_to_trigram_dictionary(ptr: *const u8, length: usize) {
+    BUFFER.with(|buffer_cell| with_sym(|sym| {
+        let mut buffer = buffer_cell.borrow_mut();
+        buffer.extend_from_slice(slice::from_raw_parts(ptr, length));
+        write_lines(sym, &mut buffer, LineKind::Trigram, false);
+    }));
+}
+
+/// Adds the bigrams and trigrams of the text to the dictionaries, see
+/// `SymSpell::learn_ngrams`. Returns false, learning nothing, when the text
+/// is rejected.
+///
+/// # Safety
+///
+/// `ptr` must point to `length` readable bytes. Bytes that are not valid
+/// utf-8 are handled according to the `invalid_utf8_policy`.
+#[no_mangle]
+pub unsafe extern "C" fn learn_ngrams(ptr: *const u8, length: usize) -> bool {
+    let text = match read_input(ptr, length) {
+        Some(text) => text,
+        None => return false,
+    };
+    with_sym(|sym| sym.learn_ngrams(&text));
+    true
+}
+
+/// The number of entries in the bigram dictionary, so hosts can verify the
+/// bigram file loaded before relying on compound correction quality.
+#[no_mangle]
+pub extern "C" fn bigram_entry_count() -> usize {
+    with_sym(|sym| sym.bigram_count())
+}
+
+/// The smallest count in the loaded bigram dictionary or 0 when none is loaded.
+#[no_mangle]
+pub extern "C" fn bigram_count_min() -> usize {
+    with_sym(|sym| usize::try_from(sym.bigram_count_min().unwrap_or(0)).unwrap_or(usize::MAX))
+}
+
+/// The number of entries in the trigram dictionary.
+#[no_mangle]
+pub extern "C" fn trigram_entry_count() -> usize {
+    with_sym(|sym| sym.trigram_count())
+}
+
+/// The number of words in the dictionary. With `delete_entry_count` it reports
+/// the progress of a load between the chunks passed to `write_to_dictionary`,
+/// the counterpart of `SymSpell::load_words_with_progress`.
+#[no_mangle]
+pub extern "C" fn word_entry_count() -> usize {
+    with_sym(|sym| sym.word_count())
+}
+
+/// The number of distinct deletes stored, see `SymSpell::entry_count`.
+#[no_mangle]
+pub extern "C" fn delete_entry_count() -> usize {
+    with_sym(|sym| sym.entry_count())
+}
+
+/// Stops writing dictionary lines once `max_deletes` deletes are stored, 0 for
+/// no limit (the default), so a load with a large edit distance ends before it
+/// exhausts memory, keeping the words loaded so far. Clears a stop reported by
+/// `load_stopped()`.
+#[no_mangle]
+pub extern "C" fn load_budget(max_deletes: usize) {
+    LOAD_BUDGET.with(|budget| budget.set(max_deletes));
+    LOAD_STOPPED.with(|stopped| stopped.set(false));
+}
+
+/// True once the load budget was reached. Dictionary lines written afterwards are
+/// dropped until `load_budget`, `clear_dictionary` or `reset` is called.
+#[no_mangle]
+pub extern "C" fn load_stopped() -> bool {
+    LOAD_STOPPED.with(Cell::get)
+}
+
+// whether the load budget was reached, stopping the load when it just was
+fn is_load_stopped(sym: &SymSpell) -> bool {
+    LOAD_STOPPED.with(|stopped| {
+        let budget = LOAD_BUDGET.with(Cell::get);
+        if !stopped.get() && budget > 0 && sym.entry_count() >= budget {
+            stopped.set(true);
         }
+        stopped.get()
+    })
+}
+
+/// Writes any buffered, unterminated final line to the dictionary.
+#[no_mangle]
+pub extern "C" fn flush_dictionary(is_bigram: bool) {
+    BUFFER.with(|buffer_cell| with_sym(|sym| {
+        write_lines(sym, &mut buffer_cell.borrow_mut(), LineKind::of(is_bigram), true);
+    }));
+}
+
+/// Writes any buffered, unterminated final line to the trigram dictionary.
+#[no_mangle]
+pub extern "C" fn flush_trigram_dictionary() {
+    BUFFER.with(|buffer_cell| with_sym(|sym| {
+        write_lines(sym, &mut buffer_cell.borrow_mut(), LineKind::Trigram, true);
+    }));
+}
+
+// the dictionary the lines of the buffer are written to
+#[derive(Clone, Copy)]
+enum LineKind {
+    Word,
+    Bigram,
+    Trigram,
+}
+
+impl LineKind {
+    fn of(is_bigram: bool) -> LineKind {
+        if is_bigram { LineKind::Bigram } else { LineKind::Word }
     }
+}
 
+/// Writes every '\n' terminated line in the buffer to the dictionary and
+/// removes them from the buffer. When `flush` is true the remainder is
+/// written as well.
+fn write_lines(sym: &mut SymSpell, buffer: &mut Vec<u8>, kind: LineKind, flush: bool) {
+    let mut cursor: usize = 0;
+    while let Some(offset) = buffer[cursor..].iter().position(|&ch| ch == b'\n') {
+        write_line(sym, &buffer[cursor..cursor + offset], kind);
+        cursor += offset + 1; // skip the '\n' char for the next iteration
+    }
+    if flush {
+        write_line(sym, &buffer[cursor..], kind);
+        cursor = buffer.len();
+        // a flush ends the load of a dictionary
+        if let LineKind::Word = kind {
+            sym.log(&LogEvent::DictionaryLoad { words: sym.word_count() });
+        }
+    }
     buffer.drain(0..cursor);
 }
 
+fn write_line(sym: &mut SymSpell, line: &[u8], kind: LineKind) {
+    let line = line.strip_suffix(b"\r").unwrap_or(line);
+    if line.is_empty() || matches!(kind, LineKind::Word) && is_load_stopped(sym) {
+        return;
+    }
+    // malformed lines are tallied in the load report and the last error rather than aborting the load
+    let lossy = INVALID_UTF8_POLICY.with(Cell::get) == InvalidUtf8Policy::Replace;
+    let line = match decode_utf8(line, lossy) {
+        Ok(line) => line,
+        Err(err) => {
+            sym.record_malformed_line();
+            record_error(err.into());
+            return;
+        }
+    };
+    let written = match kind {
+        LineKind::Word => sym.write_line_to_dictionary(&line, " "),
+        LineKind::Bigram => sym.write_line_to_bigram_dictionary(&line, " ").map_err(SpellcheckerError::from),
+        LineKind::Trigram => sym.write_line_to_trigram_dictionary(&line, " ").map_err(SpellcheckerError::from),
+    };
+    if let Err(err) = written {
+        record_error(err);
+    }
+}
+
+/// # Safety
+///
+/// `ptr` must point to `length` readable bytes. Bytes that are not valid
+/// utf-8 are handled according to the `invalid_utf8_policy`.
+#[cfg(feature = "result-handler")]
 #[no_mangle]
-pub unsafe extern fn lookup(ptr: *mut u8, length: usize, verbosity: Verbosity, max_edit_distance: usize, include_unknown: bool, include_self: bool) {
-    let sym_cell = SYM.as_ref().unwrap();
-    let sym = sym_cell.borrow();
-    let bytes = slice::from_raw_parts(ptr, length);
-    let results = sym.lookup(str::from_utf8_unchecked(bytes), verbosity, max_edit_distance, include_unknown, include_self);
+pub unsafe extern "C" fn lookup(ptr: *mut u8, length: usize, verbosity: u32, max_edit_distance: usize, include_unknown: bool, include_self: bool) {
+    let verbosity = match read_verbosity(verbosity) {
+        Some(verbosity) => verbosity,
+        None => return,
+    };
+    if !valid_edit_distance(max_edit_distance) {
+        return;
+    }
+    let input = match read_input(ptr, length) {
+        Some(input) => input,
+        None => return,
+    };
+    // encoded while the suggestions borrow the dictionary, emitted once it is released
+    let payloads = with_sym(|sym| encode_payloads(None, &sym.lookup_borrowed(&input, verbosity, max_edit_distance, include_unknown, include_self), TextEncoding::Utf8));
+
+    emit_payloads(payloads)
+}
+
+/// Identical to `lookup` except the caller supplied `request_id` is
+/// prepended to the payload sent to the `result_handler` so hosts
+/// issuing several lookups can match each result to its query.
+///
+/// # Safety
+///
+/// `ptr` must point to `length` readable bytes. Bytes that are not valid
+/// utf-8 are handled according to the `invalid_utf8_policy`.
+#[cfg(feature = "result-handler")]
+#[no_mangle]
+pub unsafe extern "C" fn lookup_with_id(request_id: u32, ptr: *mut u8, length: usize, verbosity: u32, max_edit_distance: usize, include_unknown: bool, include_self: bool) {
+    let verbosity = match read_verbosity(verbosity) {
+        Some(verbosity) => verbosity,
+        None => return,
+    };
+    if !valid_edit_distance(max_edit_distance) {
+        return;
+    }
+    let input = match read_input(ptr, length) {
+        Some(input) => input,
+        None => return,
+    };
+    let payloads = with_sym(|sym| encode_payloads(Some(request_id), &sym.lookup_borrowed(&input, verbosity, max_edit_distance, include_unknown, include_self), TextEncoding::Utf8));
+
+    emit_payloads(payloads)
+}
+
+/// Identical to `lookup` with the suggestions ranked by how often they follow
+/// the previous word in the bigram dictionary. See `SymSpell::lookup_with_context`.
+///
+/// # Safety
+///
+/// `previous_ptr` and `ptr` must point to `previous_length` and `length`
+/// readable bytes. Bytes that are not valid utf-8 are handled according to
+/// the `invalid_utf8_policy`.
+#[cfg(feature = "result-handler")]
+#[no_mangle]
+pub unsafe extern "C" fn lookup_with_context(previous_ptr: *const u8, previous_length: usize, ptr: *mut u8, length: usize, verbosity: u32, max_edit_distance: usize, include_unknown: bool, include_self: bool) {
+    let verbosity = match read_verbosity(verbosity) {
+        Some(verbosity) => verbosity,
+        None => return,
+    };
+    if !valid_edit_distance(max_edit_distance) {
+        return;
+    }
+    let previous_word = match read_input(previous_ptr, previous_length) {
+        Some(previous_word) => previous_word,
+        None => return,
+    };
+    let input = match read_input(ptr, length) {
+        Some(input) => input,
+        None => return,
+    };
+    let results = with_sym(|sym| sym.lookup_with_context(&previous_word, &input, verbosity, max_edit_distance, include_unknown, include_self));
+
+    emit_results(None, results, TextEncoding::Utf8)
+}
+
+/// Dictionary words starting with the prefix, the most frequent first, tolerating
+/// one edit in the prefix when fuzzy is true. See `SymSpell::complete` and
+/// `SymSpell::complete_fuzzy`.
+///
+/// # Safety
+///
+/// `ptr` must point to `length` readable bytes. Bytes that are not valid
+/// utf-8 are handled according to the `invalid_utf8_policy`.
+#[cfg(feature = "result-handler")]
+#[no_mangle]
+pub unsafe extern "C" fn complete(ptr: *mut u8, length: usize, max_results: usize, fuzzy: bool) {
+    let prefix = match read_input(ptr, length) {
+        Some(prefix) => prefix,
+        None => return,
+    };
+    let results = with_sym(|sym| completions(sym, &prefix, max_results, fuzzy));
+
+    emit_results(None, results, TextEncoding::Utf8)
+}
+
+/// # Safety
+///
+/// `ptr` must point to `length` readable bytes. Bytes that are not valid
+/// utf-8 are handled according to the `invalid_utf8_policy`.
+#[cfg(feature = "result-handler")]
+#[no_mangle]
+pub unsafe extern "C" fn lookup_compound(ptr: *mut u8, length: usize, max_edit_distance: usize) {
+    if !valid_edit_distance(max_edit_distance) {
+        return;
+    }
+    let input = match read_input(ptr, length) {
+        Some(input) => input,
+        None => return,
+    };
+    let results = with_sym(|sym| sym.lookup_compound(&input, max_edit_distance));
+
+    emit_results(None, results, TextEncoding::Utf8);
+}
+
+/// Identical to `lookup_compound` except the caller supplied `request_id`
+/// is prepended to the payload sent to the `result_handler`.
+///
+/// # Safety
+///
+/// `ptr` must point to `length` readable bytes. Bytes that are not valid
+/// utf-8 are handled according to the `invalid_utf8_policy`.
+#[cfg(feature = "result-handler")]
+#[no_mangle]
+pub unsafe extern "C" fn lookup_compound_with_id(request_id: u32, ptr: *mut u8, length: usize, max_edit_distance: usize) {
+    if !valid_edit_distance(max_edit_distance) {
+        return;
+    }
+    let input = match read_input(ptr, length) {
+        Some(input) => input,
+        None => return,
+    };
+    let results = with_sym(|sym| sym.lookup_compound(&input, max_edit_distance));
+
+    emit_results(Some(request_id), results, TextEncoding::Utf8);
+}
+
+/// Identical to `lookup` except the input is `code_unit_length` UTF-16LE
+/// code units, as held by JS strings, so hosts can skip transcoding to
+/// UTF-8. When `utf16_output` is true the terms in the payload (or the
+/// whole payload for JSON) are UTF-16LE as well. Unpaired surrogates are
+/// replaced with U+FFFD.
+///
+/// # Safety
+///
+/// `ptr` must be 2 byte aligned and point to `code_unit_length` readable u16 values.
+#[cfg(feature = "result-handler")]
+#[no_mangle]
+pub unsafe extern "C" fn lookup_utf16(ptr: *const u16, code_unit_length: usize, verbosity: u32, max_edit_distance: usize, include_unknown: bool, include_self: bool, utf16_output: bool) {
+    let verbosity = match read_verbosity(verbosity) {
+        Some(verbosity) => verbosity,
+        None => return,
+    };
+    if !valid_edit_distance(max_edit_distance) {
+        return;
+    }
+    let input = String::from_utf16_lossy(slice::from_raw_parts(ptr, code_unit_length));
+    INVALID_UTF8_OFFSET.with(|offset| offset.set(None));
+    let payloads = with_sym(|sym| encode_payloads(None, &sym.lookup_borrowed(&input, verbosity, max_edit_distance, include_unknown, include_self), text_encoding(utf16_output)));
+
+    emit_payloads(payloads)
+}
+
+/// Pull based alternative to `lookup_utf16`. See `lookup_into_buffer`.
+///
+/// # Safety
+///
+/// `ptr` must be 2 byte aligned and point to `code_unit_length` readable u16 values.
+#[no_mangle]
+pub unsafe extern "C" fn lookup_utf16_into_buffer(ptr: *const u16, code_unit_length: usize, verbosity: u32, max_edit_distance: usize, include_unknown: bool, include_self: bool, utf16_output: bool) -> *const u8 {
+    let verbosity = match read_verbosity(verbosity) {
+        Some(verbosity) => verbosity,
+        None => return rejected_input(),
+    };
+    if !valid_edit_distance(max_edit_distance) {
+        return rejected_input();
+    }
+    let input = String::from_utf16_lossy(slice::from_raw_parts(ptr, code_unit_length));
+    INVALID_UTF8_OFFSET.with(|offset| offset.set(None));
+    let payload = with_sym(|sym| encode_results(None, &sym.lookup_borrowed(&input, verbosity, max_edit_distance, include_unknown, include_self), text_encoding(utf16_output)));
+
+    store_results(payload)
+}
+
+fn text_encoding(utf16: bool) -> TextEncoding {
+    if utf16 { TextEncoding::Utf16 } else { TextEncoding::Utf8 }
+}
+
+/// Runs `lookup` for every '\n' separated token in the input and sends a
+/// single combined payload to the `result_handler`, amortizing the call
+/// overhead when checking a whole document. See `encode_batch_results`
+/// for the payload layout.
+///
+/// # Safety
+///
+/// `ptr` must point to `length` readable bytes. Bytes that are not valid
+/// utf-8 are handled according to the `invalid_utf8_policy`.
+#[cfg(feature = "result-handler")]
+#[no_mangle]
+pub unsafe extern "C" fn lookup_batch(ptr: *mut u8, length: usize, verbosity: u32, max_edit_distance: usize, include_unknown: bool, include_self: bool) {
+    let verbosity = match read_verbosity(verbosity) {
+        Some(verbosity) => verbosity,
+        None => return,
+    };
+    if !valid_edit_distance(max_edit_distance) {
+        return;
+    }
+    let input = match read_input(ptr, length) {
+        Some(input) => input,
+        None => return,
+    };
+    let results = lookup_lines(&input, verbosity, max_edit_distance, include_unknown, include_self);
+
+    let payload = encode_batch_results(&results);
+    result_handler(payload.as_ptr(), payload.len());
+}
+
+/// Pull based alternative to `lookup_batch`. See `lookup_into_buffer`.
+///
+/// # Safety
+///
+/// `ptr` must point to `length` readable bytes. Bytes that are not valid
+/// utf-8 are handled according to the `invalid_utf8_policy`.
+#[no_mangle]
+pub unsafe extern "C" fn lookup_batch_into_buffer(ptr: *mut u8, length: usize, verbosity: u32, max_edit_distance: usize, include_unknown: bool, include_self: bool) -> *const u8 {
+    let verbosity = match read_verbosity(verbosity) {
+        Some(verbosity) => verbosity,
+        None => return rejected_input(),
+    };
+    if !valid_edit_distance(max_edit_distance) {
+        return rejected_input();
+    }
+    let input = match read_input(ptr, length) {
+        Some(input) => input,
+        None => return rejected_input(),
+    };
+    let results = lookup_lines(&input, verbosity, max_edit_distance, include_unknown, include_self);
 
-    emit_results(results)
+    store_results(encode_batch_results(&results))
+}
+
+fn lookup_lines(text: &str, verbosity: Verbosity, max_edit_distance: usize, include_unknown: bool, include_self: bool) -> Vec<Vec<SuggestItem>> {
+    with_sym(|sym| {
+        text.lines()
+            .map(|token| sym.lookup(token, verbosity, max_edit_distance, include_unknown, include_self))
+            .collect()
+    })
+}
+
+/// Checks every word of the input and sends the `result_handler` only the
+/// misspelled words' byte ranges and top suggestions, which is all an
+/// editor needs to underline them. See `encode_misspelled_ranges` for the
+/// payload layout.
+///
+/// # Safety
+///
+/// `ptr` must point to `length` readable bytes. Bytes that are not valid
+/// utf-8 are handled according to the `invalid_utf8_policy`.
+#[cfg(feature = "result-handler")]
+#[no_mangle]
+pub unsafe extern "C" fn check_text(ptr: *mut u8, length: usize, max_edit_distance: usize) {
+    if !valid_edit_distance(max_edit_distance) {
+        return;
+    }
+    let input = match read_input(ptr, length) {
+        Some(input) => input,
+        None => return,
+    };
+    let misspelled = with_sym(|sym| sym.check_text(&input, max_edit_distance));
+
+    let payload = encode_misspelled_ranges(&misspelled);
+    result_handler(payload.as_ptr(), payload.len());
+}
+
+/// Pull based alternative to `check_text`. See `lookup_into_buffer`.
+///
+/// # Safety
+///
+/// `ptr` must point to `length` readable bytes. Bytes that are not valid
+/// utf-8 are handled according to the `invalid_utf8_policy`.
+#[no_mangle]
+pub unsafe extern "C" fn check_text_into_buffer(ptr: *mut u8, length: usize, max_edit_distance: usize) -> *const u8 {
+    if !valid_edit_distance(max_edit_distance) {
+        return rejected_input();
+    }
+    let input = match read_input(ptr, length) {
+        Some(input) => input,
+        None => return rejected_input(),
+    };
+    let misspelled = with_sym(|sym| sym.check_text(&input, max_edit_distance));
+
+    store_results(encode_misspelled_ranges(&misspelled))
+}
+
+/// Opens a document checked incrementally with `check_document_into_buffer` and
+/// `edit_document_into_buffer`, returning its handle. Handles are never 0, which is
+/// returned for a max edit distance greater than the dictionary was built for.
+#[no_mangle]
+pub extern "C" fn open_document(max_edit_distance: usize) -> u32 {
+    if !valid_edit_distance(max_edit_distance) {
+        return 0;
+    }
+    let handle = NEXT_DOCUMENT.with(|next| {
+        let handle = next.get();
+        next.set(handle.checked_add(1).unwrap_or(1));
+        handle
+    });
+    DOCUMENTS.with(|documents| documents.borrow_mut().insert(handle, IncrementalChecker::new(max_edit_distance)));
+    handle
+}
+
+/// Frees the state of the document. Returns false for an unknown handle.
+#[no_mangle]
+pub extern "C" fn close_document(handle: u32) -> bool {
+    DOCUMENTS.with(|documents| documents.borrow_mut().remove(&handle).is_some())
+}
+
+/// Checks the whole text of the document, e.g. when it is first loaded, and stores
+/// the change from the ranges reported before. See `encode_check_delta` for the
+/// payload layout. Returns null for an unknown handle.
+///
+/// # Safety
+///
+/// `ptr` must point to `length` readable bytes. Bytes that are not valid
+/// utf-8 are handled according to the `invalid_utf8_policy`.
+#[no_mangle]
+pub unsafe extern "C" fn check_document_into_buffer(handle: u32, ptr: *mut u8, length: usize) -> *const u8 {
+    let input = match read_input(ptr, length) {
+        Some(input) => input,
+        None => return rejected_input(),
+    };
+    let delta = DOCUMENTS.with(|documents| {
+        let mut documents = documents.borrow_mut();
+        let checker = documents.get_mut(&handle)?;
+        Some(with_sym(|sym| checker.check(sym, &input)))
+    });
+    match delta {
+        Some(delta) => store_results(encode_check_delta(&delta)),
+        None => rejected_input(),
+    }
+}
+
+/// Re-checks the document around an edit that replaced the bytes from
+/// `replaced_start` to `replaced_end` of its previous text with `replacement_len`
+/// bytes, giving the text passed in, and stores the change to its ranges. Returns
+/// null for an unknown handle or, recording the error, an edit that does not fit the text.
+///
+/// # Safety
+///
+/// `ptr` must point to `length` readable bytes. Bytes that are not valid
+/// utf-8 are handled according to the `invalid_utf8_policy`.
+#[no_mangle]
+pub unsafe extern "C" fn edit_document_into_buffer(handle: u32, ptr: *mut u8, length: usize, replaced_start: usize, replaced_end: usize, replacement_len: usize) -> *const u8 {
+    let input = match read_input(ptr, length) {
+        Some(input) => input,
+        None => return rejected_input(),
+    };
+    let delta = DOCUMENTS.with(|documents| {
+        let mut documents = documents.borrow_mut();
+        let checker = documents.get_mut(&handle)?;
+        with_sym(|sym| checker.edit(sym, &input, replaced_start..replaced_end, replacement_len)).map_err(record_error).ok()
+    });
+    match delta {
+        Some(delta) => store_results(encode_check_delta(&delta)),
+        None => rejected_input(),
+    }
+}
+
+/// Starts counting the words of a corpus passed in with `train_corpus`, to build a
+/// frequency dictionary, see `CorpusTrainer`. 0 is no limit for `max_words` and
+/// `max_tokens`. Replaces any training in progress.
+#[no_mangle]
+pub extern "C" fn corpus_trainer(max_words: usize, max_tokens: usize, dedup_lines: bool, drop_hapax_legomena: bool) {
+    let options = TrainerOptions {
+        max_words: Some(max_words).filter(|max_words| *max_words > 0),
+        max_tokens: Some(max_tokens).filter(|max_tokens| *max_tokens > 0),
+        dedup_lines,
+        drop_hapax_legomena,
+        ..TrainerOptions::default()
+    };
+    TRAINER.with(|trainer| trainer.replace(Some(CorpusTrainer::new(options))));
 }
 
+/// Adds the next piece of the corpus, which may end part way through a character
+/// that the next piece completes. Returns false once the trainer takes no more
+/// text, and when no training was started or the input is rejected.
+///
+/// # Safety
+///
+/// `ptr` must point to `length` readable bytes. Bytes that are not valid
+/// utf-8 are handled according to the `invalid_utf8_policy`.
 #[no_mangle]
-pub unsafe extern fn lookup_compound(ptr: *mut u8, length: usize, max_edit_distance: usize) {
-    let sym_cell = SYM.as_ref().unwrap();
-    let sym = sym_cell.borrow();
+pub unsafe extern "C" fn train_corpus(ptr: *mut u8, length: usize) -> bool {
+    let lossy = INVALID_UTF8_POLICY.with(Cell::get) == InvalidUtf8Policy::Replace;
     let bytes = slice::from_raw_parts(ptr, length);
-    let results = sym.lookup_compound(str::from_utf8_unchecked(bytes), max_edit_distance);
+    let pushed = TRAINER.with(|trainer| trainer.borrow_mut().as_mut().map(|trainer| trainer.push_bytes(bytes, lossy)));
+    let offset = match &pushed {
+        Some(Err(SpellcheckerError::InvalidUtf8 { valid_up_to })) => Some(*valid_up_to),
+        _ => None,
+    };
+    INVALID_UTF8_OFFSET.with(|invalid_offset| invalid_offset.set(offset));
+    match pushed {
+        Some(Ok(taking_text)) => taking_text,
+        Some(Err(err)) => {
+            record_error(err);
+            false
+        }
+        None => false,
+    }
+}
 
-    emit_results(results);
+/// Stores the words counted as a frequency dictionary, the lines `write_to_dictionary`
+/// reads, most frequent first. Returns null when no training was started.
+#[no_mangle]
+pub extern "C" fn corpus_dictionary_into_buffer() -> *const u8 {
+    let mut dictionary = vec![];
+    let written = TRAINER.with(|trainer| {
+        trainer.borrow_mut().as_mut().map(|trainer| trainer.write_dictionary(&mut dictionary))
+    });
+    match written {
+        Some(Ok(())) => store_results(dictionary),
+        _ => rejected_input(),
+    }
 }
 
-#[inline]
-unsafe fn emit_results(results: Vec<SuggestItem>) {
-    let num_items: [u8; 4] = transmute(results.len() as u32);
-    let mut payload: Vec<u8> = vec![num_items[0], num_items[1], num_items[2], num_items[3]];
+/// Ends the training, adding the words counted to the dictionary of the SymSpell
+/// instance. Returns the number of dictionary words after, 0 when no training was started.
+#[no_mangle]
+pub extern "C" fn finish_corpus_trainer() -> usize {
+    match TRAINER.with(|trainer| trainer.borrow_mut().take()) {
+        Some(mut trainer) => with_sym(|sym| {
+            trainer.load_into(sym);
+            sym.word_count()
+        }),
+        None => 0,
+    }
+}
+
+/// Selects the encoding of all subsequent result payloads: 0 for the
+/// binary encoding, 1 for JSON and 2 for result frames. Returns false for
+/// an unknown format.
+#[no_mangle]
+pub extern "C" fn output_format(format: u32) -> bool {
+    let format = match format {
+        0 => OutputFormat::Binary,
+        1 => OutputFormat::Json,
+        2 => OutputFormat::Framed,
+        _ => return false,
+    };
+    OUTPUT_FORMAT.with(|output_format| output_format.set(format));
+    true
+}
+
+/// Selects how text exports treat input that is not valid utf-8: 0 rejects
+/// it and 1 replaces each invalid sequence with U+FFFD. Returns false for an
+/// unknown policy.
+#[no_mangle]
+pub extern "C" fn invalid_utf8_policy(policy: u32) -> bool {
+    let policy = match policy {
+        0 => InvalidUtf8Policy::Reject,
+        1 => InvalidUtf8Policy::Replace,
+        _ => return false,
+    };
+    INVALID_UTF8_POLICY.with(|invalid_utf8_policy| invalid_utf8_policy.set(policy));
+    true
+}
+
+/// The byte offset of the first invalid utf-8 sequence in the input of the
+/// last text export, or -1 when that input was accepted. A rejected input
+/// produces no `result_handler` call and a null `*_into_buffer` pointer.
+#[no_mangle]
+pub extern "C" fn invalid_utf8_offset() -> isize {
+    INVALID_UTF8_OFFSET.with(Cell::get).map_or(-1, |offset| offset as isize)
+}
+
+/// Decodes the text passed by the host according to the `invalid_utf8_policy`,
+/// returning None when it is rejected.
+///
+/// # Safety
+///
+/// `ptr` must point to `length` readable bytes.
+unsafe fn read_input<'a>(ptr: *const u8, length: usize) -> Option<Cow<'a, str>> {
+    let lossy = INVALID_UTF8_POLICY.with(Cell::get) == InvalidUtf8Policy::Replace;
+    let decoded = decode_utf8(slice::from_raw_parts(ptr, length), lossy);
+    INVALID_UTF8_OFFSET.with(|offset| offset.set(decoded.as_ref().err().map(Utf8Error::valid_up_to)));
+    decoded.map_err(|err| record_error(err.into())).ok()
+}
+
+/// The `SpellcheckerError::code` of the most recent error, or 0 when there has been
+/// none since the instance was created or `clear_last_error()` was called. Errors are
+/// recorded by exports that return false, 0 or null for invalid arguments or input,
+/// e.g. a max edit distance greater than the dictionary was built for, or skip it, and
+/// by dictionary lines that are rejected.
+#[no_mangle]
+pub extern "C" fn last_error_code() -> u32 {
+    LAST_ERROR.with(|last_error| last_error.borrow().as_ref().map_or(0, |(code, _)| *code))
+}
+
+/// A utf-8 description of the most recent error, whose length is available from
+/// `last_error_message_len()`, or null when there is none. It remains valid until
+/// the next error or `clear_last_error()`.
+#[no_mangle]
+pub extern "C" fn last_error_message() -> *const u8 {
+    LAST_ERROR.with(|last_error| last_error.borrow().as_ref().map_or(ptr::null(), |(_, message)| message.as_ptr()))
+}
+
+/// The byte length of `last_error_message()`.
+#[no_mangle]
+pub extern "C" fn last_error_message_len() -> usize {
+    LAST_ERROR.with(|last_error| last_error.borrow().as_ref().map_or(0, |(_, message)| message.len()))
+}
+
+/// Forgets the most recent error, e.g. before a call whose errors the host checks.
+#[no_mangle]
+pub extern "C" fn clear_last_error() {
+    LAST_ERROR.with(|last_error| last_error.replace(None));
+}
+
+fn record_error(err: SpellcheckerError) {
+    LAST_ERROR.with(|last_error| last_error.replace(Some((err.code(), err.to_string()))));
+}
+
+// the verbosity the host passed, or None, recording the error, for a number that is none
+fn read_verbosity(verbosity: u32) -> Option<Verbosity> {
+    Verbosity::try_from(verbosity).map_err(record_error).ok()
+}
+
+// false, recording the error, when the dictionary was not built for the max edit distance
+fn valid_edit_distance(max_edit_distance: usize) -> bool {
+    match with_sym(|sym| sym.check_edit_distance(max_edit_distance)) {
+        Ok(()) => true,
+        Err(err) => {
+            record_error(err);
+            false
+        }
+    }
+}
+
+// the result of a `*_into_buffer` export whose input was rejected
+fn rejected_input() -> *const u8 {
+    free_result();
+    ptr::null()
+}
+
+/// Limits each payload sent to the `result_handler` to `batch_size`
+/// suggestions so large result sets (e.g. `Verbosity::All` on a short
+/// word) are never encoded in one allocation. A lookup then produces one
+/// payload per batch followed by a terminating payload with no
+/// suggestions. 0 (the default) sends every suggestion in a single payload.
+#[cfg(feature = "result-handler")]
+#[no_mangle]
+pub extern "C" fn result_batch_size(batch_size: usize) {
+    BATCH_SIZE.with(|size| size.set(batch_size));
+}
+
+/// Pull based alternative to `lookup` for hosts that do not supply a
+/// `result_handler` import. The encoded payload is stored in module owned
+/// memory and a pointer to it is returned. Its length is available from
+/// `last_result_len()` and it remains valid until the next `*_into_buffer`
+/// call or `free_result()`.
+///
+/// # Safety
+///
+/// `ptr` must point to `length` readable bytes. Bytes that are not valid
+/// utf-8 are handled according to the `invalid_utf8_policy`.
+#[no_mangle]
+pub unsafe extern "C" fn lookup_into_buffer(ptr: *mut u8, length: usize, verbosity: u32, max_edit_distance: usize, include_unknown: bool, include_self: bool) -> *const u8 {
+    let verbosity = match read_verbosity(verbosity) {
+        Some(verbosity) => verbosity,
+        None => return rejected_input(),
+    };
+    if !valid_edit_distance(max_edit_distance) {
+        return rejected_input();
+    }
+    let input = match read_input(ptr, length) {
+        Some(input) => input,
+        None => return rejected_input(),
+    };
+    let payload = with_sym(|sym| encode_results(None, &sym.lookup_borrowed(&input, verbosity, max_edit_distance, include_unknown, include_self), TextEncoding::Utf8));
+
+    store_results(payload)
+}
+
+/// Pull based alternative to `lookup_with_context`. See `lookup_into_buffer`.
+///
+/// # Safety
+///
+/// `previous_ptr` and `ptr` must point to `previous_length` and `length`
+/// readable bytes. Bytes that are not valid utf-8 are handled according to
+/// the `invalid_utf8_policy`.
+#[no_mangle]
+pub unsafe extern "C" fn lookup_with_context_into_buffer(previous_ptr: *const u8, previous_length: usize, ptr: *mut u8, length: usize, verbosity: u32, max_edit_distance: usize, include_unknown: bool, include_self: bool) -> *const u8 {
+    let verbosity = match read_verbosity(verbosity) {
+        Some(verbosity) => verbosity,
+        None => return rejected_input(),
+    };
+    if !valid_edit_distance(max_edit_distance) {
+        return rejected_input();
+    }
+    let previous_word = match read_input(previous_ptr, previous_length) {
+        Some(previous_word) => previous_word,
+        None => return rejected_input(),
+    };
+    let input = match read_input(ptr, length) {
+        Some(input) => input,
+        None => return rejected_input(),
+    };
+    let results = with_sym(|sym| sym.lookup_with_context(&previous_word, &input, verbosity, max_edit_distance, include_unknown, include_self));
+
+    store_results(encode_results(None, &results, TextEncoding::Utf8))
+}
+
+/// Pull based alternative to `complete`. See `lookup_into_buffer`.
+///
+/// # Safety
+///
+/// `ptr` must point to `length` readable bytes. Bytes that are not valid
+/// utf-8 are handled according to the `invalid_utf8_policy`.
+#[no_mangle]
+pub unsafe extern "C" fn complete_into_buffer(ptr: *mut u8, length: usize, max_results: usize, fuzzy: bool) -> *const u8 {
+    let prefix = match read_input(ptr, length) {
+        Some(prefix) => prefix,
+        None => return rejected_input(),
+    };
+    let results = with_sym(|sym| completions(sym, &prefix, max_results, fuzzy));
+
+    store_results(encode_results(None, &results, TextEncoding::Utf8))
+}
+
+fn completions(sym: &SymSpell, prefix: &str, max_results: usize, fuzzy: bool) -> Vec<SuggestItem> {
+    if fuzzy { sym.complete_fuzzy(prefix, max_results) } else { sym.complete(prefix, max_results) }
+}
+
+/// Pull based alternative to `lookup_compound`. See `lookup_into_buffer`.
+///
+/// # Safety
+///
+/// `ptr` must point to `length` readable bytes. Bytes that are not valid
+/// utf-8 are handled according to the `invalid_utf8_policy`.
+#[no_mangle]
+pub unsafe extern "C" fn lookup_compound_into_buffer(ptr: *mut u8, length: usize, max_edit_distance: usize) -> *const u8 {
+    if !valid_edit_distance(max_edit_distance) {
+        return rejected_input();
+    }
+    let input = match read_input(ptr, length) {
+        Some(input) => input,
+        None => return rejected_input(),
+    };
+    let results = with_sym(|sym| sym.lookup_compound(&input, max_edit_distance));
+
+    store_results(encode_results(None, &results, TextEncoding::Utf8))
+}
+
+/// Runs the compound lookup and stores only the corrected text, as utf-8,
+/// rather than an encoded suggestion payload. `options` is a combination
+/// of the `CORRECT_TEXT_*` flags. Like `lookup_into_buffer` the returned
+/// pointer's length is available from `last_result_len()`.
+///
+/// # Safety
+///
+/// `ptr` must point to `length` readable bytes. Bytes that are not valid
+/// utf-8 are handled according to the `invalid_utf8_policy`.
+#[no_mangle]
+pub unsafe extern "C" fn correct_text(ptr: *mut u8, length: usize, max_edit_distance: usize, options: u32) -> *const u8 {
+    if !valid_edit_distance(max_edit_distance) {
+        return rejected_input();
+    }
+    let input = match read_input(ptr, length) {
+        Some(input) => input,
+        None => return rejected_input(),
+    };
+    let options = CompoundOptions {
+        preserve_proper_nouns: options & CORRECT_TEXT_PRESERVE_PROPER_NOUNS != 0,
+        transfer_casing: options & CORRECT_TEXT_TRANSFER_CASING != 0,
+    };
+    let corrected = with_sym(|sym| sym.correct_text(&input, max_edit_distance, options));
+
+    store_results(corrected.into_bytes())
+}
+
+/// The byte length of the payload most recently stored by a `*_into_buffer` export.
+#[no_mangle]
+pub extern "C" fn last_result_len() -> usize {
+    RESULT.with(|result_cell| result_cell.borrow().len())
+}
+
+/// Releases the payload stored by the last `*_into_buffer` export.
+#[no_mangle]
+pub extern "C" fn free_result() {
+    RESULT.with(|result_cell| result_cell.replace(Vec::new()));
+}
+
+fn store_results(payload: Vec<u8>) -> *const u8 {
+    RESULT.with(|result_cell| {
+        let mut result = result_cell.borrow_mut();
+        *result = payload;
+        result.as_ptr()
+    })
+}
+
+/// Encodes the results in the selected output format.
+fn encode_results<S: AsSuggestRef>(request_id: Option<u32>, results: &[S], encoding: TextEncoding) -> Vec<u8> {
+    match OUTPUT_FORMAT.with(Cell::get) {
+        OutputFormat::Binary => encode_results_binary(request_id, results, encoding),
+        OutputFormat::Json => encode_results_json(request_id, results, encoding),
+        OutputFormat::Framed => ResultFrame::encode_items(request_id, results, encoding == TextEncoding::Utf16),
+    }
+}
+
+/// Encodes the results as `[request_id?][num_items][item_len][item]...`
+/// where every number is a little endian u32. The request id is only
+/// present when the export was called with one. Each item is
+/// `[count][distance][term_len][term][probability]`, the term written in the
+/// requested text encoding and the probability a little endian f32 that is NaN
+/// when there is none (since `abi_version()` 3).
+fn encode_results_binary<S: AsSuggestRef>(request_id: Option<u32>, results: &[S], encoding: TextEncoding) -> Vec<u8> {
+    let mut payload: Vec<u8> = Vec::new();
+    if let Some(id) = request_id {
+        payload.extend_from_slice(&id.to_le_bytes());
+    }
+    payload.extend_from_slice(&(results.len() as u32).to_le_bytes());
 
     for suggest_item in results {
-        let item = suggest_item.encode();
-        let suggest_item_len: [u8; 4] = transmute(item.len() as u32);
-        payload.extend_from_slice(&suggest_item_len);
+        let suggest_item = suggest_item.as_suggest_ref();
+        let item = match encoding {
+            TextEncoding::Utf8 => suggest_item.encode(),
+            TextEncoding::Utf16 => suggest_item.encode_utf16(),
+        };
+        payload.extend_from_slice(&(item.len() as u32).to_le_bytes());
         payload.extend_from_slice(&item);
     }
+    payload
+}
 
-    result_handler(payload.as_ptr(), payload.len());
+/// Encodes the results as a JSON array of suggestions, wrapped as
+/// `{"request_id":1,"suggestions":[...]}` when a request id is present.
+fn encode_results_json<S: AsSuggestRef>(request_id: Option<u32>, results: &[S], encoding: TextEncoding) -> Vec<u8> {
+    let mut json = String::new();
+    if let Some(id) = request_id {
+        json.push_str(&format!("{{\"request_id\":{},\"suggestions\":", id));
+    }
+    json.push('[');
+    for (i, suggest_item) in results.iter().enumerate() {
+        if i != 0 {
+            json.push(',');
+        }
+        suggest_item.as_suggest_ref().write_json(&mut json);
+    }
+    json.push(']');
+    if request_id.is_some() {
+        json.push('}');
+    }
+    match encoding {
+        TextEncoding::Utf8 => json.into_bytes(),
+        TextEncoding::Utf16 => json.encode_utf16().flat_map(u16::to_le_bytes).collect(),
+    }
 }
 
-#[allow(dead_code)]
-#[no_mangle]
+/// Encodes the results of a batch lookup in the selected output format. The
+/// binary layout is `[num_tokens]` followed by the `encode_results_binary`
+/// payload of each token in input order. JSON is an array holding each
+/// token's array of suggestions, and framed `[num_tokens]` followed by
+/// `[frame_len][frame]` for each token.
+fn encode_batch_results(results: &[Vec<SuggestItem>]) -> Vec<u8> {
+    match OUTPUT_FORMAT.with(Cell::get) {
+        OutputFormat::Binary => {
+            let mut payload: Vec<u8> = Vec::new();
+            payload.extend_from_slice(&(results.len() as u32).to_le_bytes());
+            for token_results in results {
+                payload.extend(encode_results_binary(None, token_results, TextEncoding::Utf8));
+            }
+            payload
+        }
+        OutputFormat::Json => {
+            let mut payload = vec![b'['];
+            for (i, token_results) in results.iter().enumerate() {
+                if i != 0 {
+                    payload.push(b',');
+                }
+                payload.extend(encode_results_json(None, token_results, TextEncoding::Utf8));
+            }
+            payload.push(b']');
+            payload
+        }
+        OutputFormat::Framed => {
+            let mut payload: Vec<u8> = Vec::new();
+            payload.extend_from_slice(&(results.len() as u32).to_le_bytes());
+            for token_results in results {
+                let frame = ResultFrame::encode_suggestions(None, token_results, false);
+                payload.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+                payload.extend(frame);
+            }
+            payload
+        }
+    }
+}
+
+/// Encodes misspelled ranges in the selected output format. The binary layout
+/// is `[num_ranges]` followed by `[start][end][kind][item_len][item]` for each
+/// range, where `kind` is 0 for a misspelling and 1 for a repeated word, and an
+/// `item_len` of 0 means there is no suggestion. The framed layout is the same with
+/// each item a `ResultFrame` of the one suggestion. JSON is an array of
+/// `{"start":4,"end":9,"kind":"misspelling","suggestion":{...}}` objects with a
+/// null suggestion when there is none.
+fn encode_misspelled_ranges(misspelled: &[MisspelledRange]) -> Vec<u8> {
+    match OUTPUT_FORMAT.with(Cell::get) {
+        format @ OutputFormat::Binary | format @ OutputFormat::Framed => {
+            let mut payload: Vec<u8> = Vec::new();
+            payload.extend_from_slice(&(misspelled.len() as u32).to_le_bytes());
+            for range in misspelled {
+                payload.extend_from_slice(&(range.byte_range.start as u32).to_le_bytes());
+                payload.extend_from_slice(&(range.byte_range.end as u32).to_le_bytes());
+                let kind: u32 = match range.kind {
+                    IssueKind::Misspelling => 0,
+                    IssueKind::RepeatedWord => 1,
+                };
+                payload.extend_from_slice(&kind.to_le_bytes());
+                let item = match (&range.suggestion, format) {
+                    (Some(suggestion), OutputFormat::Framed) => ResultFrame::encode_suggestions(None, slice::from_ref(suggestion), false),
+                    (suggestion, _) => suggestion.as_ref().map(SuggestItem::encode).unwrap_or_default(),
+                };
+                payload.extend_from_slice(&(item.len() as u32).to_le_bytes());
+                payload.extend_from_slice(&item);
+            }
+            payload
+        }
+        OutputFormat::Json => {
+            let mut json = String::from("[");
+            for (i, range) in misspelled.iter().enumerate() {
+                if i != 0 {
+                    json.push(',');
+                }
+                let kind = match range.kind {
+                    IssueKind::Misspelling => "misspelling",
+                    IssueKind::RepeatedWord => "repeated_word",
+                };
+                json.push_str(&format!("{{\"start\":{},\"end\":{},\"kind\":\"{}\",\"suggestion\":", range.byte_range.start, range.byte_range.end, kind));
+                match range.suggestion.as_ref() {
+                    Some(suggestion) => suggestion.write_json(&mut json),
+                    None => json.push_str("null"),
+                }
+                json.push('}');
+            }
+            json.push(']');
+            json.into_bytes()
+        }
+    }
+}
+
+/// Encodes the change to a document's ranges in the selected output format: the
+/// `encode_misspelled_ranges` payload of the added ranges followed by that of the
+/// removed ones, or `{"added":[...],"removed":[...]}` in JSON. Removed ranges keep
+/// the offsets they were reported with.
+fn encode_check_delta(delta: &CheckDelta) -> Vec<u8> {
+    let added = encode_misspelled_ranges(&delta.added);
+    let removed = encode_misspelled_ranges(&delta.removed);
+    match OUTPUT_FORMAT.with(Cell::get) {
+        OutputFormat::Binary | OutputFormat::Framed => [added, removed].concat(),
+        OutputFormat::Json => {
+            let mut json = b"{\"added\":".to_vec();
+            json.extend(added);
+            json.extend_from_slice(b",\"removed\":");
+            json.extend(removed);
+            json.push(b'}');
+            json
+        }
+    }
+}
+
+/// Encodes the results as a single payload, or as one payload per batch
+/// plus an empty terminator when `result_batch_size` is set, handing
+/// each to `f` as soon as it is encoded.
+#[cfg(feature = "result-handler")]
+fn for_each_payload<S: AsSuggestRef>(request_id: Option<u32>, results: &[S], encoding: TextEncoding, mut f: impl FnMut(Vec<u8>)) {
+    let batch_size = BATCH_SIZE.with(Cell::get);
+    if batch_size == 0 {
+        f(encode_results(request_id, results, encoding));
+        return;
+    }
+    for batch in results.chunks(batch_size) {
+        f(encode_results(request_id, batch, encoding));
+    }
+    f(encode_results::<S>(request_id, &[], encoding));
+}
+
+/// The payloads `for_each_payload` hands out, for results that must be encoded
+/// while the SymSpell instance is borrowed and emitted after.
+#[cfg(feature = "result-handler")]
+fn encode_payloads<S: AsSuggestRef>(request_id: Option<u32>, results: &[S], encoding: TextEncoding) -> Vec<Vec<u8>> {
+    let mut payloads = vec![];
+    for_each_payload(request_id, results, encoding, |payload| payloads.push(payload));
+    payloads
+}
+
+#[cfg(feature = "result-handler")]
+fn emit_payloads(payloads: Vec<Vec<u8>>) {
+    for payload in payloads {
+        unsafe { result_handler(payload.as_ptr(), payload.len()) };
+    }
+}
+
+#[cfg(feature = "result-handler")]
+#[inline]
+fn emit_results(request_id: Option<u32>, results: Vec<SuggestItem>, encoding: TextEncoding) {
+    for_each_payload(request_id, &results, encoding, |payload| {
+        unsafe { result_handler(payload.as_ptr(), payload.len()) };
+    });
+}
+
+#[cfg(feature = "result-handler")]
 extern "C" {
     fn result_handler(ptr: *const u8, len: usize);
-}
\ No newline at end of file
+}
+
+/// Sends a line describing every dictionary load, lookup, compound lookup and word
+/// segmentation, as utf-8, to the imported `log_handler` while enabled, e.g. to
+/// diagnose slow or surprising corrections in production. Survives `reset()`.
+#[cfg(feature = "log-handler")]
+#[no_mangle]
+pub extern "C" fn logging(enabled: bool) {
+    LOGGING.with(|logging| logging.set(enabled));
+    SYM.with(|sym_cell| {
+        if let Some(sym) = sym_cell.borrow_mut().as_mut() {
+            sym.set_logger(host_logger());
+        }
+    });
+}
+
+// the logger of a new instance, forwarding to the log_handler when logging is enabled
+#[cfg(feature = "log-handler")]
+fn host_logger() -> Option<Box<dyn Logger>> {
+    if !LOGGING.with(Cell::get) {
+        return None;
+    }
+    Some(Box::new(|event: &LogEvent| {
+        let line = event.to_string();
+        unsafe { log_handler(line.as_ptr(), line.len()) };
+    }))
+}
+
+#[cfg(feature = "log-handler")]
+extern "C" {
+    fn log_handler(ptr: *const u8, len: usize);
+}
+
+/// Replaces the default panic hook, and the formatting it pulls in, with one
+/// that hands the panic message to the host's `error_handler` as utf-8.
+#[cfg(all(feature = "small-binary", target_arch = "wasm32"))]
+fn install_panic_hook() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| panic::set_hook(Box::new(|info| {
+        let payload = info.payload();
+        let message = payload.downcast_ref::<&str>().copied()
+            .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("panicked");
+        unsafe { error_handler(message.as_ptr(), message.len()) };
+    })));
+}
+
+#[cfg(all(feature = "small-binary", target_arch = "wasm32"))]
+extern "C" {
+    fn error_handler(ptr: *const u8, len: usize);
+}
+
+#[cfg(test)]
+mod spellchecker_wasm_tests {
+    use crate::spellchecker_wasm::{clear_dictionary, destroy, reset, SYM, write_to_dictionary};
+    use crate::spellchecker_wasm::{encode_results, encode_results_json, estimate_memory, free_result, last_result_len, store_results, symspell_with_options, with_sym, write_lines, LineKind};
+    use crate::spellchecker_wasm::{bigram_count_min, bigram_entry_count, flush_dictionary, flush_trigram_dictionary, learn_ngrams, trigram_entry_count, write_to_trigram_dictionary};
+    use crate::spellchecker_wasm::{delete_entry_count, load_budget, load_stopped, symspell, word_entry_count};
+    use crate::spellchecker_wasm::{check_text_into_buffer, encode_misspelled_ranges, output_format};
+    use crate::spellchecker_wasm::{check_document_into_buffer, close_document, edit_document_into_buffer, open_document};
+    use crate::spellchecker_wasm::{corpus_dictionary_into_buffer, corpus_trainer, decay_counts, finish_corpus_trainer, learn_word, purge_faded, train_corpus};
+    use crate::spellchecker_wasm::{complete_into_buffer, correct_text, CORRECT_TEXT_TRANSFER_CASING, lookup_batch_into_buffer, lookup_utf16_into_buffer, lookup_with_context_into_buffer, TextEncoding};
+    use crate::spellchecker_wasm::{invalid_utf8_offset, invalid_utf8_policy, lookup_into_buffer};
+    use crate::spellchecker_wasm::{clear_last_error, last_error_code, last_error_message, last_error_message_len};
+    use crate::spellchecker_wasm::{capabilities, CAPABILITY_COMPOUND, CAPABILITY_FRAMED_OUTPUT, CAPABILITY_HANDLES, CAPABILITY_PARALLEL, CAPABILITY_RESULT_HANDLER};
+    #[cfg(feature = "result-handler")]
+    use crate::spellchecker_wasm::{for_each_payload, result_batch_size};
+    #[cfg(feature = "log-handler")]
+    use crate::spellchecker_wasm::{logging, CAPABILITY_LOG_HANDLER};
+    #[cfg(feature = "metrics")]
+    use crate::spellchecker_wasm::{metrics_average_candidates, metrics_cache_hits, metrics_distance_computations, metrics_lookups, metrics_total_time, reset_metrics, CAPABILITY_METRICS};
+    use crate::sym_spell::Encode;
+    use crate::sym_spell::result_frame::ResultFrame;
+    use crate::sym_spell::suggested_item::SuggestItem;
+    use crate::sym_spell::sym_spell::SymSpell;
+    use crate::sym_spell::verbosity::Verbosity;
+    use crate::sym_spell::Count;
+
+    #[test]
+    fn capabilities_test() {
+        let capabilities = capabilities();
+        assert_ne!(capabilities & CAPABILITY_COMPOUND, 0);
+        assert_ne!(capabilities & CAPABILITY_HANDLES, 0);
+        assert_ne!(capabilities & CAPABILITY_FRAMED_OUTPUT, 0);
+        assert_eq!(capabilities & CAPABILITY_RESULT_HANDLER != 0, cfg!(feature = "result-handler"));
+        assert_eq!(capabilities & CAPABILITY_PARALLEL != 0, SymSpell::capabilities().parallel);
+    }
+
+    // the item as a lookup returns it, with its probability
+    fn looked_up(term: &str, distance: usize, count: Count) -> SuggestItem {
+        let probability = with_sym(|sym| sym.probability(count as f64 / sym.corpus_size(), distance));
+        SuggestItem { probability: Some(probability), ..SuggestItem::new(term.into(), distance, count) }
+    }
+
+    #[test]
+    fn encode_results_with_request_id_test() {
+        let results = vec![SuggestItem::new("test".into(), 1, 2)];
+        let payload = encode_results(Some(42), &results, TextEncoding::Utf8);
+        assert_eq!(payload[0..4], 42u32.to_le_bytes());
+        assert_eq!(payload[4..8], 1u32.to_le_bytes());
+        assert_eq!(payload[4..], encode_results(None, &results, TextEncoding::Utf8)[..]);
+    }
+
+    #[test]
+    fn store_results_test() {
+        let payload = encode_results(None, &[SuggestItem::new("test".into(), 1, 2)], TextEncoding::Utf8);
+        let ptr = store_results(payload.clone());
+        assert_eq!(last_result_len(), payload.len());
+        assert_eq!(unsafe { std::slice::from_raw_parts(ptr, last_result_len()) }, &payload[..]);
+
+        free_result();
+        assert_eq!(last_result_len(), 0);
+    }
+
+    #[test]
+    fn encode_results_json_test() {
+        let results = vec![SuggestItem::new("a".into(), 1, 2), SuggestItem::new("b".into(), 0, 3)];
+        let json = encode_results_json(None, &results, TextEncoding::Utf8);
+        assert_eq!(json, br#"[{"term":"a","distance":1,"count":2,"probability":null},{"term":"b","distance":0,"count":3,"probability":null}]"#.to_vec());
+
+        let json = encode_results_json::<SuggestItem>(Some(7), &[], TextEncoding::Utf8);
+        assert_eq!(json, br#"{"request_id":7,"suggestions":[]}"#.to_vec());
+
+        let json = encode_results_json::<SuggestItem>(None, &[], TextEncoding::Utf16);
+        assert_eq!(json, vec![b'[', 0, b']', 0]);
+    }
+
+    #[cfg(feature = "result-handler")]
+    #[test]
+    fn for_each_payload_test() {
+        let results: Vec<SuggestItem> = (0..5).map(|i| SuggestItem::new(i.to_string(), 1, i)).collect();
+        let mut payloads: Vec<Vec<u8>> = vec![];
+        for_each_payload(Some(3), &results, TextEncoding::Utf8, |payload| payloads.push(payload));
+        assert_eq!(payloads, vec![encode_results(Some(3), &results, TextEncoding::Utf8)]);
+
+        result_batch_size(2);
+        payloads.clear();
+        for_each_payload(Some(3), &results, TextEncoding::Utf8, |payload| payloads.push(payload));
+        result_batch_size(0);
+
+        let counts: Vec<&[u8]> = payloads.iter().map(|payload| &payload[4..8]).collect();
+        assert_eq!(counts, vec![&2u32.to_le_bytes()[..], &2u32.to_le_bytes()[..], &1u32.to_le_bytes()[..], &0u32.to_le_bytes()[..]]);
+        assert_eq!(payloads[2], encode_results(Some(3), &results[4..], TextEncoding::Utf8));
+    }
+
+    #[test]
+    fn lookup_utf16_test() {
+        assert!(symspell_with_options(2, 1, 7));
+        with_sym(|sym| sym.create_dictionary_entry("wörld".into(), 5));
+        let input: Vec<u16> = "wörl".encode_utf16().collect();
+
+        let ptr = unsafe { lookup_utf16_into_buffer(input.as_ptr(), input.len(), Verbosity::Top as u32, 2, false, false, true) };
+        let payload = unsafe { std::slice::from_raw_parts(ptr, last_result_len()) }.to_vec();
+        assert_eq!(payload[0..4], 1u32.to_le_bytes());
+        // item length, then count, distance, the term's byte length, the term and its probability
+        assert_eq!(payload[4..8], 26u32.to_le_bytes());
+        assert_eq!(payload[16..20], 10u32.to_le_bytes());
+        let term: Vec<u16> = payload[20..30].chunks(2).map(|c| u16::from_le_bytes([c[0], c[1]])).collect();
+        assert_eq!(String::from_utf16(&term).unwrap(), "wörld");
+        assert_eq!(payload.len(), 34);
+
+        let ptr = unsafe { lookup_utf16_into_buffer(input.as_ptr(), input.len(), Verbosity::Top as u32, 2, false, false, false) };
+        let payload = unsafe { std::slice::from_raw_parts(ptr, last_result_len()) };
+        assert_eq!(&payload[20..26], "wörld".as_bytes());
+    }
+
+    #[test]
+    fn correct_text_test() {
+        assert!(symspell_with_options(2, 1, 7));
+        with_sym(|sym| {
+            sym.create_dictionary_entry("hello".into(), 10);
+            sym.create_dictionary_entry("world".into(), 10);
+        });
+        let input = b"helo wrld";
+        let ptr = unsafe { correct_text(input.as_ptr() as *mut u8, input.len(), 2, 0) };
+        assert_eq!(unsafe { std::slice::from_raw_parts(ptr, last_result_len()) }, b"hello world");
+
+        let input = b"Helo WRLD";
+        let ptr = unsafe { correct_text(input.as_ptr() as *mut u8, input.len(), 2, CORRECT_TEXT_TRANSFER_CASING) };
+        assert_eq!(unsafe { std::slice::from_raw_parts(ptr, last_result_len()) }, b"Hello WORLD");
+    }
+
+    #[test]
+    fn lookup_with_context_test() {
+        assert!(symspell_with_options(2, 1, 7));
+        with_sym(|sym| {
+            sym.create_dictionary_entry("their".into(), 50);
+            sym.create_dictionary_entry("there".into(), 40);
+            sym.write_line_to_bigram_dictionary("over there 4", " ").unwrap();
+        });
+        let input = b"ther";
+        let lookup = |previous: &[u8]| {
+            let ptr = unsafe { lookup_with_context_into_buffer(previous.as_ptr(), previous.len(), input.as_ptr() as *mut u8, input.len(), Verbosity::Top as u32, 2, false, false) };
+            unsafe { std::slice::from_raw_parts(ptr, last_result_len()) }.to_vec()
+        };
+        assert_eq!(lookup(b"over"), encode_results(None, &[looked_up("there", 1, 40)], TextEncoding::Utf8));
+        assert_eq!(lookup(b""), encode_results(None, &[looked_up("their", 1, 50)], TextEncoding::Utf8));
+        assert!(unsafe { lookup_with_context_into_buffer(b"\xff".as_ptr(), 1, input.as_ptr() as *mut u8, input.len(), Verbosity::Top as u32, 2, false, false) }.is_null());
+    }
+
+    #[test]
+    fn complete_test() {
+        assert!(symspell_with_options(2, 1, 7));
+        with_sym(|sym| {
+            sym.create_dictionary_entry("hello".into(), 10);
+            sym.create_dictionary_entry("help".into(), 20);
+        });
+        let complete = |prefix: &[u8], fuzzy: bool| {
+            let ptr = unsafe { complete_into_buffer(prefix.as_ptr() as *mut u8, prefix.len(), 5, fuzzy) };
+            unsafe { std::slice::from_raw_parts(ptr, last_result_len()) }.to_vec()
+        };
+        let help = SuggestItem::new("help".into(), 0, 20);
+        assert_eq!(complete(b"hel", false), encode_results(None, &[help.clone(), SuggestItem::new("hello".into(), 0, 10)], TextEncoding::Utf8));
+        assert_eq!(complete(b"hlep", false), encode_results::<SuggestItem>(None, &[], TextEncoding::Utf8));
+        assert_eq!(complete(b"hlep", true), encode_results(None, &[SuggestItem { distance: 1, ..help }], TextEncoding::Utf8));
+    }
+
+    #[test]
+    fn lookup_batch_test() {
+        assert!(symspell_with_options(2, 1, 7));
+        with_sym(|sym| sym.create_dictionary_entry("hello".into(), 10));
+        let input = b"helo\r\nxyzzy\nhello\n";
+        let ptr = unsafe { lookup_batch_into_buffer(input.as_ptr() as *mut u8, input.len(), Verbosity::Top as u32, 2, false, true) };
+        let payload = unsafe { std::slice::from_raw_parts(ptr, last_result_len()) };
+
+        let hello = encode_results(None, &[looked_up("hello", 1, 10)], TextEncoding::Utf8);
+        let mut expected = 3u32.to_le_bytes().to_vec();
+        expected.extend_from_slice(&hello);
+        expected.extend_from_slice(&0u32.to_le_bytes());
+        expected.extend_from_slice(&encode_results(None, &[looked_up("hello", 0, 10)], TextEncoding::Utf8));
+        assert_eq!(payload, &expected[..]);
+    }
+
+    #[test]
+    fn framed_output_test() {
+        assert!(symspell_with_options(2, 1, 7));
+        with_sym(|sym| sym.create_dictionary_entry("hello".into(), 10));
+        assert!(output_format(2));
+        let input = b"helo";
+        let ptr = unsafe { lookup_into_buffer(input.as_ptr() as *mut u8, input.len(), Verbosity::Top as u32, 2, false, false) };
+        let frame = ResultFrame::decode(unsafe { std::slice::from_raw_parts(ptr, last_result_len()) }).unwrap();
+        let payload = encode_results(Some(9), &frame.suggestions, TextEncoding::Utf16);
+
+        let input = b"helo\nhello";
+        let ptr = unsafe { lookup_batch_into_buffer(input.as_ptr() as *mut u8, input.len(), Verbosity::Top as u32, 2, false, true) };
+        let batch = unsafe { std::slice::from_raw_parts(ptr, last_result_len()) }.to_vec();
+        assert!(output_format(0));
+
+        assert_eq!(frame.request_id, None);
+        assert_eq!((frame.suggestions[0].term.as_str(), frame.suggestions[0].distance, frame.suggestions[0].count), ("hello", 1, 10));
+        assert_eq!(frame.suggestions[0].probability, looked_up("hello", 1, 10).probability);
+        let frame = ResultFrame::decode(&payload).unwrap();
+        assert_eq!((frame.request_id, frame.suggestions[0].term.as_str()), (Some(9), "hello"));
+
+        assert_eq!(batch[..4], 2u32.to_le_bytes());
+        let first_len = u32::from_le_bytes([batch[4], batch[5], batch[6], batch[7]]) as usize;
+        let first = ResultFrame::decode(&batch[8..8 + first_len]).unwrap();
+        let second = ResultFrame::decode(&batch[12 + first_len..]).unwrap();
+        assert_eq!((first.suggestions[0].distance, second.suggestions[0].distance), (1, 0));
+    }
+
+    #[test]
+    fn invalid_utf8_test() {
+        assert!(symspell_with_options(2, 1, 7));
+        with_sym(|sym| sym.create_dictionary_entry("hello".into(), 10));
+        let input = b"hel\xC3lo";
+
+        // rejected by default, with nothing stored for the host to read
+        let ptr = unsafe { lookup_into_buffer(input.as_ptr() as *mut u8, input.len(), Verbosity::Top as u32, 2, false, false) };
+        assert!(ptr.is_null());
+        assert_eq!((last_result_len(), invalid_utf8_offset()), (0, 3));
+
+        assert!(invalid_utf8_policy(1));
+        let ptr = unsafe { lookup_into_buffer(input.as_ptr() as *mut u8, input.len(), Verbosity::Top as u32, 2, false, false) };
+        let payload = unsafe { std::slice::from_raw_parts(ptr, last_result_len()) };
+        assert_eq!(payload, &encode_results(None, &[looked_up("hello", 1, 10)], TextEncoding::Utf8)[..]);
+        assert_eq!(invalid_utf8_offset(), -1);
+
+        assert!(!invalid_utf8_policy(2));
+        assert!(invalid_utf8_policy(0));
+        let ptr = unsafe { correct_text(b"helo".as_ptr() as *mut u8, 4, 2, 0) };
+        assert_eq!(unsafe { std::slice::from_raw_parts(ptr, last_result_len()) }, b"hello");
+        assert_eq!(invalid_utf8_offset(), -1);
+
+        // utf-16 input has no invalid utf-8 offset to report
+        unsafe { lookup_into_buffer(input.as_ptr() as *mut u8, input.len(), Verbosity::Top as u32, 2, false, false) };
+        let utf16: Vec<u16> = "helo".encode_utf16().collect();
+        unsafe { lookup_utf16_into_buffer(utf16.as_ptr(), utf16.len(), Verbosity::Top as u32, 2, false, false, false) };
+        assert_eq!(invalid_utf8_offset(), -1);
+    }
+
+    #[test]
+    fn invalid_utf8_dictionary_line_test() {
+        assert!(symspell_with_options(2, 1, 7));
+        let data = b"caf\xE9 5\nhello 10";
+        unsafe { write_to_dictionary(data.as_ptr(), data.len(), false) };
+        flush_dictionary(false);
+        assert_eq!((word_entry_count(), last_error_code()), (1, 6));
+        assert_eq!(with_sym(|sym| sym.load_report().malformed_lines), 1);
+
+        assert!(symspell_with_options(2, 1, 7));
+        assert!(invalid_utf8_policy(1));
+        unsafe { write_to_dictionary(data.as_ptr(), data.len(), false) };
+        flush_dictionary(false);
+        assert_eq!(with_sym(|sym| sym.lookup("caf\u{FFFD}", Verbosity::Top, 0, false, true).len()), 1);
+        assert_eq!(with_sym(|sym| sym.load_report().malformed_lines), 0);
+        assert!(invalid_utf8_policy(0));
+    }
+
+    #[test]
+    fn check_text_test() {
+        assert!(symspell_with_options(2, 1, 7));
+        with_sym(|sym| sym.create_dictionary_entry("hello".into(), 10));
+        let input = b"hello helo xyzzy";
+        let ptr = unsafe { check_text_into_buffer(input.as_ptr() as *mut u8, input.len(), 2) };
+        let payload = unsafe { std::slice::from_raw_parts(ptr, last_result_len()) };
+
+        let item = looked_up("hello", 1, 10).encode();
+        let mut expected: Vec<u8> = vec![];
+        for value in [2, 6, 10, 0, item.len() as u32].iter() {
+            expected.extend_from_slice(&value.to_le_bytes());
+        }
+        expected.extend_from_slice(&item);
+        for value in [11u32, 16, 0, 0].iter() {
+            expected.extend_from_slice(&value.to_le_bytes());
+        }
+        assert_eq!(payload, &expected[..]);
+
+        let misspelled = with_sym(|sym| sym.check_text("helo xyzzy hello hello", 2));
+        assert!(output_format(1));
+        let json = encode_misspelled_ranges(&misspelled);
+        assert!(output_format(0));
+        let mut suggestion = String::new();
+        looked_up("Hello", 1, 10).write_json(&mut suggestion);
+        let expected = format!(r#"[{{"start":0,"end":4,"kind":"misspelling","suggestion":{}}},{{"start":5,"end":10,"kind":"misspelling","suggestion":null}},{{"start":17,"end":22,"kind":"repeated_word","suggestion":null}}]"#, suggestion);
+        assert_eq!(json, expected.into_bytes());
+    }
+
+    #[test]
+    fn learn_word_test() {
+        assert!(symspell_with_options(2, 1, 7));
+        let word = b"tickex";
+        assert!(unsafe { learn_word(word.as_ptr(), word.len()) });
+        assert!(with_sym(|sym| sym.is_known("tickex")));
+        decay_counts(0.5);
+        assert!(!with_sym(|sym| sym.is_known("tickex")));
+        assert_eq!(purge_faded(), 1);
+        assert_eq!(with_sym(|sym| sym.word_count()), 0);
+        assert!(!unsafe { learn_word([0xffu8].as_ptr(), 1) });
+    }
+
+    #[test]
+    fn corpus_trainer_test() {
+        assert!(symspell_with_options(2, 1, 7));
+        let corpus = b"the cat sat\nthe cat ran\n";
+        assert!(!unsafe { train_corpus(corpus.as_ptr() as *mut u8, corpus.len()) });
+        assert_eq!(finish_corpus_trainer(), 0);
+
+        corpus_trainer(0, 0, false, true);
+        // a character split between two pieces
+        let split = "the cat sat\nthe ca".len();
+        let corpus = "the cat sat\nthe caé\n".as_bytes();
+        assert!(unsafe { train_corpus(corpus.as_ptr() as *mut u8, split + 1) });
+        assert!(unsafe { train_corpus(corpus[split + 1..].as_ptr() as *mut u8, corpus.len() - split - 1) });
+        assert!(!unsafe { train_corpus(b"\xFF".as_ptr() as *mut u8, 1) });
+        assert_eq!((last_error_code(), invalid_utf8_offset()), (6, 0));
+        let corpus = b" cat ran\n";
+        assert!(unsafe { train_corpus(corpus.as_ptr() as *mut u8, corpus.len()) });
+        let ptr = corpus_dictionary_into_buffer();
+        let dictionary = unsafe { std::slice::from_raw_parts(ptr, last_result_len()) };
+        assert_eq!(dictionary, b"cat 2\nthe 2\n");
+        assert_eq!(finish_corpus_trainer(), 2);
+        assert_eq!(with_sym(|sym| sym.lookup("caat", Verbosity::Top, 1, false, false))[0].term, "cat");
+        assert!(corpus_dictionary_into_buffer().is_null());
+    }
+
+    #[test]
+    fn symspell_test() {
+        symspell(2, 1);
+        assert_eq!(with_sym(|sym| (sym.max_edit_distance(), sym.prefix_length())), (2, 7));
+        // the prefix length grows with an edit distance it would not exceed
+        symspell(7, 1);
+        assert_eq!(with_sym(|sym| (sym.max_edit_distance(), sym.prefix_length())), (7, 8));
+    }
+
+    #[test]
+    fn document_test() {
+        assert!(symspell_with_options(2, 1, 7));
+        with_sym(|sym| sym.create_dictionary_entry("hello".into(), 10));
+        let handle = open_document(2);
+        assert_ne!(handle, 0);
+        assert_ne!(open_document(2), handle);
+
+        let text = b"hello helo";
+        let ptr = unsafe { check_document_into_buffer(handle, text.as_ptr() as *mut u8, text.len()) };
+        let payload = unsafe { std::slice::from_raw_parts(ptr, last_result_len()) };
+        let item = looked_up("hello", 1, 10).encode();
+        let mut expected: Vec<u8> = vec![];
+        for value in [1, 6, 10, 0, item.len() as u32].iter() {
+            expected.extend_from_slice(&value.to_le_bytes());
+        }
+        expected.extend_from_slice(&item);
+        expected.extend_from_slice(&0u32.to_le_bytes());
+        assert_eq!(payload, &expected[..]);
+
+        // "helo" corrected to "hello"
+        let text = b"hello hello";
+        assert!(output_format(1));
+        let ptr = unsafe { edit_document_into_buffer(handle, text.as_ptr() as *mut u8, text.len(), 8, 8, 1) };
+        let payload = unsafe { std::slice::from_raw_parts(ptr, last_result_len()) }.to_vec();
+        assert!(output_format(0));
+        let mut suggestion = String::new();
+        looked_up("hello", 1, 10).write_json(&mut suggestion);
+        let expected = format!(r#"{{"added":[{{"start":6,"end":11,"kind":"repeated_word","suggestion":null}}],"removed":[{{"start":6,"end":10,"kind":"misspelling","suggestion":{}}}]}}"#, suggestion);
+        assert_eq!(payload, expected.into_bytes());
+
+        // an edit past the end of the text or an unknown handle is rejected
+        assert!(unsafe { edit_document_into_buffer(handle, text.as_ptr() as *mut u8, text.len(), 11, 11, 1) }.is_null());
+        assert_eq!(last_error_code(), 9);
+        assert!(close_document(handle));
+        assert!(!close_document(handle));
+        assert!(unsafe { check_document_into_buffer(handle, text.as_ptr() as *mut u8, text.len()) }.is_null());
+    }
+
+    #[test]
+    fn bigram_stats_test() {
+        assert!(symspell_with_options(2, 1, 7));
+        assert_eq!((bigram_entry_count(), bigram_count_min()), (0, 0));
+
+        let data = b"hello world 20\nhello there 5";
+        unsafe { write_to_dictionary(data.as_ptr(), data.len(), true) };
+        flush_dictionary(true);
+        assert_eq!((bigram_entry_count(), bigram_count_min()), (2, 5));
+    }
+
+    #[test]
+    fn trigram_test() {
+        assert!(symspell_with_options(2, 1, 7));
+        let data = b"over there 4\nover their 4";
+        unsafe { write_to_dictionary(data.as_ptr(), data.len(), true) };
+        flush_dictionary(true);
+        let data = b"went over there 3\nwent over";
+        unsafe { write_to_trigram_dictionary(data.as_ptr(), data.len()) };
+        assert_eq!(trigram_entry_count(), 1);
+        flush_trigram_dictionary();
+        assert_eq!(trigram_entry_count(), 1);
+        assert_eq!(with_sym(|sym| sym.load_report().malformed_lines), 1);
+
+        let text = "They lost their way.";
+        assert!(unsafe { learn_ngrams(text.as_ptr(), text.len()) });
+        assert_eq!((bigram_entry_count(), trigram_entry_count()), (5, 3));
+        assert!(!unsafe { learn_ngrams([0xffu8].as_ptr(), 1) });
+        assert_eq!((last_error_code(), invalid_utf8_offset()), (6, 0));
+    }
+
+    #[test]
+    fn symspell_with_options_test() {
+        assert!(!symspell_with_options(2, 1, 2));
+        assert!(symspell_with_options(2, 1, 5));
+        assert_eq!(with_sym(|sym| sym.prefix_length()), 5);
+    }
+
+    #[test]
+    fn estimate_memory_test() {
+        assert!(estimate_memory(3, 7, 82_765) > estimate_memory(2, 7, 82_765));
+        assert_eq!(estimate_memory(2, 7, 1_000), SymSpell::estimate_memory(2, 7, 1_000));
+    }
+
+    #[test]
+    fn last_error_test() {
+        let message = || unsafe { std::str::from_utf8(std::slice::from_raw_parts(last_error_message(), last_error_message_len())) }.unwrap().to_string();
+        clear_last_error();
+        assert_eq!((last_error_code(), last_error_message_len()), (0, 0));
+        assert!(last_error_message().is_null());
+
+        assert!(!symspell_with_options(2, 1, 2));
+        assert_eq!(last_error_code(), 1);
+        assert_eq!(message(), "prefix length 2 must be at least 1 and greater than the max dictionary edit distance 2");
+
+        // a max edit distance the dictionary was not built for is rejected rather than aborting
+        assert!(symspell_with_options(1, 1, 7));
+        let input = b"helo";
+        assert!(unsafe { lookup_into_buffer(input.as_ptr() as *mut u8, input.len(), Verbosity::Top as u32, 2, false, false) }.is_null());
+        assert_eq!(last_error_code(), 2);
+        assert_eq!(open_document(2), 0);
+        assert!(unsafe { lookup_into_buffer(input.as_ptr() as *mut u8, input.len(), 3, 1, false, false) }.is_null());
+        assert_eq!((last_error_code(), message()), (8, "verbosity 3 is not 0 (top), 1 (closest) or 2 (all)".to_string()));
+
+        let data = b"hello\nworld 5\n";
+        unsafe { write_to_dictionary(data.as_ptr(), data.len(), false) };
+        assert_eq!((last_error_code(), message()), (3, "dictionary line needs a word and a count".to_string()));
+
+        assert!(unsafe { check_text_into_buffer(b"\xff".as_ptr() as *mut u8, 1, 1) }.is_null());
+        assert_eq!((last_error_code(), message()), (6, "invalid utf-8 sequence at byte 0".to_string()));
+        clear_last_error();
+        assert_eq!(last_error_code(), 0);
+    }
+
+    #[cfg(feature = "log-handler")]
+    thread_local! {
+        static LOGGED: std::cell::RefCell<Vec<String>> = const { std::cell::RefCell::new(Vec::new()) };
+    }
+
+    // stands in for the log_handler the host imports
+    #[cfg(feature = "log-handler")]
+    #[no_mangle]
+    unsafe extern "C" fn log_handler(ptr: *const u8, len: usize) {
+        let line = std::str::from_utf8(std::slice::from_raw_parts(ptr, len)).unwrap().to_string();
+        LOGGED.with(|logged| logged.borrow_mut().push(line));
+    }
+
+    #[cfg(feature = "log-handler")]
+    #[test]
+    fn logging_test() {
+        assert_ne!(capabilities() & CAPABILITY_LOG_HANDLER, 0);
+        let input = b"helo";
+        assert!(symspell_with_options(2, 1, 7));
+        let data = b"hello 10\nworld 5\n";
+        unsafe { write_to_dictionary(data.as_ptr(), data.len(), false) };
+        flush_dictionary(false);
+        unsafe { lookup_into_buffer(input.as_ptr() as *mut u8, input.len(), Verbosity::Top as u32, 2, false, false) };
+        assert!(LOGGED.with(|logged| logged.borrow().is_empty()));
+
+        logging(true);
+        unsafe { lookup_into_buffer(input.as_ptr() as *mut u8, input.len(), Verbosity::Top as u32, 2, false, false) };
+        assert!(LOGGED.with(|logged| logged.borrow()[0].starts_with("lookup \"helo\": 1 suggestions")));
+
+        // a new instance keeps logging
+        assert!(symspell_with_options(2, 1, 7));
+        unsafe { write_to_dictionary(data.as_ptr(), data.len(), false) };
+        flush_dictionary(false);
+        assert_eq!(LOGGED.with(|logged| logged.borrow()[1].clone()), "dictionary_load: 2 words");
+
+        logging(false);
+        unsafe { lookup_into_buffer(input.as_ptr() as *mut u8, input.len(), Verbosity::Top as u32, 2, false, false) };
+        assert_eq!(LOGGED.with(|logged| logged.borrow().len()), 2);
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn metrics_test() {
+        assert_ne!(capabilities() & CAPABILITY_METRICS, 0);
+        assert!(symspell_with_options(2, 1, 7));
+        let data = b"hello 10\nhelp 5\n";
+        unsafe { write_to_dictionary(data.as_ptr(), data.len(), false) };
+        let input = b"helo";
+        for _ in 0..2 {
+            unsafe { lookup_into_buffer(input.as_ptr() as *mut u8, input.len(), Verbosity::All as u32, 2, false, false) };
+        }
+        assert_eq!(metrics_lookups(), 2);
+        assert!(metrics_average_candidates() > 0.0);
+        assert!(metrics_distance_computations() > 0);
+        assert_eq!(metrics_cache_hits(), 0);
+        assert!(metrics_total_time() >= 0.0);
+
+        reset_metrics();
+        assert_eq!((metrics_lookups(), metrics_distance_computations(), metrics_average_candidates()), (0, 0, 0.0));
+    }
+
+    #[test]
+    fn clear_reset_destroy_test() {
+        assert!(symspell_with_options(2, 1, 7));
+        let data = b"hello 10\nworld 5\npartial";
+        unsafe { write_to_dictionary(data.as_ptr(), data.len(), false) };
+        assert_eq!(with_sym(|sym| sym.word_count()), 2);
+
+        clear_dictionary();
+        assert_eq!(with_sym(|sym| sym.word_count()), 0);
+        assert_eq!(with_sym(|sym| sym.prefix_length()), 7);
+
+        assert!(!reset(3, 3, 1));
+        assert!(reset(1, 4, 1));
+        assert_eq!(with_sym(|sym| (sym.max_edit_distance(), sym.prefix_length())), (1, 4));
+
+        destroy();
+        assert!(SYM.with(|sym_cell| sym_cell.borrow().is_none()));
+    }
+
+    #[test]
+    fn write_lines_test() {
+        let mut sym = SymSpell::new(None, None, None);
+        let mut buffer = vec![];
+        let data = "héllo 10\r\nwörld 5\nlast 1".as_bytes();
+        // split mid way through the two byte 'ö'
+        let split = "héllo 10\r\nw".len() + 1;
+        for chunk in [&data[..split], &data[split..]].iter() {
+            buffer.extend_from_slice(chunk);
+            write_lines(&mut sym, &mut buffer, LineKind::Word, false);
+        }
+        assert_eq!(sym.word_count(), 2);
+        assert_eq!(buffer, b"last 1");
+
+        write_lines(&mut sym, &mut buffer, LineKind::Word, true);
+        assert_eq!(sym.word_count(), 3);
+        assert!(buffer.is_empty());
+
+        let result = sym.lookup("wörld", Verbosity::Top, 0, false, true);
+        assert_eq!(result[0].count, 5);
+        let result = sym.lookup("héllo", Verbosity::Top, 0, false, true);
+        assert_eq!(result[0].count, 10);
+    }
+
+    #[test]
+    fn load_budget_test() {
+        symspell(2, 1);
+        let data = b"hello 10\nhelp 5\nworld 7\nwords 3\n";
+        load_budget(20);
+        unsafe { write_to_dictionary(data.as_ptr(), 9, false) };
+        // the host reads the progress between chunks
+        assert_eq!(word_entry_count(), 1);
+        assert!(delete_entry_count() > 0 && !load_stopped());
+        unsafe { write_to_dictionary(data[9..].as_ptr(), data.len() - 9, false) };
+        assert!(load_stopped());
+        assert!(word_entry_count() < 4);
+        assert!(delete_entry_count() >= 20);
+
+        load_budget(0);
+        assert!(!load_stopped());
+        unsafe { write_to_dictionary(data.as_ptr(), data.len(), false) };
+        assert_eq!(word_entry_count(), 4);
+    }
+}
@@ -0,0 +1,133 @@
+use std::sync::Arc;
+
+use crate::soft_wx::{
+    damerau::Damerau,
+    damerau_osa::DamaerauOSA,
+    jaro_winkler::JaroWinkler,
+    levensthtein::Levenshtein,
+    longest_common_subsequence::LongestCommonSubsequence,
+    sift4::Sift4,
+    weighted_levenshtein::{WeightedCosts, WeightedLevenshtein},
+};
+use crate::soft_wx::Similarity;
+
+/// <summary>Supported similarity algorithms.</summary>
+#[derive(Clone)]
+pub enum SimilarityAlgorithm {
+    Levenshtein,
+    DamaerauOSA,
+    Damerau,
+    /// <summary>Levenshtein with per edit costs, rounded up to whole edits.</summary>
+    Weighted(Arc<WeightedCosts>),
+    /// <summary>The fast approximate Sift4 distance using the given max offset.</summary>
+    Sift4(usize),
+    /// <summary>Insertions and deletions only, so a substitution counts as 2.</summary>
+    LongestCommonSubsequence,
+    /// <summary>Jaro-Winkler using the given prefix scale, see `jaro_winkler::DEFAULT_PREFIX_SCALE`.</summary>
+    JaroWinkler(f64),
+}
+
+/// <summary>Wrapper for the similarity algorithms, the normalized counterpart of EditDistance.</summary>
+/// <remarks>Every algorithm gives a similarity of 1 for two empty strings and 0 when only
+/// one is empty. The edit distance based algorithms are 1 - (distance / len of the longer
+/// string), never less than 0.</remarks>
+///
+/// example:
+/// let mut similarity = StringSimilarity::new(SimilarityAlgorithm::Levenshtein);
+///
+/// assert_eq!(similarity.compare("kitten", "mitten", None), Some(5.0 / 6.0));
+/// assert_eq!(similarity.compare("kitten", "sitting", Some(0.6)), None);
+pub struct StringSimilarity {
+    similarity_comparator: Box<dyn Similarity>,
+}
+
+impl StringSimilarity {
+    /// <summary>Create a new StringSimilarity object.</summary>
+    /// <param name="algorithm">The desired similarity algorithm.</param>
+    pub fn new(similarity_algorithm: SimilarityAlgorithm) -> StringSimilarity {
+        let similarity_comparator: Box<dyn Similarity> = match similarity_algorithm {
+            SimilarityAlgorithm::Levenshtein => Box::new(Levenshtein::new()),
+            SimilarityAlgorithm::DamaerauOSA => Box::new(DamaerauOSA::new()),
+            SimilarityAlgorithm::Damerau => Box::new(Damerau::new()),
+            SimilarityAlgorithm::Weighted(costs) => Box::new(WeightedLevenshtein::new(costs)),
+            SimilarityAlgorithm::Sift4(max_offset) => Box::new(Sift4::with_max_offset(max_offset)),
+            SimilarityAlgorithm::LongestCommonSubsequence => Box::new(LongestCommonSubsequence::new()),
+            SimilarityAlgorithm::JaroWinkler(prefix_scale) => Box::new(JaroWinkler::with_prefix_scale(prefix_scale)),
+        };
+
+        StringSimilarity {
+            similarity_comparator,
+        }
+    }
+
+    /// <summary>Compare two strings to determine their similarity, using the previously
+    /// selected algorithm.</summary>
+    /// <param name="minSimilarity">The minimum similarity of interest, from 0 to 1.0.</param>
+    /// <returns>The similarity from 0 to 1.0, or None when it is below minSimilarity.</returns>
+    pub fn compare(&mut self, string1: &str, string2: &str, min_similarity: Option<f64>) -> Option<f64> {
+        match min_similarity {
+            Some(min_similarity) => self.similarity_comparator.similarity2(string1, string2, min_similarity),
+            None => self.similarity_comparator.similarity(string1, string2),
+        }
+    }
+}
+
+#[cfg(test)]
+mod string_similarity_tests {
+    use std::sync::Arc;
+
+    use crate::soft_wx::jaro_winkler::DEFAULT_PREFIX_SCALE;
+    use crate::soft_wx::sift4::DEFAULT_MAX_OFFSET;
+    use crate::soft_wx::weighted_levenshtein::WeightedCosts;
+    use crate::string_similarity::{SimilarityAlgorithm, StringSimilarity};
+
+    fn algorithms() -> Vec<SimilarityAlgorithm> {
+        vec![
+            SimilarityAlgorithm::Levenshtein,
+            SimilarityAlgorithm::DamaerauOSA,
+            SimilarityAlgorithm::Damerau,
+            SimilarityAlgorithm::Weighted(Arc::new(WeightedCosts::new())),
+            SimilarityAlgorithm::Sift4(DEFAULT_MAX_OFFSET),
+            SimilarityAlgorithm::LongestCommonSubsequence,
+            SimilarityAlgorithm::JaroWinkler(DEFAULT_PREFIX_SCALE),
+        ]
+    }
+
+    #[test]
+    fn edge_cases_test() {
+        for (i, algorithm) in algorithms().into_iter().enumerate() {
+            let mut similarity = StringSimilarity::new(algorithm);
+            assert_eq!(similarity.compare("", "", None), Some(1.0), "{}", i);
+            assert_eq!(similarity.compare("abc", "", None), Some(0.0), "{}", i);
+            assert_eq!(similarity.compare("", "abc", None), Some(0.0), "{}", i);
+            assert_eq!(similarity.compare("", "abc", Some(0.5)), None, "{}", i);
+            assert_eq!(similarity.compare("héllo", "héllo", None), Some(1.0), "{}", i);
+            assert_eq!(similarity.compare("héllo", "héllo", Some(1.0)), Some(1.0), "{}", i);
+            assert_eq!(similarity.compare("abc", "xyz", None), Some(0.0), "{}", i);
+            assert_eq!(similarity.compare("abc", "xyz", Some(0.1)), None, "{}", i);
+        }
+    }
+
+    #[test]
+    fn symmetric_test() {
+        let pairs = [("kitten", "sitting"), ("ab", "abcd"), ("résumé", "resume"), ("🚀abc", "abc🚀")];
+        for (i, algorithm) in algorithms().into_iter().enumerate() {
+            let mut similarity = StringSimilarity::new(algorithm);
+            for &(string1, string2) in pairs.iter() {
+                let forward = similarity.compare(string1, string2, None).unwrap();
+                assert!((0.0..=1.0).contains(&forward), "{} {} {}", i, string1, string2);
+                assert_eq!(similarity.compare(string2, string1, None), Some(forward), "{} {} {}", i, string1, string2);
+                assert_eq!(similarity.compare(string2, string1, Some(forward)), Some(forward), "{} {} {}", i, string1, string2);
+            }
+        }
+    }
+
+    #[test]
+    fn compare_test() {
+        let mut osa = StringSimilarity::new(SimilarityAlgorithm::DamaerauOSA);
+        assert_eq!(osa.compare("abcd", "abdc", None), Some(0.75));
+        assert_eq!(osa.compare("abcd", "abdc", Some(0.8)), None);
+        // the longer string may come first
+        assert_eq!(osa.compare("abcde", "ab", Some(0.4)), Some(0.4));
+    }
+}
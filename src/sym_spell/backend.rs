@@ -0,0 +1,17 @@
+/// How a `SymSpell` finds the dictionary words within the edit distance of an
+/// input, chosen when it is created with `SymSpell::with_backend`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Backend {
+    /// Looks up the deletes of the input in the index of the deletes of every
+    /// word, the SymSpell algorithm. The fastest lookups, at about ten times the
+    /// memory of the words, see `DeleteGeneration`.
+    #[default]
+    DeleteHash,
+    /// Walks a directed acyclic word graph of the words, which stores the
+    /// endings shared by many words once, keeping only the branches within the
+    /// edit distance. No deletes are stored so it takes a fraction of the memory,
+    /// and no candidate is ever a hash collision, at the cost of slower lookups
+    /// for larger edit distances. Available with the `dawg` feature.
+    #[cfg(feature = "dawg")]
+    Dawg,
+}
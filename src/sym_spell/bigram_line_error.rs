@@ -0,0 +1,22 @@
+use std::error::Error;
+use std::fmt;
+
+/// Why a line passed to `SymSpell::write_line_to_bigram_dictionary` was rejected.
+#[derive(Clone, Debug, PartialEq)]
+pub enum BigramLineError {
+    /// The line has fewer than the two words of a bigram.
+    MissingWords,
+    /// The line has more parts than two words and a count.
+    TooManyParts(usize),
+}
+
+impl fmt::Display for BigramLineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BigramLineError::MissingWords => write!(f, "bigram line needs two words"),
+            BigramLineError::TooManyParts(parts) => write!(f, "bigram line has {} parts, expected two words and an optional count", parts),
+        }
+    }
+}
+
+impl Error for BigramLineError {}
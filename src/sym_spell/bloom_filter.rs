@@ -0,0 +1,66 @@
+use std::hash::{BuildHasher, Hasher};
+
+use crate::sym_spell::delete_hasher::Fnv1aState;
+
+/// A set of words that answers whether a word may be in it with no false
+/// negatives and false positives at the rate it was sized for, in about 10 bits
+/// per word for a 1% rate.
+#[derive(Debug)]
+pub(crate) struct BloomFilter {
+    bits: Vec<u64>,
+    hashes: u32,
+}
+
+impl BloomFilter {
+    pub(crate) fn new(len: usize, false_positive_rate: f64) -> BloomFilter {
+        let len = len.max(1) as f64;
+        let bits = (-len * false_positive_rate.ln() / (2f64.ln() * 2f64.ln())).ceil().max(64.0) as usize;
+        let hashes = (bits as f64 / len * 2f64.ln()).round().clamp(1.0, 16.0) as u32;
+        BloomFilter {
+            bits: vec![0; bits.div_ceil(64)],
+            hashes,
+        }
+    }
+
+    pub(crate) fn insert(&mut self, word: &str) {
+        for bit in self.bit_indices(word) {
+            self.bits[bit / 64] |= 1 << (bit % 64);
+        }
+    }
+
+    /// False when the word was certainly not inserted.
+    pub(crate) fn may_contain(&self, word: &str) -> bool {
+        self.bit_indices(word).all(|bit| self.bits[bit / 64] & (1 << (bit % 64)) != 0)
+    }
+
+    // the bits of the word, derived from two halves of its hash
+    fn bit_indices(&self, word: &str) -> impl Iterator<Item=usize> {
+        let mut hasher = Fnv1aState.build_hasher();
+        hasher.write(word.as_bytes());
+        let hash = hasher.finish();
+        let first = hash;
+        let second = hash.wrapping_mul(0x9e37_79b9_7f4a_7c15).rotate_left(31) | 1;
+        let len = self.bits.len() as u64 * 64;
+        (0..self.hashes as u64).map(move |i| (first.wrapping_add(i.wrapping_mul(second)) % len) as usize)
+    }
+}
+
+#[cfg(test)]
+mod bloom_filter_tests {
+    use crate::sym_spell::bloom_filter::BloomFilter;
+
+    #[test]
+    fn may_contain_test() {
+        let words: Vec<String> = (0..1000).map(|i| format!("word{}", i)).collect();
+        let mut filter = BloomFilter::new(words.len(), 0.01);
+        for word in words.iter() {
+            filter.insert(word);
+        }
+        assert!(words.iter().all(|word| filter.may_contain(word)));
+        let false_positives = (0..10000).filter(|i| filter.may_contain(&format!("other{}", i))).count();
+        assert!(false_positives < 300, "{}", false_positives);
+
+        let empty = BloomFilter::new(0, 0.01);
+        assert!(!empty.may_contain("word"));
+    }
+}
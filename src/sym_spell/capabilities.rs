@@ -0,0 +1,40 @@
+/// The optional Cargo features this build was compiled with, so hosts can
+/// adapt rather than call something that is not there.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Capabilities {
+    /// The `lookup*` and `check_text` exports that push results through `result_handler`
+    pub result_handler: bool,
+    /// The typed `wasm-bindgen` wrapper
+    pub wasm_bindgen: bool,
+    /// Panic reporting through `error_handler`
+    pub small_binary: bool,
+    /// `SymSpell::lookup_compound_parallel`
+    pub parallel: bool,
+    /// `SymSpell::set_normalization`
+    pub unicode_normalization: bool,
+    /// Spans and events of the `tracing` crate
+    pub tracing: bool,
+    /// The `logging` export forwarding log events to `log_handler`
+    pub log_handler: bool,
+    /// `SymSpell::metrics` and the `metrics_*` exports
+    pub metrics: bool,
+    /// `Backend::Dawg`
+    pub dawg: bool,
+}
+
+impl Capabilities {
+    /// The features of the running build.
+    pub const fn compiled() -> Capabilities {
+        Capabilities {
+            result_handler: cfg!(feature = "result-handler"),
+            wasm_bindgen: cfg!(feature = "wasm-bindgen"),
+            small_binary: cfg!(feature = "small-binary"),
+            parallel: cfg!(feature = "parallel"),
+            unicode_normalization: cfg!(feature = "unicode-normalization"),
+            tracing: cfg!(feature = "tracing"),
+            log_handler: cfg!(feature = "log-handler"),
+            metrics: cfg!(feature = "metrics"),
+            dawg: cfg!(feature = "dawg"),
+        }
+    }
+}
@@ -0,0 +1,21 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::sym_spell::misspelled_range::MisspelledRange;
+
+/// The change to the ranges reported for a document, returned by `IncrementalChecker`.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CheckDelta {
+    /// The ranges found by the check, with byte offsets in the text as it is now
+    pub added: Vec<MisspelledRange>,
+    /// The ranges no longer reported, with the byte offsets they were reported with
+    pub removed: Vec<MisspelledRange>,
+}
+
+impl CheckDelta {
+    /// True when the check changed nothing.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
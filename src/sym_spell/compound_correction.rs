@@ -0,0 +1,24 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::sym_spell::compound_part::CompoundPart;
+use crate::sym_spell::part_status::PartStatus;
+use crate::sym_spell::suggested_item::SuggestItem;
+
+/// The correction of a multi-word input returned by `SymSpell::lookup_compound_detailed`,
+/// along with how each of its parts was corrected.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CompoundCorrection {
+    /// The corrected input, identical to the suggestion of `SymSpell::lookup_compound`
+    pub suggestion: SuggestItem,
+    /// The parts of the input in order
+    pub parts: Vec<CompoundPart>,
+}
+
+impl CompoundCorrection {
+    /// True when some part had no correction within the max edit distance.
+    pub fn has_unknown(&self) -> bool {
+        self.parts.iter().any(|part| part.status == PartStatus::Unknown)
+    }
+}
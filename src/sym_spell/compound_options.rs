@@ -0,0 +1,10 @@
+/// Per call options for `SymSpell::lookup_compound_with_options`.
+#[derive(Clone, Copy, Default)]
+pub struct CompoundOptions {
+    /// Leave capitalized tokens that appear mid-sentence (likely proper nouns)
+    /// unchanged unless the best suggestion is also capitalized in the dictionary.
+    pub preserve_proper_nouns: bool,
+    /// Look up the input in lowercase and map its capitalization onto the
+    /// correction, so "Whereis th elove" becomes "Where is the love".
+    pub transfer_casing: bool,
+}
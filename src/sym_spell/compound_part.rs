@@ -0,0 +1,19 @@
+use std::ops::Range;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::sym_spell::part_status::PartStatus;
+use crate::sym_spell::suggested_item::SuggestItem;
+
+/// A part of the correction returned by `SymSpell::lookup_compound_detailed`, one
+/// input word or two merged ones.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CompoundPart {
+    /// The byte offsets of the part in the input, after normalization if any
+    pub byte_range: Range<usize>,
+    /// What the part was replaced with, the part itself when it is unchanged or unknown
+    pub suggestion: SuggestItem,
+    pub status: PartStatus,
+}
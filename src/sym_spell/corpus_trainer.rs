@@ -0,0 +1,326 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, BufRead, Write};
+use std::mem;
+
+use crate::grapheme_iterator::decode_utf8;
+use crate::sym_spell::delete_hasher::Fnv1aState;
+use crate::sym_spell::log_event::LogEvent;
+use crate::sym_spell::spellchecker_error::SpellcheckerError;
+use crate::sym_spell::sym_spell::SymSpell;
+use crate::sym_spell::trainer_options::TrainerOptions;
+use crate::sym_spell::Count;
+use crate::tokenizer::{TokenClass, Tokenizer, TokenizerOptions};
+use crate::utils::{lowercase_graphemes_in, CaseLocale};
+
+/// Counts the words of a text corpus that arrives in pieces, e.g. read from a file
+/// or passed in by a host, to build the frequency dictionary of a language that has
+/// none. Words are split like `SymSpell::check_text` splits them, and tokens made
+/// only of digits are not counted. A word split between two pieces is counted once.
+///
+/// example:
+/// let mut trainer = CorpusTrainer::new(TrainerOptions::default());
+/// trainer.read_from(BufReader::new(File::open("corpus.txt")?))?;
+/// trainer.write_dictionary(File::create("frequency_dictionary.txt")?)?;
+pub struct CorpusTrainer {
+    options: TrainerOptions,
+    tokenizer: Box<dyn Tokenizer>,
+    case_locale: CaseLocale,
+    counts: HashMap<String, Count, Fnv1aState>,
+    // the hashes of the lines counted when duplicate lines are skipped
+    lines_seen: HashSet<u64, Fnv1aState>,
+    // the end of the text pushed, a word or line that may continue in the next push
+    pending: String,
+    // the start of a utf-8 sequence split at the end of the bytes pushed
+    pending_bytes: Vec<u8>,
+    tokens: usize,
+}
+
+impl CorpusTrainer {
+    pub fn new(options: TrainerOptions) -> CorpusTrainer {
+        CorpusTrainer {
+            options,
+            tokenizer: Box::new(TokenizerOptions::default()),
+            case_locale: CaseLocale::default(),
+            counts: HashMap::default(),
+            lines_seen: HashSet::default(),
+            pending: String::new(),
+            pending_bytes: Vec::new(),
+            tokens: 0,
+        }
+    }
+
+    pub fn options(&self) -> TrainerOptions {
+        self.options
+    }
+
+    /// Replaces the tokenizer splitting the corpus into words, see `SymSpell::set_tokenizer`.
+    pub fn set_tokenizer(&mut self, tokenizer: Box<dyn Tokenizer>) {
+        self.tokenizer = tokenizer;
+    }
+
+    /// Sets the casing rules words are lowercased with, see `SymSpell::set_case_locale`.
+    pub fn set_case_locale(&mut self, case_locale: CaseLocale) {
+        self.case_locale = case_locale;
+    }
+
+    /// The tokens counted so far.
+    pub fn tokens(&self) -> usize {
+        self.tokens
+    }
+
+    /// The distinct words counted so far, including those seen once.
+    pub fn word_count(&self) -> usize {
+        self.counts.len()
+    }
+
+    /// True once `TrainerOptions::max_tokens` tokens were counted.
+    pub fn is_stopped(&self) -> bool {
+        self.options.max_tokens.is_some_and(|max_tokens| self.tokens >= max_tokens)
+    }
+
+    /// Appends the text to the corpus. Returns false once the trainer is stopped,
+    /// after which further text is ignored.
+    pub fn push(&mut self, text: &str) -> bool {
+        if self.is_stopped() {
+            return false;
+        }
+        self.pending.push_str(text);
+        let pending = mem::take(&mut self.pending);
+        let complete = if self.options.dedup_lines {
+            pending.rfind('\n').map_or(0, |i| i + 1)
+        } else {
+            pending.char_indices().rev().find(|(_, c)| c.is_whitespace()).map_or(0, |(i, c)| i + c.len_utf8())
+        };
+        self.count_text(&pending[..complete]);
+        if !self.is_stopped() {
+            self.pending = pending[complete..].to_string();
+        }
+        !self.is_stopped()
+    }
+
+    /// Appends utf-8 encoded text to the corpus, e.g. a chunk of a file read by a host,
+    /// like `push`. A character split between two pushes is held back until the rest
+    /// of it arrives. When `lossy` is true each invalid sequence is replaced with U+FFFD,
+    /// otherwise the push is rejected with `SpellcheckerError::InvalidUtf8`, whose
+    /// offset counts from the start of the bytes.
+    pub fn push_bytes(&mut self, bytes: &[u8], lossy: bool) -> Result<bool, SpellcheckerError> {
+        let held_back = self.pending_bytes.len();
+        let mut buffered = mem::take(&mut self.pending_bytes);
+        buffered.extend_from_slice(bytes);
+        let complete = complete_utf8_len(&buffered);
+        match decode_utf8(&buffered[..complete], lossy) {
+            Ok(text) => {
+                let taking_text = self.push(&text);
+                self.pending_bytes = buffered[complete..].to_vec();
+                Ok(taking_text)
+            }
+            Err(err) => {
+                buffered.truncate(held_back);
+                self.pending_bytes = buffered;
+                Err(SpellcheckerError::InvalidUtf8 { valid_up_to: err.valid_up_to().saturating_sub(held_back) })
+            }
+        }
+    }
+
+    /// Pushes the lines of the reader until it is exhausted or the trainer is stopped.
+    pub fn read_from(&mut self, mut reader: impl BufRead) -> io::Result<()> {
+        let mut line = String::new();
+        while reader.read_line(&mut line)? > 0 {
+            if !self.push(&line) {
+                break;
+            }
+            line.clear();
+        }
+        Ok(())
+    }
+
+    /// Counts the text still waiting for the rest of its word or line. More text
+    /// can be pushed afterwards.
+    pub fn finish(&mut self) {
+        let pending = mem::take(&mut self.pending);
+        self.count_text(&pending);
+    }
+
+    /// The words counted with their counts, most frequent first, after `finish`.
+    pub fn words(&mut self) -> Vec<(String, Count)> {
+        self.finish();
+        let min_count = if self.options.drop_hapax_legomena { 2 } else { 1 };
+        let mut words: Vec<(String, Count)> = self.counts.iter()
+            .filter(|(_, count)| **count >= min_count)
+            .map(|(word, count)| (word.clone(), *count))
+            .collect();
+        words.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        words
+    }
+
+    /// Adds the words to the dictionary of the SymSpell instance, see `words`.
+    pub fn load_into(&mut self, sym_spell: &mut SymSpell) {
+        for (word, count) in self.words() {
+            sym_spell.create_dictionary_entry(word, count);
+        }
+        sym_spell.log(&LogEvent::DictionaryLoad { words: sym_spell.word_count() });
+    }
+
+    /// The SymSpell instance with the words added to its dictionary.
+    ///
+    /// example:
+    /// let sym_spell = trainer.into_sym_spell(SymSpell::new(Some(2), Some(7), None));
+    pub fn into_sym_spell(mut self, mut sym_spell: SymSpell) -> SymSpell {
+        self.load_into(&mut sym_spell);
+        sym_spell
+    }
+
+    /// Writes the words as a frequency dictionary, a line of each word and its count
+    /// separated by a space, the format `SymSpell::write_line_to_dictionary` reads.
+    pub fn write_dictionary(&mut self, mut writer: impl Write) -> io::Result<()> {
+        for (word, count) in self.words() {
+            writeln!(writer, "{} {}", word, count)?;
+        }
+        writer.flush()
+    }
+
+    // counts the words of the text, skipping the lines seen before when duplicates are skipped
+    fn count_text(&mut self, text: &str) {
+        if !self.options.dedup_lines {
+            self.count_words(text);
+            return;
+        }
+        for line in text.split_inclusive('\n') {
+            let content = line.trim();
+            let mut hasher = Fnv1aState.build_hasher();
+            hasher.write(content.as_bytes());
+            if content.is_empty() || self.lines_seen.insert(hasher.finish()) {
+                self.count_words(line);
+            }
+        }
+    }
+
+    fn count_words(&mut self, text: &str) {
+        for token in self.tokenizer.tokenize(text) {
+            if self.is_stopped() {
+                return;
+            }
+            if token.class != TokenClass::Word || token.text.chars().all(char::is_numeric) {
+                continue;
+            }
+            self.tokens += 1;
+            let word = if self.options.lowercase { lowercase_graphemes_in(token.text, self.case_locale) } else { Cow::Borrowed(token.text) };
+            match self.counts.get_mut(word.as_ref()) {
+                Some(count) => *count = count.saturating_add(1),
+                None => {
+                    self.counts.insert(word.into_owned(), 1);
+                }
+            }
+            if self.options.max_words.is_some_and(|max_words| self.counts.len() > max_words) {
+                self.prune();
+            }
+        }
+    }
+
+    // drops the least frequent words until half of max_words are left, of words
+    // counted as often the alphabetically last
+    fn prune(&mut self) {
+        let keep = self.options.max_words.unwrap_or(usize::MAX) / 2;
+        if self.counts.len() <= keep {
+            return;
+        }
+        let mut counts: Vec<(Count, &String)> = self.counts.iter().map(|(word, count)| (*count, word)).collect();
+        let (_, &mut (threshold, first_dropped), _) = counts.select_nth_unstable_by(keep, |a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+        let first_dropped = first_dropped.clone();
+        self.counts.retain(|word, count| *count > threshold || *count == threshold && *word < first_dropped);
+    }
+}
+
+// the length of the bytes without the incomplete utf-8 sequence they may end with
+fn complete_utf8_len(bytes: &[u8]) -> usize {
+    // a sequence is at most 4 bytes, so an incomplete one starts within the last 3
+    for (i, &byte) in bytes.iter().enumerate().rev().take(3) {
+        if byte & 0xC0 != 0x80 {
+            let sequence_len = match byte {
+                0xC0..=0xDF => 2,
+                0xE0..=0xEF => 3,
+                0xF0..=0xF7 => 4,
+                _ => 1,
+            };
+            return if i + sequence_len > bytes.len() { i } else { bytes.len() };
+        }
+    }
+    bytes.len()
+}
+
+#[cfg(test)]
+mod corpus_trainer_tests {
+    use crate::sym_spell::corpus_trainer::CorpusTrainer;
+    use crate::sym_spell::spellchecker_error::SpellcheckerError;
+    use crate::sym_spell::sym_spell::SymSpell;
+    use crate::sym_spell::trainer_options::TrainerOptions;
+    use crate::sym_spell::verbosity::Verbosity;
+
+    #[test]
+    fn push_test() {
+        let mut trainer = CorpusTrainer::new(TrainerOptions::default());
+        assert!(trainer.push("The cat sat on the m"));
+        assert!(trainer.push("at. The cat"));
+        trainer.push(" ran in 2024, the mat sat");
+        assert_eq!(trainer.words(), vec![
+            ("the".to_string(), 4),
+            ("cat".to_string(), 2),
+            ("mat".to_string(), 2),
+            ("sat".to_string(), 2),
+        ]);
+        assert_eq!(trainer.tokens(), 13);
+
+        let mut dictionary = vec![];
+        trainer.write_dictionary(&mut dictionary).unwrap();
+        assert_eq!(String::from_utf8(dictionary).unwrap(), "the 4\ncat 2\nmat 2\nsat 2\n");
+
+        let sym_spell = trainer.into_sym_spell(SymSpell::new(Some(2), Some(7), None));
+        assert_eq!(sym_spell.word_count(), 4);
+        assert_eq!(sym_spell.lookup("caat", Verbosity::Top, 2, false, false)[0].term, "cat");
+    }
+
+    #[test]
+    fn options_test() {
+        let corpus = "Home | About\nthe cat sat\nHome | About\nthe dog sat\n";
+        let mut trainer = CorpusTrainer::new(TrainerOptions { dedup_lines: true, drop_hapax_legomena: false, ..TrainerOptions::default() });
+        trainer.read_from(corpus.as_bytes()).unwrap();
+        let words = trainer.words();
+        assert!(words.contains(&("home".to_string(), 1)) && words.contains(&("sat".to_string(), 2)));
+
+        let mut trainer = CorpusTrainer::new(TrainerOptions { max_tokens: Some(4), ..TrainerOptions::default() });
+        assert!(!trainer.push("a b a b a b "));
+        assert!(trainer.is_stopped());
+        assert!(!trainer.push("a b"));
+        assert_eq!(trainer.words(), vec![("a".to_string(), 2), ("b".to_string(), 2)]);
+
+        let mut trainer = CorpusTrainer::new(TrainerOptions { max_words: Some(4), lowercase: false, ..TrainerOptions::default() });
+        trainer.push("The the the cat cat a b c d e ");
+        assert!(trainer.word_count() <= 4);
+        assert_eq!(trainer.words(), vec![("cat".to_string(), 2), ("the".to_string(), 2)]);
+
+        // of words counted as often the alphabetically first are kept
+        let mut trainer = CorpusTrainer::new(TrainerOptions { max_words: Some(4), drop_hapax_legomena: false, ..TrainerOptions::default() });
+        trainer.push("d e a c b ");
+        assert_eq!(trainer.word_count(), 2);
+        assert_eq!(trainer.words(), vec![("a".to_string(), 1), ("b".to_string(), 1)]);
+    }
+
+    #[test]
+    fn push_bytes_test() {
+        let corpus = "café crème café".as_bytes();
+        let mut trainer = CorpusTrainer::new(TrainerOptions::default());
+        // every split, including those within 'é' and 'è'
+        for chunk in corpus.chunks(1) {
+            assert_eq!(trainer.push_bytes(chunk, false), Ok(true));
+        }
+        assert_eq!(trainer.words(), vec![("café".to_string(), 2)]);
+
+        let mut trainer = CorpusTrainer::new(TrainerOptions { drop_hapax_legomena: false, ..TrainerOptions::default() });
+        assert_eq!(trainer.push_bytes(b"caf\xC3", false), Ok(true));
+        assert_eq!(trainer.push_bytes(b"\xA9 t\xFFa ", false), Err(SpellcheckerError::InvalidUtf8 { valid_up_to: 3 }));
+        assert_eq!(trainer.push_bytes(b"\xA9 t\xFFa ", true), Ok(true));
+        assert_eq!(trainer.words(), vec![("a".to_string(), 1), ("café".to_string(), 1), ("t".to_string(), 1)]);
+    }
+}
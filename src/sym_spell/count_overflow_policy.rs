@@ -0,0 +1,12 @@
+/// Controls what happens when adding to a word's frequency count would overflow.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum CountOverflowPolicy {
+    /// Clamp the count at the maximum value.
+    #[default]
+    Saturate,
+    /// Discard the update, leaving the previous count in place.
+    Error,
+    /// Halve every count in the dictionary so the new total fits. This keeps
+    /// relative frequencies intact but changes the absolute counts.
+    Rescale,
+}
@@ -0,0 +1,176 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::grapheme_iterator::GraphemeClusters;
+
+// a node of the graph, its edges labelled with a grapheme each in label order
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+struct Node {
+    terminal: bool,
+    edges: Vec<(Box<str>, u32)>,
+}
+
+// a node of the trie the graph is built from
+#[derive(Default)]
+struct TrieNode<'a> {
+    terminal: bool,
+    children: BTreeMap<&'a str, usize>,
+}
+
+/// The directed acyclic word graph searched by `Backend::Dawg`: a trie of the
+/// dictionary words whose identical subtrees, such as the endings many words
+/// share, are stored once.
+#[derive(Debug, Default)]
+pub(crate) struct Dawg {
+    nodes: Vec<Node>,
+    root: u32,
+}
+
+impl Dawg {
+    pub(crate) fn new<'a>(words: impl IntoIterator<Item=&'a str>) -> Dawg {
+        let mut trie = vec![TrieNode::default()];
+        for word in words {
+            let mut node = 0;
+            for (grapheme, _) in GraphemeClusters::new(word) {
+                node = match trie[node].children.get(grapheme) {
+                    Some(&child) => child,
+                    None => {
+                        trie.push(TrieNode::default());
+                        let child = trie.len() - 1;
+                        trie[node].children.insert(grapheme, child);
+                        child
+                    }
+                };
+            }
+            trie[node].terminal = true;
+        }
+        let mut dawg = Dawg::default();
+        dawg.root = dawg.merge(&trie, 0, &mut HashMap::new());
+        dawg
+    }
+
+    // the id of the node equal to the trie node, adding it after its children unless an equal one was added already
+    fn merge(&mut self, trie: &[TrieNode], node: usize, registry: &mut HashMap<Node, u32>) -> u32 {
+        let edges = trie[node].children.iter()
+            .map(|(label, &child)| (Box::from(*label), self.merge(trie, child, registry)))
+            .collect();
+        let node = Node { terminal: trie[node].terminal, edges };
+        if let Some(&id) = registry.get(&node) {
+            return id;
+        }
+        let id = self.nodes.len() as u32;
+        self.nodes.push(node.clone());
+        registry.insert(node, id);
+        id
+    }
+
+    /// The number of nodes, each word ending and shared tail stored once.
+    #[cfg(test)]
+    pub(crate) fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Calls `found` with every word within `max_distance` edits of the input, by
+    /// the optimal string alignment distance over graphemes, and that distance. Gives
+    /// up once `max_nodes` nodes were visited, returning None, otherwise returns the
+    /// number of nodes visited.
+    pub(crate) fn search(&self, input: &str, max_distance: usize, max_nodes: Option<usize>, found: &mut dyn FnMut(&str, usize)) -> Option<usize> {
+        let input: Vec<&str> = GraphemeClusters::new(input).map(|(grapheme, _)| grapheme).collect();
+        let mut walk = Walk {
+            input: &input,
+            max_distance,
+            max_nodes: max_nodes.unwrap_or(usize::MAX),
+            word: String::new(),
+            labels: vec![],
+            rows: vec![(0..=input.len()).collect()],
+            visited: 0,
+        };
+        let completed = self.nodes.is_empty() || walk.visit(self, self.root, found);
+        completed.then_some(walk.visited)
+    }
+}
+
+// the state of a search, the edit distances of the input to the word walked so far a row per grapheme
+struct Walk<'d, 'i> {
+    input: &'i [&'i str],
+    max_distance: usize,
+    max_nodes: usize,
+    word: String,
+    labels: Vec<&'d str>,
+    rows: Vec<Vec<usize>>,
+    visited: usize,
+}
+
+impl<'d, 'i> Walk<'d, 'i> {
+    // visits the children of the node, false when the node limit was reached
+    fn visit(&mut self, dawg: &'d Dawg, node: u32, found: &mut dyn FnMut(&str, usize)) -> bool {
+        for (label, child) in dawg.nodes[node as usize].edges.iter() {
+            if self.visited >= self.max_nodes {
+                return false;
+            }
+            self.visited += 1;
+            let row = self.next_row(label);
+            let distance = row[self.input.len()];
+            let within = row.iter().min().is_some_and(|min| *min <= self.max_distance);
+            self.rows.push(row);
+            self.labels.push(label);
+            self.word.push_str(label);
+            let child_node = &dawg.nodes[*child as usize];
+            if child_node.terminal && distance <= self.max_distance {
+                found(&self.word, distance);
+            }
+            // no word below is closer than the closest prefix of the input
+            let completed = !within || self.visit(dawg, *child, found);
+            self.word.truncate(self.word.len() - label.len());
+            self.labels.pop();
+            self.rows.pop();
+            if !completed {
+                return false;
+            }
+        }
+        true
+    }
+
+    // the distances of the prefixes of the input to the word walked so far followed by the label
+    fn next_row(&self, label: &str) -> Vec<usize> {
+        let depth = self.rows.len();
+        let previous = &self.rows[depth - 1];
+        let mut row = Vec::with_capacity(previous.len());
+        row.push(depth);
+        for i in 1..previous.len() {
+            let cost = if self.input[i - 1] == label { 0 } else { 1 };
+            let mut distance = (previous[i] + 1).min(row[i - 1] + 1).min(previous[i - 1] + cost);
+            // adjacent graphemes swapped
+            if i > 1 && depth > 1 && self.input[i - 1] == self.labels[depth - 2] && self.input[i - 2] == label {
+                distance = distance.min(self.rows[depth - 2][i - 2] + 1);
+            }
+            row.push(distance);
+        }
+        row
+    }
+}
+
+#[cfg(test)]
+mod dawg_tests {
+    use crate::sym_spell::dawg::Dawg;
+
+    #[test]
+    fn search_test() {
+        let dawg = Dawg::new(vec!["hello", "help", "hell", "yellow", "jello", "cello"]);
+        // the shared "ello" ending is stored once
+        assert!(dawg.len() < 18);
+
+        let search = |input: &str, max_distance: usize| {
+            let mut found = vec![];
+            dawg.search(input, max_distance, None, &mut |word, distance| found.push((word.to_string(), distance)));
+            found.sort();
+            found
+        };
+        assert_eq!(search("helo", 1), vec![("hell".to_string(), 1), ("hello".to_string(), 1), ("help".to_string(), 1)]);
+        assert_eq!(search("hlelo", 1), vec![("hello".to_string(), 1)]);
+        assert_eq!(search("xyz", 2), vec![]);
+        assert_eq!(search("yello", 2).len(), 5);
+
+        assert_eq!(dawg.search("hello", 2, Some(3), &mut |_, _| {}), None);
+        assert_eq!(Dawg::new(Vec::new()).search("hello", 2, None, &mut |_, _| {}), Some(0));
+    }
+}
@@ -0,0 +1,15 @@
+/// Controls how much of the deletes index is built when words are added,
+/// trading lookup speed for memory.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum DeleteGeneration {
+    /// Every delete of every word is precomputed. The fastest lookups, at
+    /// about ten times the memory of the words themselves.
+    #[default]
+    Precomputed,
+    /// Only the deletes of up to `precomputed_edits` removed graphemes are
+    /// precomputed, 0 storing just the prefix of each word. The words sharing
+    /// a delete of more edits are found by scanning the dictionary when a lookup
+    /// needs them, and the most recently used `cache_capacity` of those buckets
+    /// are kept for the following lookups.
+    OnDemand { precomputed_edits: usize, cache_capacity: usize },
+}
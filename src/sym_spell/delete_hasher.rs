@@ -0,0 +1,66 @@
+use std::hash::{BuildHasher, Hasher};
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// The 64 bit FNV-1a hash. Much faster than SipHash on the short strings
+/// SymSpell hashes, and fully specified, so a hash is the same on every
+/// run, platform and Rust release.
+#[derive(Clone, Copy, Debug)]
+pub struct Fnv1aHasher {
+    hash: u64,
+}
+
+impl Default for Fnv1aHasher {
+    fn default() -> Fnv1aHasher {
+        Fnv1aHasher { hash: FNV_OFFSET_BASIS }
+    }
+}
+
+impl Hasher for Fnv1aHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.hash ^= byte as u64;
+            self.hash = self.hash.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+}
+
+/// The default `BuildHasher` of the deletes map, see `Fnv1aHasher`.
+///
+/// example:
+/// let sym_spell: SymSpell<Fnv1aState> = SymSpell::with_hasher(None, None, None, Fnv1aState);
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Fnv1aState;
+
+impl BuildHasher for Fnv1aState {
+    type Hasher = Fnv1aHasher;
+
+    fn build_hasher(&self) -> Fnv1aHasher {
+        Fnv1aHasher::default()
+    }
+}
+
+#[cfg(test)]
+mod delete_hasher_tests {
+    use std::hash::{BuildHasher, Hasher};
+
+    use crate::sym_spell::delete_hasher::Fnv1aState;
+
+    #[test]
+    fn fnv1a_test() {
+        let hash = |bytes: &[u8]| {
+            let mut hasher = Fnv1aState.build_hasher();
+            hasher.write(bytes);
+            hasher.finish()
+        };
+        // the reference values of the FNV-1a specification
+        assert_eq!(hash(b""), 0xcbf29ce484222325);
+        assert_eq!(hash(b"a"), 0xaf63dc4c8601ec8c);
+        assert_eq!(hash(b"foobar"), 0x85944171f73967e8);
+    }
+}
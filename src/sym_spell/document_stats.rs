@@ -0,0 +1,26 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A summary of how many words in a document are misspelled.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DocumentStats {
+    /// Number of words in the document
+    pub total_tokens: usize,
+    /// Words that are not in the dictionary and have no suggestion
+    pub unknown_tokens: usize,
+    /// Words that are not in the dictionary but have a suggestion
+    pub corrected_tokens: usize,
+}
+
+impl DocumentStats {
+    /// Share of words that are not in the dictionary, whether or not
+    /// a correction was found.
+    pub fn error_rate(&self) -> f64 {
+        if self.total_tokens == 0 {
+            0.0
+        } else {
+            (self.unknown_tokens + self.corrected_tokens) as f64 / self.total_tokens as f64
+        }
+    }
+}
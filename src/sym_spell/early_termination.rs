@@ -0,0 +1,39 @@
+use std::fmt;
+
+/// Why a lookup returned before searching the deletes of the input, reported in
+/// `LogEvent::Lookup`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EarlyTermination {
+    /// The input is a dictionary word and the verbosity is not `Verbosity::All`.
+    ExactMatch,
+    /// The input is ignored for the session, see `SymSpell::ignore_word`.
+    Ignored,
+    /// The input is an equivalent spelling of a dictionary word, see `SymSpell::set_equivalences`.
+    Equivalent,
+    /// The input is longer than `LookupLimits::max_input_len`.
+    InputTooLong,
+    /// The lookup checked `LookupLimits::max_candidates` candidates, returning nothing.
+    CandidateLimit,
+    /// No dictionary word is within the max edit distance of the length of the input.
+    LengthOutOfRange,
+    /// The max edit distance is 0, so only an exact match could be found.
+    ZeroEditDistance,
+    /// The suggestions came from the lookup cache, see `SymSpell::set_lookup_cache_capacity`.
+    Cached,
+}
+
+impl fmt::Display for EarlyTermination {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self {
+            EarlyTermination::ExactMatch => "exact match",
+            EarlyTermination::Ignored => "ignored word",
+            EarlyTermination::Equivalent => "equivalent spelling",
+            EarlyTermination::InputTooLong => "input longer than the lookup limits",
+            EarlyTermination::CandidateLimit => "candidate limit reached",
+            EarlyTermination::LengthOutOfRange => "no dictionary word of a length within the edit distance",
+            EarlyTermination::ZeroEditDistance => "max edit distance 0",
+            EarlyTermination::Cached => "cached",
+        };
+        f.write_str(reason)
+    }
+}
@@ -0,0 +1,41 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::utils::push_json_string;
+
+/// Facts about a dictionary word that travel with the suggestions of it, set with
+/// `SymSpell::set_entry_metadata`, e.g. so a service can show where a correction
+/// comes from without mapping it back to its dictionaries.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct EntryMetadata {
+    /// The BCP 47 tag of the language of the word, e.g. "en-GB"
+    pub language: Option<String>,
+    /// Where the word comes from, e.g. the name of a domain or user dictionary
+    pub source: Option<String>,
+    /// The form to show or apply for the word, e.g. "iPhone" for "iphone"
+    pub canonical: Option<String>,
+}
+
+impl EntryMetadata {
+    /// True when no fact is set.
+    pub fn is_empty(&self) -> bool {
+        self.language.is_none() && self.source.is_none() && self.canonical.is_none()
+    }
+
+    /// Appends the metadata to the buffer as a JSON object of the facts that are set,
+    /// e.g. {"language":"en-US","source":"brands"}.
+    pub fn write_json(&self, buffer: &mut String) {
+        buffer.push('{');
+        let facts = [("language", &self.language), ("source", &self.source), ("canonical", &self.canonical)];
+        for (i, (name, value)) in facts.iter().filter_map(|(name, value)| Some((name, value.as_ref()?))).enumerate() {
+            if i > 0 {
+                buffer.push(',');
+            }
+            push_json_string(buffer, name);
+            buffer.push(':');
+            push_json_string(buffer, value);
+        }
+        buffer.push('}');
+    }
+}
@@ -0,0 +1,89 @@
+use std::borrow::Cow;
+
+/// Groups of interchangeable spellings, e.g. "ß" and "ss", that `SymSpell` treats
+/// as the same word at no edit cost. Each variant is folded to its canonical form
+/// before dictionary words and queries are compared, so "strasse" finds "straße"
+/// at distance 0.
+///
+/// example:
+/// let equivalences = Equivalences::new().with_variant("ij", "ĳ");
+///
+/// assert_eq!(equivalences.fold("ĳs"), "ijs");
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Equivalences {
+    // (variant, canonical) pairs, the longest variants first so they win over their prefixes
+    variants: Vec<(String, String)>,
+}
+
+impl Equivalences {
+    /// No interchangeable spellings.
+    pub fn new() -> Equivalences {
+        Equivalences::default()
+    }
+
+    /// The ligatures German and Scandinavian readers write out as two letters:
+    /// "ß" as "ss", "æ" as "ae" and "œ" as "oe".
+    pub fn common() -> Equivalences {
+        Equivalences::new()
+            .with_variant("ss", "ß")
+            .with_variant("ae", "æ")
+            .with_variant("oe", "œ")
+    }
+
+    /// Adds `variant` as an interchangeable spelling of `canonical`, the form
+    /// both are compared in. Neither may be empty.
+    pub fn with_variant(mut self, canonical: &str, variant: &str) -> Equivalences {
+        assert!(!canonical.is_empty() && !variant.is_empty());
+        let at = self.variants.iter().position(|(existing, _)| existing.len() < variant.len()).unwrap_or(self.variants.len());
+        self.variants.insert(at, (variant.to_string(), canonical.to_string()));
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.variants.is_empty()
+    }
+
+    /// The text with every variant replaced by its canonical form, borrowing it
+    /// when there is no variant in it.
+    pub fn fold<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        if !self.variants.iter().any(|(variant, _)| text.contains(variant.as_str())) {
+            return Cow::Borrowed(text);
+        }
+        let mut folded = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(ch) = rest.chars().next() {
+            match self.variants.iter().find(|(variant, _)| rest.starts_with(variant.as_str())) {
+                Some((variant, canonical)) => {
+                    folded.push_str(canonical);
+                    rest = &rest[variant.len()..];
+                }
+                None => {
+                    folded.push(ch);
+                    rest = &rest[ch.len_utf8()..];
+                }
+            }
+        }
+        Cow::Owned(folded)
+    }
+}
+
+#[cfg(test)]
+mod equivalences_tests {
+    use std::borrow::Cow;
+
+    use crate::sym_spell::equivalences::Equivalences;
+
+    #[test]
+    fn fold_test() {
+        let equivalences = Equivalences::common();
+        assert_eq!(equivalences.fold("straße"), "strasse");
+        assert_eq!(equivalences.fold("Æbleskiver"), "Æbleskiver");
+        assert_eq!(equivalences.fold("mæglerœ"), "maegleroe");
+        assert!(matches!(equivalences.fold("strasse"), Cow::Borrowed(_)));
+        assert!(matches!(Equivalences::new().fold("straße"), Cow::Borrowed(_)));
+
+        // longer variants are folded before their prefixes
+        let equivalences = Equivalences::new().with_variant("x", "a").with_variant("y", "ab");
+        assert_eq!(equivalences.fold("abac"), "yxc");
+    }
+}
@@ -0,0 +1,34 @@
+use std::error::Error;
+use std::fmt;
+
+/// Why `ResultFrame::decode` could not parse a payload.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FrameError {
+    /// The payload does not start with `FRAME_MAGIC`, e.g. it is in the binary or JSON output format.
+    NotAFrame,
+    /// The frame was written in a later, incompatible layout.
+    UnsupportedVersion(u8),
+    /// The payload ends within the frame.
+    Truncated,
+    /// A number does not fit the field it is read into.
+    Overflow,
+    /// A text field is not valid in the encoding of the frame.
+    InvalidText,
+    /// A suggestion has no term.
+    MissingTerm,
+}
+
+impl fmt::Display for FrameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrameError::NotAFrame => write!(f, "payload is not a result frame"),
+            FrameError::UnsupportedVersion(version) => write!(f, "result frame version {} is not supported", version),
+            FrameError::Truncated => write!(f, "result frame is truncated"),
+            FrameError::Overflow => write!(f, "result frame holds a number out of range"),
+            FrameError::InvalidText => write!(f, "result frame holds invalid text"),
+            FrameError::MissingTerm => write!(f, "result frame holds a suggestion without a term"),
+        }
+    }
+}
+
+impl Error for FrameError {}
@@ -0,0 +1,215 @@
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::str;
+
+use fst::{Automaton, IntoStreamer, Map, Streamer};
+use levenshtein_automata::{Distance, LevenshteinAutomatonBuilder, DFA, SINK_STATE};
+
+use crate::sym_spell::spellchecker_error::SpellcheckerError;
+use crate::sym_spell::suggested_item::SuggestItem;
+use crate::sym_spell::verbosity::Verbosity;
+use crate::sym_spell::Count;
+
+/// A read only dictionary kept in a finite state transducer, for deployments where
+/// the memory of the `SymSpell` deletes index is too much. Lookups intersect the
+/// transducer with a Levenshtein automaton of the input instead of looking up
+/// precalculated deletes, which is slower but needs no index at all.
+///
+/// Distances are the Damerau OSA distance counted in chars, the same as `SymSpell`
+/// with its default distance algorithm for words without combining marks.
+///
+/// example:
+/// let dictionary = FstDictionary::from_words(vec![("hello", 10), ("help", 5)], 2);
+/// let bytes = dictionary.as_bytes().to_vec();
+///
+/// let dictionary = FstDictionary::from_bytes(bytes, 2).unwrap();
+/// assert_eq!(dictionary.lookup("helo", Verbosity::Top, 1, false)?[0].term, "hello");
+pub struct FstDictionary {
+    map: Map<Vec<u8>>,
+    // the automaton builder for every max edit distance up to the dictionary's
+    automaton_builders: Vec<LevenshteinAutomatonBuilder>,
+}
+
+impl FstDictionary {
+    /// Builds the transducer from words and their counts. The counts of repeated
+    /// words are added up, saturating at `Count::MAX`. Lookups may ask for edit
+    /// distances up to max_dictionary_edit_distance.
+    pub fn from_words<S: AsRef<str>>(words: impl IntoIterator<Item=(S, Count)>, max_dictionary_edit_distance: usize) -> FstDictionary {
+        let mut sorted: BTreeMap<String, Count> = BTreeMap::new();
+        for (word, count) in words {
+            let entry = sorted.entry(word.as_ref().to_string()).or_insert(0);
+            *entry = entry.saturating_add(count);
+        }
+        // the keys of a BTreeMap are sorted and unique, all a map in memory needs
+        let map = Map::from_iter(sorted.into_iter().map(|(word, count)| (word, to_value(count))))
+            .expect("sorted and unique keys");
+        FstDictionary::with_map(map, max_dictionary_edit_distance)
+    }
+
+    /// Reads a dictionary precompiled with `as_bytes`, failing when the bytes are
+    /// not a transducer.
+    pub fn from_bytes(bytes: Vec<u8>, max_dictionary_edit_distance: usize) -> Result<FstDictionary, fst::Error> {
+        Ok(FstDictionary::with_map(Map::new(bytes)?, max_dictionary_edit_distance))
+    }
+
+    fn with_map(map: Map<Vec<u8>>, max_dictionary_edit_distance: usize) -> FstDictionary {
+        let automaton_builders = (0..=max_dictionary_edit_distance)
+            .map(|distance| LevenshteinAutomatonBuilder::new(distance as u8, true))
+            .collect();
+        FstDictionary { map, automaton_builders }
+    }
+
+    /// The transducer as bytes, to be stored and loaded again with `from_bytes`.
+    pub fn as_bytes(&self) -> &[u8] {
+        self.map.as_fst().as_bytes()
+    }
+
+    /// The number of words in the dictionary.
+    pub fn word_count(&self) -> usize {
+        self.map.len()
+    }
+
+    /// The count of the word, None when it is not in the dictionary.
+    pub fn count(&self, word: &str) -> Option<Count> {
+        self.map.get(word).map(to_count)
+    }
+
+    /// Finds the words within the max edit distance of the input, ordered and limited
+    /// by the verbosity the same way as `SymSpell::lookup`, and like it compared as
+    /// given, without lowercasing. With include_unknown the input is returned at
+    /// max_edit_distance + 1 when no word is found. Fails with
+    /// `SpellcheckerError::EditDistanceTooLarge` like `SymSpell::try_lookup`.
+    pub fn lookup(&self, input: &str, verbosity: Verbosity, max_edit_distance: usize, include_unknown: bool) -> Result<Vec<SuggestItem>, SpellcheckerError> {
+        let builder = self.automaton_builders.get(max_edit_distance).ok_or(SpellcheckerError::EditDistanceTooLarge {
+            max_edit_distance,
+            max_dictionary_edit_distance: self.automaton_builders.len() - 1,
+        })?;
+        let automaton = LevenshteinDfa(builder.build_dfa(input));
+        let mut suggestions: Vec<SuggestItem> = Vec::new();
+        let mut stream = self.map.search_with_state(&automaton).into_stream();
+        while let Some((key, value, state)) = stream.next() {
+            if let (Ok(term), Distance::Exact(distance)) = (str::from_utf8(key), automaton.0.distance(state)) {
+                suggestions.push(SuggestItem::new(term.to_string(), distance as usize, to_count(value)));
+            }
+        }
+
+        suggestions.sort_by(|a, b| a.sort_key().cmp(&b.sort_key()));
+        match verbosity {
+            Verbosity::Top => suggestions.truncate(1),
+            Verbosity::Closest => {
+                let closest = suggestions.first().map_or(0, |si| si.distance);
+                suggestions.retain(|si| si.distance == closest);
+            }
+            Verbosity::All => {}
+        }
+        if include_unknown && suggestions.is_empty() {
+            suggestions.push(SuggestItem::new(input.to_string(), max_edit_distance + 1, 0));
+        }
+        Ok(suggestions)
+    }
+}
+
+// runs the Levenshtein DFA over the bytes of the transducer's keys
+struct LevenshteinDfa(DFA);
+
+impl Automaton for LevenshteinDfa {
+    type State = u32;
+
+    fn start(&self) -> u32 {
+        self.0.initial_state()
+    }
+
+    fn is_match(&self, state: &u32) -> bool {
+        matches!(self.0.distance(*state), Distance::Exact(_))
+    }
+
+    fn can_match(&self, state: &u32) -> bool {
+        *state != SINK_STATE
+    }
+
+    fn accept(&self, state: &u32, byte: u8) -> u32 {
+        self.0.transition(*state, byte)
+    }
+}
+
+// the transducer stores u64 values, which hold any Count
+#[allow(clippy::useless_conversion)]
+fn to_value(count: Count) -> u64 {
+    u64::from(count)
+}
+
+#[allow(clippy::useless_conversion)]
+fn to_count(value: u64) -> Count {
+    Count::try_from(value).unwrap_or(Count::MAX)
+}
+
+#[cfg(test)]
+mod fst_dictionary_tests {
+    use crate::sym_spell::fst_dictionary::FstDictionary;
+    use crate::sym_spell::spellchecker_error::SpellcheckerError;
+    use crate::sym_spell::suggested_item::SuggestItem;
+    use crate::sym_spell::sym_spell::SymSpell;
+    use crate::sym_spell::verbosity::Verbosity;
+    use crate::sym_spell::Count;
+
+    fn terms(suggestions: Vec<SuggestItem>) -> Vec<(String, usize, Count)> {
+        suggestions.into_iter().map(|si| (si.term, si.distance, si.count)).collect()
+    }
+
+    fn fst_terms(suggestions: Result<Vec<SuggestItem>, SpellcheckerError>) -> Vec<(String, usize, Count)> {
+        terms(suggestions.unwrap())
+    }
+
+    #[test]
+    fn lookup_test() {
+        let dictionary = FstDictionary::from_words(vec![("hello", 10), ("help", 5), ("hell", 10), ("yellow", 3), ("hello", 2)], 2);
+        assert_eq!(dictionary.word_count(), 4);
+        assert_eq!(dictionary.count("hello"), Some(12));
+        assert_eq!(dictionary.count("helo"), None);
+
+        assert_eq!(fst_terms(dictionary.lookup("helo", Verbosity::Top, 2, false)), vec![("hello".to_string(), 1, 12)]);
+        // the input is compared as given, the same as SymSpell
+        assert_eq!(fst_terms(dictionary.lookup("Helo", Verbosity::Top, 2, false)), vec![("hello".to_string(), 2, 12)]);
+        assert_eq!(fst_terms(dictionary.lookup("XYZ", Verbosity::Top, 1, true)), vec![("XYZ".to_string(), 2, 0)]);
+        assert_eq!(dictionary.lookup("helo", Verbosity::Top, 3, false).err(), Some(SpellcheckerError::EditDistanceTooLarge { max_edit_distance: 3, max_dictionary_edit_distance: 2 }));
+        assert_eq!(fst_terms(dictionary.lookup("helo", Verbosity::Closest, 2, false)), vec![
+            ("hello".to_string(), 1, 12),
+            ("hell".to_string(), 1, 10),
+            ("help".to_string(), 1, 5),
+        ]);
+        assert_eq!(dictionary.lookup("helo", Verbosity::All, 2, false).unwrap().len(), 3);
+        assert_eq!(fst_terms(dictionary.lookup("hello", Verbosity::Closest, 2, false)), vec![("hello".to_string(), 0, 12)]);
+
+        assert!(dictionary.lookup("xyz", Verbosity::Top, 1, false).unwrap().is_empty());
+        assert_eq!(fst_terms(dictionary.lookup("xyz", Verbosity::Top, 1, true)), vec![("xyz".to_string(), 2, 0)]);
+    }
+
+    #[test]
+    fn bytes_test() {
+        let dictionary = FstDictionary::from_words(vec![("café", 4), ("cafe", 2), ("caef", 1)], 1);
+        let dictionary = FstDictionary::from_bytes(dictionary.as_bytes().to_vec(), 1).unwrap();
+        assert_eq!(fst_terms(dictionary.lookup("cafè", Verbosity::All, 1, false)), vec![
+            ("café".to_string(), 1, 4),
+            ("cafe".to_string(), 1, 2),
+        ]);
+        // a transposition is a single edit
+        assert_eq!(fst_terms(dictionary.lookup("cafe", Verbosity::All, 1, false)), vec![
+            ("cafe".to_string(), 0, 2),
+            ("café".to_string(), 1, 4),
+            ("caef".to_string(), 1, 1),
+        ]);
+        assert!(FstDictionary::from_bytes(b"not a transducer".to_vec(), 1).is_err());
+    }
+
+    #[test]
+    fn from_sym_spell_test() {
+        let sym_spell: SymSpell = vec![("hello", 10), ("help", 5), ("hell", 8), ("understanding", 7)].into_iter().collect();
+        let dictionary = sym_spell.to_fst_dictionary();
+        assert_eq!(dictionary.word_count(), 4);
+        for input in ["helo", "Helo", "hel", "undrstandng", "xyz"].iter() {
+            for &verbosity in [Verbosity::Top, Verbosity::Closest, Verbosity::All].iter() {
+                assert_eq!(fst_terms(dictionary.lookup(input, verbosity, 2, true)), terms(sym_spell.lookup(input, verbosity, 2, true, false)), "{}", input);
+            }
+        }
+    }
+}
@@ -0,0 +1,236 @@
+use std::hash::BuildHasher;
+use std::mem;
+use std::ops::Range;
+
+use crate::markup::Markup;
+use crate::sym_spell::check_delta::CheckDelta;
+use crate::sym_spell::misspelled_range::MisspelledRange;
+use crate::sym_spell::spellchecker_error::SpellcheckerError;
+use crate::sym_spell::sym_spell::SymSpell;
+
+/// Keeps the ranges `SymSpell::check_text` reports for a document up to date as the
+/// document is edited, e.g. on every keystroke in an editor. An edit only re-checks the
+/// whitespace separated chunks it touches and the chunk after them, whose context it may
+/// have changed, with the chunk before them as the context of a repeated word or a
+/// sentence start. Each call returns what changed, so an editor only updates the
+/// underlines that came or went. Ranges after an edit that are neither added nor
+/// removed move by the change in length of the text.
+///
+/// With `Markup::Markdown` or `Markup::Html` an edit may open or close a code block far
+/// from where it happens, so the whole text is re-checked, still returning only the change.
+///
+/// example:
+/// let mut checker = IncrementalChecker::new(2);
+/// let delta = checker.check(&sym_spell, "the quikc fox");
+/// assert_eq!(delta.added[0].byte_range, 4..9);
+///
+/// // "quikc" replaced by "quick"
+/// let delta = checker.edit(&sym_spell, "the quick fox", 4..9, 5)?;
+/// assert_eq!(delta.removed[0].byte_range, 4..9);
+/// assert!(delta.added.is_empty() && checker.diagnostics().is_empty());
+#[derive(Clone, Debug)]
+pub struct IncrementalChecker {
+    max_edit_distance: usize,
+    // the ranges reported for the text of the last call, in order
+    diagnostics: Vec<MisspelledRange>,
+}
+
+impl IncrementalChecker {
+    pub fn new(max_edit_distance: usize) -> IncrementalChecker {
+        IncrementalChecker { max_edit_distance, diagnostics: vec![] }
+    }
+
+    /// The ranges reported for the text of the last call, in order.
+    pub fn diagnostics(&self) -> &[MisspelledRange] {
+        &self.diagnostics
+    }
+
+    /// Checks the whole text, e.g. when the document is opened or the dictionary has
+    /// changed, returning the change from the ranges reported before.
+    pub fn check<S: BuildHasher>(&mut self, sym_spell: &SymSpell<S>, text: &str) -> CheckDelta {
+        let stale = mem::take(&mut self.diagnostics).into_iter().map(|range| (range.byte_range.clone(), range)).collect();
+        self.recheck(sym_spell, text, 0, 0..text.len(), stale, CheckDelta::default())
+    }
+
+    /// Applies an edit that replaced the replaced byte range of the previous text with
+    /// replacement_len bytes, giving the text passed in. Fails with
+    /// `SpellcheckerError::InvalidEdit`, leaving the ranges as they were, when the
+    /// replacement does not end within the text or the edit does not fall on char boundaries.
+    pub fn edit<S: BuildHasher>(&mut self, sym_spell: &SymSpell<S>, text: &str, replaced: Range<usize>, replacement_len: usize) -> Result<CheckDelta, SpellcheckerError> {
+        let replacement_end = replaced.start.saturating_add(replacement_len);
+        if replaced.start > replaced.end || replacement_end > text.len()
+            || !text.is_char_boundary(replaced.start) || !text.is_char_boundary(replacement_end) {
+            return Err(SpellcheckerError::InvalidEdit { replaced_start: replaced.start, replaced_end: replaced.end, replacement_len });
+        }
+
+        let (core_start, region) = if sym_spell.markup() == Markup::PlainText {
+            let core_start = chunk_start(text, replaced.start);
+            (core_start, context_start(text, core_start)..next_chunk_end(text, chunk_end(text, replacement_end)))
+        } else {
+            (0, 0..text.len())
+        };
+
+        let mut delta = CheckDelta::default();
+        let mut stale = vec![];
+        for range in mem::take(&mut self.diagnostics) {
+            // the range where it is in the text now
+            let moved = if range.byte_range.end <= replaced.start {
+                range.byte_range.clone()
+            } else if range.byte_range.start >= replaced.end {
+                range.byte_range.start - replaced.end + replacement_end..range.byte_range.end - replaced.end + replacement_end
+            } else {
+                delta.removed.push(range);
+                continue;
+            };
+            if moved.start >= core_start && moved.start < region.end {
+                stale.push((moved, range));
+            } else {
+                self.diagnostics.push(MisspelledRange { byte_range: moved, ..range });
+            }
+        }
+        Ok(self.recheck(sym_spell, text, core_start, region, stale, delta))
+    }
+
+    // checks text[region] in place of the stale ranges, given with where they are now, keeping
+    // the ranges found before core_start only as context
+    fn recheck<S: BuildHasher>(
+        &mut self,
+        sym_spell: &SymSpell<S>,
+        text: &str,
+        core_start: usize,
+        region: Range<usize>,
+        mut stale: Vec<(Range<usize>, MisspelledRange)>,
+        mut delta: CheckDelta,
+    ) -> CheckDelta {
+        for range in sym_spell.check_text(&text[region.clone()], self.max_edit_distance) {
+            let range = MisspelledRange {
+                byte_range: range.byte_range.start + region.start..range.byte_range.end + region.start,
+                ..range
+            };
+            if range.byte_range.start < core_start {
+                continue;
+            }
+            match stale.iter().position(|(moved, previous)| *moved == range.byte_range && same_issue(previous, &range)) {
+                Some(i) => {
+                    stale.swap_remove(i);
+                }
+                None => delta.added.push(range.clone()),
+            }
+            self.diagnostics.push(range);
+        }
+        delta.removed.extend(stale.into_iter().map(|(_, previous)| previous));
+        delta.removed.sort_by_key(|range| range.byte_range.start);
+        self.diagnostics.sort_by_key(|range| range.byte_range.start);
+        delta
+    }
+}
+
+fn same_issue(a: &MisspelledRange, b: &MisspelledRange) -> bool {
+    let suggestion = |range: &MisspelledRange| range.suggestion.as_ref().map(|si| (si.term.clone(), si.distance, si.count));
+    a.kind == b.kind && suggestion(a) == suggestion(b)
+}
+
+// the start of the whitespace separated chunk holding the byte at, or ending right before it
+fn chunk_start(text: &str, at: usize) -> usize {
+    text[..at].char_indices().rev().find(|(_, ch)| ch.is_whitespace()).map_or(0, |(i, ch)| i + ch.len_utf8())
+}
+
+// the end of the whitespace separated chunk holding the byte at, or starting right at it
+fn chunk_end(text: &str, at: usize) -> usize {
+    text[at..].find(char::is_whitespace).map_or(text.len(), |i| at + i)
+}
+
+// the start of the closest chunk before the one starting at chunk_start that holds a word
+fn context_start(text: &str, chunk_start_at: usize) -> usize {
+    let mut start = chunk_start_at;
+    while start > 0 {
+        start = chunk_start(text, text[..start].trim_end().len());
+        if text[start..chunk_end(text, start)].chars().any(char::is_alphanumeric) {
+            break;
+        }
+    }
+    start
+}
+
+// the end of the chunk after the one ending at chunk_end_at
+fn next_chunk_end(text: &str, chunk_end_at: usize) -> usize {
+    chunk_end(text, text.len() - text[chunk_end_at..].trim_start().len())
+}
+
+#[cfg(test)]
+mod incremental_checker_tests {
+    use std::ops::Range;
+
+    use crate::markup::Markup;
+    use crate::sym_spell::incremental_checker::IncrementalChecker;
+    use crate::sym_spell::misspelled_range::MisspelledRange;
+    use crate::sym_spell::spellchecker_error::SpellcheckerError;
+    use crate::sym_spell::sym_spell::SymSpell;
+
+    fn summary(text: &str, misspelled: &[MisspelledRange]) -> Vec<(String, String)> {
+        misspelled.iter()
+            .map(|m| (text[m.byte_range.clone()].to_string(), m.suggestion.as_ref().map_or(String::new(), |si| si.term.clone())))
+            .collect()
+    }
+
+    #[test]
+    fn edit_test() {
+        let sym_spell: SymSpell = vec![("the", 1000), ("quick", 50), ("brown", 40), ("fox", 20), ("jumps", 10), ("a", 500)].into_iter().collect();
+        let mut checker = IncrementalChecker::new(2);
+        let text = "the quikc brown fox";
+        let delta = checker.check(&sym_spell, text);
+        assert_eq!(summary(text, &delta.added), vec![("quikc".to_string(), "quick".to_string())]);
+        assert!(checker.check(&sym_spell, text).is_empty());
+
+        // typing a word before a misspelling moves it without reporting it again
+        let text = "the a quikc brown fox";
+        let delta = checker.edit(&sym_spell, text, 4..4, 2).unwrap();
+        assert!(delta.is_empty());
+        assert_eq!(checker.diagnostics()[0].byte_range, 6..11);
+
+        // fixing it only removes it
+        let text = "the a quick brown fox";
+        let delta = checker.edit(&sym_spell, text, 9..11, 2).unwrap();
+        assert!(delta.added.is_empty());
+        assert_eq!(delta.removed[0].byte_range, 6..11);
+
+        // a sentence end changes the correction of the word after it
+        let text = "the a quick brown fox teh";
+        checker.edit(&sym_spell, text, 21..21, 4).unwrap();
+        assert_eq!(summary(text, checker.diagnostics()), vec![("teh".to_string(), "the".to_string())]);
+        let text = "the a quick brown fox. teh";
+        let delta = checker.edit(&sym_spell, text, 21..21, 1).unwrap();
+        assert_eq!((delta.added.len(), delta.removed.len()), (1, 1));
+        assert_eq!(summary(text, checker.diagnostics()), vec![("teh".to_string(), "The".to_string())]);
+
+        // an edit that does not fit the text is rejected without losing the ranges
+        let diagnostics = checker.diagnostics().to_vec();
+        let reversed = Range { start: 20, end: 10 };
+        assert_eq!(checker.edit(&sym_spell, text, reversed, 1).err(), Some(SpellcheckerError::InvalidEdit { replaced_start: 20, replaced_end: 10, replacement_len: 1 }));
+        assert!(checker.edit(&sym_spell, text, 24..24, 9).is_err());
+        assert!(checker.edit(&sym_spell, "té", 2..2, 0).is_err());
+        assert_eq!(format!("{:?}", checker.diagnostics()), format!("{:?}", diagnostics));
+    }
+
+    #[test]
+    fn matches_check_text_test() {
+        let mut sym_spell: SymSpell = vec![("the", 1000), ("quick", 50), ("brown", 40), ("fox", 20), ("jumps", 10), ("over", 30), ("lazy", 5), ("dog", 25)]
+            .into_iter()
+            .collect();
+        // every edit leaves the same ranges as checking the whole text
+        let edits = [(0, 0, "teh "), (4, 9, "quikc"), (9, 9, " quikc"), (0, 4, "The"), (10, 10, "!"), (3, 3, " the the"), (11, 15, ""),
+            (5, 6, "é"), (0, 0, "https://exmaple.com "), (20, 21, "\n"), (14, 14, "...  ")];
+        for &markup in [Markup::PlainText, Markup::Markdown].iter() {
+            sym_spell.set_markup(markup);
+            let mut text = String::from("the quick brown fox jumps over the lazy dog");
+            let mut checker = IncrementalChecker::new(2);
+            checker.check(&sym_spell, &text);
+            for (start, end, replacement) in edits.iter() {
+                let (start, end) = ((*start).min(text.len()), (*end).min(text.len()));
+                text.replace_range(start..end, replacement);
+                checker.edit(&sym_spell, &text, start..end, replacement.len()).unwrap();
+                assert_eq!(summary(&text, checker.diagnostics()), summary(&text, &sym_spell.check_text(&text, 2)), "{}", text);
+            }
+        }
+    }
+}
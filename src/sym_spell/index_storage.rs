@@ -0,0 +1,409 @@
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::collections::hash_map::Entry;
+use std::convert::TryFrom;
+use std::hash::{BuildHasher, Hasher};
+use std::mem::size_of;
+
+use crate::small_vec::SmallVec;
+use crate::sym_spell::delete_hasher::Fnv1aState;
+use crate::sym_spell::Count;
+
+/// The id a storage assigns to a dictionary word when it is first added.
+pub type WordId = u32;
+
+/// A dictionary word in a bucket of the deletes map along with its length in
+/// graphemes, so lookups can discard candidates by length without resolving them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct IndexedWord {
+    pub id: WordId,
+    pub len: u32,
+}
+
+// most buckets hold one or two words, which are kept inline
+const BUCKET_INLINE_CAPACITY: usize = 2;
+
+type Bucket = SmallVec<IndexedWord, BUCKET_INLINE_CAPACITY>;
+
+/// The storage behind a SymSpell index: the deletes map, from the hash of a
+/// delete to the ids of the dictionary words it was derived from, and the words,
+/// each stored once along with its frequency count.
+///
+/// Implement it to keep the index in an alternative backend such as a
+/// memory mapped file, chunks fetched from IndexedDB through host callbacks
+/// or an embedded key value store, so indexes larger than comfortably fit
+/// in wasm linear memory can be used. Backends that do not hold a bucket or
+/// word in memory may return it owned. Storage must be `Send + Sync` so a
+/// loaded SymSpell can be shared between threads for concurrent lookups.
+pub trait IndexStorage: Send + Sync {
+    /// The words whose deletes hash to `hash`, in the order they were pushed.
+    fn deletes(&self, hash: u64) -> Option<Cow<'_, [IndexedWord]>>;
+
+    /// Appends the word with the id, of `len` graphemes, to the bucket of deletes hashing to `hash`.
+    fn push_delete(&mut self, hash: u64, id: WordId, len: u32);
+
+    /// Number of distinct delete hashes stored.
+    fn delete_count(&self) -> usize;
+
+    /// Calls `f` with every delete hash and its bucket, in an order defined by the backend.
+    fn for_each_delete(&self, f: &mut dyn FnMut(u64, &[IndexedWord]));
+
+    /// The dictionary word with the id.
+    fn word(&self, id: WordId) -> Option<Cow<'_, str>>;
+
+    /// The frequency count of a dictionary word.
+    fn word_count(&self, word: &str) -> Option<Count>;
+
+    /// The dictionary word as stored, along with its frequency count. Backends holding
+    /// their words in memory should return it borrowed, which the default does not.
+    fn word_entry(&self, word: &str) -> Option<(Cow<'_, str>, Count)> {
+        self.word_count(word).map(|count| (Cow::Owned(word.to_string()), count))
+    }
+
+    /// Adds the word to the dictionary or replaces its count, returning its id.
+    fn set_word_count(&mut self, word: String, count: Count) -> WordId;
+
+    /// Number of dictionary words stored.
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Calls `f` with every dictionary word and its count, in no particular order.
+    fn for_each_word(&self, f: &mut dyn FnMut(&str, Count));
+
+    /// Applies `f` to the count of every dictionary word.
+    fn update_word_counts(&mut self, f: &mut dyn FnMut(&mut Count));
+
+    /// Removes every delete and word.
+    fn clear(&mut self);
+
+    /// The bytes the storage has allocated, None when the backend cannot tell, e.g.
+    /// because the index is kept outside of memory.
+    fn allocated_size(&self) -> Option<usize> {
+        None
+    }
+}
+
+// every dictionary word stored once, its id being its position
+#[derive(Clone, Debug, Default, PartialEq)]
+struct WordArena {
+    words: Vec<Box<str>>,
+    counts: Vec<Count>,
+    // the hash of each word to its id, a word whose hash is taken goes to collisions
+    ids: HashMap<u64, WordId>,
+    collisions: Vec<WordId>,
+}
+
+impl WordArena {
+    fn hash(word: &str) -> u64 {
+        let mut h = Fnv1aState.build_hasher();
+        h.write(word.as_bytes());
+        h.finish()
+    }
+
+    fn id(&self, word: &str) -> Option<WordId> {
+        let is_word = |id: &WordId| &*self.words[*id as usize] == word;
+        self.ids.get(&WordArena::hash(word)).copied().filter(is_word)
+            .or_else(|| self.collisions.iter().copied().find(is_word))
+    }
+
+    fn word(&self, id: WordId) -> Option<&str> {
+        self.words.get(id as usize).map(|word| &**word)
+    }
+
+    fn count(&self, word: &str) -> Option<Count> {
+        self.id(word).map(|id| self.counts[id as usize])
+    }
+
+    fn entry(&self, word: &str) -> Option<(&str, Count)> {
+        self.id(word).map(|id| (&*self.words[id as usize], self.counts[id as usize]))
+    }
+
+    fn set_count(&mut self, word: String, count: Count) -> WordId {
+        if let Some(id) = self.id(&word) {
+            self.counts[id as usize] = count;
+            return id;
+        }
+        let id = WordId::try_from(self.words.len()).expect("too many dictionary words");
+        match self.ids.entry(WordArena::hash(&word)) {
+            Entry::Vacant(entry) => {
+                entry.insert(id);
+            }
+            Entry::Occupied(_) => self.collisions.push(id),
+        }
+        self.words.push(word.into_boxed_str());
+        self.counts.push(count);
+        id
+    }
+
+    fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    fn iter(&self) -> impl Iterator<Item=(&str, Count)> {
+        self.words.iter().map(|word| &**word).zip(self.counts.iter().copied())
+    }
+
+    fn allocated_size(&self) -> usize {
+        self.words.capacity() * size_of::<Box<str>>()
+            + self.words.iter().map(|word| word.len()).sum::<usize>()
+            + self.counts.capacity() * size_of::<Count>()
+            + allocated_table_size::<u64, WordId>(self.ids.capacity())
+            + self.collisions.capacity() * size_of::<WordId>()
+    }
+}
+
+/// The default in memory storage.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HashMapStorage {
+    // Collisions of hashCodes is tolerated, because suggestions are ultimately
+    // verified via an edit distance function.
+    deletes: HashMap<u64, Bucket>,
+    words: WordArena,
+}
+
+impl HashMapStorage {
+    /// The approximate bytes taken by `words` dictionary words of `word_len` bytes
+    /// on average and `deletes` distinct deletes referring to `entries` words in all.
+    pub fn estimated_size(words: usize, word_len: usize, deletes: usize, entries: usize) -> usize {
+        let word_size = size_of::<Box<str>>() + word_len + size_of::<Count>();
+        words.saturating_mul(word_size)
+            .saturating_add(hash_table_size::<u64, WordId>(words))
+            .saturating_add(hash_table_size::<u64, Bucket>(deletes))
+            // the few words sharing a delete mostly fit inline, the buckets of the many sharing one grow by doubling
+            .saturating_add(entries.saturating_sub(deletes).saturating_mul(size_of::<IndexedWord>() * 3 / 2))
+    }
+}
+
+// the bytes of a HashMap holding len entries, whose table of a power of two slots is at most 7/8 full
+fn hash_table_size<K, V>(len: usize) -> usize {
+    if len == 0 {
+        return 0;
+    }
+    let slots = len.saturating_mul(8) / 7;
+    slots.checked_next_power_of_two().unwrap_or(usize::MAX).saturating_mul(size_of::<(K, V)>() + 1)
+}
+
+// the bytes allocated by a HashMap of the capacity, its slots and a control byte for
+// each slot and each of the 16 slots probed at once
+fn allocated_table_size<K, V>(capacity: usize) -> usize {
+    if capacity == 0 {
+        return 0;
+    }
+    let slots = if capacity < 7 { capacity + 1 } else { capacity / 7 * 8 };
+    slots * (size_of::<(K, V)>() + 1) + 16
+}
+
+impl IndexStorage for HashMapStorage {
+    fn deletes(&self, hash: u64) -> Option<Cow<'_, [IndexedWord]>> {
+        self.deletes.get(&hash).map(|words| Cow::Borrowed(words.as_slice()))
+    }
+
+    fn push_delete(&mut self, hash: u64, id: WordId, len: u32) {
+        self.deletes.entry(hash).or_default().push(IndexedWord { id, len });
+    }
+
+    fn delete_count(&self) -> usize {
+        self.deletes.len()
+    }
+
+    fn for_each_delete(&self, f: &mut dyn FnMut(u64, &[IndexedWord])) {
+        for (&hash, words) in &self.deletes {
+            f(hash, words.as_slice());
+        }
+    }
+
+    fn word(&self, id: WordId) -> Option<Cow<'_, str>> {
+        self.words.word(id).map(Cow::Borrowed)
+    }
+
+    fn word_count(&self, word: &str) -> Option<Count> {
+        self.words.count(word)
+    }
+
+    fn word_entry(&self, word: &str) -> Option<(Cow<'_, str>, Count)> {
+        self.words.entry(word).map(|(word, count)| (Cow::Borrowed(word), count))
+    }
+
+    fn set_word_count(&mut self, word: String, count: Count) -> WordId {
+        self.words.set_count(word, count)
+    }
+
+    fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    fn for_each_word(&self, f: &mut dyn FnMut(&str, Count)) {
+        for (word, count) in self.words.iter() {
+            f(word, count);
+        }
+    }
+
+    fn update_word_counts(&mut self, f: &mut dyn FnMut(&mut Count)) {
+        self.words.counts.iter_mut().for_each(f);
+    }
+
+    fn clear(&mut self) {
+        self.deletes = HashMap::new();
+        self.words = WordArena::default();
+    }
+
+    fn allocated_size(&self) -> Option<usize> {
+        let buckets: usize = self.deletes.values().map(|words| words.heap_capacity() * size_of::<IndexedWord>()).sum();
+        Some(allocated_table_size::<u64, Bucket>(self.deletes.capacity()) + buckets + self.words.allocated_size())
+    }
+}
+
+/// An in memory storage that keeps deletes ordered by hash and iterates words
+/// alphabetically, so everything that iterates the index does so in the
+/// same order on every run and platform. Slower than `HashMapStorage`;
+/// meant for golden tests of lookup internals and for tracking down
+/// ranking differences between platforms.
+///
+/// example:
+/// let mut sym_spell = SymSpell::new(None, None, None);
+/// sym_spell.set_storage(Box::new(BTreeMapStorage::default()), 0);
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BTreeMapStorage {
+    deletes: BTreeMap<u64, Bucket>,
+    words: WordArena,
+}
+
+impl IndexStorage for BTreeMapStorage {
+    fn deletes(&self, hash: u64) -> Option<Cow<'_, [IndexedWord]>> {
+        self.deletes.get(&hash).map(|words| Cow::Borrowed(words.as_slice()))
+    }
+
+    fn push_delete(&mut self, hash: u64, id: WordId, len: u32) {
+        self.deletes.entry(hash).or_default().push(IndexedWord { id, len });
+    }
+
+    fn delete_count(&self) -> usize {
+        self.deletes.len()
+    }
+
+    fn for_each_delete(&self, f: &mut dyn FnMut(u64, &[IndexedWord])) {
+        for (&hash, words) in &self.deletes {
+            f(hash, words.as_slice());
+        }
+    }
+
+    fn word(&self, id: WordId) -> Option<Cow<'_, str>> {
+        self.words.word(id).map(Cow::Borrowed)
+    }
+
+    fn word_count(&self, word: &str) -> Option<Count> {
+        self.words.count(word)
+    }
+
+    fn word_entry(&self, word: &str) -> Option<(Cow<'_, str>, Count)> {
+        self.words.entry(word).map(|(word, count)| (Cow::Borrowed(word), count))
+    }
+
+    fn set_word_count(&mut self, word: String, count: Count) -> WordId {
+        self.words.set_count(word, count)
+    }
+
+    fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    fn for_each_word(&self, f: &mut dyn FnMut(&str, Count)) {
+        let mut words: Vec<(&str, Count)> = self.words.iter().collect();
+        words.sort_unstable();
+        for (word, count) in words {
+            f(word, count);
+        }
+    }
+
+    fn update_word_counts(&mut self, f: &mut dyn FnMut(&mut Count)) {
+        self.words.counts.iter_mut().for_each(f);
+    }
+
+    fn clear(&mut self) {
+        self.deletes = BTreeMap::new();
+        self.words = WordArena::default();
+    }
+}
+
+#[cfg(test)]
+mod index_storage_tests {
+    use std::borrow::Cow;
+
+    use crate::sym_spell::index_storage::{BTreeMapStorage, HashMapStorage, IndexStorage, WordArena};
+    use crate::sym_spell::sym_spell::SymSpell;
+
+    fn load(storage: Box<dyn IndexStorage>) -> SymSpell {
+        let mut sym_spell = SymSpell::new(Some(1), None, None);
+        sym_spell.set_storage(storage, 0);
+        for (word, count) in [("world", 7), ("hello", 10), ("help", 5)].iter() {
+            sym_spell.create_dictionary_entry(word.to_string(), *count);
+        }
+        sym_spell
+    }
+
+    #[test]
+    fn btree_map_storage_test() {
+        let ordered = load(Box::new(BTreeMapStorage::default()));
+        let unordered = load(Box::new(HashMapStorage::default()));
+
+        let mut words = vec![];
+        ordered.storage().for_each_word(&mut |word, _| words.push(word.to_string()));
+        assert_eq!(words, vec!["hello", "help", "world"]);
+
+        let mut hashes = vec![];
+        ordered.storage().for_each_delete(&mut |hash, _| hashes.push(hash));
+        assert!(hashes.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(hashes.len(), unordered.entry_count());
+        assert_eq!(ordered.allocated_size(), None);
+        assert!(unordered.allocated_size().unwrap() > 0);
+
+        // the same index either way
+        let mut buckets = vec![];
+        unordered.storage().for_each_delete(&mut |hash, words| buckets.push((hash, words.to_vec())));
+        buckets.sort();
+        let mut ordered_buckets = vec![];
+        ordered.storage().for_each_delete(&mut |hash, words| ordered_buckets.push((hash, words.to_vec())));
+        assert_eq!(buckets, ordered_buckets);
+    }
+
+    #[test]
+    fn indexed_word_len_test() {
+        let mut sym_spell = SymSpell::new(Some(1), None, None);
+        sym_spell.create_dictionary_entry("wörld".to_string(), 7);
+        sym_spell.create_dictionary_entry("🚀".to_string(), 3);
+        let mut lens = vec![];
+        let storage = sym_spell.storage();
+        storage.for_each_delete(&mut |_, words| lens.extend(words.iter().map(|entry| (storage.word(entry.id).unwrap().into_owned(), entry.len))));
+        lens.sort();
+        lens.dedup();
+        assert_eq!(lens, vec![("wörld".to_string(), 5), ("🚀".to_string(), 1)]);
+    }
+
+    #[test]
+    fn word_id_test() {
+        let mut storage = HashMapStorage::default();
+        let hello = storage.set_word_count("hello".to_string(), 10);
+        let world = storage.set_word_count("world".to_string(), 7);
+        assert_ne!(hello, world);
+        // a word keeps its id when its count changes
+        assert_eq!(storage.set_word_count("hello".to_string(), 12), hello);
+        assert_eq!(storage.word(hello).as_deref(), Some("hello"));
+        assert_eq!(storage.word_count("hello"), Some(12));
+        assert_eq!(storage.word_count("help"), None);
+        assert!(matches!(storage.word_entry("hello"), Some((Cow::Borrowed("hello"), 12))));
+        assert!(storage.word_entry("help").is_none());
+        assert_eq!(storage.word(world + 1), None);
+        assert_eq!(storage.len(), 2);
+
+        // words sharing a hash are told apart by comparing them
+        let mut words = WordArena::default();
+        let id = words.set_count("hello".to_string(), 1);
+        words.ids.insert(WordArena::hash("help"), id);
+        let help = words.set_count("help".to_string(), 2);
+        assert_eq!(words.collisions, vec![help]);
+        assert_eq!((words.count("hello"), words.count("help"), words.count("world")), (Some(1), Some(2), None));
+    }
+}
@@ -0,0 +1,11 @@
+/// Controls what happens to dictionary lines whose count is zero, negative
+/// or not a number, all of which would otherwise distort the probabilities
+/// derived from the counts.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum InvalidCountPolicy {
+    /// Skip the line.
+    #[default]
+    Drop,
+    /// Keep the entry with a count of 1.
+    Clamp,
+}
@@ -0,0 +1,12 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// What is wrong with a range reported by `SymSpell::check_text`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum IssueKind {
+    /// The word is not in the dictionary.
+    Misspelling,
+    /// The word repeats the word before it, e.g. the second "the" of "the the".
+    RepeatedWord,
+}
@@ -0,0 +1,13 @@
+/// How far `SymSpell::load_words_with_progress` got, reported after every chunk
+/// of words.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LoadProgress {
+    /// Entries read from the input, including repeated words and those below the count threshold
+    pub entries_read: usize,
+    /// Dictionary words after the chunk
+    pub words: usize,
+    /// Distinct deletes stored after the chunk, see `SymSpell::entry_count`
+    pub deletes: usize,
+    /// True when the progress callback stopped the load before the input ran out
+    pub stopped: bool,
+}
@@ -0,0 +1,13 @@
+/// Notable events encountered while loading dictionary entries.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LoadReport {
+    /// Number of count updates that would have overflowed
+    pub count_overflows: usize,
+    /// Number of times every count was halved to resolve an overflow
+    pub rescales: usize,
+    /// Number of lines whose count was zero, negative or not a number
+    pub invalid_counts: usize,
+    /// Number of lines rejected for missing a part, e.g. a dictionary line without a
+    /// count, a bigram line without two words or a line that is not valid utf-8
+    pub malformed_lines: usize,
+}
@@ -0,0 +1,74 @@
+use std::fmt;
+
+use crate::sym_spell::early_termination::EarlyTermination;
+
+/// What a `SymSpell` operation reports to its `Logger` when it completes, and, with
+/// the `tracing` feature, as a debug event within the span of the operation.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LogEvent<'a> {
+    /// Words were loaded in bulk, e.g. by `SymSpell::from_words` or the `flush_dictionary` export.
+    DictionaryLoad {
+        /// The number of dictionary words after the load
+        words: usize,
+    },
+    /// A single word was looked up.
+    Lookup {
+        input: &'a str,
+        /// The number of deletes of the input checked for suggestions
+        candidates: usize,
+        /// The number of suggestions returned
+        suggestions: usize,
+        /// Why the lookup returned before checking any candidates, if it did
+        termination: Option<EarlyTermination>,
+    },
+    /// A text was corrected by `lookup_compound` or its variants.
+    LookupCompound {
+        input: &'a str,
+        /// The number of parts of the input, see `CompoundCorrection::parts`
+        parts: usize,
+        /// The edit distance between the input and the correction
+        distance: usize,
+    },
+    /// A text was segmented by `word_segmentation`.
+    WordSegmentation {
+        input: &'a str,
+        /// The number of words of the segmentation
+        words: usize,
+        /// The sum of the edit distances of the corrected words
+        distance: usize,
+    },
+}
+
+impl<'a> LogEvent<'a> {
+    // the event as a tracing event with a field per value
+    #[cfg(feature = "tracing")]
+    pub(crate) fn trace(&self) {
+        match self {
+            LogEvent::DictionaryLoad { words } => tracing::debug!(words, "dictionary loaded"),
+            LogEvent::Lookup { input, candidates, suggestions, termination } => match termination {
+                Some(termination) => tracing::debug!(input, candidates, suggestions, termination = %termination, "lookup"),
+                None => tracing::debug!(input, candidates, suggestions, "lookup"),
+            },
+            LogEvent::LookupCompound { input, parts, distance } => tracing::debug!(input, parts, distance, "lookup_compound"),
+            LogEvent::WordSegmentation { input, words, distance } => tracing::debug!(input, words, distance, "word_segmentation"),
+        }
+    }
+}
+
+/// One line, as the wasm `log_handler` receives it.
+impl<'a> fmt::Display for LogEvent<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LogEvent::DictionaryLoad { words } => write!(f, "dictionary_load: {} words", words),
+            LogEvent::Lookup { input, candidates, suggestions, termination } => {
+                write!(f, "lookup {:?}: {} suggestions from {} candidates", input, suggestions, candidates)?;
+                match termination {
+                    Some(termination) => write!(f, ", returned early: {}", termination),
+                    None => Ok(()),
+                }
+            }
+            LogEvent::LookupCompound { input, parts, distance } => write!(f, "lookup_compound {:?}: {} parts at distance {}", input, parts, distance),
+            LogEvent::WordSegmentation { input, words, distance } => write!(f, "word_segmentation {:?}: {} words at distance {}", input, words, distance),
+        }
+    }
+}
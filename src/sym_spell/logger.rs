@@ -0,0 +1,20 @@
+use crate::sym_spell::log_event::LogEvent;
+
+/// Receives a `LogEvent` for every dictionary load, lookup, compound lookup and word
+/// segmentation of the `SymSpell` it is set on, e.g. to diagnose slow or surprising
+/// corrections in production. Closures taking a `&LogEvent` are loggers too.
+///
+/// example:
+/// sym_spell.set_logger(Some(Box::new(|event: &LogEvent| eprintln!("{}", event))));
+///
+/// // lookup "helo": 1 suggestions from 9 candidates
+/// sym_spell.lookup("helo", Verbosity::Top, 2, false, false);
+pub trait Logger: Send + Sync {
+    fn log(&self, event: &LogEvent);
+}
+
+impl<F: Fn(&LogEvent) + Send + Sync> Logger for F {
+    fn log(&self, event: &LogEvent) {
+        self(event)
+    }
+}
@@ -0,0 +1,23 @@
+/// How often `SymSpell::lookup` was answered from the lookup cache since
+/// its capacity was last set.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LookupCacheStats {
+    /// Lookups answered from the cache
+    pub hits: usize,
+    /// Lookups that had to search the dictionary
+    pub misses: usize,
+    /// Results held in the cache
+    pub len: usize,
+}
+
+impl LookupCacheStats {
+    /// Share of lookups answered from the cache.
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+}
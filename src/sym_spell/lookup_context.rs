@@ -0,0 +1,9 @@
+/// What a `SuggestionRanker` knows about the lookup whose suggestions it ranks.
+#[derive(Clone, Copy, Debug)]
+pub struct LookupContext<'a> {
+    /// The word looked up, normalized
+    pub input: &'a str,
+    pub max_edit_distance: usize,
+    /// The lowercase word before the input, when looked up with `SymSpell::lookup_with_context`
+    pub previous_word: Option<&'a str>,
+}
@@ -0,0 +1,14 @@
+/// Caps on the work a single `SymSpell::lookup` may do, so pathological input
+/// such as a pasted URL or base64 blob is given up on rather than searched.
+/// A lookup over a cap finds nothing, returning only the unknown input when
+/// asked to include it, and is counted by `SymSpell::truncated_lookups`.
+///
+/// example:
+/// sym_spell.set_lookup_limits(LookupLimits { max_input_len: Some(64), max_candidates: Some(1000) });
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LookupLimits {
+    /// The longest input in graphemes that is searched, None for no limit
+    pub max_input_len: Option<usize>,
+    /// The most deletes of the input examined as candidates, None for no limit
+    pub max_candidates: Option<usize>,
+}
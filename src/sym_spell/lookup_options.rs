@@ -0,0 +1,18 @@
+use crate::keyboard::KeyboardLayout;
+
+/// Per call options for `SymSpell::lookup_with_options`.
+#[derive(Clone, Copy, Default)]
+pub struct LookupOptions {
+    /// Also look up the input with its hyphens and spaces removed so that
+    /// variants such as "e-mail" or "e mail" find the dictionary's "email".
+    /// Each run of separators counts as a single edit.
+    pub join_variants: bool,
+    /// Orders suggestions at the same edit distance by their Jaro-Winkler similarity
+    /// to the input, using this prefix scale, before their count. Suits names and
+    /// records where a matching start matters more than the raw edit count.
+    pub rerank_prefix_scale: Option<f64>,
+    /// Orders suggestions at the same edit distance so those reached by hitting a
+    /// neighbouring key on this layout come first, e.g. "hello" before "hullo" for "hwllo".
+    /// Takes precedence over `rerank_prefix_scale`.
+    pub keyboard_layout: Option<KeyboardLayout>,
+}
@@ -0,0 +1,42 @@
+use std::collections::HashSet;
+use std::ops::Range;
+
+use crate::sym_spell::delete_hasher::Fnv1aState;
+use crate::sym_spell::early_termination::EarlyTermination;
+use crate::sym_spell::index_storage::WordId;
+
+/// The buffers a lookup works in, kept between lookups so that once they
+/// have grown to fit the longest input no further allocations are needed.
+/// `SymSpell::lookup` reuses one per thread; pass one to
+/// `SymSpell::lookup_with_scratch` to manage them explicitly.
+///
+/// example:
+/// let mut scratch = LookupScratch::default();
+/// for word in ["teh", "quikc"].iter() {
+///     sym_spell.lookup_with_scratch(word, Verbosity::Top, 2, false, false, &mut scratch);
+/// }
+#[derive(Debug, Default)]
+pub struct LookupScratch {
+    // the text of every candidate back to back
+    pub(crate) candidate_text: String,
+    // the byte range of each candidate in candidate_text and the hash of its text
+    pub(crate) candidates: Vec<(Range<usize>, u64)>,
+    // the hashes of the deletes queued as candidates already
+    pub(crate) deletes_considered: HashSet<u64, Fnv1aState>,
+    // the ids of the words compared to the input already
+    pub(crate) suggestions_considered: HashSet<WordId, Fnv1aState>,
+    // the number of candidates and edit distances the last lookup checked and why it returned early, if it did
+    pub(crate) candidates_checked: usize,
+    pub(crate) distance_computations: usize,
+    pub(crate) termination: Option<EarlyTermination>,
+}
+
+impl LookupScratch {
+    // empties the buffers while keeping their capacity
+    pub(crate) fn clear(&mut self) {
+        self.candidate_text.clear();
+        self.candidates.clear();
+        self.deletes_considered.clear();
+        self.suggestions_considered.clear();
+    }
+}
@@ -0,0 +1,127 @@
+use std::borrow::Borrow;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+
+use crate::sym_spell::delete_hasher::Fnv1aState;
+
+/// A map holding up to `capacity` entries. Once full, the least recently used
+/// entry is evicted to make room for each new one. Counts how many gets found their key.
+#[derive(Debug)]
+pub(crate) struct LruCache<K, V> {
+    capacity: usize,
+    // bumped on every access, the tick of an entry is when it was last used
+    tick: u64,
+    entries: HashMap<K, (u64, V), Fnv1aState>,
+    // the key of every entry by its tick, the least recently used first
+    recency: BTreeMap<u64, K>,
+    hits: usize,
+    misses: usize,
+}
+
+impl<K: Hash + Eq + Clone, V> LruCache<K, V> {
+    pub(crate) fn new(capacity: usize) -> LruCache<K, V> {
+        LruCache {
+            capacity,
+            tick: 0,
+            entries: HashMap::default(),
+            recency: BTreeMap::new(),
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    pub(crate) fn get<Q: Hash + Eq + ?Sized>(&mut self, key: &Q) -> Option<&V> where K: Borrow<Q> {
+        self.tick += 1;
+        let tick = self.tick;
+        match self.entries.get_mut(key) {
+            Some((used, value)) => {
+                self.hits += 1;
+                if let Some(key) = self.recency.remove(used) {
+                    self.recency.insert(tick, key);
+                }
+                *used = tick;
+                Some(value)
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    pub(crate) fn insert(&mut self, key: K, value: V) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.tick += 1;
+        if let Some((used, _)) = self.entries.get(&key) {
+            self.recency.remove(used);
+        } else if self.entries.len() >= self.capacity {
+            if let Some(least_recent) = self.recency.keys().next().copied() {
+                let evicted = self.recency.remove(&least_recent).expect("the tick was just found");
+                self.entries.remove(&evicted);
+            }
+        }
+        self.recency.insert(self.tick, key.clone());
+        self.entries.insert(key, (self.tick, value));
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub(crate) fn hits(&self) -> usize {
+        self.hits
+    }
+
+    pub(crate) fn misses(&self) -> usize {
+        self.misses
+    }
+
+    // drops every entry, keeping the counters
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+        self.recency.clear();
+    }
+}
+
+impl<K: Hash + Eq + Clone, V> Default for LruCache<K, V> {
+    fn default() -> LruCache<K, V> {
+        LruCache::new(0)
+    }
+}
+
+#[cfg(test)]
+mod lru_cache_tests {
+    use crate::sym_spell::lru_cache::LruCache;
+
+    #[test]
+    fn eviction_test() {
+        let mut cache: LruCache<String, u32> = LruCache::new(4);
+        for (i, key) in ["ab", "ac", "ad", "ae"].iter().enumerate() {
+            cache.insert(key.to_string(), i as u32);
+        }
+        // "ab" and "ac" were used since so "ad" is the least recently used
+        assert!(cache.get("ab").is_some());
+        assert!(cache.get("ac").is_some());
+        cache.insert("af".to_string(), 4);
+        assert_eq!(cache.len(), 4);
+        assert_eq!(cache.get("ad"), None);
+        assert_eq!(cache.get("ab"), Some(&0));
+        assert_eq!(cache.get("ac"), Some(&1));
+        assert_eq!(cache.get("ae"), Some(&3));
+        assert_eq!(cache.get("af"), Some(&4));
+        assert_eq!((cache.hits(), cache.misses()), (6, 1));
+
+        // replacing a value makes the entry the most recently used without evicting
+        cache.insert("ab".to_string(), 5);
+        cache.insert("ag".to_string(), 6);
+        assert_eq!(cache.len(), 4);
+        assert_eq!(cache.get("ac"), None);
+        assert_eq!(cache.get("ab"), Some(&5));
+
+        let mut disabled: LruCache<String, u32> = LruCache::new(0);
+        disabled.insert("ab".to_string(), 0);
+        assert_eq!(disabled.get("ab"), None);
+    }
+}
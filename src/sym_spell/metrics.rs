@@ -0,0 +1,113 @@
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::OnceLock;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+
+/// What the lookups of a `SymSpell` cost since it was created, cleared or its
+/// metrics were reset, to measure the effect of the prefix length and edit
+/// distances on a real workload. Gathered with the `metrics` feature.
+///
+/// example:
+/// let metrics = sym_spell.metrics();
+/// println!("{:.1} candidates and {:?} per lookup", metrics.average_candidates(), metrics.average_time());
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Metrics {
+    /// Lookups that searched the dictionary, including those of `lookup_compound`
+    pub lookups: usize,
+    /// Deletes of the inputs checked for suggestions
+    pub candidates: usize,
+    /// Edit distances computed between an input and a dictionary word
+    pub distance_computations: usize,
+    /// Lookups answered from the lookup cache, see `SymSpell::set_lookup_cache_capacity`
+    pub cache_hits: usize,
+    /// The time spent in the lookups that searched the dictionary
+    pub total_time: Duration,
+}
+
+impl Metrics {
+    /// The candidates checked per lookup.
+    pub fn average_candidates(&self) -> f64 {
+        if self.lookups == 0 {
+            0.0
+        } else {
+            self.candidates as f64 / self.lookups as f64
+        }
+    }
+
+    /// The time spent per lookup.
+    pub fn average_time(&self) -> Duration {
+        if self.lookups == 0 {
+            Duration::ZERO
+        } else {
+            Duration::from_nanos((self.total_time.as_nanos() / self.lookups as u128) as u64)
+        }
+    }
+}
+
+// the running totals of the metrics, added to by concurrent lookups
+#[derive(Debug, Default)]
+pub(crate) struct MetricsRecorder {
+    lookups: AtomicUsize,
+    candidates: AtomicUsize,
+    distance_computations: AtomicUsize,
+    cache_hits: AtomicUsize,
+    total_nanos: AtomicU64,
+}
+
+impl MetricsRecorder {
+    pub(crate) fn record_lookup(&self, candidates: usize, distance_computations: usize, time: Duration) {
+        self.lookups.fetch_add(1, Ordering::Relaxed);
+        self.candidates.fetch_add(candidates, Ordering::Relaxed);
+        self.distance_computations.fetch_add(distance_computations, Ordering::Relaxed);
+        self.total_nanos.fetch_add(time.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_cache_hit(&self) {
+        self.cache_hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> Metrics {
+        Metrics {
+            lookups: self.lookups.load(Ordering::Relaxed),
+            candidates: self.candidates.load(Ordering::Relaxed),
+            distance_computations: self.distance_computations.load(Ordering::Relaxed),
+            cache_hits: self.cache_hits.load(Ordering::Relaxed),
+            total_time: Duration::from_nanos(self.total_nanos.load(Ordering::Relaxed)),
+        }
+    }
+
+    pub(crate) fn reset(&self) {
+        self.lookups.store(0, Ordering::Relaxed);
+        self.candidates.store(0, Ordering::Relaxed);
+        self.distance_computations.store(0, Ordering::Relaxed);
+        self.cache_hits.store(0, Ordering::Relaxed);
+        self.total_nanos.store(0, Ordering::Relaxed);
+    }
+}
+
+// the time since an arbitrary fixed point, to subtract from a later reading
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn now() -> Duration {
+    static START: OnceLock<Instant> = OnceLock::new();
+    START.get_or_init(Instant::now).elapsed()
+}
+
+// wasm has no clock of its own, so the host supplies one in milliseconds
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn now() -> Duration {
+    Duration::from_secs_f64(unsafe { metrics_clock() }.max(0.0) / 1000.0)
+}
+
+#[cfg(all(target_arch = "wasm32", not(feature = "wasm-bindgen")))]
+extern "C" {
+    fn metrics_clock() -> f64;
+}
+
+#[cfg(all(target_arch = "wasm32", feature = "wasm-bindgen"))]
+#[wasm_bindgen::prelude::wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = performance, js_name = now)]
+    fn metrics_clock() -> f64;
+}
@@ -0,0 +1,18 @@
+use std::ops::Range;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::sym_spell::issue_kind::IssueKind;
+use crate::sym_spell::suggested_item::SuggestItem;
+
+/// A word of a checked text that is not in the dictionary, or that repeats the word before it.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MisspelledRange {
+    /// The byte offsets of the word in the checked text
+    pub byte_range: Range<usize>,
+    /// The best correction, if any is within the max edit distance. None for a repeated word
+    pub suggestion: Option<SuggestItem>,
+    pub kind: IssueKind,
+}
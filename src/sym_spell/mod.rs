@@ -1,7 +1,68 @@
+/// The type word and bigram frequencies are counted in. `u32` by default, which
+/// halves the memory of the counts, or `u64` with the `wide-counts` feature for
+/// corpora where a count may exceed 4,294,967,295. Counts saturate or follow the
+/// `CountOverflowPolicy` either way.
+#[cfg(not(feature = "wide-counts"))]
+pub type Count = u32;
+#[cfg(feature = "wide-counts")]
+pub type Count = u64;
+
 pub trait Encode<T> {
     fn encode(&self) -> T;
 }
 
+#[allow(clippy::module_inception)]
 pub mod sym_spell;
 pub mod verbosity;
 pub mod suggested_item;
+pub mod bigram_line_error;
+pub mod backend;
+mod bloom_filter;
+pub mod capabilities;
+pub mod check_delta;
+pub mod compound_correction;
+pub mod compound_options;
+pub mod compound_part;
+pub mod corpus_trainer;
+pub mod count_overflow_policy;
+#[cfg(feature = "dawg")]
+mod dawg;
+pub mod delete_generation;
+pub mod delete_hasher;
+pub mod document_stats;
+pub mod early_termination;
+pub mod entry_metadata;
+pub mod equivalences;
+pub mod frame_error;
+#[cfg(feature = "fst-dictionary")]
+pub mod fst_dictionary;
+pub mod load_progress;
+pub mod load_report;
+pub mod log_event;
+pub mod logger;
+pub mod incremental_checker;
+pub mod index_storage;
+pub mod invalid_count_policy;
+pub mod issue_kind;
+pub mod lookup_cache_stats;
+pub mod lookup_context;
+pub mod lookup_limits;
+pub mod lookup_options;
+pub mod lookup_scratch;
+mod lru_cache;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+pub mod misspelled_range;
+#[cfg(feature = "unicode-normalization")]
+pub mod normalization;
+pub mod part_status;
+pub mod query_suggestion;
+pub mod result_frame;
+pub mod seeded_state;
+pub mod spellchecker_error;
+pub mod suggest_ref;
+pub mod suggestion_ranker;
+pub mod stream_checker;
+pub mod trainer_options;
+pub mod trigram_line_error;
+pub mod word_segmenter;
@@ -0,0 +1,34 @@
+use std::borrow::Cow;
+
+use unicode_normalization::{is_nfc_quick, is_nfkc_quick, IsNormalized, UnicodeNormalization};
+
+/// The Unicode normalization form dictionary words and queries are brought to
+/// before they are compared.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Normalization {
+    /// Compare the text as given (default).
+    #[default]
+    None,
+    /// Canonical composition, so "cafe\u{301}" and "café" are the same word.
+    Nfc,
+    /// Compatibility composition, which also folds ligatures and width
+    /// variants, e.g. "ﬁle" to "file" and "ｗｏｒｄ" to "word".
+    Nfkc,
+}
+
+impl Normalization {
+    /// The text in this normalization form, borrowing it when it already is.
+    ///
+    /// example:
+    /// assert_eq!(Normalization::Nfc.normalize("cafe\u{301}"), "café");
+    /// assert!(matches!(Normalization::Nfc.normalize("café"), Cow::Borrowed(_)));
+    pub fn normalize<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        match self {
+            Normalization::None => Cow::Borrowed(text),
+            Normalization::Nfc if is_nfc_quick(text.chars()) == IsNormalized::Yes => Cow::Borrowed(text),
+            Normalization::Nfc => Cow::Owned(text.nfc().collect()),
+            Normalization::Nfkc if is_nfkc_quick(text.chars()) == IsNormalized::Yes => Cow::Borrowed(text),
+            Normalization::Nfkc => Cow::Owned(text.nfkc().collect()),
+        }
+    }
+}
@@ -0,0 +1,14 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// How `SymSpell::lookup_compound_detailed` treated a part of its input.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum PartStatus {
+    /// The part is a dictionary word, or a proper noun kept as is, and was left unchanged.
+    Unchanged,
+    /// The part was replaced by a correction, or split or merged with its neighbor.
+    Corrected,
+    /// No correction was found within the max edit distance, so the part was kept as is.
+    Unknown,
+}
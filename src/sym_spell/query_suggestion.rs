@@ -0,0 +1,14 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The "did you mean" rewrite of a search query returned by `SymSpell::suggest_query`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct QuerySuggestion {
+    /// The corrected query, lowercase with its words separated by single spaces
+    pub term: String,
+    /// Edit distance between the lowercase query and the rewrite
+    pub distance: usize,
+    /// How many times more probable the rewrite is than the query as typed
+    pub probability_ratio: f64,
+}
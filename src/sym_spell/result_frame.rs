@@ -0,0 +1,306 @@
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+use crate::sym_spell::entry_metadata::EntryMetadata;
+use crate::sym_spell::frame_error::FrameError;
+use crate::sym_spell::suggest_ref::AsSuggestRef;
+use crate::sym_spell::suggested_item::SuggestItem;
+use crate::sym_spell::Count;
+
+/// The first byte of every frame. It never starts utf-8 text, so a frame can
+/// not be mistaken for a JSON payload.
+pub const FRAME_MAGIC: u8 = 0xF5;
+/// The layout of the frame header. Fields are added to suggestions without
+/// changing it, since decoders skip the fields they do not know.
+pub const FRAME_VERSION: u8 = 1;
+
+// header flags
+const FLAG_REQUEST_ID: u8 = 1;
+const FLAG_UTF16: u8 = 1 << 1;
+
+// field tags
+const FIELD_TERM: u8 = 1;
+const FIELD_DISTANCE: u8 = 2;
+const FIELD_COUNT: u8 = 3;
+const FIELD_PROBABILITY: u8 = 4;
+const FIELD_SCORE: u8 = 5;
+const FIELD_LANGUAGE: u8 = 6;
+const FIELD_SOURCE: u8 = 7;
+const FIELD_CANONICAL: u8 = 8;
+
+/// A list of suggestions in the self describing encoding of the framed output format,
+/// so hosts, including native Rust hosts of the wasm module, can parse results without
+/// relying on the field order of a particular build.
+///
+/// A frame is `[magic][version][flags][request_id?][num_items]` followed by each item
+/// as `[num_fields]` and a `[tag][len][value]` entry per field. Every number but the
+/// single byte magic, version, flags and tags is an unsigned LEB128 varint. Flag 1
+/// marks a request id, flag 2 text in UTF-16LE rather than utf-8. The fields are the
+/// term (1), distance (2) and count (3) as varints, the probability (4) and score (5)
+/// as little endian f64 and the language (6), source (7) and canonical form (8) of the
+/// entry metadata. Fields without a value are left out, and decoders skip unknown tags.
+///
+/// example:
+/// let frame = ResultFrame { request_id: Some(7), suggestions: sym_spell.lookup("helo", Verbosity::Top, 2, false, false) };
+/// let decoded = ResultFrame::decode(&frame.encode(false))?;
+///
+/// assert_eq!(decoded.suggestions[0].term, "hello");
+#[derive(Clone, Debug, Default)]
+pub struct ResultFrame {
+    pub request_id: Option<u32>,
+    pub suggestions: Vec<SuggestItem>,
+}
+
+impl ResultFrame {
+    /// The frame with its text in UTF-16LE when utf16 is true, utf-8 otherwise.
+    pub fn encode(&self, utf16: bool) -> Vec<u8> {
+        ResultFrame::encode_suggestions(self.request_id, &self.suggestions, utf16)
+    }
+
+    /// Identical to `encode` for suggestions that are not held in a frame.
+    pub fn encode_suggestions(request_id: Option<u32>, suggestions: &[SuggestItem], utf16: bool) -> Vec<u8> {
+        ResultFrame::encode_items(request_id, suggestions, utf16)
+    }
+
+    // encode_suggestions for borrowed suggestions as well
+    pub(crate) fn encode_items<S: AsSuggestRef>(request_id: Option<u32>, suggestions: &[S], utf16: bool) -> Vec<u8> {
+        let mut flags = 0;
+        if request_id.is_some() {
+            flags |= FLAG_REQUEST_ID;
+        }
+        if utf16 {
+            flags |= FLAG_UTF16;
+        }
+        let mut frame = vec![FRAME_MAGIC, FRAME_VERSION, flags];
+        if let Some(request_id) = request_id {
+            push_varint(&mut frame, u64::from(request_id));
+        }
+        push_varint(&mut frame, suggestions.len() as u64);
+
+        for si in suggestions {
+            let si = si.as_suggest_ref();
+            let mut fields: Vec<(u8, Vec<u8>)> = vec![
+                (FIELD_TERM, encode_text(&si.term, utf16)),
+                (FIELD_DISTANCE, varint(si.distance as u64)),
+                // lossless from u32 and u64 counts alike
+                #[allow(clippy::useless_conversion)]
+                (FIELD_COUNT, varint(u64::from(si.count))),
+            ];
+            if let Some(probability) = si.probability {
+                fields.push((FIELD_PROBABILITY, probability.to_le_bytes().to_vec()));
+            }
+            if si.score != 0.0 {
+                fields.push((FIELD_SCORE, si.score.to_le_bytes().to_vec()));
+            }
+            if let Some(metadata) = &si.metadata {
+                let facts = [(FIELD_LANGUAGE, &metadata.language), (FIELD_SOURCE, &metadata.source), (FIELD_CANONICAL, &metadata.canonical)];
+                for (tag, fact) in facts.iter() {
+                    if let Some(fact) = fact {
+                        fields.push((*tag, encode_text(fact, utf16)));
+                    }
+                }
+            }
+            push_varint(&mut frame, fields.len() as u64);
+            for (tag, value) in fields {
+                frame.push(tag);
+                push_varint(&mut frame, value.len() as u64);
+                frame.extend_from_slice(&value);
+            }
+        }
+        frame
+    }
+
+    /// Parses a frame written by `encode`, or by the wasm module in the framed output format.
+    pub fn decode(bytes: &[u8]) -> Result<ResultFrame, FrameError> {
+        let mut reader = Reader { bytes, at: 0 };
+        if reader.byte()? != FRAME_MAGIC {
+            return Err(FrameError::NotAFrame);
+        }
+        let version = reader.byte()?;
+        if version != FRAME_VERSION {
+            return Err(FrameError::UnsupportedVersion(version));
+        }
+        let flags = reader.byte()?;
+        let utf16 = flags & FLAG_UTF16 != 0;
+        let request_id = if flags & FLAG_REQUEST_ID != 0 {
+            Some(u32::try_from(reader.varint()?).map_err(|_| FrameError::Overflow)?)
+        } else {
+            None
+        };
+
+        let len = reader.len()?;
+        // every item takes at least a byte, so a corrupt length can not reserve much
+        let mut suggestions = Vec::with_capacity(len.min(bytes.len()));
+        for _ in 0..len {
+            let mut term = None;
+            let mut si = SuggestItem::default();
+            let mut metadata = EntryMetadata::default();
+            for _ in 0..reader.len()? {
+                let tag = reader.byte()?;
+                let value_len = reader.len()?;
+                let value = reader.take(value_len)?;
+                match tag {
+                    FIELD_TERM => term = Some(decode_text(value, utf16)?),
+                    FIELD_DISTANCE => si.distance = usize::try_from(read_varint(value)?).map_err(|_| FrameError::Overflow)?,
+                    FIELD_COUNT => si.count = Count::try_from(read_varint(value)?).map_err(|_| FrameError::Overflow)?,
+                    FIELD_PROBABILITY => si.probability = Some(read_f64(value)?),
+                    FIELD_SCORE => si.score = read_f64(value)?,
+                    FIELD_LANGUAGE => metadata.language = Some(decode_text(value, utf16)?),
+                    FIELD_SOURCE => metadata.source = Some(decode_text(value, utf16)?),
+                    FIELD_CANONICAL => metadata.canonical = Some(decode_text(value, utf16)?),
+                    // a field added by a later build
+                    _ => {}
+                }
+            }
+            si.term = term.ok_or(FrameError::MissingTerm)?;
+            if !metadata.is_empty() {
+                si.metadata = Some(Arc::new(metadata));
+            }
+            suggestions.push(si);
+        }
+        Ok(ResultFrame { request_id, suggestions })
+    }
+}
+
+// reads a frame front to back
+struct Reader<'a> {
+    bytes: &'a [u8],
+    at: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn byte(&mut self) -> Result<u8, FrameError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], FrameError> {
+        let end = self.at.checked_add(len).filter(|&end| end <= self.bytes.len()).ok_or(FrameError::Truncated)?;
+        let taken = &self.bytes[self.at..end];
+        self.at = end;
+        Ok(taken)
+    }
+
+    fn varint(&mut self) -> Result<u64, FrameError> {
+        let (value, len) = parse_varint(&self.bytes[self.at..])?;
+        self.at += len;
+        Ok(value)
+    }
+
+    fn len(&mut self) -> Result<usize, FrameError> {
+        usize::try_from(self.varint()?).map_err(|_| FrameError::Overflow)
+    }
+}
+
+fn varint(value: u64) -> Vec<u8> {
+    let mut bytes = vec![];
+    push_varint(&mut bytes, value);
+    bytes
+}
+
+// appends the value as an unsigned LEB128 varint, 7 bits per byte with the high bit set on all but the last
+fn push_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+// the varint at the start of the bytes and the number of bytes it takes
+fn parse_varint(bytes: &[u8]) -> Result<(u64, usize), FrameError> {
+    let mut value = 0u64;
+    for (i, &byte) in bytes.iter().enumerate() {
+        let shift = 7 * i as u32;
+        if shift >= 64 || (shift == 63 && byte > 1) {
+            return Err(FrameError::Overflow);
+        }
+        value |= u64::from(byte & 0x7F) << shift;
+        if byte & 0x80 == 0 {
+            return Ok((value, i + 1));
+        }
+    }
+    Err(FrameError::Truncated)
+}
+
+// a field value that is a single varint
+fn read_varint(value: &[u8]) -> Result<u64, FrameError> {
+    parse_varint(value).map(|(value, _)| value)
+}
+
+fn read_f64(value: &[u8]) -> Result<f64, FrameError> {
+    let bytes = <[u8; 8]>::try_from(value).map_err(|_| FrameError::Truncated)?;
+    Ok(f64::from_le_bytes(bytes))
+}
+
+fn encode_text(text: &str, utf16: bool) -> Vec<u8> {
+    if utf16 {
+        text.encode_utf16().flat_map(u16::to_le_bytes).collect()
+    } else {
+        text.as_bytes().to_vec()
+    }
+}
+
+fn decode_text(bytes: &[u8], utf16: bool) -> Result<String, FrameError> {
+    if !utf16 {
+        return String::from_utf8(bytes.to_vec()).map_err(|_| FrameError::InvalidText);
+    }
+    let chunks = bytes.chunks_exact(2);
+    if !chunks.remainder().is_empty() {
+        return Err(FrameError::InvalidText);
+    }
+    let units: Vec<u16> = chunks.map(|unit| u16::from_le_bytes([unit[0], unit[1]])).collect();
+    String::from_utf16(&units).map_err(|_| FrameError::InvalidText)
+}
+
+#[cfg(test)]
+mod result_frame_tests {
+    use std::sync::Arc;
+
+    use crate::sym_spell::entry_metadata::EntryMetadata;
+    use crate::sym_spell::frame_error::FrameError;
+    use crate::sym_spell::result_frame::{ResultFrame, FRAME_MAGIC, FRAME_VERSION};
+    use crate::sym_spell::suggested_item::SuggestItem;
+
+    fn summary(frame: &ResultFrame) -> Vec<String> {
+        frame.suggestions.iter()
+            .map(|si| format!("{:?}", (&si.term, si.distance, si.count, si.probability, si.score, &si.metadata)))
+            .collect()
+    }
+
+    #[test]
+    fn round_trip_test() {
+        let metadata = EntryMetadata { language: Some("de".into()), canonical: Some("Straße".into()), ..EntryMetadata::default() };
+        let frame = ResultFrame {
+            request_id: Some(300),
+            suggestions: vec![
+                SuggestItem { probability: Some(0.25), score: 1.5, metadata: Some(Arc::new(metadata)), ..SuggestItem::new("straße".into(), 1, 70_000) },
+                SuggestItem::new("strasse".into(), 2, 1),
+            ],
+        };
+        for &utf16 in [false, true].iter() {
+            let decoded = ResultFrame::decode(&frame.encode(utf16)).unwrap();
+            assert_eq!(decoded.request_id, Some(300));
+            assert_eq!(summary(&decoded), summary(&frame));
+        }
+
+        let empty = ResultFrame::default().encode(false);
+        assert_eq!(empty, vec![FRAME_MAGIC, FRAME_VERSION, 0, 0]);
+        assert!(ResultFrame::decode(&empty).unwrap().suggestions.is_empty());
+    }
+
+    #[test]
+    fn decode_test() {
+        // a field of a later build is skipped
+        let bytes = [FRAME_MAGIC, FRAME_VERSION, 0, 1, 3, 1, 2, b'h', b'i', 99, 3, 1, 2, 3, 3, 2, 0x80, 0x01];
+        let decoded = ResultFrame::decode(&bytes).unwrap();
+        assert_eq!((decoded.suggestions[0].term.as_str(), decoded.suggestions[0].count), ("hi", 128));
+
+        let encoded = ResultFrame::encode_suggestions(None, &[SuggestItem::new("hello".into(), 0, 5)], false);
+        assert_eq!(ResultFrame::decode(&encoded[..encoded.len() - 1]).unwrap_err(), FrameError::Truncated);
+        assert_eq!(ResultFrame::decode(b"[]").unwrap_err(), FrameError::NotAFrame);
+        assert_eq!(ResultFrame::decode(&[FRAME_MAGIC, 2, 0, 0]).unwrap_err(), FrameError::UnsupportedVersion(2));
+        assert_eq!(ResultFrame::decode(&[FRAME_MAGIC, FRAME_VERSION, 0, 1, 1, 2, 1, 1]).unwrap_err(), FrameError::MissingTerm);
+        assert_eq!(ResultFrame::decode(&[FRAME_MAGIC, FRAME_VERSION, 0, 1, 1, 1, 1, 0xFF]).unwrap_err(), FrameError::InvalidText);
+        assert_eq!(ResultFrame::decode(&[FRAME_MAGIC, FRAME_VERSION, 0, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x01]).unwrap_err(), FrameError::Overflow);
+    }
+}
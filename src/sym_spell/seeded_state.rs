@@ -0,0 +1,48 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{BuildHasher, Hasher};
+
+/// A `BuildHasher` whose hashers are keyed by a fixed seed instead of the
+/// per process random keys of `RandomState`, so iterating a collection
+/// built with it yields the same order on every run.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SeededState {
+    seed: u64,
+}
+
+impl SeededState {
+    pub fn new(seed: u64) -> SeededState {
+        SeededState { seed }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl BuildHasher for SeededState {
+    type Hasher = DefaultHasher;
+
+    fn build_hasher(&self) -> DefaultHasher {
+        let mut hasher = DefaultHasher::new();
+        hasher.write_u64(self.seed);
+        hasher
+    }
+}
+
+#[cfg(test)]
+mod seeded_state_tests {
+    use std::collections::HashSet;
+
+    use crate::sym_spell::seeded_state::SeededState;
+
+    #[test]
+    fn iteration_order_test() {
+        let order = |seed: u64| {
+            let mut set = HashSet::with_hasher(SeededState::new(seed));
+            set.extend((0..64).map(|i| i.to_string()));
+            set.into_iter().collect::<Vec<String>>()
+        };
+        assert_eq!(order(7), order(7));
+        assert_ne!(order(7), order(8));
+    }
+}
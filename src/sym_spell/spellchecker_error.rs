@@ -0,0 +1,108 @@
+use std::error::Error;
+use std::fmt;
+use std::str::Utf8Error;
+
+use crate::sym_spell::bigram_line_error::BigramLineError;
+use crate::sym_spell::frame_error::FrameError;
+use crate::sym_spell::trigram_line_error::TrigramLineError;
+
+/// The errors of the fallible `SymSpell` APIs, e.g. `try_new`, `try_lookup` and the
+/// dictionary line parsers, wrapping the errors of the more specific APIs so they can
+/// be propagated with `?`. The wasm exports report them to hosts as `code()` and the
+/// `Display` message through `last_error_code()` and `last_error_message()`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SpellcheckerError {
+    /// The prefix length is 0 or not greater than the max dictionary edit distance.
+    InvalidPrefixLength { prefix_length: usize, max_dictionary_edit_distance: usize },
+    /// A lookup asked for a greater edit distance than the dictionary was built for.
+    EditDistanceTooLarge { max_edit_distance: usize, max_dictionary_edit_distance: usize },
+    /// A dictionary line has a word but no count.
+    MissingCount,
+    /// A bigram dictionary line was rejected.
+    BigramLine(BigramLineError),
+    /// A trigram dictionary line was rejected.
+    TrigramLine(TrigramLineError),
+    /// Text passed in is not valid utf-8 from the byte offset on.
+    InvalidUtf8 { valid_up_to: usize },
+    /// A result frame could not be parsed.
+    Frame(FrameError),
+    /// A number that is not a `Verbosity`.
+    InvalidVerbosity(u32),
+    /// An edit passed to `IncrementalChecker::edit` does not fit the text or does not
+    /// fall on char boundaries.
+    InvalidEdit { replaced_start: usize, replaced_end: usize, replacement_len: usize },
+}
+
+impl SpellcheckerError {
+    /// The stable number of the variant reported by the `last_error_code()` export, never 0.
+    pub fn code(&self) -> u32 {
+        match self {
+            SpellcheckerError::InvalidPrefixLength { .. } => 1,
+            SpellcheckerError::EditDistanceTooLarge { .. } => 2,
+            SpellcheckerError::MissingCount => 3,
+            SpellcheckerError::BigramLine(_) => 4,
+            SpellcheckerError::TrigramLine(_) => 5,
+            SpellcheckerError::InvalidUtf8 { .. } => 6,
+            SpellcheckerError::Frame(_) => 7,
+            SpellcheckerError::InvalidVerbosity(_) => 8,
+            SpellcheckerError::InvalidEdit { .. } => 9,
+        }
+    }
+}
+
+impl fmt::Display for SpellcheckerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpellcheckerError::InvalidPrefixLength { prefix_length, max_dictionary_edit_distance } => write!(
+                f, "prefix length {} must be at least 1 and greater than the max dictionary edit distance {}", prefix_length, max_dictionary_edit_distance
+            ),
+            SpellcheckerError::EditDistanceTooLarge { max_edit_distance, max_dictionary_edit_distance } => write!(
+                f, "max edit distance {} exceeds the max dictionary edit distance {}", max_edit_distance, max_dictionary_edit_distance
+            ),
+            SpellcheckerError::MissingCount => write!(f, "dictionary line needs a word and a count"),
+            SpellcheckerError::BigramLine(err) => err.fmt(f),
+            SpellcheckerError::TrigramLine(err) => err.fmt(f),
+            SpellcheckerError::InvalidUtf8 { valid_up_to } => write!(f, "invalid utf-8 sequence at byte {}", valid_up_to),
+            SpellcheckerError::Frame(err) => err.fmt(f),
+            SpellcheckerError::InvalidVerbosity(verbosity) => write!(f, "verbosity {} is not 0 (top), 1 (closest) or 2 (all)", verbosity),
+            SpellcheckerError::InvalidEdit { replaced_start, replaced_end, replacement_len } => write!(
+                f, "replacing bytes {} to {} with {} bytes does not fit the text on char boundaries", replaced_start, replaced_end, replacement_len
+            ),
+        }
+    }
+}
+
+impl Error for SpellcheckerError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SpellcheckerError::BigramLine(err) => Some(err),
+            SpellcheckerError::TrigramLine(err) => Some(err),
+            SpellcheckerError::Frame(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<BigramLineError> for SpellcheckerError {
+    fn from(err: BigramLineError) -> SpellcheckerError {
+        SpellcheckerError::BigramLine(err)
+    }
+}
+
+impl From<TrigramLineError> for SpellcheckerError {
+    fn from(err: TrigramLineError) -> SpellcheckerError {
+        SpellcheckerError::TrigramLine(err)
+    }
+}
+
+impl From<FrameError> for SpellcheckerError {
+    fn from(err: FrameError) -> SpellcheckerError {
+        SpellcheckerError::Frame(err)
+    }
+}
+
+impl From<Utf8Error> for SpellcheckerError {
+    fn from(err: Utf8Error) -> SpellcheckerError {
+        SpellcheckerError::InvalidUtf8 { valid_up_to: err.valid_up_to() }
+    }
+}
@@ -0,0 +1,214 @@
+use std::mem;
+use std::ops::Range;
+
+use crate::sym_spell::issue_kind::IssueKind;
+use crate::sym_spell::misspelled_range::MisspelledRange;
+use crate::sym_spell::suggested_item::SuggestItem;
+use crate::sym_spell::sym_spell::SymSpell;
+use crate::sym_spell::verbosity::Verbosity;
+use crate::tokenizer::{Token, TokenClass};
+use crate::utils::ends_sentence;
+
+/// Spell checks text that arrives in pieces, e.g. chat messages or log lines, reporting
+/// each misspelled word as soon as the text after it shows the word is complete. Byte
+/// ranges are counted from the start of the stream. A misspelled word is held back
+/// until the next word arrives so "mis spelled" is reported as one word split by a
+/// space, and its correction is the one that most often follows the previous word
+/// in the bigram dictionary. Corrections are cased the same way as `SymSpell::check_text`.
+///
+/// example:
+/// let mut checker = StreamChecker::new(&sym_spell, 2);
+/// let mut misspelled = checker.push("the qui");
+/// misspelled.extend(checker.push("kc fox"));
+/// misspelled.extend(checker.finish());
+///
+/// assert_eq!(misspelled[0].byte_range, 4..9);
+/// assert_eq!(misspelled[0].suggestion.as_ref().unwrap().term, "quick");
+pub struct StreamChecker<'a> {
+    sym_spell: &'a SymSpell,
+    max_edit_distance: usize,
+    // the unchecked end of the stream, a word that may continue in the next push
+    pending: String,
+    // the stream offset of the start of pending
+    offset: usize,
+    // the last word checked, lowercased, for the bigram context of a correction
+    previous: Option<String>,
+    // a misspelled word that may join with the next word, and whether it starts a sentence
+    held: Option<(Range<usize>, String, bool)>,
+    // whether the next word starts a sentence
+    sentence_start: bool,
+    // the last word checked, lowercased, while only whitespace follows it
+    last_word: Option<String>,
+}
+
+impl<'a> StreamChecker<'a> {
+    pub fn new(sym_spell: &'a SymSpell, max_edit_distance: usize) -> StreamChecker<'a> {
+        StreamChecker {
+            sym_spell,
+            max_edit_distance,
+            pending: String::new(),
+            offset: 0,
+            previous: None,
+            held: None,
+            sentence_start: true,
+            last_word: None,
+        }
+    }
+
+    /// Appends the text to the stream and returns the misspelled words it completes.
+    pub fn push(&mut self, text: &str) -> Vec<MisspelledRange> {
+        self.pending.push_str(text);
+        let pending = mem::take(&mut self.pending);
+        let tokens = self.sym_spell.tokenizer().tokenize(&pending);
+        // a word at the end may continue in the next push
+        let complete = match tokens.last() {
+            Some(last) if last.class == TokenClass::Word => tokens.len() - 1,
+            _ => tokens.len(),
+        };
+        let mut misspelled = vec![];
+        for token in &tokens[..complete] {
+            self.check_token(token, &mut misspelled);
+        }
+        let consumed = tokens.get(complete).map_or(pending.len(), |token| token.byte_range.start);
+        self.pending = pending[consumed..].to_string();
+        self.offset += consumed;
+        misspelled
+    }
+
+    /// Ends the stream, returning the misspelled words still waiting to be reported.
+    /// The checker can then be reused for a new stream.
+    pub fn finish(&mut self) -> Vec<MisspelledRange> {
+        let pending = mem::take(&mut self.pending);
+        let mut misspelled = vec![];
+        for token in &self.sym_spell.tokenizer().tokenize(&pending) {
+            self.check_token(token, &mut misspelled);
+        }
+        self.release_held(&mut misspelled);
+        self.offset = 0;
+        self.previous = None;
+        self.sentence_start = true;
+        self.last_word = None;
+        misspelled
+    }
+
+    fn check_token(&mut self, token: &Token, misspelled: &mut Vec<MisspelledRange>) {
+        match token.class {
+            TokenClass::Whitespace => {}
+            TokenClass::Punctuation => {
+                // words are not joined across punctuation, nor is context carried past a sentence
+                self.release_held(misspelled);
+                self.last_word = None;
+                if ends_sentence(token.text) {
+                    self.previous = None;
+                    self.sentence_start = true;
+                }
+            }
+            TokenClass::Word => {
+                let byte_range = self.offset + token.byte_range.start..self.offset + token.byte_range.end;
+                let lowercase = self.sym_spell.lowercase(token.text).into_owned();
+                if self.last_word.as_ref().is_some_and(|last_word| self.sym_spell.is_repeated_word(last_word, &lowercase)) {
+                    self.release_held(misspelled);
+                    misspelled.push(MisspelledRange { byte_range, suggestion: None, kind: IssueKind::RepeatedWord });
+                } else {
+                    self.check_word(byte_range, token.text, misspelled);
+                }
+                self.sentence_start = false;
+                self.last_word = Some(lowercase);
+            }
+        }
+    }
+
+    fn check_word(&mut self, byte_range: Range<usize>, word: &str, misspelled: &mut Vec<MisspelledRange>) {
+        if let Some((held_range, held_word, sentence_start)) = self.held.take() {
+            let joined = held_word.clone() + word;
+            let lowercase = self.sym_spell.lowercase(&joined).into_owned();
+            let known = self.sym_spell.known_word_count(&joined).map(|count| (joined.clone(), count))
+                .or_else(|| self.sym_spell.known_word_count(&lowercase).map(|count| (lowercase, count)));
+            if let Some((term, count)) = known {
+                misspelled.push(MisspelledRange {
+                    byte_range: held_range.start..byte_range.end,
+                    suggestion: Some(SuggestItem::new(self.sym_spell.match_case(&term, &joined, sentence_start), 1, count)),
+                    kind: IssueKind::Misspelling,
+                });
+                self.previous = Some(term);
+                return;
+            }
+            self.held = Some((held_range, held_word, sentence_start));
+            self.release_held(misspelled);
+        }
+        if self.sym_spell.is_known(word) {
+            self.previous = Some(self.sym_spell.lowercase(word).into_owned());
+        } else {
+            self.held = Some((byte_range, word.to_string(), self.sentence_start));
+        }
+    }
+
+    // reports the held word with the correction that best follows the previous word
+    fn release_held(&mut self, misspelled: &mut Vec<MisspelledRange>) {
+        let (byte_range, word, sentence_start) = match self.held.take() {
+            Some(held) => held,
+            None => return,
+        };
+        let lowercase = self.sym_spell.lowercase(&word).into_owned();
+        let mut suggestions = self.sym_spell.lookup(&lowercase, Verbosity::Closest, self.max_edit_distance, false, false);
+        if let Some(previous) = &self.previous {
+            for si in suggestions.iter_mut() {
+                si.score = self.sym_spell.bigram_frequency(previous, &si.term) as f64;
+            }
+            suggestions.sort_by(|a, b| a.sort_key().cmp(&b.sort_key()));
+        }
+        let mut suggestion = suggestions.into_iter().next();
+        self.previous = Some(suggestion.as_ref().map_or(lowercase, |si| si.term.clone()));
+        if let Some(si) = suggestion.as_mut() {
+            si.term = self.sym_spell.match_case(&si.term, &word, sentence_start);
+        }
+        misspelled.push(MisspelledRange { byte_range, suggestion, kind: IssueKind::Misspelling });
+    }
+}
+
+#[cfg(test)]
+mod stream_checker_tests {
+    use crate::sym_spell::misspelled_range::MisspelledRange;
+    use crate::sym_spell::stream_checker::StreamChecker;
+    use crate::sym_spell::sym_spell::SymSpell;
+
+    fn summary(misspelled: Vec<MisspelledRange>) -> Vec<(usize, usize, String)> {
+        misspelled.into_iter()
+            .map(|m| (m.byte_range.start, m.byte_range.end, m.suggestion.map_or(String::new(), |si| si.term)))
+            .collect()
+    }
+
+    #[test]
+    fn push_test() {
+        let sym_spell: SymSpell = vec![("the", 1000), ("quick", 50), ("fox", 20)].into_iter().collect();
+        let mut checker = StreamChecker::new(&sym_spell, 2);
+
+        // words are only checked once the text after them shows they are complete
+        assert!(checker.push("the qui").is_empty());
+        assert!(checker.push("kc").is_empty());
+        assert!(checker.push(" fox").is_empty());
+        assert_eq!(summary(checker.push("x!")), vec![(4, 9, "quick".to_string()), (10, 14, "fox".to_string())]);
+        assert_eq!(summary(checker.push(" teh")), vec![]);
+        // a correction starting a sentence is capitalized
+        assert_eq!(summary(checker.finish()), vec![(16, 19, "The".to_string())]);
+
+        // finish starts a new stream
+        assert_eq!(summary(checker.push("zzzzzz.")), vec![(0, 6, String::new())]);
+    }
+
+    #[test]
+    fn join_and_context_test() {
+        let mut sym_spell: SymSpell = vec![("misspelled", 10), ("word", 100), ("a", 500), ("cat", 100), ("car", 200), ("black", 50)].into_iter().collect();
+        sym_spell.write_line_to_bigram_dictionary("black cat 40", " ").unwrap();
+        let mut checker = StreamChecker::new(&sym_spell, 2);
+
+        let mut misspelled = checker.push("a mis spelled word, a black cax. cax ");
+        misspelled.extend(checker.finish());
+        assert_eq!(summary(misspelled), vec![
+            (2, 13, "misspelled".to_string()),
+            (28, 31, "cat".to_string()),
+            // context does not carry past the end of a sentence
+            (33, 36, "Car".to_string()),
+        ]);
+    }
+}
@@ -0,0 +1,144 @@
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use crate::sym_spell::entry_metadata::EntryMetadata;
+use crate::sym_spell::suggested_item::{encode_with_term, write_json, SortKey, SuggestItem};
+use crate::sym_spell::{Count, Encode};
+
+/// A `SuggestItem` whose term borrows the copy of the word held by the dictionary
+/// instead of cloning it, returned by `SymSpell::lookup_borrowed` so that checking
+/// a document does not allocate a `String` per suggestion. Terms that are not
+/// dictionary words, such as the unknown input, or that a storage backend does not
+/// keep in memory are owned.
+///
+/// example:
+/// for suggestion in sym_spell.lookup_borrowed("helo", Verbosity::Closest, 2, false, false) {
+///     buffer.extend(suggestion.encode());
+/// }
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SuggestRef<'a> {
+    pub term: Cow<'a, str>,
+    pub distance: usize,
+    pub count: Count,
+    /// See `SuggestItem::score`.
+    pub score: f64,
+    /// See `SuggestItem::probability`.
+    pub probability: Option<f64>,
+    /// See `SuggestItem::metadata`.
+    pub metadata: Option<Arc<EntryMetadata>>,
+}
+
+impl<'a> SuggestRef<'a> {
+    pub fn new(term: Cow<'a, str>, distance: usize, count: Count) -> SuggestRef<'a> {
+        SuggestRef {
+            term,
+            distance,
+            count,
+            score: 0.0,
+            probability: None,
+            metadata: None,
+        }
+    }
+
+    /// The key lookups order their results by, see `SortKey`.
+    pub fn sort_key(&self) -> SortKey<'_> {
+        SortKey {
+            distance: self.distance,
+            score: self.score,
+            count: self.count,
+            term: &self.term,
+        }
+    }
+
+    /// The suggestion with its own copy of the term.
+    pub fn into_owned(self) -> SuggestItem {
+        SuggestItem {
+            term: self.term.into_owned(),
+            distance: self.distance,
+            count: self.count,
+            score: self.score,
+            probability: self.probability,
+            metadata: self.metadata,
+        }
+    }
+
+    /// Identical to `SuggestItem::write_json`.
+    pub fn write_json(&self, buffer: &mut String) {
+        write_json(buffer, &self.term, self.distance, self.count, self.probability, self.metadata.as_deref());
+    }
+
+    /// Identical to `SuggestItem::encode_utf16`.
+    pub fn encode_utf16(&self) -> Vec<u8> {
+        let term: Vec<u8> = self.term.encode_utf16().flat_map(u16::to_le_bytes).collect();
+        encode_with_term(self.count, self.distance, self.probability, &term)
+    }
+}
+
+impl<'a> From<SuggestItem> for SuggestRef<'a> {
+    fn from(si: SuggestItem) -> SuggestRef<'a> {
+        SuggestRef {
+            term: Cow::Owned(si.term),
+            distance: si.distance,
+            count: si.count,
+            score: si.score,
+            probability: si.probability,
+            metadata: si.metadata,
+        }
+    }
+}
+
+// the suggestions the encoders of the wasm exports and result frames take, owned or borrowed
+pub(crate) trait AsSuggestRef {
+    fn as_suggest_ref(&self) -> SuggestRef<'_>;
+}
+
+impl AsSuggestRef for SuggestItem {
+    fn as_suggest_ref(&self) -> SuggestRef<'_> {
+        SuggestRef {
+            term: Cow::Borrowed(&self.term),
+            distance: self.distance,
+            count: self.count,
+            score: self.score,
+            probability: self.probability,
+            metadata: self.metadata.clone(),
+        }
+    }
+}
+
+impl<'a> AsSuggestRef for SuggestRef<'a> {
+    fn as_suggest_ref(&self) -> SuggestRef<'_> {
+        SuggestRef { term: Cow::Borrowed(&self.term), metadata: self.metadata.clone(), ..*self }
+    }
+}
+
+/// Identical to the encoding of `SuggestItem`.
+impl<'a> Encode<Vec<u8>> for SuggestRef<'a> {
+    fn encode(&self) -> Vec<u8> {
+        encode_with_term(self.count, self.distance, self.probability, self.term.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod suggest_ref_tests {
+    use std::borrow::Cow;
+
+    use crate::sym_spell::suggest_ref::SuggestRef;
+    use crate::sym_spell::suggested_item::SuggestItem;
+    use crate::sym_spell::Encode;
+
+    #[test]
+    fn encode_test() {
+        let si = SuggestItem { probability: Some(0.25), ..SuggestItem::new("tést".into(), 1, 2) };
+        let borrowed = SuggestRef { probability: Some(0.25), ..SuggestRef::new(Cow::Borrowed("tést"), 1, 2) };
+        assert_eq!(borrowed.encode(), si.encode());
+        assert_eq!(borrowed.encode_utf16(), si.encode_utf16());
+        let (mut json, mut expected) = (String::new(), String::new());
+        borrowed.write_json(&mut json);
+        si.write_json(&mut expected);
+        assert_eq!(json, expected);
+
+        let owned = borrowed.into_owned();
+        assert_eq!((owned.term.as_str(), owned.distance, owned.count, owned.probability), ("tést", 1, 2, Some(0.25)));
+        assert_eq!(SuggestRef::from(owned.clone()).into_owned().term, owned.term);
+    }
+}
@@ -1,59 +1,177 @@
-use crate::sym_spell::Encode;
-use std::mem::transmute;
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::sync::Arc;
 
-#[derive(Clone)]
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::sym_spell::entry_metadata::EntryMetadata;
+use crate::sym_spell::{Count, Encode};
+use crate::utils::push_json_string;
+
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct SuggestItem {
     pub term: String,
     pub distance: usize,
-    pub count: usize,
+    pub count: Count,
+    /// Set by the re-ranking lookups, e.g. to the Jaro-Winkler similarity or
+    /// bigram context of the term, where higher is better. 0 otherwise.
+    pub score: f64,
+    /// The probability of the term, its count over the size of the corpus the counts
+    /// come from, discounted by the edit decay for every edit. Set by `SymSpell::lookup`
+    /// and `lookup_compound`, e.g. to only apply corrections above a threshold. None otherwise.
+    pub probability: Option<f64>,
+    /// The metadata of the dictionary entry of the term, see `SymSpell::set_entry_metadata`.
+    pub metadata: Option<Arc<EntryMetadata>>,
+}
+
+/// The order of suggestions returned by every lookup: by ascending distance,
+/// then descending score, then descending count and finally by term so that
+/// suggestions from separate lookups can be merged into a well defined order.
+/// Obtained from `SuggestItem::sort_key`.
+#[derive(Clone, Copy, Debug)]
+pub struct SortKey<'a> {
+    pub distance: usize,
+    pub score: f64,
+    pub count: Count,
+    pub term: &'a str,
+}
+
+impl Ord for SortKey<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.distance.cmp(&other.distance)
+            .then(other.score.total_cmp(&self.score))
+            .then(other.count.cmp(&self.count))
+            .then(self.term.cmp(other.term))
+    }
+}
+
+impl PartialOrd for SortKey<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
 }
 
+impl PartialEq for SortKey<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for SortKey<'_> {}
+
 #[allow(dead_code)]
 impl SuggestItem {
     /// <summary>Create a new instance of SuggestItem.</summary>
     /// <param name="term">The suggested word.</param>
     /// <param name="distance">Edit distance from search word.</param>
     /// <param name="count">Frequency of suggestion in dictionary.</param>
-    pub fn new(term: String, distance: usize, count: usize) -> SuggestItem {
+    pub fn new(term: String, distance: usize, count: Count) -> SuggestItem {
         SuggestItem {
             term,
             distance,
             count,
+            score: 0.0,
+            probability: None,
+            metadata: None,
         }
     }
 
-    pub fn default() -> SuggestItem {
-        SuggestItem {
-            term: String::new(),
-            distance: 0,
-            count: 0,
+    /// The key lookups order their results by, see `SortKey`.
+    ///
+    /// example:
+    /// suggestions.sort_by(|a, b| a.sort_key().cmp(&b.sort_key()));
+    pub fn sort_key(&self) -> SortKey<'_> {
+        SortKey {
+            distance: self.distance,
+            score: self.score,
+            count: self.count,
+            term: &self.term,
         }
     }
+
+    /// Appends the item to the buffer as a JSON object of the form
+    /// {"term":"...","distance":1,"count":2,"probability":0.001}, with a null
+    /// probability when there is none, followed by a "metadata" object when the
+    /// entry has metadata.
+    pub fn write_json(&self, buffer: &mut String) {
+        write_json(buffer, &self.term, self.distance, self.count, self.probability, self.metadata.as_deref());
+    }
+
+    /// Identical to `encode` except the term is written as UTF-16LE
+    /// and its length is given in bytes.
+    pub fn encode_utf16(&self) -> Vec<u8> {
+        let term: Vec<u8> = self.term.encode_utf16().flat_map(u16::to_le_bytes).collect();
+        encode_with_term(self.count, self.distance, self.probability, &term)
+    }
 }
 
 impl Encode<Vec<u8>> for SuggestItem {
     fn encode(&self) -> Vec<u8> {
-        unsafe {
-            let ct = transmute::<u32, [u8; 4]>(self.count as u32);
-            let dis = transmute::<u32, [u8; 4]>(self.distance as u32);
-            let len = transmute::<u32, [u8; 4]>(self.term.len() as u32);
-
-            let mut encoded = vec![];
-            encoded.extend_from_slice(&ct);
-            encoded.extend_from_slice(&dis);
-            encoded.extend_from_slice(&len);
-            encoded.extend_from_slice(self.term.as_bytes());
-
-            encoded
-        }
+        encode_with_term(self.count, self.distance, self.probability, self.term.as_bytes())
     }
 }
 
+// the JSON object of a suggestion, shared with `SuggestRef`
+pub(crate) fn write_json(buffer: &mut String, term: &str, distance: usize, count: Count, probability: Option<f64>, metadata: Option<&EntryMetadata>) {
+    buffer.push_str("{\"term\":");
+    push_json_string(buffer, term);
+    buffer.push_str(&format!(",\"distance\":{},\"count\":{},\"probability\":", distance, count));
+    match probability {
+        Some(probability) if probability.is_finite() => buffer.push_str(&probability.to_string()),
+        _ => buffer.push_str("null"),
+    }
+    if let Some(metadata) = metadata {
+        buffer.push_str(",\"metadata\":");
+        metadata.write_json(buffer);
+    }
+    buffer.push('}');
+}
+
+// the binary encoding of a suggestion with its term already encoded, shared with `SuggestRef`
+pub(crate) fn encode_with_term(count: Count, distance: usize, probability: Option<f64>, term: &[u8]) -> Vec<u8> {
+    // the encoding has room for a u32 count, wider counts saturate
+    #[allow(clippy::useless_conversion)]
+    let ct = u32::try_from(count).unwrap_or(u32::MAX).to_le_bytes();
+    let dis = (distance as u32).to_le_bytes();
+    // NaN when there is no probability
+    let probability = probability.map_or(f32::NAN, |probability| probability as f32).to_le_bytes();
+    let len = (term.len() as u32).to_le_bytes();
+
+    // the probability follows the term so decoders of the layout without it still find the term
+    let mut encoded = vec![];
+    encoded.extend_from_slice(&ct);
+    encoded.extend_from_slice(&dis);
+    encoded.extend_from_slice(&len);
+    encoded.extend_from_slice(term);
+    encoded.extend_from_slice(&probability);
+
+    encoded
+}
+
 #[cfg(test)]
 mod suggest_item_tests {
+    use crate::sym_spell::entry_metadata::EntryMetadata;
     use crate::sym_spell::suggested_item::SuggestItem;
     use crate::sym_spell::Encode;
     use std::str;
+    use std::sync::Arc;
+
+    #[test]
+    fn sort_key_test() {
+        let mut items = [
+            SuggestItem::new("b".into(), 1, 5),
+            SuggestItem::new("a".into(), 1, 5),
+            SuggestItem::new("c".into(), 1, 9),
+            SuggestItem::new("d".into(), 0, 1),
+            SuggestItem::new("e".into(), 2, 100),
+            SuggestItem { score: 0.5, ..SuggestItem::new("f".into(), 1, 1) },
+        ];
+        items.sort_by(|a, b| a.sort_key().cmp(&b.sort_key()));
+        let terms: Vec<&str> = items.iter().map(|si| si.term.as_str()).collect();
+        assert_eq!(terms, vec!["d", "f", "c", "a", "b", "e"]);
+    }
 
     #[test]
     fn encode_test() {
@@ -62,7 +180,50 @@ mod suggest_item_tests {
         assert_eq!(encoded[0], 2); // count
         assert_eq!(encoded[4], 1); // distance
         assert_eq!(encoded[8], 4); // term.len()
-        let term = unsafe { str::from_utf8_unchecked(&encoded[9..])};
-        assert_eq!(term, "test")
+        let term = unsafe { str::from_utf8_unchecked(&encoded[12..16])};
+        assert_eq!(term, "test");
+        assert!(f32::from_le_bytes([encoded[16], encoded[17], encoded[18], encoded[19]]).is_nan()); // probability
+        assert_eq!(encoded.len(), 20);
+
+        let si = SuggestItem { probability: Some(0.25), ..si };
+        assert_eq!(si.encode()[16..20], 0.25f32.to_le_bytes());
+    }
+
+    #[test]
+    fn encode_utf16_test() {
+        let si = SuggestItem::new("tést".into(), 1, 2);
+        let encoded = si.encode_utf16();
+        assert_eq!(encoded[..12], si.encode()[..8].iter().chain(&8u32.to_le_bytes()).cloned().collect::<Vec<u8>>()[..]);
+        let term: Vec<u16> = encoded[12..20].chunks(2).map(|c| u16::from_le_bytes([c[0], c[1]])).collect();
+        assert_eq!(String::from_utf16(&term).unwrap(), "tést");
+    }
+
+    #[test]
+    fn write_json_test() {
+        let mut json = String::new();
+        SuggestItem::new("te\"st".into(), 1, 2).write_json(&mut json);
+        assert_eq!(json, r#"{"term":"te\"st","distance":1,"count":2,"probability":null}"#);
+
+        json.clear();
+        SuggestItem { probability: Some(0.25), ..SuggestItem::new("test".into(), 1, 2) }.write_json(&mut json);
+        assert_eq!(json, r#"{"term":"test","distance":1,"count":2,"probability":0.25}"#);
+
+        json.clear();
+        let metadata = EntryMetadata { language: Some("en".into()), canonical: Some("iPhone".into()), ..EntryMetadata::default() };
+        SuggestItem { metadata: Some(Arc::new(metadata)), ..SuggestItem::new("iphone".into(), 0, 2) }.write_json(&mut json);
+        assert_eq!(json, r#"{"term":"iphone","distance":0,"count":2,"probability":null,"metadata":{"language":"en","canonical":"iPhone"}}"#);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_test() {
+        let metadata = EntryMetadata { source: Some("brands".into()), ..EntryMetadata::default() };
+        let si = SuggestItem { probability: Some(0.25), metadata: Some(Arc::new(metadata)), ..SuggestItem::new("test".into(), 1, 2) };
+        let json = serde_json::to_string(&si).unwrap();
+        assert_eq!(json, r#"{"term":"test","distance":1,"count":2,"score":0.0,"probability":0.25,"metadata":{"language":null,"source":"brands","canonical":null}}"#);
+
+        let deserialized: SuggestItem = serde_json::from_str(&json).unwrap();
+        assert_eq!((deserialized.term, deserialized.probability), (si.term, si.probability));
+        assert_eq!(deserialized.metadata, si.metadata);
     }
 }
\ No newline at end of file
@@ -0,0 +1,36 @@
+use crate::sym_spell::lookup_context::LookupContext;
+use crate::sym_spell::suggested_item::SuggestItem;
+
+/// Orders the suggestions of `SymSpell::lookup` and `lookup_with_context`, and so
+/// the corrections `lookup_compound` picks, e.g. to weigh phonetic similarity or
+/// a product's own vocabulary differently from distance and frequency. Suggestions
+/// are ordered by descending score, ties by their `SuggestItem::sort_key`. The ranker
+/// orders the suggestions of the closest distance, or of every distance for
+/// `Verbosity::All`, and `Verbosity::Top` keeps the first of them.
+///
+/// example:
+/// // prefers short words over frequent ones
+/// struct Shortest;
+///
+/// impl SuggestionRanker for Shortest {
+///     fn score(&self, suggestion: &SuggestItem, _: &LookupContext) -> f64 {
+///         -(suggestion.term.len() as f64)
+///     }
+/// }
+///
+/// sym_spell.set_suggestion_ranker(Some(Box::new(Shortest)));
+pub trait SuggestionRanker: Send + Sync {
+    /// The score of the suggestion, higher ranking first.
+    fn score(&self, suggestion: &SuggestItem, context: &LookupContext) -> f64;
+}
+
+/// The order of every lookup without a ranker: by ascending distance, then by the
+/// rest of the `SortKey`, i.e. descending score and count.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DefaultRanker;
+
+impl SuggestionRanker for DefaultRanker {
+    fn score(&self, suggestion: &SuggestItem, _: &LookupContext) -> f64 {
+        -(suggestion.distance as f64)
+    }
+}
@@ -29,63 +29,396 @@
 // The above copyright notice and this permission notice shall be included in all copies or substantial portions of the Software.
 // https://opensource.org/licenses/MIT
 
+use std::borrow::Cow;
+use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
-use std::collections::hash_map::DefaultHasher;
-use std::hash::{Hash, Hasher};
+use std::hash::{BuildHasher, Hasher};
+use std::iter::FromIterator;
+use std::mem;
+use std::num::IntErrorKind;
+use std::ops::Range;
 use std::str;
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
 use crate::edit_distance::{DistanceAlgorithm, EditDistance};
+use crate::filters::TextFilters;
 use crate::grapheme_iterator::GraphemeClusters;
+use crate::keyboard::KeyboardLayout;
+use crate::markup::Markup;
+use crate::small_string::SmallString;
+use crate::soft_wx::Similarity;
+use crate::soft_wx::helpers::{distance, similarity, PreparedQuery};
+use crate::soft_wx::jaro_winkler::JaroWinkler;
+use crate::soft_wx::weighted_levenshtein::{WeightedCosts, WeightedLevenshtein};
+use crate::sym_spell::bigram_line_error::BigramLineError;
+use crate::sym_spell::backend::Backend;
+use crate::sym_spell::bloom_filter::BloomFilter;
+use crate::sym_spell::capabilities::Capabilities;
+use crate::sym_spell::compound_correction::CompoundCorrection;
+use crate::sym_spell::compound_options::CompoundOptions;
+use crate::sym_spell::compound_part::CompoundPart;
+use crate::sym_spell::count_overflow_policy::CountOverflowPolicy;
+#[cfg(feature = "dawg")]
+use crate::sym_spell::dawg::Dawg;
+use crate::sym_spell::delete_generation::DeleteGeneration;
+use crate::sym_spell::delete_hasher::Fnv1aState;
+use crate::sym_spell::document_stats::DocumentStats;
+use crate::sym_spell::early_termination::EarlyTermination;
+use crate::sym_spell::entry_metadata::EntryMetadata;
+use crate::sym_spell::equivalences::Equivalences;
+#[cfg(feature = "fst-dictionary")]
+use crate::sym_spell::fst_dictionary::FstDictionary;
+use crate::sym_spell::index_storage::{HashMapStorage, IndexStorage, IndexedWord, WordId};
+use crate::sym_spell::invalid_count_policy::InvalidCountPolicy;
+use crate::sym_spell::issue_kind::IssueKind;
+use crate::sym_spell::load_progress::LoadProgress;
+use crate::sym_spell::load_report::LoadReport;
+use crate::sym_spell::log_event::LogEvent;
+use crate::sym_spell::logger::Logger;
+use crate::sym_spell::lookup_cache_stats::LookupCacheStats;
+use crate::sym_spell::lookup_context::LookupContext;
+use crate::sym_spell::lookup_limits::LookupLimits;
+use crate::sym_spell::lookup_options::LookupOptions;
+use crate::sym_spell::lookup_scratch::LookupScratch;
+use crate::sym_spell::lru_cache::LruCache;
+#[cfg(feature = "metrics")]
+use crate::sym_spell::metrics::{self, Metrics, MetricsRecorder};
+use crate::sym_spell::misspelled_range::MisspelledRange;
+#[cfg(feature = "unicode-normalization")]
+use crate::sym_spell::normalization::Normalization;
+use crate::sym_spell::part_status::PartStatus;
+use crate::sym_spell::query_suggestion::QuerySuggestion;
+use crate::sym_spell::seeded_state::SeededState;
+use crate::sym_spell::spellchecker_error::SpellcheckerError;
+use crate::sym_spell::suggest_ref::SuggestRef;
 use crate::sym_spell::suggested_item::SuggestItem;
+use crate::sym_spell::suggestion_ranker::{DefaultRanker, SuggestionRanker};
+use crate::sym_spell::trigram_line_error::TrigramLineError;
 use crate::sym_spell::verbosity::Verbosity;
-use crate::utils::is_alpha_numeric;
+use crate::sym_spell::word_segmenter::WordSegmenter;
+use crate::sym_spell::Count;
+use crate::tokenizer::{Token, TokenClass, Tokenizer, TokenizerOptions};
+use crate::utils::{capitalize_in, ends_sentence, is_all_uppercase, lowercase_graphemes_in, starts_with_uppercase, transfer_casing_in, uppercase_in, CaseLocale};
 
 const DEFAULT_MAX_EDIT_DISTANCE: usize = 2;
 const DEFAULT_PREFIX_LENGTH: usize = 7;
-const DEFAULT_COUNT_THRESHOLD: usize = 1;
-const N: f64 = 1024908267229.0;
+const DEFAULT_COUNT_THRESHOLD: Count = 1;
+/// The number of words of the Google Books corpus the counts of the bundled English
+/// dictionaries come from. As those dictionaries hold only part of its words, their
+/// counts add up to less, see `SymSpell::set_corpus_size`.
+pub const GOOGLE_BOOKS_CORPUS_SIZE: f64 = 1024908267229.0;
+const DEFAULT_BIGRAM_WEIGHT: f64 = 1.0;
+const DEFAULT_EDIT_DECAY: f64 = 0.1;
+const DEFAULT_KEYBOARD_ADJACENT_COST: f64 = 0.5;
+// shorter prefixes are within one edit of too many words to complete fuzzily
+const MIN_FUZZY_PREFIX_LEN: usize = 3;
+// the factor a word's own probability is discounted by when the pair it ends is not a known bigram
+const BIGRAM_BACKOFF: f64 = 0.4;
+// deletes up to this many bytes are built on the stack
+const DELETE_INLINE_CAPACITY: usize = 24;
+
+// the arguments of a cached lookup: input, verbosity, max edit distance, include unknown and include self
+type LookupKey = (String, u8, usize, bool, bool);
+
+thread_local! {
+    // the buffers `lookup` works in, kept so they only grow on the first lookups
+    static LOOKUP_SCRATCH: RefCell<LookupScratch> = RefCell::new(LookupScratch::default());
+}
 
-pub struct SymSpell {
+pub struct SymSpell<S = Fnv1aState> {
     dictionary_edit_distance: usize,
     prefix_length: usize,
     //prefix length  5..7
-    count_threshold: usize,
+    count_threshold: Count,
     // maximum dictionary term length
     max_dictionary_word_length: usize,
     // Dictionary that contains a mapping of lists of suggested correction words to the hashCodes
-    // of the original words and the deletes derived from them. Collisions of hashCodes is tolerated,
-    // because suggestions are ultimately verified via an edit distance function.
-    // A list of suggestions might have a single suggestion, or multiple suggestions.
-    deletes: HashMap<u64, Vec<String>>,
-    // Dictionary of unique correct spelling words, and the frequency count for each word.
-    words: HashMap<String, usize>,
+    // of the original words and the deletes derived from them, and the dictionary of unique
+    // correct spelling words with the frequency count for each word.
+    storage: Box<dyn IndexStorage>,
     // Dictionary of unique words that are below the count threshold for being considered correct spellings.
-    below_threshold_words: HashMap<String, usize>,
-    bigrams: HashMap<String, usize>,
-    bigram_count_min: usize,
+    below_threshold_words: HashMap<String, Count>,
+    bigrams: HashMap<String, Count>,
+    // the smallest count of the bigrams loaded, which those learned with learn_ngrams leave alone
+    bigram_count_min: Option<Count>,
+    // the three space separated words of a trigram to its count
+    trigrams: HashMap<String, Count>,
+    // the metadata attached to the suggestions of a word
+    entry_metadata: HashMap<String, Arc<EntryMetadata>>,
+    count_overflow_policy: CountOverflowPolicy,
+    invalid_count_policy: InvalidCountPolicy,
+    load_report: LoadReport,
+    seeded_state: SeededState,
+    tokenizer_options: TokenizerOptions,
+    // splits the text of lookup_compound, check_text and the like, the options unless a custom one is set
+    tokenizer: Box<dyn Tokenizer>,
+    text_filters: TextFilters,
+    markup: Markup,
+    // the words accepted for the session without being in the dictionary, lowercased
+    // with the case locale, to the word as given so they can be lowercased again
+    ignored_words: HashMap<String, String>,
+    // the part of the count of each word added by learn_word, which decay_counts reduces
+    learned_counts: HashMap<String, Count>,
+    // learned words whose count decayed below the count threshold, kept in the index but neither suggested nor known
+    faded_words: HashSet<String>,
+    // orders the suggestions of a lookup, by their sort key when None
+    suggestion_ranker: Option<Box<dyn SuggestionRanker>>,
+    logger: Option<Box<dyn Logger>>,
+    // the factor the probability of a suggestion is multiplied by for every edit
+    edit_decay: f64,
+    // the cost of substituting a key with a neighbouring one when suggestions are ranked by keyboard layout
+    keyboard_adjacent_cost: f64,
+    // the edit costs of each keyboard layout, built by the first lookup ranked by it
+    keyboard_costs: [OnceLock<Arc<WeightedCosts>>; 3],
+    // N of the word probability count / N when set, rather than the sum of the counts
+    corpus_size: Option<f64>,
+    // the sum of the counts, computed when first needed
+    count_sum: OnceLock<f64>,
+    // how much a bigram probability counts against the probabilities of its words
+    bigram_weight: f64,
+    distance_algorithm: DistanceAlgorithm,
+    join_line_wraps: bool,
+    case_locale: CaseLocale,
+    equivalences: Equivalences,
+    // the folded form of every dictionary word containing a variant, to the words
+    equivalent_words: HashMap<String, Vec<String>>,
+    #[cfg(feature = "unicode-normalization")]
+    normalization: Normalization,
+    // hashes the deletes to their buckets
+    delete_hasher: S,
+    delete_generation: DeleteGeneration,
+    // the words by the length of their prefix, scanned for the buckets generated on demand
+    words_by_prefix_len: Vec<Vec<IndexedWord>>,
+    // the dictionary words in byte order, built by the first completion
+    completion_index: OnceLock<Vec<String>>,
+    backend: Backend,
+    // the graph of the dictionary words searched by Backend::Dawg, built by the first lookup
+    #[cfg(feature = "dawg")]
+    dawg: OnceLock<Dawg>,
+    // the false positive rate of the bloom filter of the dictionary words when one is used
+    bloom_filter_rate: Option<f64>,
+    // the bloom filter, built by the first word checked
+    bloom_filter: OnceLock<BloomFilter>,
+    bucket_cache: Mutex<LruCache<String, Vec<IndexedWord>>>,
+    lookup_cache: Mutex<LruCache<LookupKey, Vec<SuggestItem>>>,
+    lookup_cache_capacity: usize,
+    lookup_limits: LookupLimits,
+    // lookups given up on for exceeding the limits
+    truncated_lookups: AtomicUsize,
+    #[cfg(feature = "metrics")]
+    metrics: MetricsRecorder,
 }
 
 impl SymSpell {
+    /// # Panics
+    ///
+    /// When the prefix length is 0 or not greater than the dictionary edit distance,
+    /// see `try_new`.
     pub fn new(dictionary_edit_distance: Option<usize>,
                prefix_length: Option<usize>,
-               count_threshold: Option<usize>) -> SymSpell {
+               count_threshold: Option<Count>) -> SymSpell {
+        SymSpell::with_hasher(dictionary_edit_distance, prefix_length, count_threshold, Fnv1aState)
+    }
+
+    /// Identical to `new` returning `SpellcheckerError::InvalidPrefixLength` for settings
+    /// it would panic on, e.g. when they come from a user.
+    ///
+    /// example:
+    /// assert!(SymSpell::try_new(Some(2), Some(7), None).is_ok());
+    /// assert!(SymSpell::try_new(Some(2), Some(2), None).is_err());
+    pub fn try_new(dictionary_edit_distance: Option<usize>,
+                   prefix_length: Option<usize>,
+                   count_threshold: Option<Count>) -> Result<SymSpell, SpellcheckerError> {
+        SymSpell::try_with_hasher(dictionary_edit_distance, prefix_length, count_threshold, Fnv1aState)
+    }
+
+    /// Identical to `new` finding the words within the edit distance of an input
+    /// with the backend, see `Backend`. It cannot be changed once words are loaded.
+    ///
+    /// # Panics
+    ///
+    /// When the prefix length is 0 or not greater than the dictionary edit distance,
+    /// see `try_with_backend`.
+    pub fn with_backend(dictionary_edit_distance: Option<usize>,
+                        prefix_length: Option<usize>,
+                        count_threshold: Option<Count>,
+                        backend: Backend) -> SymSpell {
+        SymSpell::try_with_backend(dictionary_edit_distance, prefix_length, count_threshold, backend)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Identical to `with_backend` returning `SpellcheckerError::InvalidPrefixLength` for
+    /// settings it would panic on.
+    ///
+    /// example:
+    /// let sym_spell = SymSpell::try_with_backend(Some(2), Some(7), None, Backend::Dawg)?;
+    pub fn try_with_backend(dictionary_edit_distance: Option<usize>,
+                            prefix_length: Option<usize>,
+                            count_threshold: Option<Count>,
+                            backend: Backend) -> Result<SymSpell, SpellcheckerError> {
+        let mut sym_spell = SymSpell::try_new(dictionary_edit_distance, prefix_length, count_threshold)?;
+        sym_spell.backend = backend;
+        Ok(sym_spell)
+    }
+
+    /// Creates a SymSpell instance with the default settings from
+    /// word/frequency count pairs rather than the line based format.
+    pub fn from_words(iter: impl IntoIterator<Item=(String, Count)>) -> SymSpell {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("dictionary_load").entered();
+        let mut sym_spell = SymSpell::new(None, None, None);
+        for (key, count) in iter {
+            sym_spell.create_dictionary_entry(key, count);
+        }
+        sym_spell.log(&LogEvent::DictionaryLoad { words: sym_spell.word_count() });
+        sym_spell
+    }
+
+    /// The optional features compiled into this build.
+    ///
+    /// example:
+    /// if SymSpell::capabilities().parallel {
+    ///     sym_spell.lookup_compound_parallel("whereis th elove", 2, CompoundOptions::default());
+    /// }
+    pub fn capabilities() -> Capabilities {
+        Capabilities::compiled()
+    }
+
+    /// The approximate bytes the deletes index of `word_count` words takes with the
+    /// dictionary edit distance and prefix length, to check that a configuration fits
+    /// before loading a dictionary. Every word is taken to be at least `prefix_len`
+    /// graphemes, so dictionaries of short words take less. Each edit multiplies the
+    /// deletes per word: a prefix length of 7 gives 29 deletes per word at an edit
+    /// distance of 2 and 64 at 3, though more of them are shared between words. The
+    /// 82,765 words of the bundled English dictionary take about 70 MB at 2 and
+    /// 100 MB at 3.
+    ///
+    /// example:
+    /// let bytes = SymSpell::estimate_memory(3, 7, 82_765);
+    /// if bytes > budget {
+    ///     sym_spell = SymSpell::new(Some(3), Some(7), None);
+    ///     sym_spell.set_delete_generation(DeleteGeneration::OnDemand { precomputed_edits: 2, cache_capacity: 1024 });
+    /// }
+    pub fn estimate_memory(edit_distance: usize, prefix_len: usize, word_count: usize) -> usize {
+        // the prefix and its deletes of up to edit_distance graphemes, down to a single grapheme
+        let deletes_per_word = (0..=edit_distance.min(prefix_len.saturating_sub(1)))
+            .map(|edits| binomial(prefix_len, edits))
+            .fold(0usize, usize::saturating_add);
+        let entries = word_count.saturating_mul(deletes_per_word);
+        // the shorter a delete the more words share it, each edit about halves the share of distinct deletes
+        let deletes = entries.checked_shr(edit_distance as u32).unwrap_or(0).max(word_count.min(entries));
+        HashMapStorage::estimated_size(word_count, prefix_len, deletes, entries)
+    }
+
+    /// Parses a str into the words that comprise it while omitting
+    /// non alphanumeric chars
+    pub fn parse_words(text: &str) -> Vec<&str> {
+        SymSpell::parse_words_with_options(text, TokenizerOptions::default())
+    }
+
+    /// Identical to `parse_words` using the supplied word character options.
+    pub fn parse_words_with_options(text: &str, options: TokenizerOptions) -> Vec<&str> {
+        SymSpell::parse_words_with_tokenizer(text, &options)
+    }
+
+    /// Identical to `parse_words` using the supplied tokenizer to tell words apart.
+    pub fn parse_words_with_tokenizer<'a>(text: &'a str, tokenizer: &dyn Tokenizer) -> Vec<&'a str> {
+        tokenizer.tokenize(text).into_iter()
+            .filter(|token| token.class == TokenClass::Word)
+            .map(|token| token.text)
+            .collect()
+    }
+}
+
+impl<S: BuildHasher> SymSpell<S> {
+    /// Identical to `new` using the supplied hasher for the deletes map instead of
+    /// FNV-1a. The hash of a delete decides its bucket in the storage, so an index
+    /// is only portable between instances using the same hasher.
+    ///
+    /// # Panics
+    ///
+    /// When the prefix length is 0 or not greater than the dictionary edit distance,
+    /// see `try_with_hasher`.
+    pub fn with_hasher(dictionary_edit_distance: Option<usize>,
+                       prefix_length: Option<usize>,
+                       count_threshold: Option<Count>,
+                       delete_hasher: S) -> SymSpell<S> {
+        SymSpell::try_with_hasher(dictionary_edit_distance, prefix_length, count_threshold, delete_hasher)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Identical to `with_hasher` returning `SpellcheckerError::InvalidPrefixLength` for
+    /// settings it would panic on.
+    pub fn try_with_hasher(dictionary_edit_distance: Option<usize>,
+                           prefix_length: Option<usize>,
+                           count_threshold: Option<Count>,
+                           delete_hasher: S) -> Result<SymSpell<S>, SpellcheckerError> {
         let max_dict_edit_dist = dictionary_edit_distance.unwrap_or(DEFAULT_MAX_EDIT_DISTANCE);
         let prefix_len = prefix_length.unwrap_or(DEFAULT_PREFIX_LENGTH);
         let ct_threshold = count_threshold.unwrap_or(DEFAULT_COUNT_THRESHOLD);
 
-        assert!(prefix_len >= 1 || prefix_len <= max_dict_edit_dist);
+        if prefix_len < 1 || prefix_len <= max_dict_edit_dist {
+            return Err(SpellcheckerError::InvalidPrefixLength { prefix_length: prefix_len, max_dictionary_edit_distance: max_dict_edit_dist });
+        }
 
-        SymSpell {
+        Ok(SymSpell {
             dictionary_edit_distance: max_dict_edit_dist,
             prefix_length: prefix_len,
             count_threshold: ct_threshold,
             max_dictionary_word_length: 0,
-            deletes: HashMap::new(),
-            words: HashMap::new(),
+            storage: Box::new(HashMapStorage::default()),
             below_threshold_words: HashMap::new(),
             bigrams: HashMap::new(),
-            bigram_count_min: usize::max_value(),
-        }
+            bigram_count_min: None,
+            trigrams: HashMap::new(),
+            entry_metadata: HashMap::new(),
+            count_overflow_policy: CountOverflowPolicy::default(),
+            invalid_count_policy: InvalidCountPolicy::default(),
+            load_report: LoadReport::default(),
+            seeded_state: SeededState::default(),
+            tokenizer_options: TokenizerOptions::default(),
+            tokenizer: Box::new(TokenizerOptions::default()),
+            text_filters: TextFilters::common(),
+            markup: Markup::default(),
+            ignored_words: HashMap::new(),
+            learned_counts: HashMap::new(),
+            faded_words: HashSet::new(),
+            suggestion_ranker: None,
+            logger: None,
+            edit_decay: DEFAULT_EDIT_DECAY,
+            keyboard_adjacent_cost: DEFAULT_KEYBOARD_ADJACENT_COST,
+            keyboard_costs: Default::default(),
+            distance_algorithm: DistanceAlgorithm::DamaerauOSA,
+            join_line_wraps: false,
+            case_locale: CaseLocale::default(),
+            equivalences: Equivalences::default(),
+            equivalent_words: HashMap::new(),
+            #[cfg(feature = "unicode-normalization")]
+            normalization: Normalization::default(),
+            delete_hasher,
+            delete_generation: DeleteGeneration::default(),
+            words_by_prefix_len: Vec::new(),
+            completion_index: OnceLock::new(),
+            backend: Backend::default(),
+            #[cfg(feature = "dawg")]
+            dawg: OnceLock::new(),
+            bloom_filter_rate: None,
+            bloom_filter: OnceLock::new(),
+            corpus_size: None,
+            count_sum: OnceLock::new(),
+            bigram_weight: DEFAULT_BIGRAM_WEIGHT,
+            bucket_cache: Mutex::new(LruCache::default()),
+            lookup_cache: Mutex::new(LruCache::default()),
+            lookup_cache_capacity: 0,
+            lookup_limits: LookupLimits::default(),
+            truncated_lookups: AtomicUsize::new(0),
+            #[cfg(feature = "metrics")]
+            metrics: MetricsRecorder::default(),
+        })
     }
 
     pub fn max_edit_distance(&self) -> usize {
@@ -98,21 +431,622 @@ impl SymSpell {
 
     pub fn max_length(&self) -> usize { self.max_dictionary_word_length }
 
-    pub fn count_threshold(&self) -> usize {
+    pub fn count_threshold(&self) -> Count {
         self.count_threshold
     }
 
-    pub fn word_count(&self) -> usize { self.words.len() }
+    pub fn word_count(&self) -> usize { self.storage.len() }
+
+    pub fn entry_count(&self) -> usize { self.storage.delete_count() }
+
+    /// The bytes the words and deletes take, None when the storage cannot tell, see
+    /// `IndexStorage::allocated_size`. Compare with `estimate_memory`.
+    pub fn allocated_size(&self) -> Option<usize> { self.storage.allocated_size() }
+
+    pub fn bigram_count(&self) -> usize { self.bigrams.len() }
+
+    /// The smallest count in the loaded bigram dictionary, None when no bigrams are
+    /// loaded. Bigrams learned with `learn_ngrams` leave it as it is, so that pairs
+    /// learned once do not lower the estimate `lookup_compound` makes for unknown pairs.
+    pub fn bigram_count_min(&self) -> Option<Count> {
+        self.bigram_count_min
+    }
+
+    pub fn trigram_count(&self) -> usize { self.trigrams.len() }
+
+    /// The words of the dictionary and their counts as an `FstDictionary`, e.g. to
+    /// precompile a dictionary loaded from text into the much smaller transducer.
+    #[cfg(feature = "fst-dictionary")]
+    pub fn to_fst_dictionary(&self) -> FstDictionary {
+        let mut words = Vec::with_capacity(self.storage.len());
+        self.storage.for_each_word(&mut |word, count| words.push((word.to_string(), count)));
+        FstDictionary::from_words(words, self.dictionary_edit_distance)
+    }
+
+    /// Removes every word, delete, bigram, trigram and entry metadata while keeping the settings
+    /// the instance was created with.
+    pub fn clear(&mut self) {
+        self.max_dictionary_word_length = 0;
+        self.storage.clear();
+        self.below_threshold_words = HashMap::new();
+        self.bigrams = HashMap::new();
+        self.bigram_count_min = None;
+        self.trigrams = HashMap::new();
+        self.entry_metadata = HashMap::new();
+        self.load_report = LoadReport::default();
+        self.learned_counts = HashMap::new();
+        self.faded_words = HashSet::new();
+        self.equivalent_words = HashMap::new();
+        self.words_by_prefix_len = Vec::new();
+        self.completion_index.take();
+        self.bloom_filter.take();
+        #[cfg(feature = "dawg")]
+        self.dawg.take();
+        self.count_sum.take();
+        self.bucket_cache = Mutex::new(LruCache::new(self.bucket_cache_capacity()));
+        self.invalidate_lookup_cache();
+        self.truncated_lookups = AtomicUsize::new(0);
+        #[cfg(feature = "metrics")]
+        self.metrics.reset();
+    }
+
+    pub fn count_overflow_policy(&self) -> CountOverflowPolicy {
+        self.count_overflow_policy
+    }
+
+    pub fn set_count_overflow_policy(&mut self, policy: CountOverflowPolicy) {
+        self.count_overflow_policy = policy;
+    }
+
+    pub fn invalid_count_policy(&self) -> InvalidCountPolicy {
+        self.invalid_count_policy
+    }
+
+    pub fn set_invalid_count_policy(&mut self, policy: InvalidCountPolicy) {
+        self.invalid_count_policy = policy;
+    }
+
+    pub fn distance_algorithm(&self) -> &DistanceAlgorithm {
+        &self.distance_algorithm
+    }
+
+    /// Sets the algorithm used to verify the edit distance of candidates in `lookup`
+    /// and `lookup_compound` (default `DamaerauOSA`). `Levenshtein` counts transposed
+    /// characters as two edits, which suits OCR errors better than typing errors.
+    pub fn set_distance_algorithm(&mut self, algorithm: DistanceAlgorithm) {
+        self.distance_algorithm = algorithm;
+        self.invalidate_lookup_cache();
+    }
+
+    pub fn join_line_wraps(&self) -> bool {
+        self.join_line_wraps
+    }
+
+    /// When set, `check_text` and `document_stats` treat a word hyphenated across a
+    /// line break, as in OCR or PDF text ("correc-\ntion"), as the single word
+    /// "correction" provided that is in the dictionary.
+    pub fn set_join_line_wraps(&mut self, join_line_wraps: bool) {
+        self.join_line_wraps = join_line_wraps;
+    }
+
+    pub fn case_locale(&self) -> CaseLocale {
+        self.case_locale
+    }
+
+    /// Sets the casing rules used wherever words are lowercased to find them in
+    /// the dictionary, e.g. `CaseLocale::Turkic` so "KIŞ" is checked as "kış".
+    /// Dictionary words keep their case, so load them lowercased in the same locale.
+    pub fn set_case_locale(&mut self, case_locale: CaseLocale) {
+        self.case_locale = case_locale;
+        // the ignored words are matched lowercased with the new rules
+        let ignored_words = mem::take(&mut self.ignored_words);
+        self.ignored_words = ignored_words.into_values().map(|word| (self.lowercase(&word).into_owned(), word)).collect();
+        self.invalidate_lookup_cache();
+    }
+
+    // the word lowercased with the configured casing rules
+    pub(crate) fn lowercase<'a>(&self, word: &'a str) -> Cow<'a, str> {
+        lowercase_graphemes_in(word, self.case_locale)
+    }
+
+    pub fn equivalences(&self) -> &Equivalences {
+        &self.equivalences
+    }
+
+    /// Sets the spellings `lookup` treats as interchangeable at no edit cost, e.g.
+    /// `Equivalences::common()` so "strasse" finds "straße" at distance 0.
+    /// Words already in the dictionary are indexed again.
+    pub fn set_equivalences(&mut self, equivalences: Equivalences) {
+        self.equivalences = equivalences;
+        self.index_equivalent_words();
+        self.invalidate_lookup_cache();
+    }
+
+    // indexes every dictionary word by its folded form
+    fn index_equivalent_words(&mut self) {
+        let mut equivalent_words: HashMap<String, Vec<String>> = HashMap::new();
+        if !self.equivalences.is_empty() {
+            let equivalences = &self.equivalences;
+            self.storage.for_each_word(&mut |word, _| {
+                if let Cow::Owned(folded) = equivalences.fold(word) {
+                    equivalent_words.entry(folded).or_default().push(word.to_string());
+                }
+            });
+        }
+        self.equivalent_words = equivalent_words;
+    }
+
+    // the dictionary words other than the input that fold to the same form
+    fn equivalents(&self, input: &str) -> Vec<SuggestItem> {
+        if self.equivalences.is_empty() {
+            return vec![];
+        }
+        let folded = self.equivalences.fold(input);
+        let words = self.equivalent_words.get(folded.as_ref()).into_iter().flatten().map(String::as_str);
+        // the input may itself hold a variant of a word spelled the canonical way
+        words.chain(Some(folded.as_ref()).filter(|folded| *folded != input))
+            .filter(|word| *word != input && !self.is_faded(word))
+            .filter_map(|word| self.storage.word_count(word).map(|count| SuggestItem::new(word.to_string(), 0, count)))
+            .collect()
+    }
+
+    #[cfg(feature = "unicode-normalization")]
+    pub fn normalization(&self) -> Normalization {
+        self.normalization
+    }
+
+    /// Sets the normalization form dictionary words, bigrams and the input of
+    /// `lookup`, `lookup_compound` and `word_segmentation` are brought to, so
+    /// text mixing composed and decomposed accents still matches. Set it before
+    /// loading any entries.
+    #[cfg(feature = "unicode-normalization")]
+    pub fn set_normalization(&mut self, normalization: Normalization) {
+        self.normalization = normalization;
+        self.invalidate_lookup_cache();
+    }
+
+    // the text in the configured normalization form
+    #[cfg(feature = "unicode-normalization")]
+    pub(crate) fn normalize<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        self.normalization.normalize(text)
+    }
+
+    #[cfg(not(feature = "unicode-normalization"))]
+    pub(crate) fn normalize<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        Cow::Borrowed(text)
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seeded_state.seed()
+    }
+
+    /// Seeds every order dependent choice made by this instance, such as the
+    /// order suggestions sharing a delete are stored in (which decides ties),
+    /// so results are reproducible across runs. Set it before loading any entries.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seeded_state = SeededState::new(seed);
+        self.invalidate_lookup_cache();
+    }
+
+    pub fn tokenizer_options(&self) -> TokenizerOptions {
+        self.tokenizer_options
+    }
+
+    /// Sets which characters count as part of a word when splitting
+    /// the input of `lookup_compound` and `word_segmentation`. Replaces
+    /// any tokenizer set with `set_tokenizer` by the built in one.
+    pub fn set_tokenizer_options(&mut self, options: TokenizerOptions) {
+        self.tokenizer_options = options;
+        self.tokenizer = Box::new(options);
+    }
+
+    pub fn tokenizer(&self) -> &dyn Tokenizer {
+        self.tokenizer.as_ref()
+    }
+
+    /// Replaces the built in tokenizer splitting the text of `lookup_compound`,
+    /// `word_segmentation`, `check_text`, `document_stats` and `suggest_in_context`
+    /// into words, e.g. to keep hashtags or product codes whole.
+    pub fn set_tokenizer(&mut self, tokenizer: Box<dyn Tokenizer>) {
+        self.tokenizer = tokenizer;
+    }
+
+    pub fn text_filters(&self) -> &TextFilters {
+        &self.text_filters
+    }
+
+    /// Sets the spans of text, e.g. URLs and email addresses, that `check_text` and
+    /// `document_stats` skip. `TextFilters::common()` by default.
+    pub fn set_text_filters(&mut self, text_filters: TextFilters) {
+        self.text_filters = text_filters;
+    }
+
+    pub fn markup(&self) -> Markup {
+        self.markup
+    }
+
+    /// Sets the markup language of the text of `check_text` and `document_stats`,
+    /// e.g. `Markup::Markdown` to skip code blocks and link destinations.
+    pub fn set_markup(&mut self, markup: Markup) {
+        self.markup = markup;
+    }
+
+    /// Accepts the word, in any case, as correctly spelled for the session, e.g. for an
+    /// editor's "Ignore All", without adding it to the dictionary. `check_text` no longer
+    /// reports it and `lookup` returns it as an exact match with a count of 0.
+    pub fn ignore_word(&mut self, word: &str) {
+        let word = self.normalize(word).into_owned();
+        self.ignored_words.insert(self.lowercase(&word).into_owned(), word);
+        self.invalidate_lookup_cache();
+    }
+
+    /// Stops accepting a word passed to `ignore_word`, returning false when it was not ignored.
+    pub fn unignore_word(&mut self, word: &str) -> bool {
+        let word = self.lowercase(&self.normalize(word)).into_owned();
+        self.invalidate_lookup_cache();
+        self.ignored_words.remove(&word).is_some()
+    }
+
+    /// Stops accepting every word passed to `ignore_word`.
+    pub fn clear_ignored(&mut self) {
+        self.ignored_words.clear();
+        self.invalidate_lookup_cache();
+    }
+
+    pub fn is_ignored(&self, word: &str) -> bool {
+        !self.ignored_words.is_empty() && self.ignored_words.contains_key(&*self.lowercase(&self.normalize(word)))
+    }
+
+    pub fn entry_metadata(&self, word: &str) -> Option<&EntryMetadata> {
+        self.entry_metadata.get(&*self.normalize(word)).map(|metadata| &**metadata)
+    }
+
+    /// Attaches metadata, e.g. its language or the dictionary it comes from, to the
+    /// suggestions of the word returned by `lookup`, `complete` and the lookups built on
+    /// them. The word need not be in the dictionary yet. None or empty metadata detaches it.
+    ///
+    /// example:
+    /// sym_spell.set_entry_metadata("iphone", Some(EntryMetadata { canonical: Some("iPhone".into()), ..EntryMetadata::default() }));
+    ///
+    /// assert_eq!(sym_spell.lookup("iphnoe", Verbosity::Top, 2, false, false)[0].metadata.as_ref().unwrap().canonical.as_deref(), Some("iPhone"));
+    pub fn set_entry_metadata(&mut self, word: &str, metadata: Option<EntryMetadata>) {
+        let word = self.normalize(word).into_owned();
+        match metadata.filter(|metadata| !metadata.is_empty()) {
+            Some(metadata) => self.entry_metadata.insert(word, Arc::new(metadata)),
+            None => self.entry_metadata.remove(&word),
+        };
+        self.invalidate_lookup_cache();
+    }
+
+    pub fn suggestion_ranker(&self) -> &dyn SuggestionRanker {
+        self.suggestion_ranker.as_deref().unwrap_or(&DefaultRanker)
+    }
+
+    /// Sets the ranker ordering the suggestions of `lookup` and `lookup_with_context`,
+    /// or with None goes back to ordering them by their `SuggestItem::sort_key`.
+    pub fn set_suggestion_ranker(&mut self, suggestion_ranker: Option<Box<dyn SuggestionRanker>>) {
+        self.suggestion_ranker = suggestion_ranker;
+        self.invalidate_lookup_cache();
+    }
+
+    pub fn logger(&self) -> Option<&dyn Logger> {
+        self.logger.as_deref()
+    }
+
+    /// Sets the logger told about every dictionary load, lookup, compound lookup and
+    /// word segmentation, or with None stops logging. See `Logger`.
+    pub fn set_logger(&mut self, logger: Option<Box<dyn Logger>>) {
+        self.logger = logger;
+    }
+
+    // reports the event to the logger and, with the tracing feature, as a tracing event
+    pub(crate) fn log(&self, event: &LogEvent) {
+        #[cfg(feature = "tracing")]
+        event.trace();
+        if let Some(logger) = &self.logger {
+            logger.log(event);
+        }
+    }
+
+    /// N of the probability count / N of a word, the number of words of the corpus the
+    /// counts come from. The sum of the loaded counts unless set with `set_corpus_size`.
+    pub fn corpus_size(&self) -> f64 {
+        self.corpus_size.unwrap_or_else(|| *self.count_sum.get_or_init(|| {
+            let mut sum = self.below_threshold_words.values().map(|&count| count as f64).sum::<f64>();
+            self.storage.for_each_word(&mut |_, count| sum += count as f64);
+            // an empty dictionary has no probabilities to distort
+            sum.max(1.0)
+        }))
+    }
+
+    /// Sets the corpus size used by `lookup_compound`, `word_segmentation` and the
+    /// probability of suggestions, e.g. `GOOGLE_BOOKS_CORPUS_SIZE` for the bundled
+    /// dictionaries to get the results of the reference SymSpell. None goes back to the
+    /// sum of the loaded counts, right for a dictionary of every word of its corpus.
+    pub fn set_corpus_size(&mut self, corpus_size: Option<f64>) {
+        self.corpus_size = corpus_size.filter(|corpus_size| *corpus_size > 0.0);
+        self.invalidate_lookup_cache();
+    }
+
+    pub fn bigram_weight(&self) -> f64 {
+        self.bigram_weight
+    }
+
+    /// Sets how much, between 0 and 1, the bigram dictionary counts against the
+    /// probabilities of single words when `lookup_compound` splits a term and when
+    /// `word_segmentation` scores a word following another. The estimate of a known pair
+    /// is bigram_weight * its bigram probability + (1 - bigram_weight) * the product of
+    /// its word probabilities. `word_segmentation` weighs a known trigram the same way.
+    /// 1 by default, 0 ignores the bigrams and trigrams.
+    pub fn set_bigram_weight(&mut self, bigram_weight: f64) {
+        self.bigram_weight = bigram_weight.clamp(0.0, 1.0);
+        self.invalidate_lookup_cache();
+    }
+
+    pub fn edit_decay(&self) -> f64 {
+        self.edit_decay
+    }
+
+    /// Sets the factor, between 0 and 1, the `SuggestItem::probability` of a suggestion
+    /// is multiplied by for every edit separating it from the input. 0.1 by default,
+    /// i.e. each edit makes a correction ten times less likely.
+    pub fn set_edit_decay(&mut self, edit_decay: f64) {
+        self.edit_decay = edit_decay.clamp(0.0, 1.0);
+        self.invalidate_lookup_cache();
+    }
+
+    pub fn keyboard_adjacent_cost(&self) -> f64 {
+        self.keyboard_adjacent_cost
+    }
+
+    /// Sets the cost, between 0 and 1, of substituting a key with a neighbouring one
+    /// when `LookupOptions::keyboard_layout` ranks suggestions. 0.5 by default, so
+    /// "hwllo" is half an edit from "hello" and a full edit from "hullo".
+    pub fn set_keyboard_adjacent_cost(&mut self, adjacent_cost: f64) {
+        if adjacent_cost.is_nan() {
+            return;
+        }
+        self.keyboard_adjacent_cost = adjacent_cost.clamp(0.0, 1.0);
+        self.keyboard_costs = Default::default();
+    }
+
+    // the edit costs of the layout, built once per layout and adjacent cost
+    fn keyboard_costs(&self, layout: KeyboardLayout) -> Arc<WeightedCosts> {
+        Arc::clone(self.keyboard_costs[layout as usize].get_or_init(|| Arc::new(layout.weighted_costs(self.keyboard_adjacent_cost))))
+    }
+
+    pub fn backend(&self) -> Backend {
+        self.backend
+    }
+
+    pub fn delete_generation(&self) -> DeleteGeneration {
+        self.delete_generation
+    }
+
+    /// Sets how much of the deletes index is precomputed, e.g. only the prefix of
+    /// each word with `DeleteGeneration::OnDemand` to save memory on mobile devices
+    /// at the cost of slower lookups. Set it before loading any entries.
+    pub fn set_delete_generation(&mut self, delete_generation: DeleteGeneration) {
+        self.delete_generation = delete_generation;
+        self.bucket_cache = Mutex::new(LruCache::new(self.bucket_cache_capacity()));
+        self.index_words_by_prefix_len();
+        self.invalidate_lookup_cache();
+    }
+
+    pub fn lookup_cache_capacity(&self) -> usize {
+        self.lookup_cache_capacity
+    }
+
+    /// Keeps the results of the last `capacity` distinct calls to `lookup`, so an
+    /// editor checking the same words as the user types finds them without another
+    /// search. The cache is emptied whenever the dictionary or a setting that changes
+    /// the results is modified. 0, the default, disables it.
+    pub fn set_lookup_cache_capacity(&mut self, capacity: usize) {
+        self.lookup_cache_capacity = capacity;
+        self.lookup_cache = Mutex::new(LruCache::new(capacity));
+    }
+
+    /// The hits and misses of the lookup cache since its capacity was set.
+    pub fn lookup_cache_stats(&self) -> LookupCacheStats {
+        let cache = self.lookup_cache.lock().unwrap_or_else(PoisonError::into_inner);
+        LookupCacheStats {
+            hits: cache.hits(),
+            misses: cache.misses(),
+            len: cache.len(),
+        }
+    }
+
+    pub fn lookup_limits(&self) -> LookupLimits {
+        self.lookup_limits
+    }
+
+    /// Sets the caps on the work a single lookup may do, see `LookupLimits`.
+    pub fn set_lookup_limits(&mut self, limits: LookupLimits) {
+        self.lookup_limits = limits;
+        self.invalidate_lookup_cache();
+    }
+
+    pub fn bloom_filter(&self) -> Option<f64> {
+        self.bloom_filter_rate
+    }
+
+    /// Checks the words of `check_text`, `document_stats` and `StreamChecker` against a
+    /// Bloom filter of the dictionary words before the dictionary itself, so that most
+    /// misspelled words are rejected without probing it. The filter takes about 10 bits
+    /// per word for a `false_positive_rate` of 0.01 and is built by the first word
+    /// checked after the dictionary changes. None, the default, disables it.
+    ///
+    /// example:
+    /// sym_spell.set_bloom_filter(Some(0.01));
+    pub fn set_bloom_filter(&mut self, false_positive_rate: Option<f64>) {
+        self.bloom_filter_rate = false_positive_rate.filter(|rate| *rate > 0.0 && *rate < 1.0);
+        self.bloom_filter.take();
+    }
+
+    /// The number of lookups given up on for exceeding the `LookupLimits` since
+    /// the instance was created or last cleared.
+    pub fn truncated_lookups(&self) -> usize {
+        self.truncated_lookups.load(Ordering::Relaxed)
+    }
+
+    /// The lookups, candidates, edit distances, cache hits and time spent since the
+    /// instance was created, cleared or `reset_metrics` was called. See `Metrics`.
+    #[cfg(feature = "metrics")]
+    pub fn metrics(&self) -> Metrics {
+        self.metrics.snapshot()
+    }
+
+    /// Starts the metrics over, e.g. before measuring another prefix length.
+    #[cfg(feature = "metrics")]
+    pub fn reset_metrics(&self) {
+        self.metrics.reset();
+    }
+
+    // drops the cached lookups, which may no longer be the results after a modification
+    fn invalidate_lookup_cache(&mut self) {
+        self.lookup_cache.get_mut().unwrap_or_else(PoisonError::into_inner).clear();
+    }
+
+    // the most graphemes removed from a prefix for the deletes stored in the index
+    fn precomputed_edits(&self) -> usize {
+        match self.delete_generation {
+            DeleteGeneration::Precomputed => self.dictionary_edit_distance,
+            DeleteGeneration::OnDemand { precomputed_edits, .. } => precomputed_edits.min(self.dictionary_edit_distance),
+        }
+    }
+
+    fn bucket_cache_capacity(&self) -> usize {
+        match self.delete_generation {
+            DeleteGeneration::Precomputed => 0,
+            DeleteGeneration::OnDemand { cache_capacity, .. } => cache_capacity,
+        }
+    }
+
+    // indexes every dictionary word by the length of its prefix when deletes are generated on demand
+    fn index_words_by_prefix_len(&mut self) {
+        self.words_by_prefix_len = Vec::new();
+        if self.precomputed_edits() == self.dictionary_edit_distance || self.backend != Backend::DeleteHash {
+            return;
+        }
+        // word ids are handed out in order starting at 0
+        for id in 0..self.storage.len() as WordId {
+            if let Some(word) = self.storage.word(id) {
+                let len = GraphemeClusters::new(&word).len();
+                self.index_word_by_prefix_len(IndexedWord { id, len: len as u32 });
+            }
+        }
+    }
+
+    fn index_word_by_prefix_len(&mut self, entry: IndexedWord) {
+        let prefix_len = (entry.len as usize).min(self.prefix_length);
+        if self.words_by_prefix_len.len() <= prefix_len {
+            self.words_by_prefix_len.resize_with(prefix_len + 1, Vec::new);
+        }
+        self.words_by_prefix_len[prefix_len].push(entry);
+    }
+
+    // the words whose bucket is the delete, generating those of more edits than precomputed
+    fn deletes(&self, delete: &str, delete_len: usize, hash: u64) -> Option<Cow<'_, [IndexedWord]>> {
+        let precomputed = self.storage.deletes(hash);
+        let precomputed_edits = self.precomputed_edits();
+        // no prefix is long enough to need more edits to reach the delete
+        if precomputed_edits == self.dictionary_edit_distance || delete_len + precomputed_edits >= self.prefix_length {
+            return precomputed;
+        }
+        let cached = self.bucket_cache.lock().unwrap_or_else(PoisonError::into_inner).get(delete).cloned();
+        // the bucket is generated without holding the lock so other threads can look up meanwhile
+        let generated = cached.unwrap_or_else(|| {
+            let generated = self.generate_bucket(delete, delete_len, precomputed_edits);
+            self.bucket_cache.lock().unwrap_or_else(PoisonError::into_inner).insert(delete.to_string(), generated.clone());
+            generated
+        });
+        match precomputed {
+            None if generated.is_empty() => None,
+            None => Some(Cow::Owned(generated)),
+            Some(precomputed) => {
+                let mut bucket = precomputed.into_owned();
+                bucket.extend(generated);
+                Some(Cow::Owned(bucket))
+            }
+        }
+    }
+
+    // the words whose prefix turns into the delete by removing more than the precomputed edits
+    fn generate_bucket(&self, delete: &str, delete_len: usize, precomputed_edits: usize) -> Vec<IndexedWord> {
+        let mut bucket = vec![];
+        let max_prefix_len = (delete_len + self.dictionary_edit_distance).min(self.prefix_length);
+        for prefix_len in delete_len + precomputed_edits + 1..=max_prefix_len {
+            for entry in self.words_by_prefix_len.get(prefix_len).into_iter().flatten() {
+                let word = match self.storage.word(entry.id) {
+                    Some(word) => word,
+                    None => continue,
+                };
+                let is_delete = if word.is_ascii() {
+                    is_subsequence(delete.as_bytes().iter(), word.as_bytes()[..prefix_len].iter())
+                } else {
+                    // the bytes are a cheap filter before comparing graphemes
+                    is_subsequence(delete.as_bytes().iter(), word.as_bytes().iter()) &&
+                        is_subsequence(GraphemeClusters::new(delete).map(|(grapheme, _)| grapheme),
+                                       GraphemeClusters::new(&word).take(prefix_len).map(|(grapheme, _)| grapheme))
+                };
+                if is_delete {
+                    bucket.push(*entry);
+                }
+            }
+        }
+        bucket
+    }
+
+    /// The storage holding the index.
+    pub fn storage(&self) -> &dyn IndexStorage {
+        self.storage.as_ref()
+    }
+
+    /// Replaces the storage holding the index, e.g. with one backed by a memory
+    /// mapped file. The supplied storage is used as is so it may already hold an
+    /// index built with the same settings, in which case `max_word_length` is the
+    /// `max_length()` of the instance that built it. Pass 0 for an empty storage.
+    pub fn set_storage(&mut self, storage: Box<dyn IndexStorage>, max_word_length: usize) {
+        self.storage = storage;
+        self.max_dictionary_word_length = max_word_length;
+        self.index_equivalent_words();
+        self.index_words_by_prefix_len();
+        self.completion_index.take();
+        self.bloom_filter.take();
+        #[cfg(feature = "dawg")]
+        self.dawg.take();
+        self.count_sum.take();
+        self.invalidate_lookup_cache();
+    }
 
-    pub fn entry_count(&self) -> usize { self.deletes.len() }
+    /// Events encountered while loading dictionary entries since
+    /// the instance was created or last cleared.
+    pub fn load_report(&self) -> &LoadReport {
+        &self.load_report
+    }
+
+    // tallies a line rejected before it reached the dictionary, e.g. one that is not valid utf-8
+    pub(crate) fn record_malformed_line(&mut self) {
+        self.load_report.malformed_lines += 1;
+    }
 
-    pub fn create_dictionary_entry(&mut self, key: String, mut count: usize) -> bool {
+    pub fn create_dictionary_entry(&mut self, key: String, mut count: Count) -> bool {
+        self.invalidate_lookup_cache();
+        self.count_sum.take();
+        let normalized = match self.normalize(&key) {
+            Cow::Owned(normalized) => Some(normalized),
+            Cow::Borrowed(_) => None,
+        };
+        let key = normalized.unwrap_or(key);
         // look first in below threshold words, update count, and allow promotion to correct spelling word if count reaches threshold
         // threshold must be >1 for there to be the possibility of low threshold words
         if self.count_threshold > 1 && self.below_threshold_words.contains_key(&key) {
             let prev_count = self.below_threshold_words[&key];
             // calculate new count for below threshold word
-            count = if usize::max_value() - prev_count > count { prev_count + count } else { usize::max_value() };
+            count = match self.merge_counts(prev_count, count) {
+                Some(count) => count,
+                None => return false,
+            };
             // has reached threshold - remove from below threshold collection (it will be added to correct words below)
             if count >= self.count_threshold {
                 self.below_threshold_words.remove(&key);
@@ -120,11 +1054,17 @@ impl SymSpell {
                 self.below_threshold_words.insert(key, count);
                 return false;
             }
-        } else if self.words.contains_key(&key) {
-            let prev_count = self.words[&key];
+        } else if let Some(prev_count) = self.storage.word_count(&key) {
             // just update count if it's an already added above threshold word
-            count = if usize::max_value() - prev_count > count { prev_count + count } else { usize::max_value() };
-            self.words.insert(key, count);
+            if let Some(count) = self.merge_counts(prev_count, count) {
+                // a faded word is a dictionary word again once its count is back over the threshold
+                let unfaded = count >= self.count_threshold && self.faded_words.remove(&key);
+                self.storage.set_word_count(key, count);
+                if unfaded {
+                    self.completion_index.take();
+                    return true;
+                }
+            }
             return false;
         } else if count < self.count_threshold {
             // new or existing below threshold word
@@ -135,146 +1075,545 @@ impl SymSpell {
         //edits/suggestions are created only once, no matter how often word occurs
         //edits/suggestions are created only as soon as the word occurs in the corpus,
         //even if the same term existed before in the dictionary as an edit from another word
+        self.index_word(key, count);
+        true
+    }
+
+    // stores the new dictionary word along with its deletes
+    fn index_word(&mut self, key: String, count: Count) {
         let key_len = GraphemeClusters::new(&key).len();
         if key_len > self.max_dictionary_word_length {
             self.max_dictionary_word_length = key_len;
         }
-        let set = self.create_deletes(&key);
+        if let Cow::Owned(folded) = self.equivalences.fold(&key) {
+            self.equivalent_words.entry(folded).or_default().push(key.clone());
+        }
+        self.completion_index.take();
+        self.bloom_filter.take();
+        if self.backend != Backend::DeleteHash {
+            #[cfg(feature = "dawg")]
+            self.dawg.take();
+            self.storage.set_word_count(key, count);
+            return;
+        }
+        let set = self.create_deletes(&key, key_len);
+        // the word is stored once, the deletes refer to it by id
+        let id = self.storage.set_word_count(key, count);
         for s in set {
-            self.insert_delete(&s, &key);
+            self.insert_delete(&s, id, key_len);
         }
-        self.words.insert(key, count);
-
-        true
-    }
-
-    /// <summary>Load multiple dictionary entries from a file of word/frequency count pairs</summary>
-    /// <remarks>Merges with any dictionary data already loaded.</remarks>
-    pub fn write_line_to_bigram_dictionary(&mut self, line: &str, separator: &str) {
-        let parts: Vec<&str> = line.split(separator).collect();
-        let key = parts[0].to_owned() + " " + parts[1];
-
-        let count = parts[2].trim_end().parse::<usize>().unwrap_or(0);
-        self.bigrams.insert(key, count);
-
-        if count < self.bigram_count_min {
-            self.bigram_count_min = count;
+        if self.precomputed_edits() < self.dictionary_edit_distance {
+            self.index_word_by_prefix_len(IndexedWord { id, len: key_len as u32 });
         }
     }
 
-    /// <summary>Load multiple dictionary entries from a stream of word/frequency count pairs</summary>
-    /// <remarks>Merges with any dictionary data already loaded.</remarks>
-    pub fn write_line_to_dictionary(&mut self, line: &str, separator: &str) {
-        let mut parts = vec![];
-        let mut idx = 0;
-        let line_bytes = line.as_bytes();
-        let separator_bytes = separator.as_bytes();
-        for i in 0..line_bytes.len() {
-            let ch = &line_bytes[i..i + 1];
-            if ch == separator_bytes {
-                parts.push(&line[idx..i]);
-                idx = i + 1;
+    /// Loads word/count pairs like `create_dictionary_entry`, `chunk_size` at a time,
+    /// calling `progress` after each chunk with the words and deletes stored so far.
+    /// Large edit distances generate many deletes per word, so a load can be watched
+    /// and stopped by returning false before it exhausts memory, keeping the words
+    /// loaded so far. Returns the progress after the last chunk.
+    ///
+    /// example:
+    /// let progress = sym_spell.load_words_with_progress(words, 10_000, |progress| {
+    ///     println!("{} words, {} deletes", progress.words, progress.deletes);
+    ///     progress.deletes < 50_000_000
+    /// });
+    pub fn load_words_with_progress(&mut self, words: impl IntoIterator<Item=(String, Count)>, chunk_size: usize,
+                                    mut progress: impl FnMut(&LoadProgress) -> bool) -> LoadProgress {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("dictionary_load").entered();
+        let mut words = words.into_iter().peekable();
+        let mut report = LoadProgress::default();
+        while words.peek().is_some() {
+            for (key, count) in words.by_ref().take(chunk_size.max(1)) {
+                self.create_dictionary_entry(key, count);
+                report.entries_read += 1;
+            }
+            report.words = self.word_count();
+            report.deletes = self.entry_count();
+            if !progress(&report) {
+                report.stopped = words.peek().is_some();
+                break;
             }
         }
-        parts.push(&line[idx..]);
+        self.log(&LogEvent::DictionaryLoad { words: self.word_count() });
+        report
+    }
 
-        if parts.len() < 2 {
-            return;
-        }
-        let key = parts[0].to_string();
-        let count = parts[1].trim_end().parse::<usize>().unwrap_or(0);
-        self.create_dictionary_entry(key, count);
+    /// Adds an occurrence of a word met at runtime, e.g. one the user added or kept
+    /// typing, like `create_dictionary_entry` with a count of 1, remembering that the
+    /// occurrence was learned so that `decay_counts` can fade it again. Returns true
+    /// when the word became a dictionary word.
+    ///
+    /// example:
+    /// sym_spell.learn_word("JIRA-1234");
+    pub fn learn_word(&mut self, word: &str) -> bool {
+        let key = self.normalize(word).into_owned();
+        let learned = self.learned_counts.entry(key.clone()).or_insert(0);
+        *learned = learned.saturating_add(1);
+        self.create_dictionary_entry(key, 1)
     }
 
-    /// Parses a str into the words that comprise it while omitting
-    /// non alphanumeric chars
-    fn parse_words(text: &str) -> Vec<&str> {
-        let mut words = vec![];
-        let mut last_char_alpha_numeric = false;
-        let mut cursor = 0;
-        let gc = GraphemeClusters::new(text);
-        let len = gc.len();
-        for i in 0..len {
-            let grapheme = &gc[i];
-            let alpha_numeric = is_alpha_numeric(grapheme);
-            if !alpha_numeric {
-                if last_char_alpha_numeric {
-                    let range = gc.get_slice_range(cursor..i);
-                    words.push(&text[range]);
+    /// Multiplies the counts added by `learn_word` by the factor, between 0 and 1, so
+    /// that words learned once, such as ticket ids and misspelled names, fade while
+    /// words learned again and again stay. Call it periodically, e.g. daily with 0.5
+    /// for a half-life of a day. A learned word whose count falls below the count
+    /// threshold is neither suggested nor known until it is learned again. Counts
+    /// loaded from dictionaries never decay. Faded words keep their deletes in the
+    /// index, so an instance learning many one-off words grows until `purge_faded`
+    /// is called.
+    pub fn decay_counts(&mut self, factor: f64) {
+        let factor = factor.clamp(0.0, 1.0);
+        let threshold = self.count_threshold.max(1);
+        for (word, learned) in mem::take(&mut self.learned_counts) {
+            let decayed = (learned as f64 * factor) as Count;
+            let lost = learned - decayed;
+            if decayed > 0 {
+                self.learned_counts.insert(word.clone(), decayed);
+            }
+            if lost == 0 {
+                continue;
+            }
+            if let Some(count) = self.below_threshold_words.get_mut(&word) {
+                *count = count.saturating_sub(lost);
+                if *count == 0 {
+                    self.below_threshold_words.remove(&word);
                 }
-                cursor = i;
+            } else if let Some(count) = self.storage.word_count(&word) {
+                let count = count.saturating_sub(lost);
+                if count < threshold {
+                    self.faded_words.insert(word.clone());
+                }
+                self.storage.set_word_count(word, count);
             }
-            last_char_alpha_numeric = alpha_numeric;
-        }
-        if last_char_alpha_numeric && cursor != text.len() {
-            let range = gc.get_slice_range(cursor..len);
-            words.push(&text[range]);
         }
-        words
+        self.completion_index.take();
+        self.count_sum.take();
+        self.invalidate_lookup_cache();
     }
 
-    fn edits(&mut self, subject: &str, mut edit_distance: usize, delete_words: &mut HashSet<String>) {
-        let len = subject.len();
-        if len == 1 {
-            return;
+    /// Drops the faded words whose count decayed to 0, i.e. those only ever learned,
+    /// along with their deletes, rebuilding the index from the remaining words. It
+    /// takes about as long as loading the dictionary again, so call it now and then,
+    /// e.g. after `decay_counts`. Returns the number of words dropped.
+    pub fn purge_faded(&mut self) -> usize {
+        let mut words = Vec::with_capacity(self.storage.len());
+        self.storage.for_each_word(&mut |word, count| words.push((word.to_string(), count)));
+        let purged: HashSet<String> = words.iter()
+            .filter(|(word, count)| *count == 0 && self.faded_words.contains(word))
+            .map(|(word, _)| word.clone())
+            .collect();
+        if purged.is_empty() {
+            return 0;
         }
-        edit_distance += 1;
-        let iter = GraphemeClusters::new(subject);
-        for (s, range) in iter {
-            let mut slice: Vec<u8> = Vec::new();
-            let s_len = s.len();
-            if range.start != 0 {
-                slice.extend_from_slice(subject[..range.end - s_len].as_bytes());
-            }
-            if range.end != len {
-                slice.extend_from_slice(subject[range.start + s_len..].as_bytes());
-            }
-            let delete = unsafe { String::from_utf8_unchecked(slice) };
-            if !delete_words.contains(&delete) {
-                if edit_distance < self.dictionary_edit_distance {
-                    // recursion, if maximum edit distance not yet reached
-                    self.edits(&delete, edit_distance, delete_words);
+        self.faded_words.retain(|word| !purged.contains(word));
+        self.max_dictionary_word_length = 0;
+        self.storage.clear();
+        self.equivalent_words = HashMap::new();
+        self.words_by_prefix_len = Vec::new();
+        self.bucket_cache = Mutex::new(LruCache::new(self.bucket_cache_capacity()));
+        for (word, count) in words.into_iter().filter(|(word, _)| !purged.contains(word)) {
+            self.index_word(word, count);
+        }
+        self.completion_index.take();
+        self.bloom_filter.take();
+        #[cfg(feature = "dawg")]
+        self.dawg.take();
+        self.count_sum.take();
+        self.invalidate_lookup_cache();
+        purged.len()
+    }
+
+    // whether the learned word decayed below the count threshold
+    fn is_faded(&self, word: &str) -> bool {
+        !self.faded_words.is_empty() && self.faded_words.contains(word)
+    }
+
+    // sum of two counts according to the overflow policy, None when the update should be discarded
+    fn merge_counts(&mut self, prev_count: Count, count: Count) -> Option<Count> {
+        if let Some(sum) = prev_count.checked_add(count) {
+            return Some(sum);
+        }
+        self.load_report.count_overflows += 1;
+        match self.count_overflow_policy {
+            CountOverflowPolicy::Saturate => Some(Count::MAX),
+            CountOverflowPolicy::Error => None,
+            CountOverflowPolicy::Rescale => {
+                self.storage.update_word_counts(&mut |count| *count /= 2);
+                for count in self.below_threshold_words.values_mut() {
+                    *count /= 2;
                 }
-                delete_words.insert(delete);
+                self.load_report.rescales += 1;
+                Some(prev_count / 2 + count / 2)
+            }
+        }
+    }
+
+    /// <summary>Load a bigram dictionary entry from a line of two words and an optional
+    /// count, which defaults to 1.</summary>
+    /// <remarks>Merges with any dictionary data already loaded. Surrounding whitespace is
+    /// ignored and a whitespace separator matches any run of spaces or tabs. Lines without
+    /// two words, or with more than two words and a count, are rejected.</remarks>
+    ///
+    /// example:
+    /// sym_spell.write_line_to_bigram_dictionary("in the\t 822", " ")?;
+    /// sym_spell.write_line_to_bigram_dictionary("of the", " ")?;
+    ///
+    /// assert!(sym_spell.write_line_to_bigram_dictionary("the", " ").is_err());
+    pub fn write_line_to_bigram_dictionary(&mut self, line: &str, separator: &str) -> Result<(), BigramLineError> {
+        let parts = Self::ngram_line_parts(line, separator);
+        if parts.len() > 3 {
+            self.load_report.malformed_lines += 1;
+            return Err(BigramLineError::TooManyParts(parts.len()));
+        }
+        if parts.len() < 2 || parts[..2].iter().any(|word| word.is_empty()) {
+            self.load_report.malformed_lines += 1;
+            return Err(BigramLineError::MissingWords);
+        }
+        // an invalid count is handled by the invalid count policy rather than rejected
+        let count = match self.ngram_line_count(parts.get(2)) {
+            Some(count) => count,
+            None => return Ok(()),
+        };
+        let bigram = self.normalize(&parts[..2].join(" ")).into_owned();
+        self.bigrams.insert(bigram, count);
+        self.invalidate_lookup_cache();
+
+        self.bigram_count_min = Some(self.bigram_count_min.map_or(count, |min| min.min(count)));
+        Ok(())
+    }
+
+    /// <summary>Load a trigram dictionary entry from a line of three words and an optional
+    /// count, which defaults to 1.</summary>
+    /// <remarks>Merges with any dictionary data already loaded. Parsed the same way as the
+    /// lines of `write_line_to_bigram_dictionary`. The count of a trigram is weighed
+    /// against the count of its first two words in the bigram dictionary, so trigrams are
+    /// only used where that bigram is loaded too.</remarks>
+    ///
+    /// example:
+    /// sym_spell.write_line_to_trigram_dictionary("one of the 120", " ")?;
+    ///
+    /// assert!(sym_spell.write_line_to_trigram_dictionary("of the", " ").is_err());
+    pub fn write_line_to_trigram_dictionary(&mut self, line: &str, separator: &str) -> Result<(), TrigramLineError> {
+        let parts = Self::ngram_line_parts(line, separator);
+        if parts.len() > 4 {
+            self.load_report.malformed_lines += 1;
+            return Err(TrigramLineError::TooManyParts(parts.len()));
+        }
+        if parts.len() < 3 || parts[..3].iter().any(|word| word.is_empty()) {
+            self.load_report.malformed_lines += 1;
+            return Err(TrigramLineError::MissingWords);
+        }
+        let count = match self.ngram_line_count(parts.get(3)) {
+            Some(count) => count,
+            None => return Ok(()),
+        };
+        let trigram = self.normalize(&parts[..3].join(" ")).into_owned();
+        self.trigrams.insert(trigram, count);
+        self.invalidate_lookup_cache();
+        Ok(())
+    }
+
+    /// Adds the bigrams and trigrams of the text to the bigram and trigram dictionaries,
+    /// counting every occurrence once on top of the counts loaded, e.g. to adapt them to
+    /// the documents of a user. The words are lowercased and no n-gram spans the end of
+    /// a sentence. The words themselves are not added to the dictionary.
+    ///
+    /// example:
+    /// sym_spell.learn_ngrams("One of the best. One of the worst.");
+    ///
+    /// assert_eq!(sym_spell.trigram_count(), 3);
+    pub fn learn_ngrams(&mut self, text: &str) {
+        let normalized = self.normalize(text).into_owned();
+        let mut sentence: Vec<String> = vec![];
+        let mut previous_end = 0;
+        let mut ngrams: Vec<String> = vec![];
+        for token in self.tokenizer.tokenize(&normalized) {
+            if token.class != TokenClass::Word {
+                continue;
+            }
+            if ends_sentence(&normalized[previous_end..token.byte_range.start]) {
+                sentence.clear();
+            }
+            previous_end = token.byte_range.end;
+            sentence.push(self.lowercase(token.text).into_owned());
+            for order in 2..=sentence.len().min(3) {
+                ngrams.push(sentence[sentence.len() - order..].join(" "));
+            }
+        }
+        if ngrams.is_empty() {
+            return;
+        }
+        for ngram in ngrams {
+            let ngrams = if ngram.matches(' ').count() == 1 { &mut self.bigrams } else { &mut self.trigrams };
+            let count = ngrams.entry(ngram).or_insert(0);
+            *count = count.saturating_add(1);
+        }
+        self.invalidate_lookup_cache();
+    }
+
+    // the words and the optional count of a bigram or trigram line
+    fn ngram_line_parts<'l>(line: &'l str, separator: &str) -> Vec<&'l str> {
+        if separator.trim().is_empty() {
+            line.split_whitespace().collect()
+        } else {
+            line.trim().split(separator).map(str::trim).collect()
+        }
+    }
+
+    // the count of a bigram or trigram line, 1 when it has none and None when it is invalid
+    fn ngram_line_count(&mut self, count: Option<&&str>) -> Option<Count> {
+        match count {
+            None => Some(1),
+            Some(count) => self.parse_count(count),
+        }
+    }
+
+    /// <summary>Load multiple dictionary entries from a stream of word/frequency count pairs</summary>
+    /// <remarks>Merges with any dictionary data already loaded. Lines without a count are
+    /// rejected with `SpellcheckerError::MissingCount`, while an invalid count is handled by
+    /// the invalid count policy.</remarks>
+    pub fn write_line_to_dictionary(&mut self, line: &str, separator: &str) -> Result<(), SpellcheckerError> {
+        let mut parts = vec![];
+        let mut idx = 0;
+        let line_bytes = line.as_bytes();
+        let separator_bytes = separator.as_bytes();
+        for i in 0..line_bytes.len() {
+            let ch = &line_bytes[i..i + 1];
+            if ch == separator_bytes {
+                parts.push(&line[idx..i]);
+                idx = i + 1;
+            }
+        }
+        parts.push(&line[idx..]);
+
+        if parts.len() < 2 {
+            self.load_report.malformed_lines += 1;
+            return Err(SpellcheckerError::MissingCount);
+        }
+        let key = parts[0].to_string();
+        if let Some(count) = self.parse_count(parts[1]) {
+            self.create_dictionary_entry(key, count);
+        }
+        Ok(())
+    }
+
+    // the count of a dictionary line according to the invalid count policy, None when the line should be skipped
+    fn parse_count(&mut self, count: &str) -> Option<Count> {
+        match count.trim_end().parse::<Count>() {
+            Ok(count) if count > 0 => return Some(count),
+            // too big to represent is still a valid count
+            Err(ref err) if *err.kind() == IntErrorKind::PosOverflow => return Some(Count::MAX),
+            _ => {}
+        }
+        self.load_report.invalid_counts += 1;
+        match self.invalid_count_policy {
+            InvalidCountPolicy::Drop => None,
+            InvalidCountPolicy::Clamp => Some(1),
+        }
+    }
+
+    fn edits(&mut self, subject: &str, mut edit_distance: usize, delete_words: &mut HashSet<String, SeededState>) {
+        let len = subject.len();
+        if len == 1 {
+            return;
+        }
+        edit_distance += 1;
+        // every byte of an ASCII string is a grapheme so there is no need to find their boundaries
+        if subject.is_ascii() {
+            for i in 0..len {
+                self.edit(subject, i..i + 1, edit_distance, delete_words);
+            }
+        } else {
+            for (_, range) in GraphemeClusters::new(subject) {
+                self.edit(subject, range, edit_distance, delete_words);
+            }
+        }
+    }
+
+    // adds the delete of the grapheme at the byte range and, within the max edit distance, its own deletes
+    fn edit(&mut self, subject: &str, range: Range<usize>, edit_distance: usize, delete_words: &mut HashSet<String, SeededState>) {
+        let delete = Self::delete_at(subject, range);
+        if !delete_words.contains(delete.as_str()) {
+            if edit_distance < self.precomputed_edits() {
+                // recursion, if maximum edit distance not yet reached
+                self.edits(&delete, edit_distance, delete_words);
             }
+            delete_words.insert(delete.into_string());
         }
     }
 
-    fn create_deletes(&mut self, mut delete: &str) -> HashSet<String> {
-        let mut set: HashSet<String> = HashSet::new();
-        let gc = GraphemeClusters::new(delete);
-        let key_len = gc.len();
-        let key = delete.clone();
-        if key_len <= self.dictionary_edit_distance {
+    // the subject with the grapheme at the byte range removed. Deletes of short
+    // words fit inline so no allocation is needed to check if they were seen already
+    fn delete_at(subject: &str, range: Range<usize>) -> SmallString<DELETE_INLINE_CAPACITY> {
+        let mut delete = SmallString::new();
+        delete.push_str(&subject[..range.start]);
+        delete.push_str(&subject[range.end..]);
+        delete
+    }
+
+    fn create_deletes(&mut self, mut delete: &str, key_len: usize) -> HashSet<String, SeededState> {
+        let mut set = HashSet::with_hasher(self.seeded_state);
+        // any deletes of more edits are generated on demand during lookup
+        let precomputed_edits = self.precomputed_edits();
+        if key_len <= precomputed_edits {
             set.insert(String::new());
         }
         if key_len > self.prefix_length {
-            let slice_range = gc.get_slice_range(0..self.prefix_length);
-            delete = &delete[slice_range];
+            delete = GraphemeClusters::new(delete).slice(0..self.prefix_length);
         }
         set.insert(String::from(delete));
-        self.insert_delete(delete, key);
 
-        self.edits(delete, 0, &mut set);
+        if precomputed_edits > 0 {
+            self.edits(delete, 0, &mut set);
+        }
 
         set
     }
 
-    fn insert_delete(&mut self, delete: &str, key: &str) {
+    fn insert_delete(&mut self, delete: &str, id: WordId, key_len: usize) {
         let delete_hash = self.get_string_hash(delete);
-        if let Some(suggestions) = self.deletes.get_mut(&delete_hash) {
-            suggestions.push(key.to_string());
-        } else {
-            self.deletes.insert(delete_hash, vec![key.to_string()]);
-        }
+        self.storage.push_delete(delete_hash, id, key_len as u32);
     }
 
+    // the bytes of the str are hashed directly rather than through `Hash`, whose
+    // encoding of a str is an implementation detail of the standard library
     fn get_string_hash(&self, s: &str) -> u64 {
-        let mut h = DefaultHasher::new();
-        s.hash(&mut h);
+        let mut h = self.delete_hasher.build_hasher();
+        h.write(s.as_bytes());
         h.finish()
     }
 
+    /// The single best suggestion for the input, the input itself when it is in the
+    /// dictionary, or None when max_edit_distance exceeds the max dictionary edit distance.
+    /// Equivalent to the first suggestion of `lookup` with `Verbosity::Top`, without the
+    /// lookup cache, and only the term of the suggestion returned is copied. The closest
+    /// suggestions are still sorted when a `SuggestionRanker` is set or on the DAWG backend.
+    ///
+    /// example:
+    /// let best = sym_spell.lookup_top1("speling", 2);
+    ///
+    /// assert_eq!(best.unwrap().term, "spelling");
+    pub fn lookup_top1(&self, input: &str, max_edit_distance: usize) -> Option<SuggestItem> {
+        self.check_edit_distance(max_edit_distance).ok()?;
+        let top1 = |scratch: &mut LookupScratch| self.lookup_borrowed_with_scratch(input, Verbosity::Top, max_edit_distance, false, true, scratch)
+            .into_iter()
+            .next()
+            .map(SuggestRef::into_owned);
+        LOOKUP_SCRATCH.with(|scratch| match scratch.try_borrow_mut() {
+            Ok(mut scratch) => top1(&mut scratch),
+            // a custom distance may look words up itself while the buffers are in use
+            Err(_) => top1(&mut LookupScratch::default()),
+        })
+    }
+
+    /// Dictionary words at least `min_similarity` similar to the input, where similarity
+    /// is 1 - edit distance / length of the longer word as returned by the `Similarity`
+    /// implementations, so long words tolerate more edits than short ones. The score of
+    /// each suggestion is its similarity and they are ordered from the most similar.
+    /// Only words within the dictionary's max edit distance can be found. The minimum
+    /// is clamped between 0 and 1, and no words are similar to a NaN minimum.
+    ///
+    /// example:
+    /// let similar = sym_spell.find_similar("restaurnt", 0.8);
+    ///
+    /// assert_eq!(similar[0].term, "restaurant");
+    /// assert_eq!(similar[0].score, 0.9);
+    pub fn find_similar(&self, input: &str, min_similarity: f64) -> Vec<SuggestItem> {
+        if min_similarity.is_nan() {
+            return Vec::new();
+        }
+        let min_similarity = min_similarity.clamp(0.0, 1.0);
+        let input_len = GraphemeClusters::new(input).len();
+        let longest = input_len.max(self.max_dictionary_word_length);
+        let max_edit_distance = distance(min_similarity, longest).min(self.dictionary_edit_distance);
+        let mut suggestions: Vec<SuggestItem> = self.lookup(input, Verbosity::All, max_edit_distance, false, true).into_iter()
+            .filter_map(|mut si| {
+                let length = input_len.max(GraphemeClusters::new(&si.term).len());
+                si.score = similarity(si.distance as f64, length as f64)?;
+                // the epsilon keeps e.g. 1 - 1 / 5 from falling just short of 0.8
+                if si.score + 1e-9 >= min_similarity { Some(si) } else { None }
+            })
+            .collect();
+        suggestions.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.sort_key().cmp(&b.sort_key())));
+        suggestions
+    }
+
+    /// Identical to `lookup` with additional per call behavior.
+    ///
+    /// example:
+    /// let options = LookupOptions { join_variants: true, ..LookupOptions::default() };
+    /// let suggestions = sym_spell.lookup_with_options("e-mail", Verbosity::Top, 2, false, false, options);
+    ///
+    /// assert_eq!(suggestions[0].term, "email");
+    /// assert_eq!(suggestions[0].distance, 1);
+    pub fn lookup_with_options(&self, input: &str, verbosity: Verbosity, max_edit_distance: usize, include_unknown: bool, include_self: bool, options: LookupOptions) -> Vec<SuggestItem> {
+        // re-ranking may promote any of the closest suggestions so Top has to see them all
+        let rerank = options.rerank_prefix_scale.is_some() || options.keyboard_layout.is_some();
+        let lookup_verbosity = if rerank && verbosity == Verbosity::Top { Verbosity::Closest } else { verbosity };
+        let mut suggestions = self.lookup(input, lookup_verbosity, max_edit_distance, include_unknown, include_self);
+        if options.join_variants {
+            suggestions = self.merge_join_variants(input, suggestions, lookup_verbosity, max_edit_distance);
+        }
+        if rerank {
+            let mut keyboard = options.keyboard_layout.map(|layout| WeightedLevenshtein::new(self.keyboard_costs(layout)));
+            let mut jaro_winkler = options.rerank_prefix_scale.map(JaroWinkler::with_prefix_scale);
+            let mut ranked: Vec<(f64, SuggestItem)> = suggestions.into_iter().map(|mut si| {
+                let keyboard_cost = keyboard.as_mut().map_or(0.0, |keyboard| keyboard.weighted_distance(input, &si.term));
+                let similarity = jaro_winkler.as_mut().and_then(|jaro_winkler| jaro_winkler.similarity(input, &si.term)).unwrap_or(0.0);
+                si.score = similarity / 4.0 - keyboard_cost;
+                (keyboard_cost, si)
+            }).collect();
+            // the similarity only breaks the ties of the keyboard costs, whatever the adjacent cost
+            ranked.sort_by(|a, b| a.1.distance.cmp(&b.1.distance)
+                .then_with(|| a.0.total_cmp(&b.0))
+                .then_with(|| a.1.sort_key().cmp(&b.1.sort_key())));
+            suggestions = ranked.into_iter().map(|(_, si)| si).collect();
+            if verbosity == Verbosity::Top {
+                suggestions.truncate(1);
+            }
+        }
+        suggestions
+    }
+
+    // adds the suggestions for the input with its separators removed, each run of
+    // separators costing one edit
+    fn merge_join_variants(&self, input: &str, suggestions: Vec<SuggestItem>, verbosity: Verbosity, max_edit_distance: usize) -> Vec<SuggestItem> {
+        let is_separator = |ch: char| ch == '-' || ch == ' ';
+        let joined: String = input.chars().filter(|&ch| !is_separator(ch)).collect();
+        // "e - mail" is as much a variant of "email" as "e-mail" so a run of separators is one edit
+        let separators = input.split(is_separator).filter(|part| !part.is_empty()).count().saturating_sub(1);
+        if separators == 0 || separators > max_edit_distance || joined.is_empty() {
+            return suggestions;
+        }
+        let joined_suggestions = self.lookup(&joined, verbosity, max_edit_distance - separators, false, true);
+        if joined_suggestions.is_empty() {
+            return suggestions;
+        }
+
+        // the placeholder added by include_unknown no longer applies
+        let mut merged: Vec<SuggestItem> = suggestions.into_iter()
+            .filter(|si| si.count > 0 || self.storage.word_count(&si.term).is_some())
+            .collect();
+        for mut si in joined_suggestions {
+            si.distance += separators;
+            match merged.iter_mut().find(|existing| existing.term == si.term) {
+                Some(existing) => existing.distance = existing.distance.min(si.distance),
+                None => merged.push(si),
+            }
+        }
+        merged.sort_by(|a, b| a.sort_key().cmp(&b.sort_key()));
+        match verbosity {
+            Verbosity::Top => merged.truncate(1),
+            Verbosity::Closest => {
+                let closest = merged[0].distance;
+                merged.retain(|si| si.distance == closest);
+            }
+            Verbosity::All => {}
+        }
+        merged
+    }
+
     /// <summary>Find suggested spellings for a given input word.</summary>
     /// <param name="input">The word being spell checked.</param>
     /// <param name="verbosity">The value controlling the quantity/closeness of the retuned suggestions.</param>
@@ -282,69 +1621,264 @@ impl SymSpell {
     /// <param name="include_unknown">Include input word in suggestions, if no words within edit distance found.</param>
     /// <param name="include_self">Include input word in suggestions, when an exact match is found.</param>
     /// <returns>A List of SuggestItem object representing suggested correct spellings for the input word,
-    /// sorted by edit distance, and secondarily by count frequency.</returns>
+    /// ordered by their `SuggestItem::sort_key`, i.e. by edit distance, and secondarily by count frequency.
+    /// Each term appears once and ties are broken alphabetically, so the results do not depend on the
+    /// hasher or the order the dictionary was loaded in.</returns>
+    ///
+    /// # Panics
+    ///
+    /// When max_edit_distance exceeds the max dictionary edit distance, see `try_lookup`.
     pub fn lookup(&self, input: &str, verbosity: Verbosity, max_edit_distance: usize, include_unknown: bool, include_self: bool) -> Vec<SuggestItem> {
+        if self.lookup_cache_capacity == 0 {
+            return self.lookup_uncached(input, verbosity, max_edit_distance, include_unknown, include_self);
+        }
+        let key = (input.to_string(), verbosity as u8, max_edit_distance, include_unknown, include_self);
+        if let Some(suggestions) = self.lookup_cache.lock().unwrap_or_else(PoisonError::into_inner).get(&key) {
+            #[cfg(feature = "metrics")]
+            self.metrics.record_cache_hit();
+            self.log(&LogEvent::Lookup { input, candidates: 0, suggestions: suggestions.len(), termination: Some(EarlyTermination::Cached) });
+            return suggestions.clone();
+        }
+        let suggestions = self.lookup_uncached(input, verbosity, max_edit_distance, include_unknown, include_self);
+        self.lookup_cache.lock().unwrap_or_else(PoisonError::into_inner).insert(key, suggestions.clone());
+        suggestions
+    }
+
+    /// Identical to `lookup` returning `SpellcheckerError::EditDistanceTooLarge` when
+    /// max_edit_distance exceeds the max dictionary edit distance, where `lookup` panics.
+    ///
+    /// example:
+    /// let sym_spell = SymSpell::new(Some(2), None, None);
+    ///
+    /// assert!(sym_spell.try_lookup("helo", Verbosity::Top, 3, false, false).is_err());
+    pub fn try_lookup(&self, input: &str, verbosity: Verbosity, max_edit_distance: usize, include_unknown: bool,
+                      include_self: bool) -> Result<Vec<SuggestItem>, SpellcheckerError> {
+        self.check_edit_distance(max_edit_distance)?;
+        Ok(self.lookup(input, verbosity, max_edit_distance, include_unknown, include_self))
+    }
+
+    /// `SpellcheckerError::EditDistanceTooLarge` when max_edit_distance exceeds the max
+    /// dictionary edit distance, which every lookup built on `lookup` panics on, e.g. to
+    /// validate a setting once rather than on every call.
+    pub fn check_edit_distance(&self, max_edit_distance: usize) -> Result<(), SpellcheckerError> {
+        if max_edit_distance > self.dictionary_edit_distance {
+            return Err(SpellcheckerError::EditDistanceTooLarge { max_edit_distance, max_dictionary_edit_distance: self.dictionary_edit_distance });
+        }
+        Ok(())
+    }
+
+    // the lookup in the buffers kept for the current thread
+    fn lookup_uncached(&self, input: &str, verbosity: Verbosity, max_edit_distance: usize, include_unknown: bool, include_self: bool) -> Vec<SuggestItem> {
+        LOOKUP_SCRATCH.with(|scratch| match scratch.try_borrow_mut() {
+            Ok(mut scratch) => self.lookup_with_scratch(input, verbosity, max_edit_distance, include_unknown, include_self, &mut scratch),
+            // a custom distance may look words up itself while the buffers are in use
+            Err(_) => self.lookup_with_scratch(input, verbosity, max_edit_distance, include_unknown, include_self, &mut LookupScratch::default()),
+        })
+    }
+
+    /// Identical to `lookup` working in the supplied buffers instead of the ones
+    /// kept for the current thread.
+    pub fn lookup_with_scratch(&self, input: &str, verbosity: Verbosity, max_edit_distance: usize, include_unknown: bool,
+                               include_self: bool, scratch: &mut LookupScratch) -> Vec<SuggestItem> {
+        self.lookup_borrowed_with_scratch(input, verbosity, max_edit_distance, include_unknown, include_self, scratch)
+            .into_iter()
+            .map(SuggestRef::into_owned)
+            .collect()
+    }
+
+    /// Identical to `lookup` with the terms of the suggestions borrowed from the dictionary
+    /// rather than cloned, see `SuggestRef`. The lookup cache holds its own copies, so with
+    /// the cache enabled the terms are owned.
+    ///
+    /// # Panics
+    ///
+    /// When max_edit_distance exceeds the max dictionary edit distance, see `try_lookup`.
+    pub fn lookup_borrowed(&self, input: &str, verbosity: Verbosity, max_edit_distance: usize, include_unknown: bool, include_self: bool) -> Vec<SuggestRef<'_>> {
+        if self.lookup_cache_capacity != 0 {
+            return self.lookup(input, verbosity, max_edit_distance, include_unknown, include_self).into_iter().map(SuggestRef::from).collect();
+        }
+        LOOKUP_SCRATCH.with(|scratch| match scratch.try_borrow_mut() {
+            Ok(mut scratch) => self.lookup_borrowed_with_scratch(input, verbosity, max_edit_distance, include_unknown, include_self, &mut scratch),
+            // a custom distance may look words up itself while the buffers are in use
+            Err(_) => self.lookup_borrowed_with_scratch(input, verbosity, max_edit_distance, include_unknown, include_self, &mut LookupScratch::default()),
+        })
+    }
+
+    // the suggestions of lookup_with_scratch before their terms are cloned
+    fn lookup_borrowed_with_scratch(&self, input: &str, verbosity: Verbosity, max_edit_distance: usize, include_unknown: bool,
+                                    include_self: bool, scratch: &mut LookupScratch) -> Vec<SuggestRef<'_>> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("lookup", input, max_edit_distance).entered();
+        #[cfg(feature = "metrics")]
+        let start = metrics::now();
+        let mut suggestions = if self.suggestion_ranker.is_none() {
+            self.gather_suggestions(input, verbosity, max_edit_distance, include_unknown, include_self, scratch)
+        } else {
+            // the ranker picks the top suggestion among the closest ones
+            let gathered = if verbosity == Verbosity::Top { Verbosity::Closest } else { verbosity };
+            let mut suggestions: Vec<SuggestItem> = self.gather_suggestions(input, gathered, max_edit_distance, include_unknown, include_self, scratch)
+                .into_iter()
+                .map(SuggestRef::into_owned)
+                .collect();
+            let normalized = self.normalize(input);
+            self.rank(&mut suggestions, &LookupContext { input: &normalized, max_edit_distance, previous_word: None });
+            if verbosity == Verbosity::Top {
+                suggestions.truncate(1);
+            }
+            suggestions.into_iter().map(SuggestRef::from).collect()
+        };
+        let corpus_size = self.corpus_size();
+        for si in suggestions.iter_mut() {
+            si.probability = Some(self.probability(si.count as f64 / corpus_size, si.distance));
+            if !self.entry_metadata.is_empty() {
+                si.metadata = self.entry_metadata.get(si.term.as_ref()).cloned();
+            }
+        }
+        #[cfg(feature = "metrics")]
+        self.metrics.record_lookup(scratch.candidates_checked, scratch.distance_computations, metrics::now().saturating_sub(start));
+        self.log(&LogEvent::Lookup { input, candidates: scratch.candidates_checked, suggestions: suggestions.len(), termination: scratch.termination });
+        suggestions
+    }
+
+    // sets the metadata of the suggestions whose word has any
+    fn attach_entry_metadata(&self, suggestions: &mut [SuggestItem]) {
+        if self.entry_metadata.is_empty() {
+            return;
+        }
+        for si in suggestions.iter_mut() {
+            si.metadata = self.entry_metadata.get(&si.term).cloned();
+        }
+    }
+
+    // the probability of the words, discounted by the edit decay for every edit
+    pub(crate) fn probability(&self, words_probability: f64, distance: usize) -> f64 {
+        words_probability.min(1.0) * self.edit_decay.powi(distance.min(i32::MAX as usize) as i32)
+    }
+
+    // orders the suggestions by the suggestion ranker, or by their sort key without one
+    fn rank(&self, suggestions: &mut Vec<SuggestItem>, context: &LookupContext) {
+        match &self.suggestion_ranker {
+            Some(ranker) => {
+                let mut scored: Vec<(f64, SuggestItem)> = suggestions.drain(..).map(|si| (ranker.score(&si, context), si)).collect();
+                scored.sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| a.1.sort_key().cmp(&b.1.sort_key())));
+                suggestions.extend(scored.into_iter().map(|(_, si)| si));
+                dedup_terms(suggestions, |si| &si.term);
+            }
+            None => suggestions.sort_by(|a, b| a.sort_key().cmp(&b.sort_key())),
+        }
+    }
+
+    // the suggestions of lookup_with_scratch in the order of their sort key
+    fn gather_suggestions<'s>(&'s self, input: &str, verbosity: Verbosity, max_edit_distance: usize, include_unknown: bool,
+                              include_self: bool, scratch: &mut LookupScratch) -> Vec<SuggestRef<'s>> {
         //verbosity=Top: the suggestion with the highest term frequency of the suggestions of smallest edit distance found
         //verbosity=Closest: all suggestions of smallest edit distance found, the suggestions are ordered by term frequency
         //verbosity=All: all suggestions <= maxEditDistance, the suggestions are ordered by edit distance, then by term frequency (slower, no early termination)
 
         // maxEditDistance used in Lookup can't be bigger than the maxDictionaryEditDistance
         // used to construct the underlying dictionary structure.
-        assert!(max_edit_distance <= self.dictionary_edit_distance);
+        if let Err(err) = self.check_edit_distance(max_edit_distance) {
+            panic!("{}", err);
+        }
+        let normalized = self.normalize(input);
+        let input: &str = &normalized;
         let mut suggestions = vec![];
         let input_gc = GraphemeClusters::new(input);
+        scratch.candidates_checked = 0;
+        scratch.distance_computations = 0;
+        scratch.termination = None;
         let input_len = input_gc.len();
 
-        let end = |mut suggestions: Vec<SuggestItem>| -> Vec<SuggestItem> {
+        let end = |mut suggestions: Vec<SuggestRef<'s>>| -> Vec<SuggestRef<'s>> {
             if include_unknown && suggestions.is_empty() {
-                suggestions.push(SuggestItem::new(String::from(input), max_edit_distance + 1, 0));
+                suggestions.push(SuggestRef::new(Cow::Owned(String::from(input)), max_edit_distance + 1, 0));
             }
             suggestions
         };
 
-        // early exit - word is too big to possibly match any words
-        if input_len < max_edit_distance || input_len - max_edit_distance > self.max_dictionary_word_length {
-            return end(suggestions);
-        }
-
         // quick look for exact match
-        if self.words.contains_key(input) {
+        if let Some((term, count)) = self.storage.word_entry(input).filter(|_| !self.is_faded(input)) {
             // early exit - return exact match, unless caller wants all matches
             if include_self {
-                suggestions.push(SuggestItem::new(String::from(input), 0, self.words[input]));
+                suggestions.push(SuggestRef::new(term, 0, count));
             }
             if verbosity != Verbosity::All {
+                scratch.termination = Some(EarlyTermination::ExactMatch);
                 return end(suggestions);
             }
         }
 
+        // a word ignored for the session is as good as an exact match
+        if self.is_ignored(input) {
+            scratch.termination = Some(EarlyTermination::Ignored);
+            if include_self {
+                suggestions.push(SuggestRef::new(Cow::Owned(String::from(input)), 0, 0));
+            }
+            return end(suggestions);
+        }
+
+        // interchangeable spellings are as close as an exact match
+        let equivalents: Vec<SuggestRef> = self.equivalents(input).into_iter().map(SuggestRef::from).collect();
+        if !equivalents.is_empty() && verbosity != Verbosity::All {
+            let mut equivalents = equivalents;
+            equivalents.sort_by(|a, b| a.sort_key().cmp(&b.sort_key()));
+            dedup_terms(&mut equivalents, |si| &si.term);
+            if verbosity == Verbosity::Top {
+                equivalents.truncate(1);
+            }
+            scratch.termination = Some(EarlyTermination::Equivalent);
+            return equivalents;
+        }
+
+        if self.lookup_limits.max_input_len.is_some_and(|max_input_len| input_len > max_input_len) {
+            self.truncated_lookups.fetch_add(1, Ordering::Relaxed);
+            scratch.termination = Some(EarlyTermination::InputTooLong);
+            return end(vec![]);
+        }
+
+        // early exit - word is too big to possibly match any words
+        if input_len < max_edit_distance || input_len - max_edit_distance > self.max_dictionary_word_length {
+            scratch.termination = Some(EarlyTermination::LengthOutOfRange);
+            suggestions.extend(equivalents);
+            return end(suggestions);
+        }
+
         // early termination, if we only want to check if word in dictionary or get its frequency e.g. for word segmentation
         if max_edit_distance == 0 {
+            scratch.termination = Some(EarlyTermination::ZeroEditDistance);
+            suggestions.extend(equivalents);
             return end(suggestions);
         }
 
-        // deletes we've considered already
-        let mut deletes_considered: HashSet<String> = HashSet::new();
-        // suggestions we've considered already
-        let mut suggestions_considered: HashSet<&str> = HashSet::new();
-        // we considered the input already in the word.TryGetValue above
-        suggestions_considered.insert(input);
+        #[cfg(feature = "dawg")]
+        if self.backend == Backend::Dawg {
+            suggestions.extend(self.search_dawg(input, verbosity, max_edit_distance, scratch));
+            return self.finish_suggestions(suggestions, equivalents, end);
+        }
+
+        scratch.clear();
+        // candidates are appended to one buffer and deletes are deduplicated by hash.
+        // suggestions we've considered already are tracked by id, the input was
+        // considered in the word.TryGetValue above and is skipped wherever it is found
+        let LookupScratch { candidate_text, candidates, deletes_considered, suggestions_considered, candidates_checked, distance_computations, termination } = scratch;
 
         let mut max_edit_distance2 = max_edit_distance;
         let mut candidate_pointer = 0;
-        let mut candidates: Vec<String> = Vec::new();
 
         // add original prefix
         let mut input_prefix_len = input_len;
-        if input_prefix_len > self.prefix_length {
+        let input_prefix = if input_prefix_len > self.prefix_length {
             input_prefix_len = self.prefix_length;
-            let range = input_gc.get_slice_range(0..input_prefix_len);
-            candidates.push(unsafe { String::from(input.get_unchecked(range)) });
+            input_gc.slice(0..input_prefix_len)
         } else {
-            candidates.push(String::from(input));
-        }
+            input
+        };
+        candidate_text.push_str(input_prefix);
+        candidates.push((0..input_prefix.len(), self.get_string_hash(input_prefix)));
 
-        let mut distance_comparator = EditDistance::new(DistanceAlgorithm::DamaerauOSA);
+        let mut distance_comparator = EditDistance::new(self.distance_algorithm.clone());
+        // the input is split into graphemes once for every candidate compared to it
+        let prepared_input = PreparedQuery::new(input);
 
         let should_continue = |prefix_length: usize,
                                suggestion_len: usize,
@@ -365,8 +1899,8 @@ impl SymSpell {
                 let l = suggestion_len - min;
 
                 if input.as_bytes()[i..] != suggestion.as_bytes()[j..] ||
-                    (min > 0 && &input_gc[k] != &suggestion_gc[l] &&
-                        (&input_gc[k - 1] != &suggestion_gc[l] || &input_gc[k] != &suggestion_gc[l - 1])) {
+                    (min > 0 && input_gc[k] != suggestion_gc[l] &&
+                        (input_gc[k - 1] != suggestion_gc[l] || input_gc[k] != suggestion_gc[l - 1])) {
                     // number of edits in prefix == max_edit_distance  AND no identical suffix
                     //, then edit_distance > max_edit_distance and no need for Levenshtein calculation
                     //      (input_len >= prefix_length) && (suggestion_len >= prefix_length)
@@ -377,8 +1911,16 @@ impl SymSpell {
         };
 
         while candidate_pointer < candidates.len() {
-            let candidate = &candidates[candidate_pointer].clone();
+            if self.lookup_limits.max_candidates.is_some_and(|max_candidates| candidate_pointer >= max_candidates) {
+                // the suggestions found so far may not be the closest so none are returned
+                self.truncated_lookups.fetch_add(1, Ordering::Relaxed);
+                *candidates_checked = candidate_pointer;
+                *termination = Some(EarlyTermination::CandidateLimit);
+                return end(vec![]);
+            }
+            let (candidate_range, str_hash) = candidates[candidate_pointer].clone();
             candidate_pointer += 1;
+            let candidate = &candidate_text[candidate_range];
             let candidate_gc = GraphemeClusters::new(candidate);
             let candidate_len = candidate_gc.len();
             let len_diff = input_prefix_len - candidate_len;
@@ -393,19 +1935,24 @@ impl SymSpell {
                 break;
             }
             // read candidate entry from dictionary
-            let str_hash = self.get_string_hash(candidate);
-            if self.deletes.contains_key(&str_hash) {
-                let dict_suggestions = self.deletes.get(&str_hash).unwrap();
+            if let Some(dict_suggestions) = self.deletes(candidate, candidate_len, str_hash) {
                 // iterate through suggestions (to other correct dictionary items) of delete item and add them to suggestion list
-                for suggestion in dict_suggestions {
-                    if suggestion == input {
+                for entry in dict_suggestions.iter() {
+                    let suggestion_len = entry.len as usize;
+                    if suggestion_len > input_len && f64::abs((suggestion_len - input_len) as f64) > max_edit_distance2 as f64 || // input and sug lengths diff > allowed/current best distance
+                        suggestion_len < candidate_len // sug must be for a different delete string, in same bin only because of hash collision
+                    {
                         continue;
                     }
-                    let suggestion_gc = GraphemeClusters::new(suggestion);
-                    let suggestion_len = suggestion_gc.len();
-                    if suggestion_len > input_len && f64::abs((suggestion_len - input_len) as f64) > max_edit_distance2 as f64 || // input and sug lengths diff > allowed/current best distance
-                        suggestion_len < candidate_len || // sug must be for a different delete string, in same bin only because of hash collision
-                        (suggestion_len == candidate_len && suggestion != candidate) // if sug len = delete len, then it either equals delete or is in same bin only because of hash collision
+                    // the word is only resolved from its id once its length is of interest
+                    let suggestion_entry = match self.storage.word(entry.id) {
+                        Some(word) => word,
+                        None => continue,
+                    };
+                    let suggestion: &str = &suggestion_entry;
+                    if suggestion == input ||
+                        (suggestion_len == candidate_len && suggestion != candidate) || // if sug len = delete len, then it either equals delete or is in same bin only because of hash collision
+                        self.is_faded(suggestion)
                     {
                         continue;
                     }
@@ -413,6 +1960,7 @@ impl SymSpell {
                     if suggestion_prefix_len > input_prefix_len && suggestion_prefix_len - candidate_len > max_edit_distance2 {
                         continue;
                     }
+                    let suggestion_gc = GraphemeClusters::new(suggestion);
                     // True Damerau-Levenshtein Edit Distance: adjust distance, if both distances>0
                     // We allow simultaneous edits (deletes) of maxEditDistance on on both the dictionary and the input term.
                     // For replaces and adjacent transposes the resulting edit distance stays <= maxEditDistance.
@@ -420,11 +1968,11 @@ impl SymSpell {
                     // To prevent suggestions of a higher edit distance, we need to calculate the resulting edit distance, if there are simultaneous edits on both sides.
                     // Example: (bank==bnak and bank==bink, but bank!=kanb and bank!=xban and bank!=baxn for maxEditDistance=1)
                     // Two deletes on each side of a pair makes them all equal, but the first two pairs have edit distance=1, the others edit distance=2.
-                    let mut distance = 0;
+                    let distance;
                     if candidate_len == 0 {
                         // suggestions which have no common chars with input (inputLen<=maxEditDistance && suggestionLen<=maxEditDistance)
                         distance = input_len.max(suggestion_len);
-                        if distance > max_edit_distance2 || !suggestions_considered.insert(suggestion) {
+                        if distance > max_edit_distance2 || !suggestions_considered.insert(entry.id) {
                             continue;
                         }
                     } else if suggestion_len == 1 {
@@ -434,15 +1982,20 @@ impl SymSpell {
                         } else {
                             distance = input_len - 1;
                         }
+                        // a one char word is in the bucket of every delete down to itself
+                        if distance > max_edit_distance2 || !suggestions_considered.insert(entry.id) {
+                            continue;
+                        }
                     } else if should_continue(self.prefix_length, suggestion_len, max_edit_distance, candidate_len, input_len, suggestion, input, &input_gc, &suggestion_gc) {
                         continue;
                     } else {
                         // DeleteInSuggestionPrefix is somewhat expensive, and only pays off when verbosity is Top or Closest.
-                        if verbosity != Verbosity::All && !self.delete_in_suggestion_prefix(&candidate, &suggestion) ||
-                            !suggestions_considered.insert(suggestion) {
+                        if verbosity != Verbosity::All && !self.delete_in_suggestion_prefix(candidate, suggestion) ||
+                            !suggestions_considered.insert(entry.id) {
                             continue;
                         }
-                        let distance_comparison = distance_comparator.compare(input, suggestion, Some(max_edit_distance2));
+                        *distance_computations += 1;
+                        let distance_comparison = distance_comparator.compare_prepared(&prepared_input, suggestion, Some(max_edit_distance2));
                         if distance_comparison.is_none() {
                             continue;
                         }
@@ -452,20 +2005,19 @@ impl SymSpell {
                     // save some time do not process higher distances than those already found,
                     // if verbosity<All (note: maxEditDistance2 will always equal maxEditDistance when Verbosity.All)
                     if distance <= max_edit_distance2 {
-                        let suggestion_ct = *self.words.get(suggestion).unwrap_or(&0);
-                        let si = SuggestItem::new(suggestion.clone(), distance as usize, suggestion_ct);
+                        let suggestion_ct = self.storage.word_count(suggestion).unwrap_or(0);
                         if !suggestions.is_empty() {
                             match verbosity {
-                                Verbosity::Closest => {
-                                    if distance < max_edit_distance2 {
-                                        suggestions.clear();
-                                    }
+                                Verbosity::Closest if distance < max_edit_distance2 => {
+                                    suggestions.clear();
                                 }
 
                                 Verbosity::Top => {
-                                    if distance < max_edit_distance2 || suggestion_ct > suggestions[0].count {
+                                    // equal counts are decided alphabetically rather than by the order of the buckets
+                                    let top = &suggestions[0];
+                                    if distance < max_edit_distance2 || suggestion_ct > top.count || (suggestion_ct == top.count && suggestion < top.term.as_ref()) {
                                         max_edit_distance2 = distance;
-                                        suggestions[0] = si;
+                                        suggestions[0] = SuggestRef::new(suggestion_entry.clone(), distance, suggestion_ct);
                                     }
                                     continue;
                                 }
@@ -475,7 +2027,7 @@ impl SymSpell {
                         if verbosity != Verbosity::All {
                             max_edit_distance2 = distance;
                         }
-                        suggestions.push(si);
+                        suggestions.push(SuggestRef::new(suggestion_entry.clone(), distance, suggestion_ct));
                     }
                 }
             }
@@ -488,53 +2040,205 @@ impl SymSpell {
                 if verbosity != Verbosity::All && len_diff >= max_edit_distance2 {
                     continue;
                 }
-                let len = candidate.len();
-                for (s, range) in candidate_gc {
-                    let mut slice: Vec<u8> = Vec::new();
-                    let s_len = s.len();
-                    if range.start != 0 {
-                        slice.extend_from_slice(candidate[..range.end - s_len].as_bytes());
-                    }
-                    if range.end != len {
-                        slice.extend_from_slice(candidate[range.start + s_len..].as_bytes());
-                    }
-                    let delete = unsafe { String::from_utf8_unchecked(slice) };
-                    if deletes_considered.insert(delete.clone()) {
-                        candidates.push(delete);
+                // the candidate is copied out of the buffer its deletes are appended to
+                let mut candidate_copy: SmallString<DELETE_INLINE_CAPACITY> = SmallString::new();
+                candidate_copy.push_str(candidate);
+                for (_, range) in GraphemeClusters::new(&candidate_copy) {
+                    let delete = Self::delete_at(&candidate_copy, range);
+                    let delete_hash = self.get_string_hash(&delete);
+                    if deletes_considered.insert(delete_hash) {
+                        let start = candidate_text.len();
+                        candidate_text.push_str(&delete);
+                        candidates.push((start..candidate_text.len(), delete_hash));
                     }
                 }
             }
         }
+        *candidates_checked = candidate_pointer;
+        self.finish_suggestions(suggestions, equivalents, end)
+    }
+
+    // merges the equivalent spellings into the suggestions found and orders them
+    fn finish_suggestions<'s>(&self, mut suggestions: Vec<SuggestRef<'s>>, equivalents: Vec<SuggestRef<'s>>,
+                              end: impl FnOnce(Vec<SuggestRef<'s>>) -> Vec<SuggestRef<'s>>) -> Vec<SuggestRef<'s>> {
+        if !equivalents.is_empty() {
+            suggestions.retain(|si| equivalents.iter().all(|equivalent| equivalent.term != si.term));
+            suggestions.extend(equivalents);
+        }
         if suggestions.len() > 1 {
-            suggestions.sort_by(|a, b| {
-                if a.distance == b.distance {
-                    return b.count.cmp(&a.count);
-                }
-                b.distance.cmp(&a.distance)
-            })
+            suggestions.sort_by(|a, b| a.sort_key().cmp(&b.sort_key()));
+            dedup_terms(&mut suggestions, |si| &si.term);
         }
         end(suggestions)
     }
 
+    // the suggestions of gather_suggestions found by walking the graph of the words rather than their deletes
+    #[cfg(feature = "dawg")]
+    fn search_dawg(&self, input: &str, verbosity: Verbosity, max_edit_distance: usize, scratch: &mut LookupScratch) -> Vec<SuggestRef<'static>> {
+        let dawg = self.dawg.get_or_init(|| {
+            let mut words = Vec::with_capacity(self.storage.len());
+            self.storage.for_each_word(&mut |word, _| words.push(word.to_string()));
+            Dawg::new(words.iter().map(String::as_str))
+        });
+        let mut distance_comparator = EditDistance::new(self.distance_algorithm.clone());
+        let mut suggestions: Vec<SuggestRef> = vec![];
+        let visited = dawg.search(input, max_edit_distance, self.lookup_limits.max_candidates, &mut |word, _| {
+            if word == input || self.is_faded(word) {
+                return;
+            }
+            // the walk bounds the optimal string alignment distance, the distance algorithm has the final say
+            scratch.distance_computations += 1;
+            if let Some(distance) = distance_comparator.compare(input, word, Some(max_edit_distance)) {
+                let count = self.storage.word_count(word).unwrap_or(0);
+                suggestions.push(SuggestRef::new(Cow::Owned(word.to_string()), distance, count));
+            }
+        });
+        scratch.candidates_checked = match visited {
+            Some(visited) => visited,
+            None => {
+                // the suggestions found so far may not be the closest so none are returned
+                self.truncated_lookups.fetch_add(1, Ordering::Relaxed);
+                scratch.termination = Some(EarlyTermination::CandidateLimit);
+                return vec![];
+            }
+        };
+        if verbosity != Verbosity::All {
+            let closest = suggestions.iter().map(|si| si.distance).min().unwrap_or(0);
+            suggestions.retain(|si| si.distance == closest);
+            suggestions.sort_by(|a, b| a.sort_key().cmp(&b.sort_key()));
+            if verbosity == Verbosity::Top {
+                suggestions.truncate(1);
+            }
+        }
+        suggestions
+    }
+
     /// <summary>Find suggested spellings for a multi-word input string (supports word splitting/merging).</summary>
     /// <param name="input">The string being spell checked.</param>
     /// <param name="maxEditDistance">The maximum edit distance between input and suggested words.</param>
     /// <returns>A List of SuggestItem object representing suggested correct spellings for the input string.</returns>
     pub fn lookup_compound(&self, input: &str, max_edit_distance: usize) -> Vec<SuggestItem> {
-        let term_list = SymSpell::parse_words(input);
+        self.lookup_compound_with_options(input, max_edit_distance, CompoundOptions::default())
+    }
+
+    /// <summary>Find suggested spellings for a multi-word input string using the supplied options.</summary>
+    /// <param name="input">The string being spell checked.</param>
+    /// <param name="maxEditDistance">The maximum edit distance between input and suggested words.</param>
+    /// <param name="options">Per call heuristics, see CompoundOptions.</param>
+    /// <returns>A List of SuggestItem object representing suggested correct spellings for the input string.</returns>
+    pub fn lookup_compound_with_options(&self, input: &str, max_edit_distance: usize, options: CompoundOptions) -> Vec<SuggestItem> {
+        vec![self.lookup_compound_detailed(input, max_edit_distance, options).suggestion]
+    }
+
+    /// Identical to `lookup_compound_with_options` also returning every part of the
+    /// input with what it was replaced with and whether it was unchanged, corrected or
+    /// unknown, i.e. kept as is for lack of a correction, so a UI can style each differently.
+    ///
+    /// example:
+    /// let correction = sym_spell.lookup_compound_detailed("teh qxzvw fox", 2, CompoundOptions::default());
+    /// let statuses: Vec<PartStatus> = correction.parts.iter().map(|part| part.status).collect();
+    ///
+    /// assert_eq!(statuses, vec![PartStatus::Corrected, PartStatus::Unknown, PartStatus::Unchanged]);
+    pub fn lookup_compound_detailed(&self, input: &str, max_edit_distance: usize, options: CompoundOptions) -> CompoundCorrection {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("lookup_compound", input, max_edit_distance).entered();
+        let normalized = self.normalize(input);
+        let input: &str = &normalized;
+        let tokens = self.compound_tokens(input);
+        let terms = self.compound_terms(&tokens, options);
+        self.merge_compound(input, &tokens, &terms, max_edit_distance, options,
+                            |i| self.lookup(&terms[i], Verbosity::Top, max_edit_distance, false, true),
+                            |i| self.lookup(&Self::combi(&terms, i), Verbosity::Top, max_edit_distance, false, true))
+    }
+
+    /// Identical to `lookup_compound_detailed` returning `SpellcheckerError::EditDistanceTooLarge`
+    /// when max_edit_distance exceeds the max dictionary edit distance, where it panics.
+    pub fn try_lookup_compound_detailed(&self, input: &str, max_edit_distance: usize, options: CompoundOptions) -> Result<CompoundCorrection, SpellcheckerError> {
+        self.check_edit_distance(max_edit_distance)?;
+        Ok(self.lookup_compound_detailed(input, max_edit_distance, options))
+    }
+
+    /// Identical to `lookup_compound_with_options` returning `SpellcheckerError::EditDistanceTooLarge`
+    /// when max_edit_distance exceeds the max dictionary edit distance, where it panics.
+    ///
+    /// example:
+    /// let sym_spell = SymSpell::new(Some(2), None, None);
+    ///
+    /// assert!(sym_spell.try_lookup_compound("whereis th elove", 3, CompoundOptions::default()).is_err());
+    pub fn try_lookup_compound(&self, input: &str, max_edit_distance: usize, options: CompoundOptions) -> Result<Vec<SuggestItem>, SpellcheckerError> {
+        self.check_edit_distance(max_edit_distance)?;
+        Ok(self.lookup_compound_with_options(input, max_edit_distance, options))
+    }
+
+    /// Identical to `lookup_compound_with_options` except the lookups for every
+    /// term, and for every pair of adjacent terms that may be combined, run
+    /// concurrently before the sequential merge pass. Pays off for long inputs
+    /// such as paragraphs corrected server side.
+    #[cfg(feature = "parallel")]
+    pub fn lookup_compound_parallel(&self, input: &str, max_edit_distance: usize, options: CompoundOptions) -> Vec<SuggestItem>
+        where S: Sync {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("lookup_compound", input, max_edit_distance).entered();
+        let normalized = self.normalize(input);
+        let input: &str = &normalized;
+        let tokens = self.compound_tokens(input);
+        let terms = self.compound_terms(&tokens, options);
+        let (singles, combis): (Vec<Vec<SuggestItem>>, Vec<Vec<SuggestItem>>) = rayon::join(
+            || terms.par_iter().map(|term| self.lookup(term, Verbosity::Top, max_edit_distance, false, true)).collect(),
+            || (1..terms.len()).into_par_iter().map(|i| self.lookup(&Self::combi(&terms, i), Verbosity::Top, max_edit_distance, false, true)).collect(),
+        );
+        vec![self.merge_compound(input, &tokens, &terms, max_edit_distance, options, |i| singles[i].clone(), |i| combis[i - 1].clone()).suggestion]
+    }
+
+    fn compound_tokens<'a>(&self, input: &'a str) -> Vec<Token<'a>> {
+        self.tokenizer.tokenize(input).into_iter()
+            .filter(|token| token.class == TokenClass::Word)
+            .collect()
+    }
+
+    // the text of the tokens, lowercase when the casing of the input is transferred to the correction
+    fn compound_terms<'a>(&self, tokens: &[Token<'a>], options: CompoundOptions) -> Vec<Cow<'a, str>> {
+        tokens.iter()
+            .map(|token| if options.transfer_casing { self.lowercase(token.text) } else { Cow::Borrowed(token.text) })
+            .collect()
+    }
+
+    // the term at i appended to the one before it
+    fn combi(terms: &[Cow<str>], i: usize) -> String {
+        let mut combi = String::from(&*terms[i - 1]);
+        combi.push_str(&terms[i]);
+        combi
+    }
+
+    // the sequential pass of lookup_compound, `single` and `combi` supply the Verbosity::Top
+    // suggestions for the term at i and for it combined with the term before it
+    #[allow(clippy::too_many_arguments)]
+    fn merge_compound(&self, input: &str, tokens: &[Token], terms: &[Cow<str>], max_edit_distance: usize, options: CompoundOptions,
+                      single: impl Fn(usize) -> Vec<SuggestItem>, combi: impl Fn(usize) -> Vec<SuggestItem>) -> CompoundCorrection {
+        let term_list: Vec<&str> = terms.iter().map(|term| &**term).collect();
+        let n = self.corpus_size();
         let mut suggestion_parts: Vec<SuggestItem> = Vec::new(); // 1 line with separate parts
-        let mut distance_comparator = EditDistance::new(DistanceAlgorithm::DamaerauOSA);
+        // the input range and status of each of the suggestion parts
+        let mut part_statuses: Vec<(Range<usize>, PartStatus)> = Vec::new();
+        let mut distance_comparator = EditDistance::new(self.distance_algorithm.clone());
 
         // translate every term to its best suggestion, otherwise it remains unchanged
         let mut last_combi = false;
         for i in 0..term_list.len() {
-            let mut suggestions = self.lookup(&term_list[i], Verbosity::Top, max_edit_distance, false, true); // suggestions for a single term
+            let mut suggestions = single(i); // suggestions for a single term
 
-            if i > 0 && !last_combi {
-                let mut combi = String::from(term_list[i - 1]);
-                combi.push_str(&term_list[i]);
+            // likely a proper noun - keep it as is and never merge it with its neighbors
+            if options.preserve_proper_nouns && i > 0 && Self::is_proper_noun_candidate(input, &tokens[i - 1], &tokens[i]) &&
+                !suggestions.first().is_some_and(|s| starts_with_uppercase(&s.term)) {
+                let count = self.known_word_count(tokens[i].text).unwrap_or(0);
+                suggestion_parts.push(SuggestItem::new(String::from(tokens[i].text), 0, count));
+                part_statuses.push((tokens[i].byte_range.clone(), if count > 0 { PartStatus::Unchanged } else { PartStatus::Unknown }));
+                last_combi = true;
+                continue;
+            }
 
-                let mut suggestions_combi = self.lookup(&combi, Verbosity::Top, max_edit_distance, false, true);
+            if i > 0 && !last_combi {
+                let mut suggestions_combi = combi(i);
                 if !suggestions_combi.is_empty() {
                     let best1 = suggestion_parts.last().unwrap();
                     let mut best2 = &mut SuggestItem::default();
@@ -547,16 +2251,18 @@ impl SymSpell {
                         best2.distance = max_edit_distance + 1;
                         // estimated word occurrence probability P=10 / (N * 10^word length l)
                         let term_len = GraphemeClusters::new(&best2.term).len();
-                        best2.count = (10.0 / 10.0f64.powf(term_len as f64)) as usize;
+                        best2.count = (10.0 / 10.0f64.powf(term_len as f64)) as Count;
                     }
                     // distance1=edit distance between 2 split terms und their best corrections : als comparative value for the combination
                     let distance = best1.distance + best2.distance;
                     let suggestion_combi = &mut suggestions_combi[0];
                     if suggestion_combi.distance + 1 < distance ||
-                        (suggestion_combi.distance + 1 == distance && suggestion_combi.count > (best1.count as f64 / N * best2.count as f64) as usize) {
+                        (suggestion_combi.distance + 1 == distance && suggestion_combi.count > (best1.count as f64 / n * best2.count as f64) as Count) {
                         suggestion_combi.distance += 1;
                         suggestion_parts.pop();
                         suggestion_parts.push(suggestions_combi.remove(0));
+                        part_statuses.pop();
+                        part_statuses.push((tokens[i - 1].byte_range.start..tokens[i].byte_range.end, PartStatus::Corrected));
                         last_combi = true;
 
                         continue;
@@ -570,22 +2276,31 @@ impl SymSpell {
             let term = &term_list[i];
             let term_gc = GraphemeClusters::new(term);
             let term_len = term_gc.len();
+            let byte_range = tokens[i].byte_range.clone();
+            // with trigrams loaded the closest corrections are ranked by the two words before them
+            if !self.trigrams.is_empty() && suggestions.first().is_some_and(|si| si.distance > 0) {
+                let words: Vec<&str> = suggestion_parts.iter().flat_map(|si| si.term.split(' ')).collect();
+                let context = words[words.len().saturating_sub(2)..].join(" ");
+                if !context.is_empty() {
+                    suggestions = self.lookup_with_context(&context, term, Verbosity::Top, max_edit_distance, false, true);
+                }
+            }
             if !suggestions.is_empty() && (suggestions[0].distance == 0 || term_len == 1) {
                 // choose best suggestion
+                let status = if suggestions[0].distance == 0 { PartStatus::Unchanged } else { PartStatus::Corrected };
                 suggestion_parts.push(suggestions.remove(0));
+                part_statuses.push((byte_range, status));
             } else {
                 // if no perfect suggestion, split word into pairs
                 let mut best_suggestion_split: Option<SuggestItem> = None;
                 // add original term
                 if !suggestions.is_empty() {
-                    best_suggestion_split = suggestions.get(0).cloned();
+                    best_suggestion_split = suggestions.first().cloned();
                 }
                 if term_len > 1 {
                     for j in 1..term_len {
-                        let part1_range = term_gc.get_slice_range(0..j);
-                        let part2_range = term_gc.get_slice_range(j..term_len);
-                        let part1 = unsafe { term.get_unchecked(part1_range) };
-                        let part2 = unsafe { term.get_unchecked(part2_range) };
+                        let part1 = term_gc.slice(0..j);
+                        let part2 = term_gc.slice(j..term_len);
 
                         let mut suggestion_split = SuggestItem::default();
                         let suggestions1 = self.lookup(part1, Verbosity::Top, max_edit_distance, false, true);
@@ -594,10 +2309,10 @@ impl SymSpell {
                             if !suggestions2.is_empty() {
                                 // select best suggestion for split pair
                                 suggestion_split.term.push_str(&suggestions1[0].term);
-                                suggestion_split.term.push_str(" ");
+                                suggestion_split.term.push(' ');
                                 suggestion_split.term.push_str(&suggestions2[0].term);
 
-                                let distance_opt = distance_comparator.compare(&term, &suggestion_split.term, Some(max_edit_distance));
+                                let distance_opt = distance_comparator.compare(term, &suggestion_split.term, Some(max_edit_distance));
                                 let distance2 = distance_opt.unwrap_or(max_edit_distance + 1);
 
                                 if best_suggestion_split.as_ref().is_some() {
@@ -610,9 +2325,12 @@ impl SymSpell {
                                     }
                                 }
                                 suggestion_split.distance = distance2;
+                                // The Naive Bayes probability of the word combination is the product of the two word probabilities: P(AB) = P(A) * P(B)
+                                let naive_count = suggestions1[0].count as f64 / n * suggestions2[0].count as f64;
                                 // if bigram exists in bigram dictionary
-                                if self.bigrams.contains_key(&suggestion_split.term) {
-                                    suggestion_split.count = *self.bigrams.get(&suggestion_split.term).unwrap();
+                                if let Some(&bigram_count) = self.bigrams.get(&suggestion_split.term) {
+                                    // weighed against the naive estimate by the bigram weight
+                                    suggestion_split.count = (self.bigram_weight * bigram_count as f64 + (1.0 - self.bigram_weight) * naive_count) as Count;
                                     // increase count, if split.corrections are part of or identical to input
                                     // single term correction exists
                                     let mut term_compare = String::from(&suggestions1[0].term);
@@ -632,9 +2350,8 @@ impl SymSpell {
                                         suggestion_split.count = suggestion_split.count.max(suggestions1[0].count.max(suggestions2[0].count + 1));
                                     }
                                 } else {
-                                    // The Naive Bayes probability of the word combination is the product of the two word probabilities: P(AB) = P(A) * P(B)
-                                    // use it to estimate the frequency count of the combination, which then is used to rank/select the best splitting variant
-                                    suggestion_split.count = self.bigram_count_min.min((suggestions1[0].count as f64 / N * suggestions2[0].count as f64) as usize)
+                                    // use the naive estimate of the frequency count of the combination, which then is used to rank/select the best splitting variant
+                                    suggestion_split.count = self.bigram_count_min.map_or(naive_count as Count, |min| min.min(naive_count as Count))
                                 }
                                 if best_suggestion_split.is_none() || suggestion_split.count > best_suggestion_split.as_ref().unwrap().count {
                                     best_suggestion_split = Some(suggestion_split);
@@ -642,148 +2359,594 @@ impl SymSpell {
                             }
                         }
                     }
-                    if best_suggestion_split.is_some() {
-                        suggestion_parts.push(best_suggestion_split.unwrap())
+                    if let Some(best_suggestion_split) = best_suggestion_split {
+                        suggestion_parts.push(best_suggestion_split);
+                        part_statuses.push((byte_range, PartStatus::Corrected));
                     } else {
-                        let si = SuggestItem::new(String::from(*term), 10 / 10f64.powf(term_len as f64) as usize, max_edit_distance + 1);
+                        let si = SuggestItem::new(String::from(*term), max_edit_distance + 1, (10.0 / 10f64.powf(term_len as f64)) as Count);
                         suggestion_parts.push(si);
+                        part_statuses.push((byte_range, PartStatus::Unknown));
                     }
                 } else {
-                    let si = SuggestItem::new(String::from(term_list[i]), 10 / 10f64.powf(term_len as f64) as usize, max_edit_distance + 1);
+                    let si = SuggestItem::new(String::from(term_list[i]), max_edit_distance + 1, (10.0 / 10f64.powf(term_len as f64)) as Count);
                     suggestion_parts.push(si);
+                    part_statuses.push((byte_range, PartStatus::Unknown));
                 }
             }
         }
 
-        let mut count = N;
+        if options.transfer_casing {
+            for (suggestion_item, (byte_range, _)) in suggestion_parts.iter_mut().zip(&part_statuses) {
+                suggestion_item.term = transfer_casing_in(&input[byte_range.clone()], &suggestion_item.term, self.case_locale);
+            }
+        }
+
+        let mut count = n;
         let mut suggestion = SuggestItem::default();
         let mut s = String::new();
         let len = suggestion_parts.len();
-        for i in 0..len {
-            let suggestion_item = &mut suggestion_parts[i];
+        for (i, suggestion_item) in suggestion_parts.iter().enumerate() {
             s.push_str(&suggestion_item.term);
             if i != len - 1 {
-                s.push_str(" ");
+                s.push(' ');
             }
-            count *= suggestion_item.count as f64 / N;
+            count *= suggestion_item.count as f64 / n;
         }
 
-        suggestion.count = count as usize;
+        suggestion.count = count as Count;
         suggestion.term = s;
-        suggestion.distance = distance_comparator.compare(input, &suggestion.term, Some(usize::max_value())).unwrap_or(0);
+        suggestion.distance = distance_comparator.compare(input, &suggestion.term, Some(usize::MAX)).unwrap_or(0);
+        suggestion.probability = Some(self.probability(count / n, suggestion.distance));
 
-        return vec![suggestion];
+        let parts: Vec<CompoundPart> = suggestion_parts.into_iter().zip(part_statuses)
+            .map(|(suggestion, (byte_range, status))| CompoundPart { byte_range, suggestion, status })
+            .collect();
+        self.log(&LogEvent::LookupCompound { input, parts: parts.len(), distance: suggestion.distance });
+        CompoundCorrection { suggestion, parts }
     }
 
-    /// <summary>Find suggested spellings for a multi-word input string (supports word splitting/merging).</summary>
-    /// <param name="input">The string being spell checked.</param>
-    /// <param name="maxSegmentationWordLength">The maximum word length that should be considered.</param>
-    /// <param name="maxEditDistance">The maximum edit distance between input and corrected words
-    /// (0=no correction/segmentation only).</param>
-    /// <returns>The word segmented string,
-    /// the word segmented and spelling corrected string,
-    /// the Edit distance sum between input string and corrected string,
-    /// the Sum of word occurence probabilities in log scale (a measure of how common and probable the corrected segmentation is).</returns>
-    pub fn word_segmentation(&self, input: &str, max_edit_distance: usize, max_segmentation_word_len_opt: Option<usize>) -> (String, String, usize, f64) {
-        let max_segmentation_word_len = max_segmentation_word_len_opt.unwrap_or(self.max_dictionary_word_length);
-        let input_gc = GraphemeClusters::new(input);
-        let input_len = input_gc.len();
-        let capacity = max_segmentation_word_len.min(input_len);
-        let mut compositions: Vec<(String, String, usize, f64)> = Vec::with_capacity(capacity);
-
-        let mut circular_index = -1;
-        // outer loop (column): all possible part start positions
-        for j in 0..input_len {
-            // inner loop (row): all possible part lengths (from start position): part can't be bigger than longest word in dictionary (other than long unknown word)
-            let max = max_segmentation_word_len.min(input_len - j);
-            for i in 1..max + 1 {
-                // get top spelling correction/ed for part
-                let input_range = input_gc.get_slice_range(j..i);
-                let mut part = unsafe { input.get_unchecked(input_range).to_string() };
-                let mut separator_len = 0;
-                let mut top_edit_distance = 0;
-
-                // remove space for levensthein calculation
-                if " \n\r\t".contains(unsafe { input.get_unchecked(0..1) }) {
-                    part = unsafe { input.get_unchecked(j + 1..i).to_string() };
-                } else {
-                    // add ed+1: space did not exist, had to be inserted
-                    separator_len = 1;
+    /// Identical to `lookup` with the suggestions of the closest distance, or of every
+    /// distance for `Verbosity::All`, ranked by how often they follow the previous word
+    /// in the bigram dictionary. Their score is that bigram count, so "ther" after
+    /// "over" suggests "there" while after "lost" it suggests "their". Without a
+    /// bigram for any suggestion the order is that of `lookup`. Given the two previous
+    /// words, e.g. "went over", they are ranked by their trigram counts instead when
+    /// the trigram dictionary holds any of them.
+    ///
+    /// example:
+    /// let suggestions = sym_spell.lookup_with_context("over", "ther", Verbosity::Top, 2, false, false);
+    ///
+    /// assert_eq!(suggestions[0].term, "there");
+    pub fn lookup_with_context(&self, previous_word: &str, input: &str, verbosity: Verbosity, max_edit_distance: usize, include_unknown: bool, include_self: bool) -> Vec<SuggestItem> {
+        let candidates = match verbosity {
+            Verbosity::All => Verbosity::All,
+            Verbosity::Top | Verbosity::Closest => Verbosity::Closest,
+        };
+        let mut suggestions = self.lookup(input, candidates, max_edit_distance, include_unknown, include_self);
+        let previous = self.lowercase(previous_word);
+        let context: Vec<&str> = previous.split_whitespace().collect();
+        let context = &context[context.len().saturating_sub(2)..];
+        if let Some(last) = context.last() {
+            let trigram_counts: Vec<Count> = match context {
+                [first, second] => suggestions.iter().map(|si| self.trigram_frequency(first, second, &si.term)).collect(),
+                _ => vec![],
+            };
+            // back off to the bigrams when no suggestion follows the two words
+            let by_trigram = trigram_counts.iter().any(|&count| count > 0);
+            for (i, si) in suggestions.iter_mut().enumerate() {
+                si.score = if by_trigram { trigram_counts[i] } else { self.bigram_frequency(last, &si.term) } as f64;
+            }
+        }
+        let normalized = self.normalize(input);
+        let previous_word = context.last().copied();
+        self.rank(&mut suggestions, &LookupContext { input: &normalized, max_edit_distance, previous_word });
+        if verbosity == Verbosity::Top {
+            suggestions.truncate(1);
+        }
+        suggestions
+    }
+
+    /// Ranked suggestions for the token at a text cursor, e.g. for the suggestion strip
+    /// of an input field. While the token is still being typed, i.e. it is not followed by
+    /// whitespace or punctuation, dictionary words it is a prefix of are offered with a
+    /// distance of 0 alongside corrections of the token within the dictionary's max edit
+    /// distance. Each suggestion's score is how often it follows the previous word and
+    /// precedes the next one in the bigram dictionary, ordering them by their sort key.
+    ///
+    /// example:
+    /// let suggestions = sym_spell.suggest_in_context("please ", "hel", "", 3);
+    ///
+    /// assert_eq!(suggestions[0].term, "help");
+    pub fn suggest_in_context(&self, left_text: &str, current_token: &str, right_text: &str, max_results: usize) -> Vec<SuggestItem> {
+        let token = self.lowercase(current_token);
+        if token.is_empty() || max_results == 0 {
+            return vec![];
+        }
+        let mut suggestions = self.lookup(&token, Verbosity::All, self.dictionary_edit_distance, false, true);
+        let complete = GraphemeClusters::new(right_text).next()
+            .is_some_and(|(grapheme, _)| !self.tokenizer.is_word(grapheme));
+        if !complete {
+            let mut completions = vec![];
+            self.storage.for_each_word(&mut |word, count| {
+                if word.len() > token.len() && word.starts_with(&*token) {
+                    completions.push(SuggestItem::new(word.to_string(), 0, count));
                 }
+            });
+            for completion in completions {
+                match suggestions.iter_mut().find(|si| si.term == completion.term) {
+                    Some(existing) => existing.distance = 0,
+                    None => suggestions.push(completion),
+                }
+            }
+        }
 
-                // remove space from part1, add number of removed spaces to topEd
-                top_edit_distance += GraphemeClusters::new(&part).len();
-                // remove space
-                part = part.replace(" ", "");
-                // add number of removed spaces to ed
-                let part_len = GraphemeClusters::new(&part).len();
-                top_edit_distance -= part_len;
-
-                let results = self.lookup(&part, Verbosity::Top, max_edit_distance, false, true);
-                let (top_result, top_probability_log) = if !results.is_empty() {
-                    let result = &results[0];
-                    top_edit_distance += result.distance;
-                    // Naive Bayes Rule
-                    // we assume the word probabilities of two words to be independent
-                    // therefore the resulting probability of the word combination is the product of the two word probabilities
-
-                    // instead of computing the product of probabilities we are computing the sum of the logarithm of probabilities
-                    // because the probabilities of words are about 10^-10, the product of many such small numbers could exceed (underflow) the floating number range and become zero
-                    // log(ab)=log(a)+log(b)
-                    (&result.term, (result.count as f64 / N).log10())
-                } else {
-                    // default, if word not found
-                    // otherwise long input text would win as long unknown word (with ed=edmax+1 ), although there there should many spaces inserted
-                    (&part, (10.0 / (N * 10.0f64.powf(part_len as f64))).log10())
-                };
+        // context does not carry across the end of a sentence
+        let previous = self.tokenizer.tokenize(left_text).into_iter().rev()
+            .find(|token| token.class == TokenClass::Word)
+            .filter(|token| !ends_sentence(&left_text[token.byte_range.end..]))
+            .map(|token| self.lowercase(token.text).into_owned());
+        let next = self.tokenizer.tokenize(right_text).into_iter()
+            .find(|token| token.class == TokenClass::Word)
+            .filter(|token| !ends_sentence(&right_text[..token.byte_range.start]))
+            .map(|token| self.lowercase(token.text).into_owned());
+        for si in suggestions.iter_mut() {
+            let context = previous.as_ref().map_or(0, |previous| self.bigram_frequency(previous, &si.term))
+                .saturating_add(next.as_ref().map_or(0, |next| self.bigram_frequency(&si.term, next)));
+            si.score = context as f64;
+        }
+        suggestions.sort_by(|a, b| a.sort_key().cmp(&b.sort_key()));
+        suggestions.truncate(max_results);
+        suggestions
+    }
 
-                let destination_index = ((i as i32 + circular_index) % capacity as i32) as usize;
+    /// Dictionary words starting with the prefix, e.g. for the autocomplete of a search
+    /// box, the most frequent first and at most max_results of them. The prefix is
+    /// matched as is, the way `lookup` matches its input. The words are kept sorted in
+    /// an index built by the first completion after the dictionary changed.
+    ///
+    /// example:
+    /// let completions = sym_spell.complete("hel", 3);
+    ///
+    /// assert_eq!(completions[0].term, "help");
+    pub fn complete(&self, prefix: &str, max_results: usize) -> Vec<SuggestItem> {
+        let prefix = self.normalize(prefix);
+        let index = self.completion_index();
+        let mut completions: Vec<(&str, Count)> = index[completion_range(index, &prefix)].iter()
+            .map(|word| (word.as_str(), self.storage.word_count(word).unwrap_or(0)))
+            .collect();
+        completions.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        completions.truncate(max_results);
+        let mut completions: Vec<SuggestItem> = completions.into_iter().map(|(word, count)| SuggestItem::new(word.to_string(), 0, count)).collect();
+        self.attach_entry_metadata(&mut completions);
+        completions
+    }
 
-                //set values in first loop
-                if j == 0 {
-                    compositions[destination_index] = (part.to_string(), top_result.to_string(), top_edit_distance, top_probability_log);
-                }
+    /// Identical to `complete` tolerating one edit in a prefix of at least three
+    /// graphemes, so "hwlp" still completes to "helpful". The distance of a completion is
+    /// that of the closest prefix of the word, words starting with the prefix as typed
+    /// coming first.
+    ///
+    /// example:
+    /// let completions = sym_spell.complete_fuzzy("hwlp", 3);
+    ///
+    /// assert_eq!((completions[0].term.as_str(), completions[0].distance), ("help", 1));
+    pub fn complete_fuzzy(&self, prefix: &str, max_results: usize) -> Vec<SuggestItem> {
+        let prefix = self.normalize(prefix);
+        let graphemes = GraphemeClusters::new(&prefix).as_slices();
+        if graphemes.len() < MIN_FUZZY_PREFIX_LEN {
+            return self.complete(&prefix, max_results);
+        }
+        let index = self.completion_index();
+        let (first, rest) = prefix.split_at(graphemes[0].len());
 
-                if circular_index == -1 {
+        // the words within one edit of the prefix start with the prefix's first grapheme,
+        // with the rest of the prefix, or with another first grapheme
+        let mut starts = vec![first.to_string(), rest.to_string(), graphemes[1].to_string() + graphemes[0] + &rest[graphemes[1].len()..]];
+        let mut i = 0;
+        while let Some(word) = index.get(i) {
+            let ch = word.chars().next().unwrap_or_default();
+            if !first.starts_with(ch) {
+                starts.push(ch.to_string() + rest);
+                starts.push(ch.to_string() + &prefix);
+            }
+            i += index[i..].partition_point(|word| word.chars().next() <= Some(ch));
+        }
+
+        let mut distance_comparator = EditDistance::new(self.distance_algorithm.clone());
+        let mut distances: HashMap<&str, usize> = HashMap::new();
+        for start in starts {
+            for word in &index[completion_range(index, &start)] {
+                if distances.contains_key(word.as_str()) {
                     continue;
                 }
-                // Cleaner conditionals this way
-                let (_, _, d_distance_sum, d_probability_log_sum) = &compositions[destination_index];
-                let (c_segmented_string, c_corrected_string, c_distance_sum, c_probability_log_sum) = &compositions[circular_index as usize];
-
-                if i == max_segmentation_word_len ||
-                    //replace values if better probabilityLogSum, if same edit distance OR one space difference
-                    ((c_distance_sum + top_edit_distance == *d_distance_sum || c_distance_sum + separator_len + top_edit_distance == *d_distance_sum) &&
-                        d_probability_log_sum < c_probability_log_sum) ||
-                    c_distance_sum + separator_len + top_edit_distance < *d_distance_sum {
-                    compositions[destination_index] = (
-                        c_segmented_string.to_owned() + " " + &part,
-                        c_corrected_string.to_owned() + " " + top_result,
-                        *c_distance_sum + separator_len + top_edit_distance,
-                        *c_probability_log_sum + top_probability_log
-                    );
+                let word_graphemes = GraphemeClusters::new(word);
+                let len = word_graphemes.len();
+                // the closest of the word's prefixes one grapheme shorter or longer than the prefix
+                let distance = (graphemes.len() - 1..=graphemes.len() + 1)
+                    .filter(|&prefix_len| prefix_len <= len)
+                    .filter_map(|prefix_len| distance_comparator.compare(&prefix, word_graphemes.slice(0..prefix_len), Some(1)))
+                    .min();
+                if let Some(distance) = distance {
+                    distances.insert(word, distance);
                 }
             }
-            circular_index += 1;
-            if circular_index as usize == capacity {
-                circular_index = 0;
-            }
         }
+        let mut completions: Vec<SuggestItem> = distances.into_iter()
+            .map(|(word, distance)| SuggestItem::new(word.to_string(), distance, self.storage.word_count(word).unwrap_or(0)))
+            .collect();
+        completions.sort_by(|a, b| a.sort_key().cmp(&b.sort_key()));
+        completions.truncate(max_results);
+        self.attach_entry_metadata(&mut completions);
+        completions
+    }
 
-        compositions.remove(circular_index as usize)
+    // the dictionary words in byte order, sorted on first use
+    fn completion_index(&self) -> &[String] {
+        self.completion_index.get_or_init(|| {
+            let mut words = Vec::with_capacity(self.storage.len());
+            self.storage.for_each_word(&mut |word, _| {
+                if !self.is_faded(word) {
+                    words.push(word.to_string());
+                }
+            });
+            words.sort_unstable();
+            words
+        })
     }
 
-    // check whether all delete chars are present in the suggestion prefix in correct order, otherwise this is just a hash collision
-    fn delete_in_suggestion_prefix(&self, delete: &str, suggestion: &str) -> bool {
-        let delete_gc = GraphemeClusters::new(delete);
-        let suggestion_gc = GraphemeClusters::new(suggestion);
+    // how often the second word follows the first in the bigram dictionary
+    pub(crate) fn bigram_frequency(&self, first: &str, second: &str) -> Count {
+        self.bigrams.get(&(first.to_owned() + " " + second)).copied().unwrap_or(0)
+    }
 
-        let delete_len = delete_gc.len();
-        if delete_len == 0 {
-            return true;
+    // how often the third word follows the first two in the trigram dictionary
+    pub(crate) fn trigram_frequency(&self, first: &str, second: &str, third: &str) -> Count {
+        if self.trigrams.is_empty() {
+            return 0;
         }
+        self.trigrams.get(&[first, second, third].join(" ")).copied().unwrap_or(0)
+    }
 
-        let suggestion_len = suggestion_gc.len().min(self.prefix_length);
+    /// Counts the words of the text that are misspelled. A word is correct when it, or
+    /// its lowercase form, is in the dictionary and corrected when a suggestion within
+    /// the dictionary's max edit distance exists.
+    ///
+    /// example:
+    /// let stats = sym_spell.document_stats("Teh quick brown fox");
+    ///
+    /// assert_eq!(stats.corrected_tokens, 1);
+    /// assert_eq!(stats.error_rate(), 0.25);
+    pub fn document_stats(&self, text: &str) -> DocumentStats {
+        let mut stats = DocumentStats::default();
+        for (_, word) in self.document_words(text) {
+            stats.total_tokens += 1;
+            if self.is_known(&word) {
+                continue;
+            }
+            if self.lookup(&self.lowercase(&word), Verbosity::Top, self.dictionary_edit_distance, false, false).is_empty() {
+                stats.unknown_tokens += 1;
+            } else {
+                stats.corrected_tokens += 1;
+            }
+        }
+        stats
+    }
+
+    /// Finds the words of the text that are not in the dictionary along with
+    /// their byte offsets and best correction, e.g. to underline them in an editor.
+    /// A word repeating the word before it, with only whitespace between them, is
+    /// reported as an `IssueKind::RepeatedWord` unless the bigram dictionary holds
+    /// the pair, as it may for "had had". Corrections follow the case of the word
+    /// they replace and are capitalized at the start of a sentence, so "teh" opening
+    /// the text is corrected to "The".
+    ///
+    /// example:
+    /// let misspelled = sym_spell.check_text("the quikc fox", 2);
+    ///
+    /// assert_eq!(misspelled[0].byte_range, 4..9);
+    /// assert_eq!(misspelled[0].suggestion.as_ref().unwrap().term, "quick");
+    pub fn check_text(&self, text: &str, max_edit_distance: usize) -> Vec<MisspelledRange> {
+        let mut misspelled = vec![];
+        let mut previous: Option<(Range<usize>, String)> = None;
+        for (byte_range, word) in self.document_words(text) {
+            let lowercase = self.lowercase(&word).into_owned();
+            let gap = previous.as_ref().map(|(previous_range, _)| &text[previous_range.end..byte_range.start]);
+            let repeated = previous.as_ref().is_some_and(|(_, previous_word)| {
+                gap.is_some_and(|gap| gap.chars().all(char::is_whitespace)) && self.is_repeated_word(previous_word, &lowercase)
+            });
+            if repeated {
+                misspelled.push(MisspelledRange { byte_range: byte_range.clone(), suggestion: None, kind: IssueKind::RepeatedWord });
+            } else if !self.is_known(&word) {
+                let sentence_start = gap.map_or(true, ends_sentence);
+                let mut suggestion = self.lookup(&lowercase, Verbosity::Top, max_edit_distance, false, false).into_iter().next();
+                if let Some(si) = suggestion.as_mut() {
+                    si.term = self.match_case(&si.term, &word, sentence_start);
+                }
+                misspelled.push(MisspelledRange { byte_range: byte_range.clone(), suggestion, kind: IssueKind::Misspelling });
+            }
+            previous = Some((byte_range, lowercase));
+        }
+        misspelled
+    }
+
+    // the correction cased like the word it replaces: in capitals for a word in capitals and
+    // capitalized for a capitalized word or one starting a sentence. A correction with
+    // capitals of its own, such as a name, keeps them
+    pub(crate) fn match_case(&self, term: &str, word: &str, sentence_start: bool) -> String {
+        if term.chars().any(char::is_uppercase) {
+            term.to_string()
+        } else if is_all_uppercase(word) {
+            uppercase_in(term, self.case_locale)
+        } else if sentence_start || starts_with_uppercase(word) {
+            capitalize_in(term, self.case_locale)
+        } else {
+            term.to_string()
+        }
+    }
+
+    // true when the lowercase word is the previous one and the bigram dictionary does not hold the pair
+    pub(crate) fn is_repeated_word(&self, previous: &str, word: &str) -> bool {
+        previous == word && self.bigram_frequency(previous, word) == 0
+    }
+
+    // the words checked by document_stats and check_text with their byte ranges, less
+    // those within markup or the spans of the text filters. When join_line_wraps is set a word
+    // hyphenated across a line break is a single word spanning both parts, provided
+    // the joined word is in the dictionary
+    fn document_words<'a>(&self, text: &'a str) -> Vec<(Range<usize>, Cow<'a, str>)> {
+        let tokens = self.tokenizer.tokenize(text);
+        let skipped = self.skipped_spans(text);
+        let mut next_skipped = 0;
+        let mut words = vec![];
+        let mut i = 0;
+        while i < tokens.len() {
+            let token = &tokens[i];
+            i += 1;
+            if token.class != TokenClass::Word {
+                continue;
+            }
+            while skipped.get(next_skipped).is_some_and(|span| span.end <= token.byte_range.start) {
+                next_skipped += 1;
+            }
+            if skipped.get(next_skipped).is_some_and(|span| span.start < token.byte_range.end) {
+                continue;
+            }
+            if self.join_line_wraps {
+                if let [hyphen, gap, next, ..] = &tokens[i..] {
+                    if hyphen.text == "-" && gap.class == TokenClass::Whitespace && gap.text.contains('\n') && next.class == TokenClass::Word {
+                        let joined = String::from(token.text) + next.text;
+                        if self.is_known(&joined) {
+                            words.push((token.byte_range.start..next.byte_range.end, Cow::Owned(joined)));
+                            i += 3;
+                            continue;
+                        }
+                    }
+                }
+            }
+            words.push((token.byte_range.clone(), Cow::Borrowed(token.text)));
+        }
+        words
+    }
+
+    // the markup and text filter spans of the text, in order and merged where they overlap
+    fn skipped_spans(&self, text: &str) -> Vec<Range<usize>> {
+        let mut spans = self.markup.spans(text);
+        spans.extend(self.text_filters.spans(text));
+        spans.sort_by_key(|span| span.start);
+        let mut merged: Vec<Range<usize>> = Vec::with_capacity(spans.len());
+        for span in spans {
+            match merged.last_mut() {
+                Some(last) if span.start <= last.end => last.end = last.end.max(span.end),
+                _ => merged.push(span),
+            }
+        }
+        merged
+    }
+
+    // true when the word, or its lowercase form, is in the dictionary or ignored
+    pub(crate) fn is_known(&self, word: &str) -> bool {
+        let normalized = self.normalize(word);
+        let word: &str = &normalized;
+        if self.known_word_count(word).is_some() || self.is_ignored(word) {
+            return true;
+        }
+        match self.lowercase(word) {
+            Cow::Owned(lowercase) => self.known_word_count(&lowercase).is_some(),
+            Cow::Borrowed(_) => false,
+        }
+    }
+
+    // the count of the dictionary word, the bloom filter turning away most words that are not one
+    pub(crate) fn known_word_count(&self, word: &str) -> Option<Count> {
+        if let Some(rate) = self.bloom_filter_rate {
+            let bloom_filter = self.bloom_filter.get_or_init(|| {
+                let mut bloom_filter = BloomFilter::new(self.storage.len(), rate);
+                self.storage.for_each_word(&mut |word, _| bloom_filter.insert(word));
+                bloom_filter
+            });
+            if !bloom_filter.may_contain(word) {
+                return None;
+            }
+        }
+        self.storage.word_count(word).filter(|_| !self.is_faded(word))
+    }
+
+    /// Runs `lookup_compound_with_options` and returns only the corrected text.
+    ///
+    /// example:
+    /// let corrected = sym_spell.correct_text("whereis th elove", 2, CompoundOptions::default());
+    ///
+    /// assert_eq!(corrected, "where is the love");
+    pub fn correct_text(&self, input: &str, max_edit_distance: usize, options: CompoundOptions) -> String {
+        self.lookup_compound_with_options(input, max_edit_distance, options)
+            .into_iter()
+            .next()
+            .map(|suggestion| suggestion.term)
+            .unwrap_or_default()
+    }
+
+    /// <summary>Find suggested spellings for a multi-word input string (supports word splitting/merging).</summary>
+    /// <param name="input">The string being spell checked.</param>
+    /// <param name="maxSegmentationWordLength">The maximum word length that should be considered.</param>
+    /// <param name="maxEditDistance">The maximum edit distance between input and corrected words
+    /// (0=no correction/segmentation only).</param>
+    /// <returns>The word segmented string,
+    /// the word segmented and spelling corrected string,
+    /// the Edit distance sum between input string and corrected string,
+    /// the Sum of word occurence probabilities in log scale (a measure of how common and probable the corrected segmentation is).</returns>
+    pub fn word_segmentation(&self, input: &str, max_edit_distance: usize, max_segmentation_word_len_opt: Option<usize>) -> (String, String, usize, f64) {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("word_segmentation", input, max_edit_distance).entered();
+        let mut segmenter = WordSegmenter::new(self, max_edit_distance, max_segmentation_word_len_opt);
+        let (segmented, corrected) = segmenter.push(input);
+        let (segmented_end, corrected_end, distance_sum, probability_log_sum) = segmenter.finish();
+        let corrected = corrected + &corrected_end;
+        self.log(&LogEvent::WordSegmentation { input, words: corrected.split_whitespace().count(), distance: distance_sum });
+        (segmented + &segmented_end, corrected, distance_sum, probability_log_sum)
+    }
+
+    /// Identical to `word_segmentation` returning `SpellcheckerError::EditDistanceTooLarge`
+    /// when max_edit_distance exceeds the max dictionary edit distance, where it panics.
+    pub fn try_word_segmentation(&self, input: &str, max_edit_distance: usize, max_segmentation_word_len_opt: Option<usize>) -> Result<(String, String, usize, f64), SpellcheckerError> {
+        self.check_edit_distance(max_edit_distance)?;
+        Ok(self.word_segmentation(input, max_edit_distance, max_segmentation_word_len_opt))
+    }
+
+    // the top spelling correction of a word_segmentation part, its edit distance and probability in log scale
+    pub(crate) fn segmentation_part(&self, part: &str, part_len: usize, max_edit_distance: usize) -> (String, usize, f64) {
+        // parts holding non word characters (e.g. digits) are kept as they are
+        let results = if self.tokenizer.is_word(part) {
+            self.lookup(part, Verbosity::Top, max_edit_distance, false, true)
+        } else {
+            vec![]
+        };
+        match results.into_iter().next() {
+            // Naive Bayes Rule
+            // we assume the word probabilities of two words to be independent
+            // therefore the resulting probability of the word combination is the product of the two word probabilities
+
+            // instead of computing the product of probabilities we are computing the sum of the logarithm of probabilities
+            // because the probabilities of words are about 10^-10, the product of many such small numbers could exceed (underflow) the floating number range and become zero
+            // log(ab)=log(a)+log(b)
+            Some(result) => (result.term, result.distance, (result.count as f64 / self.corpus_size()).log10()),
+            // default, if word not found
+            // otherwise long input text would win as long unknown word (with ed=edmax+1 ), although there there should many spaces inserted
+            None => (part.to_string(), part_len, self.unknown_word_log_probability(part_len)),
+        }
+    }
+
+    /// Suggests a rewrite of a search query, the "did you mean" of a search engine, when
+    /// one is sufficiently more probable than the query as typed. The candidates are the
+    /// `lookup_compound` correction and the `word_segmentation` of the lowercase query,
+    /// scored with the bigram dictionary as P(w1) P(w2|w1) ... and discounted by the edit
+    /// decay for every edit. None when no candidate differs from the query or the best is
+    /// less than confidence_threshold times as probable as the query.
+    ///
+    /// example:
+    /// let suggestion = sym_spell.suggest_query("teh quikc brownfox", 2, 10.0).unwrap();
+    ///
+    /// assert_eq!(suggestion.term, "the quick brown fox");
+    pub fn suggest_query(&self, input: &str, max_edit_distance: usize, confidence_threshold: f64) -> Option<QuerySuggestion> {
+        let normalized = self.normalize(input);
+        let query = self.lowercase(&normalized).split_whitespace().collect::<Vec<_>>().join(" ");
+        if query.is_empty() {
+            return None;
+        }
+        let mut candidates = vec![self.word_segmentation(&query, max_edit_distance, None).1];
+        candidates.extend(self.lookup_compound(&query, max_edit_distance).into_iter().map(|si| si.term));
+
+        let mut distance_comparator = EditDistance::new(self.distance_algorithm.clone());
+        let query_log_probability = self.query_log_probability(&query);
+        candidates.into_iter()
+            .filter(|candidate| *candidate != query)
+            .filter_map(|term| {
+                let distance = distance_comparator.compare(&query, &term, Some(usize::MAX))?;
+                let log_probability = self.query_log_probability(&term) + distance as f64 * self.edit_decay.log10();
+                Some(QuerySuggestion { probability_ratio: 10.0f64.powf(log_probability - query_log_probability), term, distance })
+            })
+            .max_by(|a, b| a.probability_ratio.total_cmp(&b.probability_ratio))
+            .filter(|suggestion| suggestion.probability_ratio >= confidence_threshold)
+    }
+
+    // the probability in log scale given a word that is not in the dictionary, lower the longer it is
+    fn unknown_word_log_probability(&self, len: usize) -> f64 {
+        (10.0 / (self.corpus_size() * 10.0f64.powf(len as f64))).log10()
+    }
+
+    // the probability in log scale of the word following the words before it, its own probability
+    // weighed against that of the n-gram they end with by the bigram weight when one is loaded
+    pub(crate) fn following_log_probability(&self, context: &[String], word: &str, log_probability: f64) -> f64 {
+        if self.bigram_weight == 0.0 {
+            return log_probability;
+        }
+        match self.ngram_probability(context, word) {
+            Some(ngram_probability) => (self.bigram_weight * ngram_probability + (1.0 - self.bigram_weight) * 10.0f64.powf(log_probability)).log10(),
+            None => log_probability,
+        }
+    }
+
+    // the probability of the word given the last two words before it in the trigram dictionary,
+    // backing off to the last word in the bigram dictionary, None when neither holds the words
+    fn ngram_probability(&self, context: &[impl AsRef<str>], word: &str) -> Option<f64> {
+        if let [.., first, second] = context {
+            let (first, second) = (first.as_ref(), second.as_ref());
+            let trigram_count = self.trigram_frequency(first, second, word);
+            let bigram_count = self.bigram_frequency(first, second);
+            if trigram_count > 0 && bigram_count > 0 {
+                return Some((trigram_count as f64 / bigram_count as f64).min(1.0));
+            }
+        }
+        let previous = context.last()?.as_ref();
+        let bigram_count = self.bigram_frequency(previous, word);
+        let previous_count = self.storage.word_count(previous).unwrap_or(0);
+        if bigram_count == 0 || previous_count == 0 {
+            return None;
+        }
+        Some((bigram_count as f64 / previous_count as f64).min(1.0))
+    }
+
+    // the probability of the space separated words in log scale, each word given the two
+    // before it in the trigram dictionary or the one before it in the bigram dictionary, or
+    // its own probability backed off when neither holds the words
+    fn query_log_probability(&self, query: &str) -> f64 {
+        let words: Vec<&str> = query.split(' ').collect();
+        let mut log_probability = 0.0;
+        for (i, word) in words.iter().enumerate() {
+            let count = self.storage.word_count(word).unwrap_or(0);
+            let unigram = if count > 0 {
+                (count as f64 / self.corpus_size()).log10()
+            } else {
+                self.unknown_word_log_probability(GraphemeClusters::new(word).len())
+            };
+            // without bigrams the words are independent, as in word_segmentation
+            log_probability += if i == 0 || self.bigrams.is_empty() {
+                unigram
+            } else {
+                match self.ngram_probability(&words[i.saturating_sub(2)..i], word) {
+                    Some(ngram_probability) => ngram_probability.log10(),
+                    None => unigram + BIGRAM_BACKOFF.log10(),
+                }
+            };
+        }
+        log_probability
+    }
+
+    // a capitalized word that does not start a sentence
+    fn is_proper_noun_candidate(input: &str, prev_word: &Token, word: &Token) -> bool {
+        starts_with_uppercase(word.text) && !ends_sentence(&input[prev_word.byte_range.end..word.byte_range.start])
+    }
+
+    // check whether all delete chars are present in the suggestion prefix in correct order, otherwise this is just a hash collision
+    fn delete_in_suggestion_prefix(&self, delete: &str, suggestion: &str) -> bool {
+        let delete_gc = GraphemeClusters::new(delete);
+        let suggestion_gc = GraphemeClusters::new(suggestion);
+
+        let delete_len = delete_gc.len();
+        if delete_len == 0 {
+            return true;
+        }
+
+        let suggestion_len = suggestion_gc.len().min(self.prefix_length);
 
         let mut j = 0;
         for (delete_char, _) in delete_gc {
@@ -798,14 +2961,1482 @@ impl SymSpell {
     }
 }
 
+// keeps the first, i.e. the best ranked, suggestion of each term
+fn dedup_terms<T>(suggestions: &mut Vec<T>, term: impl Fn(&T) -> &str) {
+    let mut seen = HashSet::with_capacity(suggestions.len());
+    let first: Vec<bool> = suggestions.iter().map(|si| seen.insert(term(si))).collect();
+    let mut first = first.into_iter();
+    suggestions.retain(|_| first.next().unwrap_or(true));
+}
+
+// the range of the sorted words starting with the prefix
+fn completion_range(words: &[String], prefix: &str) -> Range<usize> {
+    let start = words.partition_point(|word| word.as_str() < prefix);
+    start..start + words[start..].partition_point(|word| word.starts_with(prefix))
+}
+
+// whether the items of the needle all appear in the haystack in the same order
+fn is_subsequence<T: PartialEq>(needle: impl IntoIterator<Item=T>, haystack: impl IntoIterator<Item=T>) -> bool {
+    let mut haystack = haystack.into_iter();
+    needle.into_iter().all(|item| haystack.any(|other| other == item))
+}
+
+// the number of ways to pick k of n items, saturating
+fn binomial(n: usize, k: usize) -> usize {
+    if k > n {
+        return 0;
+    }
+    (0..k).fold(1usize, |ways, i| ways.saturating_mul(n - i) / (i + 1))
+}
+
+impl FromIterator<(String, Count)> for SymSpell {
+    fn from_iter<I: IntoIterator<Item=(String, Count)>>(iter: I) -> SymSpell {
+        SymSpell::from_words(iter)
+    }
+}
+
+impl<'a> FromIterator<(&'a str, Count)> for SymSpell {
+    fn from_iter<I: IntoIterator<Item=(&'a str, Count)>>(iter: I) -> SymSpell {
+        SymSpell::from_words(iter.into_iter().map(|(key, count)| (key.to_string(), count)))
+    }
+}
+
 #[cfg(test)]
 mod sym_spell_tests {
-    use crate::sym_spell::sym_spell::SymSpell;
+    use std::borrow::Cow;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    use crate::edit_distance::DistanceAlgorithm;
+    use crate::filters::TextFilters;
+    use crate::keyboard::KeyboardLayout;
+    use crate::markup::Markup;
+    use crate::sym_spell::issue_kind::IssueKind;
+    use crate::sym_spell::stream_checker::StreamChecker;
+    use crate::soft_wx::Distance;
+    use crate::soft_wx::weighted_levenshtein::WeightedCosts;
+    #[cfg(feature = "dawg")]
+    use crate::sym_spell::backend::Backend;
+    use crate::sym_spell::bigram_line_error::BigramLineError;
+    use crate::sym_spell::compound_options::CompoundOptions;
+    use crate::sym_spell::count_overflow_policy::CountOverflowPolicy;
+    use crate::sym_spell::delete_generation::DeleteGeneration;
+    use crate::sym_spell::entry_metadata::EntryMetadata;
+    use crate::sym_spell::equivalences::Equivalences;
+    use crate::sym_spell::index_storage::{HashMapStorage, IndexStorage, IndexedWord, WordId};
+    use crate::sym_spell::invalid_count_policy::InvalidCountPolicy;
+    use crate::sym_spell::load_progress::LoadProgress;
+    use crate::sym_spell::log_event::LogEvent;
+    use crate::sym_spell::lookup_cache_stats::LookupCacheStats;
+    use crate::sym_spell::lookup_context::LookupContext;
+    use crate::sym_spell::lookup_limits::LookupLimits;
+    use crate::sym_spell::lookup_options::LookupOptions;
+    use crate::sym_spell::lookup_scratch::LookupScratch;
+    #[cfg(feature = "metrics")]
+    use crate::sym_spell::metrics::Metrics;
+    use crate::sym_spell::part_status::PartStatus;
+    use crate::sym_spell::seeded_state::SeededState;
+    use crate::sym_spell::spellchecker_error::SpellcheckerError;
+    use crate::sym_spell::suggest_ref::SuggestRef;
+    use crate::sym_spell::suggested_item::SuggestItem;
+    use crate::sym_spell::suggestion_ranker::{DefaultRanker, SuggestionRanker};
+    use crate::sym_spell::sym_spell::{SymSpell, GOOGLE_BOOKS_CORPUS_SIZE};
+    use crate::sym_spell::trigram_line_error::TrigramLineError;
+    use crate::sym_spell::verbosity::Verbosity;
+    use crate::sym_spell::Count;
+    use crate::tokenizer::{Token, TokenClass, Tokenizer, TokenizerOptions, WordCharPolicy};
+    use crate::utils::CaseLocale;
 
     #[test]
     fn parse_words_test() {
         let text = "this is a - test, (does it work)?";
         let words = SymSpell::parse_words(text);
-        assert_eq!(words.len(), 7)
+        assert_eq!(words.len(), 7);
+        assert_eq!(words, vec!["this", "is", "a", "test", "does", "it", "work"]);
+    }
+
+    #[test]
+    fn from_iterator_test() {
+        let sym_spell: SymSpell = vec![("hello", 10), ("help", 5), ("hello", 2)].into_iter().collect();
+        assert_eq!(sym_spell.word_count(), 2);
+
+        let result = sym_spell.lookup("helo", Verbosity::Top, 2, false, false);
+        assert_eq!(result[0].term, "hello");
+        assert_eq!(result[0].count, 12);
+    }
+
+    #[test]
+    fn count_overflow_policy_test() {
+        let count = |sym_spell: &SymSpell, word: &str| sym_spell.lookup(word, Verbosity::Top, 0, false, true)[0].count;
+        let big = Count::MAX - 10;
+        for &policy in [CountOverflowPolicy::Saturate, CountOverflowPolicy::Error, CountOverflowPolicy::Rescale].iter() {
+            let mut sym_spell = SymSpell::new(None, None, None);
+            sym_spell.set_count_overflow_policy(policy);
+            sym_spell.create_dictionary_entry("hello".into(), big);
+            sym_spell.create_dictionary_entry("world".into(), 100);
+            sym_spell.create_dictionary_entry("hello".into(), 20);
+            assert_eq!(sym_spell.load_report().count_overflows, 1);
+
+            match policy {
+                CountOverflowPolicy::Saturate => assert_eq!(count(&sym_spell, "hello"), Count::MAX),
+                CountOverflowPolicy::Error => assert_eq!(count(&sym_spell, "hello"), big),
+                CountOverflowPolicy::Rescale => {
+                    assert_eq!(count(&sym_spell, "hello"), big / 2 + 10);
+                    assert_eq!(count(&sym_spell, "world"), 50);
+                    assert_eq!(sym_spell.load_report().rescales, 1);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rerank_prefix_scale_test() {
+        let sym_spell: SymSpell = vec![("barton", 500), ("carlon", 100), ("cart", 50)].into_iter().collect();
+        let options = LookupOptions { rerank_prefix_scale: Some(0.1), ..LookupOptions::default() };
+
+        let result = sym_spell.lookup("carton", Verbosity::Top, 2, false, false);
+        assert_eq!(result[0].term, "barton");
+        let result = sym_spell.lookup_with_options("carton", Verbosity::Top, 2, false, false, options);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].term, "carlon");
+
+        let result = sym_spell.lookup_with_options("carton", Verbosity::All, 2, false, false, options);
+        let terms: Vec<&str> = result.iter().map(|si| si.term.as_str()).collect();
+        assert_eq!(terms, vec!["carlon", "barton", "cart"]);
+    }
+
+    #[test]
+    fn keyboard_layout_test() {
+        let mut sym_spell: SymSpell = vec![("hello", 10), ("hullo", 100)].into_iter().collect();
+        let options = LookupOptions { keyboard_layout: Some(KeyboardLayout::Qwerty), ..LookupOptions::default() };
+
+        assert_eq!(sym_spell.lookup("hwllo", Verbosity::Top, 2, false, false)[0].term, "hullo");
+        let result = sym_spell.lookup_with_options("hwllo", Verbosity::Top, 2, false, false, options);
+        assert_eq!(result.len(), 1);
+        assert_eq!((result[0].term.as_str(), result[0].distance, result[0].score), ("hello", 1, -0.5));
+
+        sym_spell.set_keyboard_adjacent_cost(0.25);
+        let result = sym_spell.lookup_with_options("hwllo", Verbosity::Top, 2, false, false, options);
+        assert_eq!((result[0].term.as_str(), result[0].score), ("hello", -0.25));
+        // a neighbouring key costing a full edit no longer ranks the suggestions
+        sym_spell.set_keyboard_adjacent_cost(2.0);
+        assert_eq!(sym_spell.keyboard_adjacent_cost(), 1.0);
+        assert_eq!(sym_spell.lookup_with_options("hwllo", Verbosity::Top, 2, false, false, options)[0].term, "hullo");
+    }
+
+    #[test]
+    fn join_variants_test() {
+        let sym_spell: SymSpell = vec![("email", 100), ("mail", 50), ("e", 10)].into_iter().collect();
+        let options = LookupOptions { join_variants: true, ..LookupOptions::default() };
+
+        let result = sym_spell.lookup_with_options("e-mail", Verbosity::Top, 2, true, false, options);
+        assert_eq!(result.len(), 1);
+        assert_eq!((result[0].term.as_str(), result[0].distance), ("email", 1));
+
+        let result = sym_spell.lookup_with_options("e mial", Verbosity::Top, 2, false, false, options);
+        assert_eq!((result[0].term.as_str(), result[0].distance), ("email", 2));
+
+        let result = sym_spell.lookup_with_options("e - mail", Verbosity::Closest, 2, false, false, options);
+        assert_eq!((result[0].term.as_str(), result[0].distance), ("email", 1));
+
+        let result = sym_spell.lookup_with_options("e - mail", Verbosity::Top, 2, false, false, LookupOptions::default());
+        assert!(result.iter().all(|si| si.term != "email"));
+
+        // two joins exceed the allowed distance once the misspelling is counted
+        let result = sym_spell.lookup_with_options("e-ma-il", Verbosity::Top, 1, false, false, options);
+        assert!(result.iter().all(|si| si.term != "email"));
+    }
+
+    #[test]
+    fn correct_text_test() {
+        let sym_spell: SymSpell = vec![("where", 100), ("is", 500), ("the", 1000), ("love", 50)].into_iter().collect();
+        assert_eq!(sym_spell.correct_text("whereis th love", 2, CompoundOptions::default()), "where is the love");
+        assert_eq!(sym_spell.correct_text("", 2, CompoundOptions::default()), "");
+    }
+
+    #[test]
+    fn seed_test() {
+        let build = |seed: u64| {
+            let mut sym_spell = SymSpell::new(None, None, None);
+            sym_spell.set_seed(seed);
+            for (i, word) in ["abcd", "abce", "abcf", "abdc", "bacd", "acbd"].iter().enumerate() {
+                sym_spell.create_dictionary_entry(word.to_string(), 10 + i as Count % 2);
+            }
+            sym_spell
+        };
+        let suggestions = |sym_spell: &SymSpell| -> Vec<String> {
+            sym_spell.lookup("abc", Verbosity::All, 2, false, false).into_iter().map(|si| si.term).collect()
+        };
+        assert_eq!(build(3).seed(), 3);
+        assert_eq!(suggestions(&build(3)), suggestions(&build(3)));
+        let hash = build(3).get_string_hash("abc");
+        assert_eq!(build(3).storage.deletes(hash), build(3).storage.deletes(hash));
+
+        // the seed keys the hasher, so the order deletes are generated in follows it
+        let deletes = |seed: u64| -> Vec<String> { build(seed).create_deletes("abcdefg", 7).into_iter().collect() };
+        assert_eq!(deletes(3), deletes(3));
+        assert_ne!(deletes(3), deletes(4));
+        let mut sorted = (deletes(3), deletes(4));
+        sorted.0.sort();
+        sorted.1.sort();
+        assert_eq!(sorted.0, sorted.1);
+    }
+
+    #[test]
+    fn delete_hasher_test() {
+        let words = [("hello", 10), ("help", 5), ("world", 7)];
+        let sym_spell: SymSpell = words.iter().copied().collect();
+        // the deletes of an index land in the same buckets on every run and platform
+        assert_eq!(sym_spell.get_string_hash("abc"), 0xe71fa2190541574b);
+        let mut buckets = vec![];
+        sym_spell.storage.for_each_delete(&mut |hash, words| buckets.push((hash, words.to_vec())));
+        buckets.sort();
+        assert_eq!(buckets.len(), sym_spell.entry_count());
+        assert!(buckets.iter().any(|(hash, _)| *hash == sym_spell.get_string_hash("hel")));
+
+        // any BuildHasher can be supplied
+        let mut seeded = SymSpell::with_hasher(None, None, None, SeededState::new(1));
+        for (word, count) in words.iter() {
+            seeded.create_dictionary_entry(word.to_string(), *count);
+        }
+        assert_ne!(seeded.get_string_hash("abc"), 0xe71fa2190541574b);
+        let terms = |suggestions: Vec<SuggestItem>| -> Vec<String> { suggestions.into_iter().map(|si| si.term).collect() };
+        assert_eq!(terms(seeded.lookup("helo", Verbosity::All, 2, false, false)), terms(sym_spell.lookup("helo", Verbosity::All, 2, false, false)));
+    }
+
+    struct Hashtags;
+
+    // keeps each '#' with the word after it
+    impl Tokenizer for Hashtags {
+        fn tokenize<'a>(&self, text: &'a str) -> Vec<Token<'a>> {
+            let mut tokens: Vec<Token<'a>> = vec![];
+            for token in TokenizerOptions::default().tokenize(text) {
+                match tokens.last_mut() {
+                    Some(last) if last.text == "#" && token.class == TokenClass::Word => {
+                        last.byte_range.end = token.byte_range.end;
+                        last.text = &text[last.byte_range.clone()];
+                        last.class = TokenClass::Word;
+                    }
+                    _ => tokens.push(token),
+                }
+            }
+            tokens
+        }
+    }
+
+    #[test]
+    fn tokenizer_test() {
+        let mut sym_spell: SymSpell = vec![("#rustlang", 10), ("rust", 50), ("i", 100), ("love", 80)].into_iter().collect();
+        // hashtags are skipped by the default text filters
+        sym_spell.set_text_filters(TextFilters::new());
+        let text = "i love #rustlnag";
+        assert_eq!(sym_spell.check_text(text, 2)[0].byte_range, 8..16);
+        assert_eq!(SymSpell::parse_words_with_tokenizer(text, sym_spell.tokenizer()), vec!["i", "love", "rustlnag"]);
+
+        sym_spell.set_tokenizer(Box::new(Hashtags));
+        let misspelled = sym_spell.check_text(text, 2);
+        assert_eq!(misspelled[0].byte_range, 7..16);
+        assert_eq!(misspelled[0].suggestion.as_ref().unwrap().term, "#rustlang");
+        assert_eq!(sym_spell.correct_text(text, 2, CompoundOptions::default()), "i love #rustlang");
+        assert_eq!(SymSpell::parse_words_with_tokenizer(text, &Hashtags), vec!["i", "love", "#rustlnag"]);
+
+        // the options bring back the built in tokenizer
+        sym_spell.set_tokenizer_options(TokenizerOptions::default());
+        assert_eq!(sym_spell.check_text(text, 2)[0].byte_range, 8..16);
+    }
+
+    #[test]
+    fn text_filters_test() {
+        let mut sym_spell: SymSpell = vec![("mail", 100), ("me", 200), ("at", 300), ("or", 200), ("see", 100)].into_iter().collect();
+        let text = "mail me at jane@example.com or see https://exmaple.com/docs, 0x1f or PROJ-12";
+        let words = |sym_spell: &SymSpell| -> Vec<String> {
+            sym_spell.check_text(text, 2).into_iter().map(|m| text[m.byte_range].to_string()).collect()
+        };
+        assert_eq!(words(&sym_spell), vec!["PROJ"]);
+        assert_eq!(sym_spell.document_stats(text).total_tokens, 7);
+
+        sym_spell.set_text_filters(TextFilters::common().with_pattern("PROJ-*"));
+        assert!(words(&sym_spell).is_empty());
+
+        sym_spell.set_text_filters(TextFilters::new());
+        assert_eq!(words(&sym_spell), vec!["jane", "example", "com", "https", "exmaple", "com", "docs", "x", "f", "PROJ"]);
+    }
+
+    #[test]
+    fn markup_test() {
+        let mut sym_spell: SymSpell = vec![("run", 100), ("the", 500), ("tests", 50), ("with", 200), ("docs", 40), ("see", 80)].into_iter().collect();
+        let text = "Run the tsets with `cargo test`, see [the dcos](https://example.com/dcos).\n```\nlet x = 1;\n```\n";
+        let words = |sym_spell: &SymSpell| -> Vec<&str> {
+            sym_spell.check_text(text, 2).into_iter().map(|m| &text[m.byte_range]).collect()
+        };
+        assert_eq!(words(&sym_spell), vec!["tsets", "cargo", "test", "dcos", "https", "example", "com", "dcos", "let", "x"]);
+
+        sym_spell.set_markup(Markup::Markdown);
+        // the byte ranges are still those of the original text
+        assert_eq!(words(&sym_spell), vec!["tsets", "dcos"]);
+        assert_eq!(sym_spell.check_text(text, 2)[1].byte_range, 42..46);
+        assert_eq!(sym_spell.document_stats(text).total_tokens, 7);
+    }
+
+    #[test]
+    fn ignore_word_test() {
+        let mut sym_spell: SymSpell = vec![("the", 500), ("cluster", 40), ("kube", 5)].into_iter().collect();
+        let text = "the Kubernetes clustr";
+        assert_eq!(sym_spell.check_text(text, 2).len(), 2);
+
+        sym_spell.ignore_word("kubernetes");
+        assert!(sym_spell.is_ignored("KUBERNETES"));
+        let misspelled = sym_spell.check_text(text, 2);
+        assert_eq!(misspelled.len(), 1);
+        assert_eq!(misspelled[0].byte_range, 15..21);
+        let result = sym_spell.lookup("kubernetes", Verbosity::All, 2, false, true);
+        assert_eq!(result.iter().map(|si| (si.term.as_str(), si.distance, si.count)).collect::<Vec<_>>(), vec![("kubernetes", 0, 0)]);
+        assert!(sym_spell.lookup("kubernetes", Verbosity::Top, 2, false, false).is_empty());
+        assert_eq!(sym_spell.correct_text("the kubernetes clustr", 2, CompoundOptions::default()), "the kubernetes cluster");
+        // the dictionary is untouched
+        assert_eq!(sym_spell.word_count(), 3);
+
+        // the lookup cache does not keep answers from before the list changed
+        sym_spell.set_lookup_cache_capacity(10);
+        assert!(sym_spell.lookup("kubernetes", Verbosity::Top, 2, false, false).is_empty());
+        assert!(sym_spell.unignore_word("Kubernetes"));
+        assert!(!sym_spell.unignore_word("Kubernetes"));
+        assert_eq!(sym_spell.check_text(text, 2).len(), 2);
+
+        sym_spell.ignore_word("clustr");
+        sym_spell.ignore_word("kubernetes");
+        assert!(sym_spell.check_text(text, 2).is_empty());
+        sym_spell.clear_ignored();
+        assert_eq!(sym_spell.check_text(text, 2).len(), 2);
+    }
+
+    #[test]
+    fn repeated_word_test() {
+        let mut sym_spell: SymSpell = vec![("the", 500), ("cat", 40), ("had", 50), ("it", 100)].into_iter().collect();
+        let text = "The the cat, cat\nhad had it. It it tehh tehh";
+        let issues: Vec<(&str, IssueKind)> = sym_spell.check_text(text, 2).into_iter().map(|m| (&text[m.byte_range], m.kind)).collect();
+        assert_eq!(issues, vec![
+            ("the", IssueKind::RepeatedWord),
+            ("had", IssueKind::RepeatedWord),
+            ("it", IssueKind::RepeatedWord),
+            ("tehh", IssueKind::Misspelling),
+            ("tehh", IssueKind::RepeatedWord),
+        ]);
+
+        // pairs in the bigram dictionary are not repetitions
+        sym_spell.write_line_to_bigram_dictionary("had had 10", " ").unwrap();
+        assert_eq!(sym_spell.check_text(text, 2).len(), 4);
+
+        let mut checker = StreamChecker::new(&sym_spell, 2);
+        let mut misspelled = checker.push("the th");
+        misspelled.extend(checker.push("e cat, cat it"));
+        misspelled.extend(checker.finish());
+        let issues: Vec<(usize, usize, IssueKind)> = misspelled.into_iter().map(|m| (m.byte_range.start, m.byte_range.end, m.kind)).collect();
+        assert_eq!(issues, vec![(4, 7, IssueKind::RepeatedWord)]);
+    }
+
+    #[test]
+    fn word_segmentation_test() {
+        let sym_spell: SymSpell = vec![("the", 100), ("café", 20), ("is", 80), ("open", 30), ("pen", 10)].into_iter().collect();
+        // parts are sliced by grapheme, not by byte
+        let (segmented, corrected, distance_sum, _) = sym_spell.word_segmentation("thecaféisopen", 0, None);
+        assert_eq!(segmented, "the café is open");
+        assert_eq!(corrected, "the café is open");
+        assert_eq!(distance_sum, 3);
+
+        // spaces already in the input cost nothing, corrections and unknown parts do
+        assert_eq!(sym_spell.word_segmentation("the cafe isopen", 1, None).1, "the café is open");
+        assert_eq!(sym_spell.word_segmentation("the cafe isopen", 1, Some(5)).2, 2);
+        let (segmented, _, distance_sum, probability_log_sum) = sym_spell.word_segmentation("the 42", 0, None);
+        assert_eq!((segmented.as_str(), distance_sum), ("the 42", 2));
+        assert!(probability_log_sum < sym_spell.word_segmentation("the", 0, None).3 - 2.0);
+
+        assert_eq!(sym_spell.word_segmentation("", 0, None), (String::new(), String::new(), 0, 0.0));
+        assert_eq!(sym_spell.word_segmentation("isopen", 0, Some(1)).0, "i s o p e n");
+    }
+
+    #[test]
+    fn tokenizer_options_test() {
+        let mut sym_spell: SymSpell = vec![("abc123", 100), ("abc", 100), ("cases", 100)].into_iter().collect();
+        assert_eq!(sym_spell.correct_text("abc123 cazes", 2, CompoundOptions::default()), "abc cases");
+
+        sym_spell.set_tokenizer_options(TokenizerOptions { underscore_is_word: true, digits_are_word: true, ..TokenizerOptions::default() });
+        assert_eq!(sym_spell.correct_text("abc123 cazes", 2, CompoundOptions::default()), "abc123 cases");
+        assert_eq!(SymSpell::parse_words_with_options("a_b 19", sym_spell.tokenizer_options()), vec!["a_b", "19"]);
+
+        let mut sym_spell: SymSpell = vec![("don't", 100), ("don", 10), ("stop", 100)].into_iter().collect();
+        assert_eq!(sym_spell.check_text("don't stop", 2).len(), 1);
+        let word_char_policy = WordCharPolicy { apostrophes: true, hyphens: false };
+        sym_spell.set_tokenizer_options(TokenizerOptions { word_char_policy, ..TokenizerOptions::default() });
+        assert!(sym_spell.check_text("don't stop", 2).is_empty());
+        assert_eq!(SymSpell::parse_words_with_options("don't 'stop'", sym_spell.tokenizer_options()), vec!["don't", "stop"]);
+    }
+
+    #[test]
+    fn document_stats_test() {
+        let sym_spell: SymSpell = vec![("the", 100), ("quick", 10), ("fox", 10)].into_iter().collect();
+        let stats = sym_spell.document_stats("Teh quick, brown fox");
+        assert_eq!(stats.total_tokens, 4);
+        assert_eq!(stats.corrected_tokens, 1);
+        assert_eq!(stats.unknown_tokens, 1);
+        assert_eq!(stats.error_rate(), 0.5);
+        assert_eq!(sym_spell.document_stats("").error_rate(), 0.0);
+    }
+
+    #[test]
+    fn check_text_test() {
+        let sym_spell: SymSpell = vec![("the", 100), ("quick", 10), ("fox", 10)].into_iter().collect();
+        let text = "The quikc, brown fox";
+        let misspelled = sym_spell.check_text(text, 2);
+        assert_eq!(misspelled.len(), 2);
+        assert_eq!(&text[misspelled[0].byte_range.clone()], "quikc");
+        assert_eq!(misspelled[0].suggestion.as_ref().unwrap().term, "quick");
+        assert_eq!(&text[misspelled[1].byte_range.clone()], "brown");
+        assert!(misspelled[1].suggestion.is_none());
+    }
+
+    #[test]
+    fn bloom_filter_test() {
+        let mut sym_spell: SymSpell = vec![("the", 100), ("quick", 10), ("fox", 10)].into_iter().collect();
+        let text = "The quikc, brown fox jumps";
+        let expected = format!("{:?}", sym_spell.check_text(text, 2));
+        sym_spell.set_bloom_filter(Some(0.01));
+        assert_eq!(sym_spell.bloom_filter(), Some(0.01));
+        assert_eq!(format!("{:?}", sym_spell.check_text(text, 2)), expected);
+        assert_eq!(sym_spell.known_word_count("quick"), Some(10));
+        assert_eq!(sym_spell.known_word_count("quikc"), None);
+
+        // words added after the filter was built are known
+        sym_spell.create_dictionary_entry("brown".to_string(), 5);
+        assert_eq!(sym_spell.known_word_count("brown"), Some(5));
+        assert_eq!(sym_spell.check_text(text, 2).len(), 2);
+
+        sym_spell.set_bloom_filter(Some(1.5));
+        assert_eq!(sym_spell.bloom_filter(), None);
+    }
+
+    #[test]
+    fn sentence_case_test() {
+        let sym_spell: SymSpell = vec![("the", 100), ("quick", 10), ("fox", 10), ("in", 50), ("Paris", 5)].into_iter().collect();
+        let text = "teh Quick fox. teh fox, teh Fox! TEH QUIKC fox in Pariss? Teh fox";
+        let corrections: Vec<(&str, String)> = sym_spell.check_text(text, 2).into_iter()
+            .map(|m| (&text[m.byte_range], m.suggestion.unwrap().term))
+            .collect();
+        // a capitalized word mid sentence matches its lowercase entry, so "Quick" and "Fox" are known
+        assert_eq!(corrections, vec![
+            ("teh", "The".to_string()),
+            ("teh", "The".to_string()),
+            ("teh", "the".to_string()),
+            ("TEH", "THE".to_string()),
+            ("QUIKC", "QUICK".to_string()),
+            ("Pariss", "Paris".to_string()),
+            ("Teh", "The".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn storage_test() {
+        // hands out owned buckets the way a backend fetching from elsewhere would
+        #[derive(Default)]
+        struct OwnedStorage(HashMapStorage);
+
+        impl IndexStorage for OwnedStorage {
+            fn deletes(&self, hash: u64) -> Option<Cow<'_, [IndexedWord]>> {
+                self.0.deletes(hash).map(|words| Cow::Owned(words.into_owned()))
+            }
+            fn push_delete(&mut self, hash: u64, id: WordId, len: u32) { self.0.push_delete(hash, id, len) }
+            fn delete_count(&self) -> usize { self.0.delete_count() }
+            fn for_each_delete(&self, f: &mut dyn FnMut(u64, &[IndexedWord])) { self.0.for_each_delete(f) }
+            fn word(&self, id: WordId) -> Option<Cow<'_, str>> { self.0.word(id).map(|word| Cow::Owned(word.into_owned())) }
+            fn word_count(&self, word: &str) -> Option<Count> { self.0.word_count(word) }
+            fn set_word_count(&mut self, word: String, count: Count) -> WordId { self.0.set_word_count(word, count) }
+            fn len(&self) -> usize { self.0.len() }
+            fn for_each_word(&self, f: &mut dyn FnMut(&str, Count)) { self.0.for_each_word(f) }
+            fn update_word_counts(&mut self, f: &mut dyn FnMut(&mut Count)) { self.0.update_word_counts(f) }
+            fn clear(&mut self) { self.0.clear() }
+        }
+
+        let mut sym_spell = SymSpell::new(None, None, None);
+        sym_spell.set_storage(Box::new(OwnedStorage::default()), 0);
+        for (word, count) in [("hello", 10), ("help", 5), ("world", 7)].iter() {
+            sym_spell.create_dictionary_entry(word.to_string(), *count);
+        }
+        let terms: Vec<String> = sym_spell.lookup("helo", Verbosity::All, 2, false, false).into_iter().map(|si| si.term).collect();
+        assert_eq!(terms, vec!["hello", "help"]);
+        assert_eq!(sym_spell.word_count(), 3);
+    }
+
+    #[test]
+    fn concurrent_lookup_test() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<SymSpell>();
+
+        let sym_spell: Arc<SymSpell> = Arc::new(vec![("hello", 10), ("help", 5), ("world", 7)].into_iter().collect());
+        let handles: Vec<_> = (0..4).map(|_| {
+            let sym_spell = Arc::clone(&sym_spell);
+            thread::spawn(move || {
+                (0..100).map(|_| sym_spell.lookup("wrld", Verbosity::Top, 2, false, false)[0].term.clone()).collect::<Vec<String>>()
+            })
+        }).collect();
+        for handle in handles {
+            assert!(handle.join().unwrap().iter().all(|term| term == "world"));
+        }
+    }
+
+    #[test]
+    fn invalid_count_policy_test() {
+        let lines = ["zero 0", "negative -5", "nan abc", "valid 3", "huge 99999999999999999999999"];
+        for &policy in [InvalidCountPolicy::Drop, InvalidCountPolicy::Clamp].iter() {
+            let mut sym_spell = SymSpell::new(None, None, None);
+            sym_spell.set_invalid_count_policy(policy);
+            // an invalid count follows the policy rather than failing the line
+            for line in lines.iter() {
+                assert_eq!(sym_spell.write_line_to_dictionary(line, " "), Ok(()));
+            }
+            assert_eq!(sym_spell.load_report().invalid_counts, 3);
+            let huge = sym_spell.lookup("huge", Verbosity::Top, 0, false, true);
+            assert_eq!(huge[0].count, Count::MAX);
+
+            let zero = sym_spell.lookup("zero", Verbosity::Top, 0, false, true);
+            match policy {
+                InvalidCountPolicy::Drop => {
+                    assert_eq!(sym_spell.word_count(), 2);
+                    assert!(zero.is_empty());
+                }
+                InvalidCountPolicy::Clamp => {
+                    assert_eq!(sym_spell.word_count(), 5);
+                    assert_eq!(zero[0].count, 1);
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn lookup_compound_parallel_test() {
+        let sym_spell: SymSpell = vec![("where", 100), ("is", 500), ("the", 1000), ("love", 50), ("he", 300)].into_iter().collect();
+        for input in ["whereis th elove", "the love", "", "wh ere is"].iter() {
+            let sequential = sym_spell.lookup_compound(input, 2);
+            let parallel = sym_spell.lookup_compound_parallel(input, 2, CompoundOptions::default());
+            assert_eq!(sequential[0].term, parallel[0].term);
+            assert_eq!(sequential[0].count, parallel[0].count);
+        }
+    }
+
+    #[test]
+    fn distance_algorithm_test() {
+        struct Fixed;
+
+        impl Distance for Fixed {
+            fn distance(&mut self, _: &str, _: &str) -> Option<usize> { Some(1) }
+            fn distance2(&mut self, _: &str, _: &str, _: usize) -> Option<usize> { Some(1) }
+        }
+
+        let mut sym_spell: SymSpell = vec![("the", 100), ("then", 10)].into_iter().collect();
+        let top = |sym_spell: &SymSpell| {
+            let result = sym_spell.lookup("teh", Verbosity::Top, 2, false, false);
+            (result[0].term.clone(), result[0].distance)
+        };
+        assert_eq!(top(&sym_spell), ("the".to_string(), 1));
+
+        sym_spell.set_distance_algorithm(DistanceAlgorithm::Levenshtein);
+        assert_eq!(top(&sym_spell), ("the".to_string(), 2));
+
+        sym_spell.set_distance_algorithm(DistanceAlgorithm::Custom(Arc::new(|| Box::new(Fixed))));
+        let result = sym_spell.lookup("thne", Verbosity::All, 2, false, false);
+        assert!(result.iter().all(|si| si.distance == 1));
+    }
+
+    #[test]
+    fn weighted_distance_algorithm_test() {
+        let mut sym_spell: SymSpell = vec![("model", 10), ("yodel", 100)].into_iter().collect();
+        let top = |sym_spell: &SymSpell| {
+            let result = sym_spell.lookup("rnodel", Verbosity::Top, 2, false, false);
+            (result[0].term.clone(), result[0].distance)
+        };
+        assert_eq!(top(&sym_spell), ("yodel".to_string(), 2));
+
+        // an OCR reading "m" as "rn" is far more likely than any other edit
+        let costs = WeightedCosts::new().with_substitution("rn", "m", 0.3);
+        sym_spell.set_distance_algorithm(DistanceAlgorithm::Weighted(Arc::new(costs)));
+        assert_eq!(top(&sym_spell), ("model".to_string(), 1));
+    }
+
+    #[test]
+    fn lookup_order_test() {
+        let sym_spell: SymSpell = vec![("help", 5), ("hello", 10), ("hell", 10), ("held", 10), ("he", 1000)].into_iter().collect();
+        let result = sym_spell.lookup("helo", Verbosity::All, 2, false, false);
+        let ordered: Vec<(&str, usize)> = result.iter().map(|si| (si.term.as_str(), si.distance)).collect();
+        assert_eq!(ordered, vec![("held", 1), ("hell", 1), ("hello", 1), ("help", 1), ("he", 2)]);
+        assert!(result.windows(2).all(|pair| pair[0].sort_key() < pair[1].sort_key()));
+    }
+
+    #[test]
+    fn lookup_scratch_test() {
+        let sym_spell: SymSpell = vec![("help", 5), ("hello", 10), ("hell", 10), ("cafe\u{301}", 10), ("understanding", 7)].into_iter().collect();
+        let terms = |suggestions: Vec<SuggestItem>| -> Vec<(String, usize, Count)> {
+            suggestions.into_iter().map(|si| (si.term, si.distance, si.count)).collect()
+        };
+        let mut scratch = LookupScratch::default();
+        // the buffers hold the previous lookup's candidates, which must not leak into the next
+        for input in ["understandnig", "helo", "cafe", "hello", "x", "helo"].iter() {
+            for verbosity in [Verbosity::Top, Verbosity::Closest, Verbosity::All].iter() {
+                assert_eq!(terms(sym_spell.lookup_with_scratch(input, *verbosity, 2, true, true, &mut scratch)),
+                           terms(sym_spell.lookup(input, *verbosity, 2, true, true)), "{}", input);
+            }
+        }
+        assert_eq!(terms(sym_spell.lookup_with_scratch("undrestanding", Verbosity::Top, 2, false, false, &mut scratch)),
+                   vec![("understanding".to_string(), 1, 7)]);
+    }
+
+    #[test]
+    fn delete_generation_test() {
+        let words = [("help", 5), ("hello", 12), ("hell", 10), ("yellow", 3), ("understanding", 7), ("under", 20),
+            ("a", 50), ("an", 40), ("caf\u{e9}", 9), ("cafe\u{301}s", 4), ("straße", 2), ("misspelled", 8)];
+        let build = |delete_generation: DeleteGeneration| {
+            let mut sym_spell = SymSpell::new(Some(2), Some(5), None);
+            sym_spell.set_delete_generation(delete_generation);
+            for (word, count) in words.iter() {
+                sym_spell.create_dictionary_entry(word.to_string(), *count);
+            }
+            sym_spell
+        };
+        let terms = |mut suggestions: Vec<SuggestItem>| -> Vec<(String, usize, Count)> {
+            suggestions.sort_by(|a, b| a.sort_key().cmp(&b.sort_key()));
+            suggestions.into_iter().map(|si| (si.term, si.distance, si.count)).collect()
+        };
+
+        let precomputed = build(DeleteGeneration::Precomputed);
+        let prefixes_only = build(DeleteGeneration::OnDemand { precomputed_edits: 0, cache_capacity: 16 });
+        let uncached = build(DeleteGeneration::OnDemand { precomputed_edits: 1, cache_capacity: 0 });
+        assert!(prefixes_only.entry_count() < uncached.entry_count());
+        assert!(uncached.entry_count() < precomputed.entry_count());
+
+        for input in ["helo", "hel", "yelow", "undrstanding", "unde", "x", "", "ab", "cafe", "caf\u{e9}", "strase", "mispeled", "hello"].iter() {
+            for &verbosity in [Verbosity::Top, Verbosity::Closest, Verbosity::All].iter() {
+                for max_edit_distance in 0..3 {
+                    let expected = terms(precomputed.lookup(input, verbosity, max_edit_distance, true, false));
+                    assert_eq!(terms(prefixes_only.lookup(input, verbosity, max_edit_distance, true, false)), expected, "{} {}", input, max_edit_distance);
+                    assert_eq!(terms(uncached.lookup(input, verbosity, max_edit_distance, true, false)), expected, "{} {}", input, max_edit_distance);
+                }
+            }
+        }
+        // cached buckets give the same results the second time round
+        assert_eq!(terms(prefixes_only.lookup("helo", Verbosity::All, 2, false, false)),
+                   terms(precomputed.lookup("helo", Verbosity::All, 2, false, false)));
+    }
+
+    #[test]
+    fn lookup_cache_test() {
+        let mut sym_spell: SymSpell = vec![("hello", 10), ("help", 5)].into_iter().collect();
+        let terms = |suggestions: Vec<SuggestItem>| -> Vec<String> { suggestions.into_iter().map(|si| si.term).collect() };
+        sym_spell.lookup("helo", Verbosity::Top, 2, false, false);
+        assert_eq!(sym_spell.lookup_cache_stats(), LookupCacheStats::default());
+
+        sym_spell.set_lookup_cache_capacity(8);
+        assert_eq!(terms(sym_spell.lookup("helo", Verbosity::Top, 2, false, false)), vec!["hello"]);
+        assert_eq!(terms(sym_spell.lookup("helo", Verbosity::Top, 2, false, false)), vec!["hello"]);
+        // every argument is part of the key
+        assert_eq!(terms(sym_spell.lookup("helo", Verbosity::All, 2, false, false)), vec!["hello", "help"]);
+        assert_eq!(terms(sym_spell.lookup("helo", Verbosity::Top, 1, false, false)), vec!["hello"]);
+        assert_eq!(sym_spell.lookup_cache_stats(), LookupCacheStats { hits: 1, misses: 3, len: 3 });
+        assert_eq!(sym_spell.lookup_cache_stats().hit_rate(), 0.25);
+
+        // a dictionary change is seen by the next lookup
+        sym_spell.create_dictionary_entry("helot".to_string(), 100);
+        assert_eq!(sym_spell.lookup_cache_stats().len, 0);
+        assert_eq!(terms(sym_spell.lookup("helo", Verbosity::Top, 2, false, false)), vec!["helot"]);
+        sym_spell.clear();
+        assert!(sym_spell.lookup("helo", Verbosity::Top, 2, false, false).is_empty());
+        assert_eq!(sym_spell.lookup_cache_stats(), LookupCacheStats { hits: 1, misses: 5, len: 1 });
+    }
+
+    #[test]
+    fn lookup_limits_test() {
+        let mut sym_spell: SymSpell = vec![("hello", 10), ("help", 5), ("understanding", 7)].into_iter().collect();
+        sym_spell.set_lookup_limits(LookupLimits { max_input_len: Some(8), max_candidates: None });
+        assert!(sym_spell.lookup("undrstanding", Verbosity::Top, 2, false, false).is_empty());
+        let unknown = sym_spell.lookup("undrstanding", Verbosity::Top, 2, true, false);
+        assert_eq!((unknown[0].term.as_str(), unknown[0].distance), ("undrstanding", 3));
+        // exact matches are still found
+        assert_eq!(sym_spell.lookup("understanding", Verbosity::Top, 2, false, true)[0].term, "understanding");
+        assert_eq!(sym_spell.lookup("helo", Verbosity::Top, 2, false, false)[0].term, "hello");
+        assert_eq!(sym_spell.truncated_lookups(), 2);
+
+        // "helo" and its 4 deletes are all the candidates of one edit, the deletes of those are cut off
+        sym_spell.set_lookup_limits(LookupLimits { max_input_len: None, max_candidates: Some(5) });
+        assert_eq!(sym_spell.lookup("helo", Verbosity::Top, 1, false, false)[0].term, "hello");
+        assert!(sym_spell.lookup("helo", Verbosity::All, 2, false, false).is_empty());
+        assert_eq!(sym_spell.truncated_lookups(), 3);
+
+        sym_spell.clear();
+        assert_eq!(sym_spell.truncated_lookups(), 0);
+    }
+
+    #[test]
+    fn find_similar_test() {
+        let sym_spell: SymSpell = vec![("restaurant", 10), ("restaurants", 5), ("rest", 100), ("test", 50)].into_iter().collect();
+        let similar = |input: &str, min_similarity: f64| -> Vec<(String, f64)> {
+            sym_spell.find_similar(input, min_similarity).into_iter().map(|si| (si.term, (si.score * 1000.0).round() / 1000.0)).collect()
+        };
+
+        assert_eq!(similar("restaurnt", 0.8), vec![("restaurant".to_string(), 0.9), ("restaurants".to_string(), 0.818)]);
+        assert_eq!(similar("restaurnt", 0.85), vec![("restaurant".to_string(), 0.9)]);
+        // one edit is a fifth of a short word
+        assert_eq!(similar("tesst", 0.8), vec![("test".to_string(), 0.8)]);
+        assert_eq!(similar("tesst", 0.5), vec![("test".to_string(), 0.8), ("rest".to_string(), 0.6)]);
+        assert_eq!(similar("test", 1.0), vec![("test".to_string(), 1.0)]);
+        assert!(similar("xyz", 0.5).is_empty());
+        // out of range minimums are clamped rather than rejected
+        assert_eq!(similar("test", 1.5), vec![("test".to_string(), 1.0)]);
+        assert_eq!(similar("tesst", -1.0), similar("tesst", 0.0));
+        assert!(similar("test", f64::NAN).is_empty());
+    }
+
+    #[test]
+    fn grapheme_cluster_lookup_test() {
+        // a decomposed accent is part of its letter's grapheme so it is one edit, not two
+        let sym_spell: SymSpell = vec![("cafe\u{301}", 10), ("👨\u{200D}👩\u{200D}👧", 5)].into_iter().collect();
+        let result = sym_spell.lookup("cafe", Verbosity::Top, 1, false, false);
+        assert_eq!((result[0].term.as_str(), result[0].distance), ("cafe\u{301}", 1));
+        let result = sym_spell.lookup("cafe\u{300}", Verbosity::Top, 1, false, false);
+        assert_eq!((result[0].term.as_str(), result[0].distance), ("cafe\u{301}", 1));
+        let result = sym_spell.lookup("👨", Verbosity::Top, 1, false, false);
+        assert_eq!(result[0].distance, 1);
+    }
+
+    #[test]
+    fn case_locale_test() {
+        let mut sym_spell: SymSpell = vec![("kış", 10), ("istanbul", 10), ("odası", 10), ("οδός", 10)].into_iter().collect();
+        let text = "KIŞ İSTANBUL ODASI ΟΔΌΣ";
+        // the default rules dot the 'I' and keep the dot of 'İ' as a combining mark
+        let misspelled = sym_spell.check_text(text, 2);
+        assert_eq!(misspelled.iter().map(|m| &text[m.byte_range.clone()]).collect::<Vec<_>>(), vec!["KIŞ", "İSTANBUL", "ODASI"]);
+        // the correction of a word in capitals is in capitals too
+        assert_eq!(misspelled[0].suggestion.as_ref().unwrap().term, "KIŞ");
+
+        sym_spell.set_case_locale(CaseLocale::from_language_tag("tr-TR"));
+        assert_eq!(sym_spell.case_locale(), CaseLocale::Turkic);
+        assert!(sym_spell.check_text(text, 2).is_empty());
+        assert!(sym_spell.is_known("İstanbul"));
+
+        // ignored words and cached lookups follow the rules
+        let mut sym_spell = SymSpell::new(Some(2), Some(7), None);
+        sym_spell.set_lookup_cache_capacity(16);
+        sym_spell.ignore_word("KIŞ");
+        let terms = |sym_spell: &SymSpell, input: &str| -> Vec<(String, usize)> {
+            sym_spell.lookup(input, Verbosity::Top, 2, false, true).into_iter().map(|si| (si.term, si.distance)).collect()
+        };
+        assert_eq!(terms(&sym_spell, "KIŞ"), vec![("KIŞ".to_string(), 0)]);
+        assert_eq!(terms(&sym_spell, "kış"), vec![]);
+        sym_spell.set_case_locale(CaseLocale::Turkic);
+        assert_eq!(terms(&sym_spell, "kış"), vec![("kış".to_string(), 0)]);
+        assert_eq!(terms(&sym_spell, "KIŞ"), vec![("KIŞ".to_string(), 0)]);
+        assert_eq!(terms(&sym_spell, "kiş"), vec![]);
+        assert!(sym_spell.unignore_word("KIŞ"));
+        assert_eq!(terms(&sym_spell, "KIŞ"), vec![]);
+    }
+
+    #[test]
+    fn equivalences_test() {
+        let mut sym_spell: SymSpell = vec![("straße", 50), ("strasser", 5), ("maerchen", 10)].into_iter().collect();
+        assert_eq!(sym_spell.lookup("strasse", Verbosity::Top, 2, false, false)[0].term, "strasser");
+
+        // words already loaded are indexed when the equivalences are set
+        sym_spell.set_equivalences(Equivalences::common());
+        let terms = |suggestions: Vec<SuggestItem>| -> Vec<(String, usize)> {
+            suggestions.into_iter().map(|si| (si.term, si.distance)).collect()
+        };
+        assert_eq!(terms(sym_spell.lookup("strasse", Verbosity::Closest, 2, false, false)), vec![("straße".to_string(), 0)]);
+        assert_eq!(terms(sym_spell.lookup("strasse", Verbosity::All, 2, false, false)), vec![("straße".to_string(), 0), ("strasser".to_string(), 1)]);
+        assert_eq!(terms(sym_spell.lookup("strasse", Verbosity::Top, 0, false, false)), vec![("straße".to_string(), 0)]);
+        // the variant may be in the input as well as in the dictionary word
+        assert_eq!(terms(sym_spell.lookup("mærchen", Verbosity::Top, 2, false, false)), vec![("maerchen".to_string(), 0)]);
+        assert!(sym_spell.lookup("straße", Verbosity::Top, 2, false, false).is_empty());
+
+        sym_spell.create_dictionary_entry("grœn".to_string(), 3);
+        assert_eq!(terms(sym_spell.lookup("groen", Verbosity::Top, 1, false, false)), vec![("grœn".to_string(), 0)]);
+        sym_spell.clear();
+        assert!(sym_spell.lookup("groen", Verbosity::Top, 1, false, false).is_empty());
+    }
+
+    #[cfg(feature = "unicode-normalization")]
+    #[test]
+    fn normalization_test() {
+        use crate::sym_spell::normalization::Normalization;
+
+        let load = |normalization: Normalization| -> SymSpell {
+            let mut sym_spell = SymSpell::new(None, None, None);
+            sym_spell.set_normalization(normalization);
+            sym_spell.create_dictionary_entry("café".to_string(), 10);
+            sym_spell.create_dictionary_entry("file".to_string(), 5);
+            sym_spell.write_line_to_bigram_dictionary("café file 3", " ").unwrap();
+            sym_spell
+        };
+        let exact = |sym_spell: &SymSpell, input: &str| -> Vec<String> {
+            sym_spell.lookup(input, Verbosity::Top, 0, false, true).into_iter().map(|si| si.term).collect()
+        };
+
+        let none = load(Normalization::None);
+        assert!(exact(&none, "cafe\u{301}").is_empty());
+
+        let nfc = load(Normalization::Nfc);
+        assert_eq!(exact(&nfc, "cafe\u{301}"), vec!["café"]);
+        assert!(exact(&nfc, "ﬁle").is_empty());
+        assert!(nfc.check_text("cafe\u{301}", 2).is_empty());
+        assert_eq!(nfc.bigram_frequency("café", "file"), 3);
+
+        let nfkc = load(Normalization::Nfkc);
+        assert_eq!(exact(&nfkc, "ﬁle"), vec!["file"]);
+        assert_eq!(nfkc.lookup_compound("cafe\u{301} ﬁle", 2)[0].term, "café file");
+        // a dictionary word in another form is stored normalized
+        let mut sym_spell = SymSpell::new(None, None, None);
+        sym_spell.set_normalization(Normalization::Nfc);
+        sym_spell.create_dictionary_entry("nai\u{308}ve".to_string(), 1);
+        assert_eq!(exact(&sym_spell, "naïve"), vec!["naïve"]);
+    }
+
+    #[test]
+    fn lookup_top1_test() {
+        let sym_spell: SymSpell = vec![("spelling", 100), ("spewing", 5), ("the", 1000)].into_iter().collect();
+        let best = sym_spell.lookup_top1("speling", 2).unwrap();
+        assert_eq!((best.term.as_str(), best.distance, best.count), ("spelling", 1, 100));
+        assert_eq!(sym_spell.lookup_top1("the", 2).unwrap().distance, 0);
+        assert!(sym_spell.lookup_top1("xyzzy", 2).is_none());
+        assert!(sym_spell.lookup_top1("speling", 3).is_none());
+        let looked_up = sym_spell.lookup("speling", Verbosity::Top, 1, false, true).pop().unwrap();
+        let best = sym_spell.lookup_top1("speling", 1).unwrap();
+        assert_eq!((best.term, best.distance, best.probability), (looked_up.term, looked_up.distance, looked_up.probability));
+    }
+
+    #[test]
+    fn join_line_wraps_test() {
+        let mut sym_spell: SymSpell = vec![("the", 100), ("correction", 10), ("well", 10), ("known", 10)].into_iter().collect();
+        let text = "the correc-\ntion well-\r\nknown";
+        assert_eq!(sym_spell.check_text(text, 2).len(), 2);
+
+        sym_spell.set_join_line_wraps(true);
+        assert!(sym_spell.check_text(text, 2).is_empty());
+        // "wellknown" is not a word so "well" and "known" stay separate
+        assert_eq!(sym_spell.document_stats(text).total_tokens, 4);
+        // without a line break the hyphen is kept
+        assert_eq!(sym_spell.document_stats("correc- tion").error_rate(), 1.0);
+    }
+
+    #[test]
+    fn spellchecker_error_test() {
+        assert_eq!(SymSpell::try_new(Some(2), Some(2), None).err(), Some(SpellcheckerError::InvalidPrefixLength { prefix_length: 2, max_dictionary_edit_distance: 2 }));
+        assert_eq!(SymSpell::try_new(Some(0), Some(0), None).err().map(|err| err.code()), Some(1));
+
+        let mut sym_spell = SymSpell::new(Some(1), Some(7), None);
+        assert_eq!(sym_spell.write_line_to_dictionary("hello 10", " "), Ok(()));
+        assert_eq!(sym_spell.write_line_to_dictionary("hello", " "), Err(SpellcheckerError::MissingCount));
+        assert_eq!(sym_spell.load_report().malformed_lines, 1);
+        assert_eq!(sym_spell.try_lookup("helo", Verbosity::Top, 1, false, false).unwrap()[0].term, "hello");
+        let err = sym_spell.try_lookup("helo", Verbosity::Top, 2, false, false).unwrap_err();
+        assert_eq!(err.to_string(), "max edit distance 2 exceeds the max dictionary edit distance 1");
+        assert_eq!(sym_spell.try_lookup_compound("helo", 1, CompoundOptions::default()).unwrap()[0].term, "hello");
+        assert_eq!(sym_spell.try_lookup_compound("helo", 2, CompoundOptions::default()).err(), Some(err.clone()));
+        assert_eq!(sym_spell.try_lookup_compound_detailed("helo", 2, CompoundOptions::default()).err(), Some(err.clone()));
+        assert_eq!(sym_spell.try_word_segmentation("hellohello", 1, None).unwrap().1, "hello hello");
+        assert_eq!(sym_spell.try_word_segmentation("hellohello", 2, None).err(), Some(err));
+
+        // the errors of the line parsers propagate with ?
+        let write = |sym_spell: &mut SymSpell, line: &str| -> Result<(), SpellcheckerError> {
+            sym_spell.write_line_to_bigram_dictionary(line, " ")?;
+            Ok(())
+        };
+        assert_eq!(write(&mut sym_spell, "the"), Err(SpellcheckerError::BigramLine(BigramLineError::MissingWords)));
+        assert!(std::error::Error::source(&write(&mut sym_spell, "the").unwrap_err()).is_some());
+    }
+
+    #[test]
+    #[should_panic(expected = "max edit distance 3 exceeds the max dictionary edit distance 2")]
+    fn lookup_edit_distance_panic_test() {
+        SymSpell::new(Some(2), None, None).lookup("helo", Verbosity::Top, 3, false, false);
+    }
+
+    #[test]
+    fn bigram_line_formats_test() {
+        let mut sym_spell = SymSpell::new(None, None, None);
+        assert_eq!(sym_spell.write_line_to_bigram_dictionary("in the 822", " "), Ok(()));
+        assert_eq!(sym_spell.write_line_to_bigram_dictionary("  of\tthe \t 12\r\n", " "), Ok(()));
+        assert_eq!(sym_spell.write_line_to_bigram_dictionary("to be", " "), Ok(()));
+        assert_eq!(sym_spell.write_line_to_bigram_dictionary("new , york , 7", ","), Ok(()));
+        assert_eq!(sym_spell.bigram_count(), 4);
+        assert_eq!(sym_spell.bigram_count_min(), Some(1));
+
+        assert_eq!(sym_spell.write_line_to_bigram_dictionary("", " "), Err(BigramLineError::MissingWords));
+        assert_eq!(sym_spell.write_line_to_bigram_dictionary("the", " "), Err(BigramLineError::MissingWords));
+        assert_eq!(sym_spell.write_line_to_bigram_dictionary("a,,5", ","), Err(BigramLineError::MissingWords));
+        assert_eq!(sym_spell.write_line_to_bigram_dictionary("one two three 4", " "), Err(BigramLineError::TooManyParts(4)));
+        assert_eq!(sym_spell.load_report().malformed_lines, 4);
+
+        // a bad count follows the invalid count policy rather than failing the line
+        assert_eq!(sym_spell.write_line_to_bigram_dictionary("at the zero", " "), Ok(()));
+        assert_eq!(sym_spell.bigram_count(), 4);
+        assert_eq!(sym_spell.load_report().invalid_counts, 1);
+    }
+
+    #[test]
+    fn deterministic_order_test() {
+        let words = [("ab", 10), ("ac", 10), ("ad", 10), ("a", 5), ("b", 5)];
+        let mut results = vec![];
+        for seed in 0..8 {
+            let mut sym_spell = SymSpell::with_hasher(None, None, None, SeededState::new(seed));
+            // a different insertion order for every seed as well
+            for i in 0..words.len() {
+                let (word, count) = words[(i + seed as usize) % words.len()];
+                sym_spell.create_dictionary_entry(word.to_string(), count);
+            }
+            let lookup = |input: &str, verbosity: Verbosity| -> Vec<(String, usize)> {
+                sym_spell.lookup(input, verbosity, 2, false, false).into_iter().map(|si| (si.term, si.distance)).collect()
+            };
+            // ties on distance and count are broken alphabetically
+            assert_eq!(lookup("ae", Verbosity::Top), vec![("ab".to_string(), 1)]);
+            let all = lookup("ab", Verbosity::All);
+            // one char words are found through more than one delete, but reported once
+            let mut terms: Vec<&String> = all.iter().map(|(term, _)| term).collect();
+            terms.dedup();
+            assert_eq!(terms.len(), all.len(), "{:?}", all);
+            results.push((lookup("ae", Verbosity::Closest), all));
+        }
+        assert!(results.windows(2).all(|pair| pair[0] == pair[1]));
+    }
+
+    #[test]
+    fn suggestion_ranker_test() {
+        // prefers long words, then the word after "the"
+        struct Longest;
+
+        impl SuggestionRanker for Longest {
+            fn score(&self, suggestion: &SuggestItem, context: &LookupContext) -> f64 {
+                let after_the = context.previous_word == Some("the") && suggestion.term == "help";
+                suggestion.term.len() as f64 + if after_the { 10.0 } else { 0.0 }
+            }
+        }
+
+        let mut sym_spell: SymSpell = vec![("hello", 100), ("help", 500), ("held", 50), ("helot", 1), ("world", 10)].into_iter().collect();
+        sym_spell.set_lookup_cache_capacity(8);
+        let terms = |sym_spell: &SymSpell, verbosity: Verbosity| -> Vec<String> {
+            sym_spell.lookup("helo", verbosity, 2, false, false).into_iter().map(|si| si.term).collect()
+        };
+        let by_sort_key = [Verbosity::Top, Verbosity::Closest, Verbosity::All].iter().map(|&verbosity| terms(&sym_spell, verbosity)).collect::<Vec<_>>();
+        assert_eq!(by_sort_key[0], vec!["help"]);
+
+        // the default ranker keeps the order of the sort key
+        sym_spell.set_suggestion_ranker(Some(Box::new(DefaultRanker)));
+        for (i, &verbosity) in [Verbosity::Top, Verbosity::Closest, Verbosity::All].iter().enumerate() {
+            assert_eq!(terms(&sym_spell, verbosity), by_sort_key[i]);
+        }
+
+        // setting a ranker empties the lookup cache
+        sym_spell.set_suggestion_ranker(Some(Box::new(Longest)));
+        assert_eq!(terms(&sym_spell, Verbosity::Top), vec!["hello"]);
+        assert_eq!(terms(&sym_spell, Verbosity::Closest), vec!["hello", "helot", "help", "held"]);
+        assert_eq!(sym_spell.lookup_compound("helo wrld", 2)[0].term, "hello world");
+        assert_eq!(sym_spell.lookup_with_context("The", "helo", Verbosity::Top, 2, false, false)[0].term, "help");
+
+        sym_spell.set_suggestion_ranker(None);
+        assert_eq!(terms(&sym_spell, Verbosity::Top), vec!["help"]);
+    }
+
+    #[test]
+    fn probability_test() {
+        let mut sym_spell: SymSpell = vec![("hello", 1000), ("help", 4000), ("world", 500)].into_iter().collect();
+        sym_spell.set_lookup_cache_capacity(8);
+        let probability = |sym_spell: &SymSpell, input: &str| sym_spell.lookup(input, Verbosity::Top, 2, false, true)[0].probability.unwrap();
+        const N: f64 = 5500.0;
+
+        assert_eq!(probability(&sym_spell, "help"), 4000.0 / N);
+        // every edit multiplies it by the decay
+        assert!((probability(&sym_spell, "helo") - 4000.0 / N * 0.1).abs() < 1e-12);
+        assert!((probability(&sym_spell, "hlpx") - 4000.0 / N * 0.01).abs() < 1e-12);
+
+        // the decay applies to cached lookups too
+        sym_spell.set_edit_decay(0.5);
+        assert!((probability(&sym_spell, "helo") - 4000.0 / N * 0.5).abs() < 1e-12);
+        sym_spell.set_edit_decay(2.0);
+        assert_eq!(sym_spell.edit_decay(), 1.0);
+
+        let compound = &sym_spell.lookup_compound("hello wrld", 2)[0];
+        assert_eq!(compound.term, "hello world");
+        assert!((compound.probability.unwrap() - 1000.0 / N * 500.0 / N).abs() < 1e-12);
+        assert_eq!(SuggestItem::new("hello".into(), 0, 1000).probability, None);
+    }
+
+    #[test]
+    fn trigram_test() {
+        let mut sym_spell: SymSpell = vec![("went", 100), ("over", 100), ("there", 50), ("their", 60), ("lost", 50)].into_iter().collect();
+        sym_spell.write_line_to_bigram_dictionary("went over 10", " ").unwrap();
+        sym_spell.write_line_to_bigram_dictionary("over there 5", " ").unwrap();
+        sym_spell.write_line_to_bigram_dictionary("over their 5", " ").unwrap();
+        assert_eq!(sym_spell.lookup_compound("went over ther", 2)[0].term, "went over their");
+
+        assert_eq!(sym_spell.write_line_to_trigram_dictionary("went over there 4", " "), Ok(()));
+        assert_eq!(sym_spell.write_line_to_trigram_dictionary("over there", " "), Err(TrigramLineError::MissingWords));
+        assert_eq!(sym_spell.write_line_to_trigram_dictionary("a b c d 5", " "), Err(TrigramLineError::TooManyParts(5)));
+        assert_eq!(sym_spell.trigram_count(), 1);
+        assert_eq!(sym_spell.lookup_compound("went over ther", 2)[0].term, "went over there");
+        assert_eq!(sym_spell.lookup_with_context("Went over", "ther", Verbosity::Top, 2, false, false)[0].term, "there");
+        // a single previous word backs off to the bigrams
+        assert_eq!(sym_spell.lookup_with_context("went", "ovr", Verbosity::Top, 2, false, false)[0].score, 10.0);
+
+        // no n-gram spans a sentence end
+        sym_spell.learn_ngrams("They lost their way. Went over there!");
+        assert_eq!(sym_spell.bigram_count(), 6);
+        assert_eq!(sym_spell.bigram_frequency("went", "over"), 11);
+        assert_eq!(sym_spell.trigram_frequency("went", "over", "there"), 5);
+        assert_eq!(sym_spell.trigram_frequency("their", "way", "went"), 0);
+        assert_eq!(sym_spell.trigram_count(), 3);
+        // the learned pairs seen once leave the minimum of the loaded bigrams
+        assert_eq!(sym_spell.bigram_count_min(), Some(5));
+
+        sym_spell.clear();
+        assert_eq!(sym_spell.trigram_count(), 0);
+    }
+
+    #[test]
+    fn entry_metadata_test() {
+        let mut sym_spell: SymSpell = vec![("iphone", 100), ("phone", 500)].into_iter().collect();
+        sym_spell.set_lookup_cache_capacity(8);
+        assert!(sym_spell.lookup("iphnoe", Verbosity::Top, 2, false, false)[0].metadata.is_none());
+
+        let metadata = EntryMetadata { source: Some("brands".into()), canonical: Some("iPhone".into()), ..EntryMetadata::default() };
+        sym_spell.set_entry_metadata("iphone", Some(metadata.clone()));
+        assert_eq!(sym_spell.entry_metadata("iphone"), Some(&metadata));
+        // the metadata travels with every suggestion of the word, cached or not
+        let metadata_of = |suggestions: Vec<SuggestItem>| suggestions[0].metadata.as_deref().cloned();
+        assert_eq!(metadata_of(sym_spell.lookup("iphnoe", Verbosity::Top, 2, false, false)), Some(metadata.clone()));
+        assert_eq!(metadata_of(sym_spell.complete("iph", 1)), Some(metadata.clone()));
+        let correction = sym_spell.lookup_compound_detailed("my iphnoe", 2, CompoundOptions::default());
+        assert_eq!(correction.parts[1].suggestion.metadata.as_deref(), Some(&metadata));
+        assert!(metadata_of(sym_spell.lookup("phnoe", Verbosity::Top, 2, false, false)).is_none());
+
+        sym_spell.set_entry_metadata("iphone", Some(EntryMetadata::default()));
+        assert_eq!(sym_spell.entry_metadata("iphone"), None);
+        assert!(metadata_of(sym_spell.lookup("iphnoe", Verbosity::Top, 2, false, false)).is_none());
+    }
+
+    #[test]
+    fn corpus_size_test() {
+        let mut sym_spell: SymSpell = vec![("hello", 1000), ("help", 4000)].into_iter().collect();
+        assert_eq!(sym_spell.corpus_size(), 5000.0);
+        assert_eq!(sym_spell.lookup("hello", Verbosity::Top, 2, false, true)[0].probability, Some(0.2));
+
+        sym_spell.set_corpus_size(Some(GOOGLE_BOOKS_CORPUS_SIZE));
+        assert_eq!(sym_spell.corpus_size(), GOOGLE_BOOKS_CORPUS_SIZE);
+        assert_eq!(sym_spell.lookup("hello", Verbosity::Top, 2, false, true)[0].probability, Some(1000.0 / GOOGLE_BOOKS_CORPUS_SIZE));
+        sym_spell.set_corpus_size(Some(-1.0));
+        assert_eq!(sym_spell.corpus_size(), 5000.0);
+
+        // the sum follows the entries added
+        sym_spell.create_dictionary_entry("world".into(), 5000);
+        assert_eq!(sym_spell.corpus_size(), 10000.0);
+        sym_spell.clear();
+        assert_eq!(sym_spell.corpus_size(), 1.0);
+    }
+
+    #[test]
+    fn bigram_weight_test() {
+        let mut sym_spell: SymSpell = vec![("the", 1000), ("rapist", 1), ("therapist", 5), ("in", 800)].into_iter().collect();
+        sym_spell.write_line_to_bigram_dictionary("the rapist 900", " ").unwrap();
+        sym_spell.write_line_to_bigram_dictionary("in the 700", " ").unwrap();
+        assert_eq!(sym_spell.bigram_weight(), 1.0);
+        assert_eq!(sym_spell.word_segmentation("therapist", 0, None).1, "the rapist");
+        assert_eq!(sym_spell.lookup_compound("inthw", 1)[0].count, 700);
+
+        // without the bigrams each word counts on its own
+        sym_spell.set_bigram_weight(0.0);
+        assert_eq!(sym_spell.word_segmentation("therapist", 0, None).1, "therapist");
+        let naive_count = sym_spell.lookup_compound("inthw", 1)[0].count;
+        assert!(naive_count < 700, "{}", naive_count);
+
+        sym_spell.set_bigram_weight(0.5);
+        assert_eq!(sym_spell.lookup_compound("inthw", 1)[0].count, (350.0 + naive_count as f64 / 2.0) as Count);
+        sym_spell.set_bigram_weight(3.0);
+        assert_eq!(sym_spell.bigram_weight(), 1.0);
+    }
+
+    #[test]
+    fn complete_test() {
+        let mut sym_spell: SymSpell = vec![("help", 500), ("hello", 100), ("helpful", 50), ("held", 50), ("shell", 80), ("yellow", 30), ("hex", 10)]
+            .into_iter()
+            .collect();
+        let terms = |completions: Vec<SuggestItem>| -> Vec<(String, usize)> { completions.into_iter().map(|si| (si.term, si.distance)).collect() };
+
+        assert_eq!(terms(sym_spell.complete("hel", 10)), vec![("help".into(), 0), ("hello".into(), 0), ("held".into(), 0), ("helpful".into(), 0)]);
+        assert_eq!(terms(sym_spell.complete("help", 1)), vec![("help".into(), 0)]);
+        assert!(sym_spell.complete("helx", 10).is_empty());
+        assert_eq!(sym_spell.complete("", 10).len(), 7);
+
+        // words added after the index was built are completed too
+        sym_spell.create_dictionary_entry("helium".into(), 20);
+        assert_eq!(sym_spell.complete("heli", 10)[0].term, "helium");
+
+        // one edit anywhere in the prefix, the first grapheme included
+        let fuzzy = |prefix: &str| -> Vec<String> { sym_spell.complete_fuzzy(prefix, 10).into_iter().map(|si| si.term).collect() };
+        assert_eq!(fuzzy("hlep"), vec!["help", "helpful"]);
+        assert_eq!(fuzzy("jell"), vec!["hello", "yellow"]);
+        assert_eq!(fuzzy("ehll"), vec!["hello"]);
+        // words starting with the prefix as typed come first
+        assert_eq!(fuzzy("hell"), vec!["hello", "help", "shell", "held", "helpful", "yellow", "helium"]);
+        assert_eq!(terms(sym_spell.complete_fuzzy("helpf", 2)), vec![("helpful".into(), 0), ("help".into(), 1)]);
+        // short prefixes are completed exactly
+        assert_eq!(fuzzy("hx"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn lookup_compound_detailed_test() {
+        let sym_spell: SymSpell = vec![("the", 1000), ("quick", 50), ("brown", 40), ("fox", 20), ("in", 300), ("side", 60), ("inside", 80)].into_iter().collect();
+        let input = "teh qxzvw brown fox ins ide";
+        let correction = sym_spell.lookup_compound_detailed(input, 2, CompoundOptions::default());
+        assert_eq!(correction.suggestion.term, sym_spell.lookup_compound(input, 2)[0].term);
+        let parts: Vec<(&str, &str, PartStatus)> = correction.parts.iter()
+            .map(|part| (&input[part.byte_range.clone()], part.suggestion.term.as_str(), part.status))
+            .collect();
+        assert_eq!(parts, vec![
+            ("teh", "the", PartStatus::Corrected),
+            ("qxzvw", "qxzvw", PartStatus::Unknown),
+            ("brown", "brown", PartStatus::Unchanged),
+            ("fox", "fox", PartStatus::Unchanged),
+            ("ins ide", "inside", PartStatus::Corrected),
+        ]);
+        assert!(correction.has_unknown());
+        // an unknown part is estimated at one more than the max edit distance
+        assert_eq!(correction.parts[1].suggestion.distance, 3);
+
+        let correction = sym_spell.lookup_compound_detailed("the quick", 2, CompoundOptions::default());
+        assert!(correction.parts.iter().all(|part| part.status == PartStatus::Unchanged));
+        assert!(!correction.has_unknown());
+    }
+
+    #[test]
+    fn suggest_query_test() {
+        let mut sym_spell: SymSpell = vec![("the", 1000), ("quick", 50), ("brown", 40), ("fox", 20), ("hello", 100), ("world", 80)].into_iter().collect();
+        for bigram in ["the quick 20", "quick brown 15", "brown fox 10"].iter() {
+            sym_spell.write_line_to_bigram_dictionary(bigram, " ").unwrap();
+        }
+
+        let suggestion = sym_spell.suggest_query("Teh  quikc brownfox", 2, 10.0).unwrap();
+        assert_eq!((suggestion.term.as_str(), suggestion.distance), ("the quick brown fox", 3));
+        assert!(suggestion.probability_ratio > 1e10);
+        assert_eq!(sym_spell.suggest_query("thequickbrown fox", 2, 10.0).unwrap().term, "the quick brown fox");
+
+        // a correct query has nothing more probable
+        assert_eq!(sym_spell.suggest_query("the quick brown fox", 2, 0.0), None);
+        assert_eq!(sym_spell.suggest_query(" ", 2, 0.0), None);
+
+        // "helo" is unknown, 10 / (N * 10^4), and "hello" with one edit 100 / N * 0.1
+        let suggestion = sym_spell.suggest_query("helo", 2, 1000.0).unwrap();
+        assert!((suggestion.probability_ratio - 1e4).abs() < 1e-6);
+        assert_eq!(sym_spell.suggest_query("helo", 2, 1e5), None);
+    }
+
+    #[test]
+    fn lookup_with_context_test() {
+        let mut sym_spell: SymSpell = vec![("their", 500), ("there", 400), ("the", 5000), ("over", 100), ("lost", 50)].into_iter().collect();
+        sym_spell.write_line_to_bigram_dictionary("over there 40", " ").unwrap();
+        sym_spell.write_line_to_bigram_dictionary("lost their 30", " ").unwrap();
+        let terms = |previous: &str, verbosity: Verbosity| -> Vec<String> {
+            sym_spell.lookup_with_context(previous, "ther", verbosity, 1, false, false).into_iter().map(|si| si.term).collect()
+        };
+
+        assert_eq!(terms("over", Verbosity::Top), vec!["there"]);
+        assert_eq!(terms("Lost", Verbosity::Top), vec!["their"]);
+        assert_eq!(terms("over", Verbosity::Closest), vec!["there", "the", "their"]);
+        // without context the order is that of lookup
+        assert_eq!(terms("", Verbosity::Closest), terms("unrelated", Verbosity::Closest));
+        let lookup: Vec<String> = sym_spell.lookup("ther", Verbosity::Closest, 1, false, false).into_iter().map(|si| si.term).collect();
+        assert_eq!(terms("", Verbosity::Closest), lookup);
+        assert_eq!(sym_spell.lookup_with_context("over", "there", Verbosity::Top, 1, false, true)[0].score, 40.0);
+    }
+
+    #[test]
+    fn suggest_in_context_test() {
+        let mut sym_spell: SymSpell = vec![("hello", 100), ("help", 500), ("held", 50), ("he", 2000)].into_iter().collect();
+        sym_spell.write_line_to_bigram_dictionary("say hello 5", " ").unwrap();
+        sym_spell.write_line_to_bigram_dictionary("held up 30", " ").unwrap();
+        let terms = |left: &str, token: &str, right: &str| -> Vec<String> {
+            sym_spell.suggest_in_context(left, token, right, 3).into_iter().map(|si| si.term).collect()
+        };
+
+        // still typing so completions rank ahead of corrections
+        assert_eq!(terms("", "Hel", ""), vec!["help", "hello", "held"]);
+        assert_eq!(terms("I say ", "hel", ""), vec!["hello", "help", "held"]);
+        assert_eq!(terms("I say. ", "hel", ""), vec!["help", "hello", "held"]);
+        // a finished token is only corrected
+        assert_eq!(terms("", "hel", " now"), vec!["he", "help", "held"]);
+        assert_eq!(terms("", "hel", " up"), vec!["held", "he", "help"]);
+        assert!(terms("", "", "").is_empty());
+    }
+
+    #[test]
+    fn clear_test() {
+        let mut sym_spell: SymSpell = vec![("hello", 10)].into_iter().collect();
+        sym_spell.write_line_to_bigram_dictionary("hello world 5", " ").unwrap();
+        sym_spell.clear();
+        assert_eq!(sym_spell.word_count(), 0);
+        assert_eq!(sym_spell.entry_count(), 0);
+        assert_eq!(sym_spell.max_length(), 0);
+        assert_eq!(sym_spell.prefix_length(), 7);
+        assert!(sym_spell.lookup("helo", Verbosity::Top, 2, false, false).is_empty());
+    }
+
+    #[test]
+    fn preserve_proper_nouns_test() {
+        let sym_spell: SymSpell = vec![("i", 100), ("met", 100), ("mark", 100), ("today", 100), ("Paris", 50), ("in", 100)].into_iter().collect();
+        let options = CompoundOptions { preserve_proper_nouns: true, ..CompoundOptions::default() };
+
+        let result = sym_spell.lookup_compound_with_options("i met Marc today", 2, options);
+        assert_eq!(result[0].term, "i met Marc today");
+
+        let result = sym_spell.lookup_compound_with_options("i met Marc in Pariss", 2, options);
+        assert_eq!(result[0].term, "i met Marc in Paris");
+
+        let result = sym_spell.lookup_compound_with_options("i met mark. Todya", 2, options);
+        assert_eq!(result[0].term, "i met mark today");
+
+        let result = sym_spell.lookup_compound("i met Marc today", 2);
+        assert_eq!(result[0].term, "i met mark today");
+    }
+
+    #[test]
+    fn transfer_casing_test() {
+        let sym_spell: SymSpell = vec![("where", 100), ("is", 300), ("the", 1000), ("love", 80), ("i", 100), ("met", 100), ("mark", 100), ("today", 100)]
+            .into_iter()
+            .collect();
+        let options = CompoundOptions { transfer_casing: true, ..CompoundOptions::default() };
+
+        assert_eq!(sym_spell.correct_text("Whereis th elove", 2, options), "Where is the love");
+        assert_eq!(sym_spell.correct_text("WHEREIS TH ELOVE", 2, options), "WHERE IS THE LOVE");
+        // without it the correction is in the case of the dictionary
+        assert_eq!(sym_spell.correct_text("Whereis th elove", 2, CompoundOptions::default()), "where is the love");
+
+        let correction = sym_spell.lookup_compound_detailed("I met Mrak todya", 2, options);
+        assert_eq!(correction.suggestion.term, "I met Mark today");
+        assert_eq!(correction.parts[2].suggestion.term, "Mark");
+
+        // proper nouns are kept as they are
+        let options = CompoundOptions { preserve_proper_nouns: true, transfer_casing: true };
+        assert_eq!(sym_spell.correct_text("I met Marc todya", 2, options), "I met Marc today");
+    }
+
+    #[test]
+    fn logger_test() {
+        let mut sym_spell: SymSpell = vec![("hello", 100), ("world", 50)].into_iter().collect();
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let logged = Arc::clone(&lines);
+        sym_spell.set_logger(Some(Box::new(move |event: &LogEvent| logged.lock().unwrap().push(event.to_string()))));
+        let last = || lines.lock().unwrap().last().cloned().unwrap();
+
+        sym_spell.lookup("hello", Verbosity::Top, 2, false, false);
+        assert_eq!(last(), "lookup \"hello\": 0 suggestions from 0 candidates, returned early: exact match");
+        sym_spell.lookup("helo", Verbosity::Closest, 2, false, false);
+        assert!(last().starts_with("lookup \"helo\": 1 suggestions from "));
+        assert!(!last().contains("returned early"));
+
+        sym_spell.set_lookup_cache_capacity(10);
+        sym_spell.lookup("helo", Verbosity::Closest, 2, false, false);
+        sym_spell.lookup("helo", Verbosity::Closest, 2, false, false);
+        assert!(last().ends_with("returned early: cached"));
+
+        sym_spell.lookup_compound("helo wrld", 2);
+        assert_eq!(last(), "lookup_compound \"helo wrld\": 2 parts at distance 2");
+        sym_spell.word_segmentation("helloworld", 0, None);
+        assert_eq!(last(), "word_segmentation \"helloworld\": 2 words at distance 1");
+
+        sym_spell.set_logger(None);
+        let count = lines.lock().unwrap().len();
+        sym_spell.lookup("hello", Verbosity::Top, 2, false, false);
+        assert_eq!(lines.lock().unwrap().len(), count);
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn metrics_test() {
+        let mut sym_spell: SymSpell = vec![("hello", 100), ("help", 50), ("world", 50)].into_iter().collect();
+        assert_eq!(sym_spell.metrics(), Metrics::default());
+        assert_eq!(sym_spell.metrics().average_candidates(), 0.0);
+
+        // an exact match checks no candidates
+        sym_spell.lookup("hello", Verbosity::Top, 2, false, false);
+        sym_spell.lookup("helo", Verbosity::All, 2, false, false);
+        let metrics = sym_spell.metrics();
+        assert_eq!(metrics.lookups, 2);
+        assert!(metrics.candidates > 0);
+        assert_eq!(metrics.average_candidates(), metrics.candidates as f64 / 2.0);
+        assert!(metrics.distance_computations > 0 && metrics.distance_computations < metrics.candidates);
+        assert_eq!(metrics.average_time(), metrics.total_time / 2);
+
+        sym_spell.set_lookup_cache_capacity(10);
+        sym_spell.lookup("helo", Verbosity::All, 2, false, false);
+        sym_spell.lookup("helo", Verbosity::All, 2, false, false);
+        assert_eq!((sym_spell.metrics().lookups, sym_spell.metrics().cache_hits), (3, 1));
+
+        // compound lookups count the lookups of their words
+        sym_spell.reset_metrics();
+        sym_spell.lookup_compound("helo wrld", 2);
+        assert!(sym_spell.metrics().lookups >= 2);
+        sym_spell.clear();
+        assert_eq!(sym_spell.metrics(), Metrics::default());
+    }
+
+    #[test]
+    fn lookup_borrowed_test() {
+        let mut sym_spell: SymSpell = vec![("hello", 100), ("help", 50), ("hell", 50)].into_iter().collect();
+        let owned = |suggestions: Vec<SuggestRef>| -> Vec<String> { suggestions.into_iter().map(|si| format!("{:?}", si.into_owned())).collect() };
+        let expected = |input: &str, include_unknown: bool| -> Vec<String> {
+            sym_spell.lookup(input, Verbosity::All, 2, include_unknown, true).into_iter().map(|si| format!("{:?}", si)).collect()
+        };
+
+        for input in ["helo", "hello", "xyzzy"].iter() {
+            assert_eq!(owned(sym_spell.lookup_borrowed(input, Verbosity::All, 2, true, true)), expected(input, true));
+        }
+        // dictionary words are borrowed, the unknown input is not
+        let suggestions = sym_spell.lookup_borrowed("helo", Verbosity::All, 2, false, false);
+        assert!(suggestions.len() > 1 && suggestions.iter().all(|si| matches!(si.term, Cow::Borrowed(_))));
+        let unknown = sym_spell.lookup_borrowed("xyzzy", Verbosity::Top, 2, true, false);
+        assert!(matches!(unknown[0].term, Cow::Owned(_)));
+
+        // the lookup cache holds owned copies
+        sym_spell.set_lookup_cache_capacity(10);
+        let suggestions = sym_spell.lookup_borrowed("helo", Verbosity::All, 2, false, false);
+        assert!(suggestions.iter().all(|si| matches!(si.term, Cow::Owned(_))));
+        assert_eq!(sym_spell.lookup_cache_stats().misses, 1);
+    }
+
+    #[test]
+    #[cfg(feature = "dawg")]
+    fn dawg_backend_test() {
+        let words = [("hello", 100), ("help", 50), ("hell", 50), ("yellow", 20), ("jello", 10), ("halo", 5), ("héllo", 1)];
+        let mut delete_hash = SymSpell::new(Some(2), Some(7), Some(1));
+        let mut dawg = SymSpell::with_backend(Some(2), Some(7), Some(1), Backend::Dawg);
+        for (word, count) in words.iter() {
+            delete_hash.create_dictionary_entry(word.to_string(), *count);
+            dawg.create_dictionary_entry(word.to_string(), *count);
+        }
+        assert_eq!(dawg.backend(), Backend::Dawg);
+        assert_eq!(dawg.word_count(), delete_hash.word_count());
+        // no deletes are stored
+        assert_eq!(dawg.entry_count(), 0);
+
+        let terms = |suggestions: Vec<SuggestItem>| -> Vec<(String, usize, Count)> {
+            suggestions.into_iter().map(|si| (si.term, si.distance, si.count)).collect()
+        };
+        for input in ["helo", "hllo", "yelow", "hello", "xyzzy", "h"].iter() {
+            for verbosity in [Verbosity::Top, Verbosity::Closest, Verbosity::All].iter() {
+                for max_edit_distance in 1..=2 {
+                    assert_eq!(terms(dawg.lookup(input, *verbosity, max_edit_distance, false, false)),
+                               terms(delete_hash.lookup(input, *verbosity, max_edit_distance, false, false)),
+                               "{} {:?} {}", input, verbosity, max_edit_distance);
+                }
+            }
+        }
+
+        // words added after a lookup are found by the next one
+        dawg.create_dictionary_entry("heron".to_string(), 1);
+        assert_eq!(dawg.lookup("herom", Verbosity::Top, 1, false, false)[0].term, "heron");
+        dawg.clear();
+        assert!(dawg.lookup("helo", Verbosity::All, 2, false, false).is_empty());
+        assert_eq!(dawg.backend(), Backend::Dawg);
+
+        assert!(matches!(SymSpell::try_with_backend(Some(2), Some(1), None, Backend::Dawg), Err(SpellcheckerError::InvalidPrefixLength { .. })));
+    }
+
+    #[test]
+    fn estimate_memory_test() {
+        assert_eq!(SymSpell::estimate_memory(2, 7, 0), 0);
+        let distance2 = SymSpell::estimate_memory(2, 7, 82_765);
+        // the bundled English dictionary takes about 70 MB
+        assert!(distance2 > 50_000_000 && distance2 < 100_000_000, "{}", distance2);
+        assert!(SymSpell::estimate_memory(3, 7, 82_765) > distance2);
+        assert!(SymSpell::estimate_memory(1, 7, 82_765) < distance2);
+        assert!(SymSpell::estimate_memory(2, 5, 82_765) < distance2);
+        assert!(SymSpell::estimate_memory(2, 7, 1_000) < SymSpell::estimate_memory(2, 7, 2_000));
+    }
+
+    #[test]
+    fn load_words_with_progress_test() {
+        let words = || vec![("hello", 100), ("help", 50), ("hell", 50), ("yellow", 20), ("jello", 10)]
+            .into_iter()
+            .map(|(word, count): (&str, Count)| (word.to_string(), count));
+        let mut sym_spell = SymSpell::new(Some(3), Some(7), None);
+        let mut reports = vec![];
+        let progress = sym_spell.load_words_with_progress(words(), 2, |progress| {
+            reports.push(*progress);
+            true
+        });
+        assert_eq!(reports.iter().map(|progress| progress.words).collect::<Vec<_>>(), vec![2, 4, 5]);
+        assert_eq!(progress, LoadProgress { entries_read: 5, words: 5, deletes: sym_spell.entry_count(), stopped: false });
+        // three edits away
+        assert_eq!(sym_spell.lookup("jelloxyz", Verbosity::Top, 3, false, false)[0].term, "jello");
+
+        let mut sym_spell = SymSpell::new(Some(3), Some(7), None);
+        let progress = sym_spell.load_words_with_progress(words(), 2, |progress| progress.words < 2);
+        assert_eq!((progress.entries_read, progress.words, progress.stopped), (2, 2, true));
+        assert_eq!(sym_spell.word_count(), 2);
+        let progress = sym_spell.load_words_with_progress(words().take(2), 2, |_| false);
+        assert!(!progress.stopped);
+    }
+
+    #[test]
+    fn decay_counts_test() {
+        let mut sym_spell: SymSpell = vec![("ticket", 50), ("tickets", 20)].into_iter().collect();
+        assert!(sym_spell.learn_word("tickex"));
+        assert!(!sym_spell.learn_word("tickex"));
+        sym_spell.learn_word("ticket");
+        let terms = |sym_spell: &SymSpell, input: &str| -> Vec<String> {
+            sym_spell.lookup(input, Verbosity::All, 1, false, false).into_iter().map(|si| si.term).collect()
+        };
+        assert_eq!(terms(&sym_spell, "tickez"), vec!["ticket", "tickex"]);
+
+        sym_spell.decay_counts(0.5);
+        assert_eq!(sym_spell.lookup("tickex", Verbosity::Top, 1, false, true)[0].count, 1);
+        assert_eq!(sym_spell.lookup("ticket", Verbosity::Top, 1, false, true)[0].count, 50);
+        // a word not learned again fades, loaded counts never decay
+        sym_spell.decay_counts(0.5);
+        sym_spell.decay_counts(0.0);
+        assert!(!sym_spell.is_known("tickex") && sym_spell.is_known("ticket"));
+        assert_eq!(terms(&sym_spell, "tickez"), vec!["ticket"]);
+        assert_eq!(sym_spell.lookup("tickex", Verbosity::Top, 1, false, false)[0].term, "ticket");
+        assert_eq!(sym_spell.complete("tick", 10).len(), 2);
+        assert_eq!(sym_spell.check_text("ticket tickex", 1).len(), 1);
+
+        assert!(sym_spell.learn_word("tickex"));
+        assert!(sym_spell.is_known("tickex"));
+        assert_eq!(sym_spell.complete("tick", 10).len(), 3);
+
+        // a faded word is not an equivalent spelling either
+        let mut sym_spell: SymSpell = vec![("strasser", 5)].into_iter().collect();
+        sym_spell.set_equivalences(Equivalences::common());
+        sym_spell.learn_word("straße");
+        assert_eq!(sym_spell.lookup("strasse", Verbosity::Top, 2, false, false)[0].term, "straße");
+        sym_spell.decay_counts(0.0);
+        assert_eq!(sym_spell.lookup("strasse", Verbosity::Top, 2, false, false)[0].term, "strasser");
+
+        // purged words take no deletes
+        let mut sym_spell: SymSpell = vec![("ticket", 50)].into_iter().collect();
+        let entries = sym_spell.entry_count();
+        sym_spell.learn_word("zyxwvut");
+        sym_spell.learn_word("ticket");
+        assert!(sym_spell.entry_count() > entries);
+        sym_spell.decay_counts(0.0);
+        assert_eq!(sym_spell.word_count(), 2);
+        assert_eq!(sym_spell.purge_faded(), 1);
+        assert_eq!((sym_spell.word_count(), sym_spell.entry_count()), (1, entries));
+        assert_eq!(sym_spell.lookup("tickez", Verbosity::Top, 1, false, false)[0].count, 50);
+        assert_eq!(sym_spell.purge_faded(), 0);
+        assert!(sym_spell.learn_word("zyxwvut"));
+
+        // below the count threshold again
+        let mut sym_spell = SymSpell::new(Some(2), Some(7), Some(3));
+        assert!(!sym_spell.learn_word("tickex") && !sym_spell.learn_word("tickex"));
+        assert!(sym_spell.learn_word("tickex"));
+        sym_spell.decay_counts(0.5);
+        assert!(!sym_spell.is_known("tickex"));
+        assert!(!sym_spell.learn_word("tickex"));
+        assert!(sym_spell.learn_word("tickex"));
+        assert!(sym_spell.is_known("tickex"));
     }
 }
@@ -0,0 +1,32 @@
+/// The settings of a `CorpusTrainer`. The defaults count every word of the
+/// corpus, lowercased, and keep those seen at least twice.
+///
+/// example:
+/// let trainer = CorpusTrainer::new(TrainerOptions { max_words: Some(500_000), dedup_lines: true, ..TrainerOptions::default() });
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TrainerOptions {
+    /// The most distinct words counted at once, None for no limit. Once exceeded the
+    /// least frequent words are dropped until half as many are left, so a long corpus
+    /// does not fill memory with typos and one-off tokens.
+    pub max_words: Option<usize>,
+    /// The tokens counted before the trainer stops taking text, None for no limit
+    pub max_tokens: Option<usize>,
+    /// Skips lines identical to one seen before, e.g. the boilerplate repeated on every page of a web crawl
+    pub dedup_lines: bool,
+    /// Leaves out the words seen only once, mostly typos and rare names (default true)
+    pub drop_hapax_legomena: bool,
+    /// Counts words lowercased, so the capitalized word starting a sentence counts as the same word (default true)
+    pub lowercase: bool,
+}
+
+impl Default for TrainerOptions {
+    fn default() -> TrainerOptions {
+        TrainerOptions {
+            max_words: None,
+            max_tokens: None,
+            dedup_lines: false,
+            drop_hapax_legomena: true,
+            lowercase: true,
+        }
+    }
+}
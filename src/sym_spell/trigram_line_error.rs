@@ -0,0 +1,22 @@
+use std::error::Error;
+use std::fmt;
+
+/// Why a line passed to `SymSpell::write_line_to_trigram_dictionary` was rejected.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TrigramLineError {
+    /// The line has fewer than the three words of a trigram.
+    MissingWords,
+    /// The line has more parts than three words and a count.
+    TooManyParts(usize),
+}
+
+impl fmt::Display for TrigramLineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrigramLineError::MissingWords => write!(f, "trigram line needs three words"),
+            TrigramLineError::TooManyParts(parts) => write!(f, "trigram line has {} parts, expected three words and an optional count", parts),
+        }
+    }
+}
+
+impl Error for TrigramLineError {}
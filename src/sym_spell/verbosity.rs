@@ -1,5 +1,17 @@
+use std::convert::TryFrom;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "wasm-bindgen")]
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::sym_spell::spellchecker_error::SpellcheckerError;
+
 /// <summary>Controls the closeness/quantity of returned spelling suggestions.</summary>
-#[derive(PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[repr(u8)]
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen)]
 pub enum Verbosity {
     /// <summary>Top suggestion with the highest term frequency of the suggestions of smallest edit distance found.</summary>
     Top,
@@ -8,4 +20,44 @@ pub enum Verbosity {
     /// <summary>All suggestions within maxEditDistance, suggestions ordered by edit distance
     /// , then by term frequency (slower, no early termination).</summary>
     All,
-}
\ No newline at end of file
+}
+/// The verbosity of its number, e.g. as passed by a host through the wasm exports,
+/// or `SpellcheckerError::InvalidVerbosity` for a number that is none.
+impl TryFrom<u32> for Verbosity {
+    type Error = SpellcheckerError;
+
+    fn try_from(verbosity: u32) -> Result<Verbosity, SpellcheckerError> {
+        match verbosity {
+            0 => Ok(Verbosity::Top),
+            1 => Ok(Verbosity::Closest),
+            2 => Ok(Verbosity::All),
+            _ => Err(SpellcheckerError::InvalidVerbosity(verbosity)),
+        }
+    }
+}
+
+impl TryFrom<u8> for Verbosity {
+    type Error = SpellcheckerError;
+
+    fn try_from(verbosity: u8) -> Result<Verbosity, SpellcheckerError> {
+        Verbosity::try_from(u32::from(verbosity))
+    }
+}
+
+#[cfg(test)]
+mod verbosity_tests {
+    use std::convert::TryFrom;
+
+    use crate::sym_spell::spellchecker_error::SpellcheckerError;
+    use crate::sym_spell::verbosity::Verbosity;
+
+    #[test]
+    fn try_from_test() {
+        for &verbosity in [Verbosity::Top, Verbosity::Closest, Verbosity::All].iter() {
+            assert_eq!(Verbosity::try_from(verbosity as u8), Ok(verbosity));
+            assert_eq!(Verbosity::try_from(verbosity as u32), Ok(verbosity));
+        }
+        assert_eq!(Verbosity::try_from(3u8), Err(SpellcheckerError::InvalidVerbosity(3)));
+        assert_eq!(Verbosity::try_from(u32::MAX), Err(SpellcheckerError::InvalidVerbosity(u32::MAX)));
+    }
+}
@@ -0,0 +1,233 @@
+use std::hash::BuildHasher;
+use std::mem;
+
+use crate::grapheme_iterator::GraphemeClusters;
+use crate::sym_spell::delete_hasher::Fnv1aState;
+use crate::sym_spell::sym_spell::SymSpell;
+
+/// Runs `SymSpell::word_segmentation` over text that arrives in chunks, e.g. a long
+/// document read piece by piece. Only the last max_segmentation_word_len graphemes and
+/// the segmentations still competing for them are kept, and words are handed out as
+/// soon as every competing segmentation agrees on them, so memory does not grow with
+/// the length of the text. The words are the same as segmenting the whole text at once.
+///
+/// example:
+/// let mut segmenter = WordSegmenter::new(&sym_spell, 0, None);
+/// let (mut segmented, _) = segmenter.push("thequickbrown");
+/// segmented += &segmenter.push("foxjumps").0;
+/// segmented += &segmenter.finish().0;
+///
+/// assert_eq!(segmented, "the quick brown fox jumps");
+pub struct WordSegmenter<'a, S = Fnv1aState> {
+    sym_spell: &'a SymSpell<S>,
+    max_edit_distance: usize,
+    max_segmentation_word_len: usize,
+    // the text no part starts in yet
+    pending: String,
+    // the best segmentations ending at each of the next max_segmentation_word_len positions, circular
+    compositions: Vec<Composition>,
+    // the slot of the segmentation ending where the next part starts, None before the first part
+    circular_index: Option<usize>,
+    // whether words were handed out already, so the next ones need a separating space
+    emitted: bool,
+}
+
+#[derive(Clone, Debug, Default)]
+struct Composition {
+    // the (part, correction) pairs not handed out yet
+    words: Vec<(String, String)>,
+    // the corrections of the last two words, kept once the words are handed out as the context of the next
+    context: Vec<String>,
+    distance_sum: usize,
+    probability_log_sum: f64,
+}
+
+impl<'a, S: BuildHasher> WordSegmenter<'a, S> {
+    /// Parts are corrected within max_edit_distance and are at most
+    /// max_segmentation_word_len graphemes long, by default the length of the
+    /// longest dictionary word.
+    pub fn new(sym_spell: &'a SymSpell<S>, max_edit_distance: usize, max_segmentation_word_len_opt: Option<usize>) -> WordSegmenter<'a, S> {
+        let max_segmentation_word_len = max_segmentation_word_len_opt.unwrap_or_else(|| sym_spell.max_length()).max(1);
+        WordSegmenter {
+            sym_spell,
+            max_edit_distance,
+            max_segmentation_word_len,
+            pending: String::new(),
+            compositions: vec![Composition::default(); max_segmentation_word_len],
+            circular_index: None,
+            emitted: false,
+        }
+    }
+
+    /// Appends the text and returns the segmented and the corrected words it settles,
+    /// starting with a space when they follow words handed out before.
+    pub fn push(&mut self, text: &str) -> (String, String) {
+        self.pending.push_str(text);
+        let pending = self.sym_spell.normalize(&mem::take(&mut self.pending)).into_owned();
+        let graphemes = GraphemeClusters::new(&pending).as_slices();
+        // the last grapheme may continue in the next push, e.g. with a combining accent
+        let complete = graphemes.len().saturating_sub(1);
+        let mut start = 0;
+        while start + self.max_segmentation_word_len <= complete {
+            self.segment_from(&graphemes[start..start + self.max_segmentation_word_len]);
+            start += 1;
+        }
+        let consumed: usize = graphemes[..start].iter().map(|grapheme| grapheme.len()).sum();
+        self.pending = pending[consumed..].to_string();
+        if start == 0 {
+            return (String::new(), String::new());
+        }
+        self.settle()
+    }
+
+    /// Ends the text, returning the segmented and the corrected words still held back,
+    /// the edit distance sum of the whole text and the sum of its word probabilities in
+    /// log scale. The segmenter can then be reused for a new text.
+    pub fn finish(&mut self) -> (String, String, usize, f64) {
+        let pending = mem::take(&mut self.pending);
+        let graphemes = GraphemeClusters::new(&pending).as_slices();
+        for start in 0..graphemes.len() {
+            let end = graphemes.len().min(start + self.max_segmentation_word_len);
+            self.segment_from(&graphemes[start..end]);
+        }
+        let composition = match self.circular_index.take() {
+            Some(circular_index) => mem::take(&mut self.compositions[circular_index]),
+            None => Composition::default(),
+        };
+        let (segmented, corrected) = self.emit(&composition.words);
+        self.compositions.iter_mut().for_each(|composition| *composition = Composition::default());
+        self.emitted = false;
+        (segmented, corrected, composition.distance_sum, composition.probability_log_sum)
+    }
+
+    // extends the segmentations ending at the first grapheme with every part starting there
+    fn segment_from(&mut self, graphemes: &[&str]) {
+        let capacity = self.compositions.len();
+        for i in 1..=graphemes.len() {
+            let mut part_graphemes = &graphemes[..i];
+            let mut separator_len = 0;
+            if is_whitespace(part_graphemes[0]) {
+                // the space before the part is in the input already
+                part_graphemes = &part_graphemes[1..];
+            } else {
+                // a space has to be inserted before the part
+                separator_len = 1;
+            }
+            // every space removed from inside the part is an edit
+            let part: String = part_graphemes.iter().copied().filter(|grapheme| !is_whitespace(grapheme)).collect();
+            let part_len = part_graphemes.iter().filter(|grapheme| !is_whitespace(grapheme)).count();
+            let (top_result, distance, top_probability_log) = self.sym_spell.segmentation_part(&part, part_len, self.max_edit_distance);
+            let top_edit_distance = part_graphemes.len() - part_len + distance;
+
+            // a lone space is no word, only a step to the word after it
+            let word = if part.is_empty() { None } else { Some((part, top_result)) };
+
+            let circular_index = match self.circular_index {
+                Some(circular_index) => circular_index,
+                None => {
+                    self.compositions[i - 1] = Composition {
+                        context: word.iter().map(|(_, top_result)| top_result.clone()).collect(),
+                        words: word.into_iter().collect(),
+                        distance_sum: top_edit_distance,
+                        probability_log_sum: top_probability_log,
+                    };
+                    continue;
+                }
+            };
+            // the word given the ones before it
+            let top_probability_log = match &word {
+                Some((_, top_result)) => self.sym_spell.following_log_probability(&self.compositions[circular_index].context, top_result, top_probability_log),
+                None => top_probability_log,
+            };
+            let destination_index = (circular_index + i) % capacity;
+            let (c, d) = (&self.compositions[circular_index], &self.compositions[destination_index]);
+            // the slot held the segmentation of a position passed already, or a worse one
+            if i == self.max_segmentation_word_len ||
+                //replace values if better probabilityLogSum, if same edit distance OR one space difference
+                ((c.distance_sum + top_edit_distance == d.distance_sum || c.distance_sum + separator_len + top_edit_distance == d.distance_sum) &&
+                    d.probability_log_sum < c.probability_log_sum + top_probability_log) ||
+                c.distance_sum + separator_len + top_edit_distance < d.distance_sum {
+                let mut words = c.words.clone();
+                let mut context = c.context.clone();
+                if let Some((_, top_result)) = &word {
+                    context.push(top_result.clone());
+                    context.drain(..context.len().saturating_sub(2));
+                }
+                words.extend(word);
+                self.compositions[destination_index] = Composition {
+                    words,
+                    context,
+                    distance_sum: c.distance_sum + separator_len + top_edit_distance,
+                    probability_log_sum: c.probability_log_sum + top_probability_log,
+                };
+            }
+        }
+        self.circular_index = Some(self.circular_index.map_or(0, |circular_index| (circular_index + 1) % capacity));
+    }
+
+    // hands out the words every segmentation still competing starts with
+    fn settle(&mut self) -> (String, String) {
+        let first = &self.compositions[0].words;
+        let settled = self.compositions[1..].iter().fold(first.len(), |settled, composition| {
+            first.iter().zip(&composition.words).take(settled).take_while(|(a, b)| a == b).count()
+        });
+        if settled == 0 {
+            return (String::new(), String::new());
+        }
+        let words: Vec<(String, String)> = self.compositions[0].words[..settled].to_vec();
+        for composition in self.compositions.iter_mut() {
+            composition.words.drain(..settled);
+        }
+        self.emit(&words)
+    }
+
+    fn emit(&mut self, words: &[(String, String)]) -> (String, String) {
+        let mut segmented = String::new();
+        let mut corrected = String::new();
+        for (part, top_result) in words {
+            if self.emitted {
+                segmented.push(' ');
+                corrected.push(' ');
+            }
+            segmented.push_str(part);
+            corrected.push_str(top_result);
+            self.emitted = true;
+        }
+        (segmented, corrected)
+    }
+}
+
+fn is_whitespace(grapheme: &str) -> bool {
+    grapheme.chars().all(char::is_whitespace)
+}
+
+#[cfg(test)]
+mod word_segmenter_tests {
+    use crate::sym_spell::sym_spell::SymSpell;
+    use crate::sym_spell::word_segmenter::WordSegmenter;
+
+    #[test]
+    fn chunks_test() {
+        let sym_spell: SymSpell = vec![("the", 100), ("quick", 40), ("brown", 30), ("fox", 20), ("jumps", 10), ("over", 50), ("lazy", 5), ("dog", 25)]
+            .into_iter()
+            .collect();
+        let text = "thequickbrownfoxjumpsoverthelazydog";
+        let whole = sym_spell.word_segmentation(text, 0, None);
+        assert_eq!(whole.0, "the quick brown fox jumps over the lazy dog");
+
+        // any chunking gives the same words as the whole text, most of them before the end
+        let mut segmenter = WordSegmenter::new(&sym_spell, 0, None);
+        for chunk_len in 1..text.len() {
+            let (mut segmented, mut corrected) = (String::new(), String::new());
+            for chunk in text.as_bytes().chunks(chunk_len) {
+                let (s, c) = segmenter.push(std::str::from_utf8(chunk).unwrap());
+                segmented += &s;
+                corrected += &c;
+            }
+            assert!(segmented.starts_with("the quick brown fox jumps"), "{}", chunk_len);
+            let (s, c, distance_sum, probability_log_sum) = segmenter.finish();
+            assert_eq!((segmented + &s, corrected + &c, distance_sum, probability_log_sum), whole, "{}", chunk_len);
+        }
+        assert_eq!(segmenter.finish(), (String::new(), String::new(), 0, 0.0));
+    }
+}
@@ -0,0 +1,229 @@
+use std::ops::Range;
+
+use crate::grapheme_iterator::GraphemeClusters;
+use crate::utils::is_alpha_numeric;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TokenClass {
+    /// A run of alphanumeric graphemes
+    Word,
+    /// A run of whitespace
+    Whitespace,
+    /// A single grapheme that is neither alphanumeric nor whitespace
+    Punctuation,
+}
+
+/// Punctuation that is kept inside a word when it sits between two word
+/// characters. At the start or end of a word it is still punctuation.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct WordCharPolicy {
+    /// Keep "don't" and "o’clock" as one word (default false).
+    pub apostrophes: bool,
+    /// Keep "e-mail" and "well‐known" as one word (default false).
+    pub hyphens: bool,
+}
+
+impl WordCharPolicy {
+    // true when the grapheme joins the words on either side of it
+    fn is_word_internal(&self, grapheme: &str) -> bool {
+        match grapheme {
+            "'" | "\u{2019}" => self.apostrophes,
+            "-" | "\u{2010}" | "\u{2011}" => self.hyphens,
+            _ => false,
+        }
+    }
+}
+
+/// Choices about which non-letter characters count as part of a word.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TokenizerOptions {
+    /// Keep "foo_bar" as one word rather than splitting it at the '_' (default true).
+    pub underscore_is_word: bool,
+    /// Keep "covid19" as one word rather than splitting off the digits (default false).
+    pub digits_are_word: bool,
+    /// Punctuation kept within words (default none).
+    pub word_char_policy: WordCharPolicy,
+}
+
+impl Default for TokenizerOptions {
+    fn default() -> TokenizerOptions {
+        TokenizerOptions {
+            underscore_is_word: true,
+            digits_are_word: false,
+            word_char_policy: WordCharPolicy::default(),
+        }
+    }
+}
+
+/// A slice of the tokenized text along with its byte offsets in that text.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Token<'a> {
+    pub text: &'a str,
+    pub byte_range: Range<usize>,
+    pub class: TokenClass,
+}
+
+fn classify(grapheme: &str, options: TokenizerOptions) -> TokenClass {
+    let is_word = match grapheme.chars().next() {
+        Some('_') => options.underscore_is_word,
+        Some(ch) if ch.is_numeric() => options.digits_are_word,
+        _ => is_alpha_numeric(grapheme),
+    };
+    if is_word {
+        TokenClass::Word
+    } else if grapheme.chars().all(char::is_whitespace) {
+        TokenClass::Whitespace
+    } else {
+        TokenClass::Punctuation
+    }
+}
+
+/// Splits the text into tokens covering every byte of the input so that
+/// `&text[token.byte_range]` always equals `token.text`.
+///
+/// example:
+/// let tokens = tokenize_with_offsets("hi, you");
+///
+/// assert_eq!(tokens[0].text, "hi");
+/// assert_eq!(tokens[3].byte_range, 4..7);
+pub fn tokenize_with_offsets(text: &str) -> Vec<Token<'_>> {
+    tokenize_with_options(text, TokenizerOptions::default())
+}
+
+/// Identical to `tokenize_with_offsets` using the supplied word character options.
+pub fn tokenize_with_options(text: &str, options: TokenizerOptions) -> Vec<Token<'_>> {
+    let mut tokens: Vec<Token> = vec![];
+    let mut graphemes = GraphemeClusters::new(text).peekable();
+    while let Some((grapheme, range)) = graphemes.next() {
+        let mut class = classify(grapheme, options);
+        if class == TokenClass::Punctuation
+            && options.word_char_policy.is_word_internal(grapheme)
+            && tokens.last().is_some_and(|last| last.class == TokenClass::Word)
+            && graphemes.peek().is_some_and(|&(next, _)| classify(next, options) == TokenClass::Word) {
+            class = TokenClass::Word;
+        }
+        if let Some(last) = tokens.last_mut() {
+            if last.class == class && class != TokenClass::Punctuation {
+                last.byte_range.end = range.end;
+                last.text = &text[last.byte_range.clone()];
+                continue;
+            }
+        }
+        tokens.push(Token { text: grapheme, byte_range: range, class });
+    }
+    tokens
+}
+
+/// Returns true when the text is a single word, e.g. "don't" when apostrophes are word internal.
+pub fn is_word(text: &str, options: TokenizerOptions) -> bool {
+    options.is_word(text)
+}
+
+/// Decides what `SymSpell` treats as a word when it checks, corrects or segments text,
+/// e.g. to keep hashtags or product codes such as "SKU-1234" whole. `TokenizerOptions`
+/// is the built in tokenizer; set another with `SymSpell::set_tokenizer`.
+///
+/// example:
+/// struct Hashtags;
+///
+/// impl Tokenizer for Hashtags {
+///     fn tokenize<'a>(&self, text: &'a str) -> Vec<Token<'a>> {
+///         let mut tokens: Vec<Token<'a>> = vec![];
+///         for token in TokenizerOptions::default().tokenize(text) {
+///             match tokens.last_mut() {
+///                 Some(last) if last.text == "#" && token.class == TokenClass::Word => {
+///                     last.byte_range.end = token.byte_range.end;
+///                     last.text = &text[last.byte_range.clone()];
+///                     last.class = TokenClass::Word;
+///                 }
+///                 _ => tokens.push(token),
+///             }
+///         }
+///         tokens
+///     }
+/// }
+///
+/// sym_spell.set_tokenizer(Box::new(Hashtags));
+pub trait Tokenizer: Send + Sync {
+    /// Splits the text into tokens in order. Only `Word` tokens are checked; the others
+    /// show where words end, e.g. a line break after a hyphen. Each token's byte_range
+    /// must be its place in the text, `&text[token.byte_range] == token.text`.
+    fn tokenize<'a>(&self, text: &'a str) -> Vec<Token<'a>>;
+
+    /// True when the text is a single word, or empty.
+    fn is_word(&self, text: &str) -> bool {
+        match self.tokenize(text).as_slice() {
+            [] => true,
+            [token] => token.class == TokenClass::Word,
+            _ => false,
+        }
+    }
+}
+
+impl Tokenizer for TokenizerOptions {
+    fn tokenize<'a>(&self, text: &'a str) -> Vec<Token<'a>> {
+        tokenize_with_options(text, *self)
+    }
+}
+
+#[cfg(test)]
+mod tokenizer_tests {
+    use crate::tokenizer::{is_word, tokenize_with_offsets, tokenize_with_options, TokenClass, TokenizerOptions, WordCharPolicy};
+
+    #[test]
+    fn tokenize_with_offsets_test() {
+        let text = "🚀 héllo,  wörld?!";
+        let tokens = tokenize_with_offsets(text);
+        let classes: Vec<_> = tokens.iter().map(|t| t.class).collect();
+        assert_eq!(classes, vec![
+            TokenClass::Punctuation, // 🚀 is neither alphabetic nor numeric
+            TokenClass::Whitespace,
+            TokenClass::Word,
+            TokenClass::Punctuation,
+            TokenClass::Whitespace,
+            TokenClass::Word,
+            TokenClass::Punctuation,
+            TokenClass::Punctuation,
+        ]);
+        for token in &tokens {
+            assert_eq!(&text[token.byte_range.clone()], token.text);
+        }
+        assert_eq!(tokens[2].text, "héllo");
+        assert_eq!(tokens[5].text, "wörld");
+    }
+
+    #[test]
+    fn tokenizer_options_test() {
+        let words = |options: TokenizerOptions| -> Vec<&str> {
+            tokenize_with_options("foo_bar covid19", options).into_iter()
+                .filter(|token| token.class == TokenClass::Word)
+                .map(|token| token.text)
+                .collect()
+        };
+        assert_eq!(words(TokenizerOptions::default()), vec!["foo_bar", "covid"]);
+        assert_eq!(words(TokenizerOptions { underscore_is_word: false, digits_are_word: true, ..TokenizerOptions::default() }), vec!["foo", "bar", "covid19"]);
+        assert!(!is_word("covid19", TokenizerOptions::default()));
+        // any script's digits are digits
+        assert!(!is_word("٣", TokenizerOptions::default()));
+    }
+
+    #[test]
+    fn word_char_policy_test() {
+        let text = "'don't' e-mail -- o’clock- a'-b";
+        let words = |word_char_policy: WordCharPolicy| -> Vec<&str> {
+            let options = TokenizerOptions { word_char_policy, ..TokenizerOptions::default() };
+            tokenize_with_options(text, options).into_iter()
+                .filter(|token| token.class == TokenClass::Word)
+                .map(|token| token.text)
+                .collect()
+        };
+        assert_eq!(words(WordCharPolicy::default()), vec!["don", "t", "e", "mail", "o", "clock", "a", "b"]);
+        assert_eq!(words(WordCharPolicy { apostrophes: true, hyphens: false }), vec!["don't", "e", "mail", "o’clock", "a", "b"]);
+        assert_eq!(words(WordCharPolicy { apostrophes: true, hyphens: true }), vec!["don't", "e-mail", "o’clock", "a", "b"]);
+
+        let options = TokenizerOptions { word_char_policy: WordCharPolicy { apostrophes: true, hyphens: false }, ..TokenizerOptions::default() };
+        assert!(is_word("don't", options));
+        assert!(!is_word("don't", TokenizerOptions::default()));
+        assert!(!is_word("'", options));
+    }
+}
@@ -1,45 +1,322 @@
+use std::borrow::Cow;
 
+use crate::grapheme_iterator::GraphemeClusters;
+
+/// The code point a grapheme starts with, e.g. 'e' for an 'e' followed by a combining accent.
 pub fn to_char_code(grapheme: &str) -> u32 {
-    let bytes = grapheme.as_bytes();
-    let len = bytes.len();
-    let char_code = match len {
-        1 => bytes[0] as u32,
-        2 => ((bytes[0] as u32 & 0x1f) << 6) | (bytes[1] as u32 & 0x3f),
-        3 => ((bytes[0] as u32 & 0x0f) << 12) | ((bytes[1] as u32 & 0x3f) << 6) | (bytes[2] as u32 & 0x3f),
-        4 => ((bytes[0] as u32 & 0x07) << 18) | ((bytes[1] as u32 & 0x3f) << 12) | ((bytes[2] as u32 & 0x3f) << 6) | (bytes[3] as u32 & 0x3f),
-        _ => 0
-    };
-    char_code
+    grapheme.chars().next().map_or(0, |ch| ch as u32)
 }
 
+/// True when the grapheme starts with a char of the Unicode Alphabetic or Numeric
+/// properties, so letters of every script, their combining marks and digits.
+///
+/// example:
+/// assert!(is_alpha_numeric("ж") && is_alpha_numeric("٣") && is_alpha_numeric("e\u{301}"));
+/// assert!(!is_alpha_numeric("_") && !is_alpha_numeric("🚀"));
 pub fn is_alpha_numeric(grapheme: &str) -> bool {
-    let char_code = to_char_code(grapheme);
-    match char_code {
-        0x41..=0x5A => true,   // A-Z
-        0x5F => true,          // _
-        0x61..=0x7A => true,   // a-z
-        0xC0..=0xD6 => true,   // À-Ö
-        0xD8..=0xF6 => true,   // Ø-ö
-        0xF8..=0x02FF => true, // ø-˿
-        0x0370..=0x037D => true, // etc...
-        0x037F..=0x1FFF => true,
-        0x200C..=0x200D => true,
-        0x2070..=0x218F => true,
-        0x2C00..=0x2FEF => true,
-        0x3001..=0xD7FF => true,
-        0xF900..=0xFDCF => true,
-        0xFDF0..=0xFFFD => true,
-        0x10000..=0xEFFFF => true,
-        _ => false,
+    grapheme.chars().next().is_some_and(char::is_alphanumeric)
+}
+
+/// Appends the str to the buffer as a quoted JSON string.
+pub fn push_json_string(buffer: &mut String, s: &str) {
+    buffer.push('"');
+    for ch in s.chars() {
+        match ch {
+            '"' => buffer.push_str("\\\""),
+            '\\' => buffer.push_str("\\\\"),
+            '\n' => buffer.push_str("\\n"),
+            '\r' => buffer.push_str("\\r"),
+            '\t' => buffer.push_str("\\t"),
+            ch if (ch as u32) < 0x20 => buffer.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => buffer.push(ch),
+        }
+    }
+    buffer.push('"');
+}
+
+/// Returns true when the first char of the str is an uppercase letter.
+pub fn starts_with_uppercase(s: &str) -> bool {
+    s.chars().next().is_some_and(char::is_uppercase)
+}
+
+/// Returns true when a sentence terminator appears in the text between two words.
+pub fn ends_sentence(gap: &str) -> bool {
+    gap.contains(['.', '!', '?'])
+}
+
+/// Returns true when the str has more than one letter and all of them are uppercase, e.g. "NASA".
+pub fn is_all_uppercase(s: &str) -> bool {
+    s.chars().filter(|ch| ch.is_alphabetic()).count() > 1 && !s.chars().any(char::is_lowercase)
+}
+
+/// Language specific rules for changing the case of words.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum CaseLocale {
+    /// The Unicode default mappings. Greek needs nothing more, its final sigma
+    /// is applied in every locale (default).
+    #[default]
+    Default,
+    /// Turkish and Azerbaijani, where 'I' lowercases to the dotless 'ı' and 'İ' to 'i'.
+    Turkic,
+}
+
+impl CaseLocale {
+    /// The locale for a BCP 47 language tag such as "tr-TR" or "el", falling back
+    /// to Default for languages without special casing rules.
+    ///
+    /// example:
+    /// assert_eq!(CaseLocale::from_language_tag("az-Latn-AZ"), CaseLocale::Turkic);
+    pub fn from_language_tag(tag: &str) -> CaseLocale {
+        let language = tag.split(['-', '_']).next().unwrap_or_default();
+        if language.eq_ignore_ascii_case("tr") || language.eq_ignore_ascii_case("az") {
+            CaseLocale::Turkic
+        } else {
+            CaseLocale::Default
+        }
+    }
+
+    // appends the uppercase form of the char
+    fn push_uppercase(&self, uppercase: &mut String, ch: char) {
+        match (self, ch) {
+            (CaseLocale::Turkic, 'i') => uppercase.push('İ'),
+            _ => uppercase.extend(ch.to_uppercase()),
+        }
+    }
+
+    // appends the lowercase form of the str
+    fn push_lowercase(&self, lowercase: &mut String, s: &str) {
+        let mut chars = s.chars().peekable();
+        while let Some(ch) = chars.next() {
+            match (self, ch) {
+                (CaseLocale::Turkic, 'İ') => lowercase.push('i'),
+                // an 'I' with a combining dot above is the decomposed 'İ'
+                (CaseLocale::Turkic, 'I') if chars.next_if_eq(&'\u{307}').is_some() => lowercase.push('i'),
+                (CaseLocale::Turkic, 'I') => lowercase.push('ı'),
+                _ => lowercase.extend(ch.to_lowercase()),
+            }
+        }
+    }
+}
+
+/// Lowercases the str one grapheme at a time so the result has the same
+/// graphemes as the input, borrowing it when there is nothing to change.
+/// Multi-char mappings such as 'İ' to "i̇" stay within their grapheme and a
+/// 'Σ' ending a word becomes the final form 'ς'.
+///
+/// example:
+/// assert_eq!(lowercase_graphemes("ΟΔΟΣ"), "οδος");
+/// assert!(matches!(lowercase_graphemes("road"), Cow::Borrowed(_)));
+pub fn lowercase_graphemes(s: &str) -> Cow<'_, str> {
+    lowercase_graphemes_in(s, CaseLocale::Default)
+}
+
+/// Identical to `lowercase_graphemes` using the casing rules of the locale.
+///
+/// example:
+/// assert_eq!(lowercase_graphemes_in("DİYARBAKIR", CaseLocale::Turkic), "diyarbakır");
+pub fn lowercase_graphemes_in(s: &str, locale: CaseLocale) -> Cow<'_, str> {
+    if !s.chars().any(changes_case) {
+        return Cow::Borrowed(s);
+    }
+    let graphemes: Vec<&str> = GraphemeClusters::new(s).map(|(grapheme, _)| grapheme).collect();
+    let mut lowercase = String::with_capacity(s.len());
+    for (i, grapheme) in graphemes.iter().enumerate() {
+        if let Some(rest) = grapheme.strip_prefix('Σ') {
+            let after_letter = i > 0 && graphemes[i - 1].chars().any(char::is_alphabetic);
+            let before_letter = graphemes.get(i + 1).is_some_and(|next| next.chars().any(char::is_alphabetic));
+            if after_letter && !before_letter {
+                lowercase.push('ς');
+                locale.push_lowercase(&mut lowercase, rest);
+                continue;
+            }
+        }
+        locale.push_lowercase(&mut lowercase, grapheme);
+    }
+    Cow::Owned(lowercase)
+}
+
+/// The str with its first char uppercased using the casing rules of the locale.
+///
+/// example:
+/// assert_eq!(capitalize_in("istanbul", CaseLocale::Turkic), "İstanbul");
+pub fn capitalize_in(s: &str, locale: CaseLocale) -> String {
+    let mut chars = s.chars();
+    let mut capitalized = String::with_capacity(s.len() + 2);
+    if let Some(first) = chars.next() {
+        locale.push_uppercase(&mut capitalized, first);
+    }
+    capitalized.push_str(chars.as_str());
+    capitalized
+}
+
+/// The str in uppercase using the casing rules of the locale.
+///
+/// example:
+/// assert_eq!(uppercase_in("diyarbakır", CaseLocale::Turkic), "DİYARBAKIR");
+pub fn uppercase_in(s: &str, locale: CaseLocale) -> String {
+    let mut uppercase = String::with_capacity(s.len());
+    for ch in s.chars() {
+        locale.push_uppercase(&mut uppercase, ch);
+    }
+    uppercase
+}
+
+/// The text with the capitalization of the cased str, a version of it with different
+/// spelling or spacing, mapped onto it. The two are aligned by a case insensitive edit
+/// script: a kept char is copied, a substituted one takes the case of the char it
+/// replaces and an inserted one is uppercase when the chars of the word around it are.
+///
+/// example:
+/// assert_eq!(transfer_casing_in("Whereis th elove", "where is the love", CaseLocale::Default), "Where is the love");
+pub fn transfer_casing_in(cased: &str, text: &str, locale: CaseLocale) -> String {
+    let cased: Vec<char> = cased.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let same = |a: char, b: char| a == b || a.to_lowercase().eq(b.to_lowercase());
+
+    // distances[i][j] between the first i chars of cased and the first j chars of text
+    let mut distances = vec![vec![0usize; text.len() + 1]; cased.len() + 1];
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, distance) in distances[0].iter_mut().enumerate() {
+        *distance = j;
+    }
+    for i in 1..=cased.len() {
+        for j in 1..=text.len() {
+            let substitution = distances[i - 1][j - 1] + usize::from(!same(cased[i - 1], text[j - 1]));
+            distances[i][j] = substitution.min(distances[i - 1][j] + 1).min(distances[i][j - 1] + 1);
+        }
+    }
+
+    // walks the edit script back from the end, casing the chars of text in reverse
+    let mut reversed: Vec<String> = Vec::with_capacity(text.len());
+    let (mut i, mut j) = (cased.len(), text.len());
+    while j > 0 {
+        let ch = text[j - 1];
+        let mut piece = String::new();
+        if i > 0 && distances[i][j] == distances[i - 1][j - 1] + usize::from(!same(cased[i - 1], ch)) {
+            match cased[i - 1] {
+                kept if same(kept, ch) => piece.push(kept),
+                replaced if replaced.is_uppercase() => locale.push_uppercase(&mut piece, ch),
+                _ => locale.push_lowercase(&mut piece, ch.encode_utf8(&mut [0; 4])),
+            }
+            i -= 1;
+        } else if i > 0 && distances[i][j] == distances[i - 1][j] + 1 {
+            i -= 1;
+            continue;
+        } else {
+            // the chars of the same word on either side of the insertion
+            let before = cased[..i].last().filter(|before| !before.is_whitespace());
+            let after = cased.get(i).filter(|after| !after.is_whitespace());
+            if before.into_iter().chain(after).all(|ch| ch.is_uppercase()) && (before.is_some() || after.is_some()) {
+                locale.push_uppercase(&mut piece, ch);
+            } else {
+                locale.push_lowercase(&mut piece, ch.encode_utf8(&mut [0; 4]));
+            }
+        }
+        reversed.push(piece);
+        j -= 1;
     }
+    reversed.into_iter().rev().collect()
 }
+
+// true when lowercasing the char produces something other than the char itself
+fn changes_case(ch: char) -> bool {
+    let mut lowercase = ch.to_lowercase();
+    !(lowercase.next() == Some(ch) && lowercase.next().is_none())
+}
+
 #[cfg(test)]
 mod utils_tests {
-    use crate::utils::to_char_code;
+    use std::borrow::Cow;
+
+    use crate::utils::{capitalize_in, ends_sentence, is_all_uppercase, is_alpha_numeric, lowercase_graphemes, lowercase_graphemes_in, push_json_string, starts_with_uppercase, to_char_code, transfer_casing_in, uppercase_in, CaseLocale};
 
     #[test]
     fn to_char_code_test() {
         let char_code = to_char_code("踰");
-        assert_eq!(char_code, 0x8e30)
+        assert_eq!(char_code, 0x8e30);
+        assert_eq!(to_char_code("e\u{301}"), 0x65);
+        assert!(is_alpha_numeric("e\u{301}"));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn is_alpha_numeric_test() {
+        for grapheme in ["a", "Ж", "ש", "ع", "क्", "中", "ㅎ", "ß", "7", "٣", "Ⅻ"].iter() {
+            assert!(is_alpha_numeric(grapheme), "{}", grapheme);
+        }
+        for grapheme in ["", "_", "-", "'", "’", "«", "🚀", "\u{301}", " ", "\u{3000}", "・"].iter() {
+            assert!(!is_alpha_numeric(grapheme), "{}", grapheme);
+        }
+    }
+
+    #[test]
+    fn starts_with_uppercase_test() {
+        assert!(starts_with_uppercase("Émile"));
+        assert!(!starts_with_uppercase("émile"));
+        assert!(!starts_with_uppercase(""));
+        assert!(ends_sentence(". "));
+        assert!(!ends_sentence(", "));
+    }
+
+    #[test]
+    fn push_json_string_test() {
+        let mut s = String::new();
+        push_json_string(&mut s, "a\"b\\c\nd\u{1}é");
+        assert_eq!(s, r#""a\"b\\c\nd\u0001é""#);
+    }
+
+    #[test]
+    fn lowercase_graphemes_test() {
+        assert!(matches!(lowercase_graphemes("héllo wörld"), Cow::Borrowed("héllo wörld")));
+        assert_eq!(lowercase_graphemes("HÉLLO Wörld"), "héllo wörld");
+        // combining marks stay attached to their lowercased base
+        assert_eq!(lowercase_graphemes("E\u{301}TE\u{301}"), "e\u{301}te\u{301}");
+        assert_eq!(lowercase_graphemes("İstanbul"), "i\u{307}stanbul");
+        assert_eq!(lowercase_graphemes("ǅemal"), "ǆemal");
+        assert_eq!(lowercase_graphemes("ΟΔΟΣ ΣΟΦΟΣ."), "οδος σοφος.");
+        assert_eq!(lowercase_graphemes("Σ"), "σ");
+    }
+
+    #[test]
+    fn case_locale_test() {
+        assert_eq!(CaseLocale::from_language_tag("tr"), CaseLocale::Turkic);
+        assert_eq!(CaseLocale::from_language_tag("AZ_az"), CaseLocale::Turkic);
+        assert_eq!(CaseLocale::from_language_tag("el-GR"), CaseLocale::Default);
+        assert_eq!(CaseLocale::from_language_tag(""), CaseLocale::Default);
+
+        assert_eq!(lowercase_graphemes_in("İSTANBUL", CaseLocale::Turkic), "istanbul");
+        assert_eq!(lowercase_graphemes_in("ISPARTA Iğdır", CaseLocale::Turkic), "ısparta ığdır");
+        assert_eq!(lowercase_graphemes_in("I\u{307}ZMI\u{307}R", CaseLocale::Turkic), "izmir");
+        assert_eq!(lowercase_graphemes_in("ISPARTA", CaseLocale::Default), "isparta");
+        assert_eq!(lowercase_graphemes_in("ΟΔΥΣΣΕΥΣ", CaseLocale::Turkic), "οδυσσευς");
+    }
+
+    #[test]
+    fn uppercase_test() {
+        assert_eq!(capitalize_in("émile", CaseLocale::Default), "Émile");
+        assert_eq!(capitalize_in("straße", CaseLocale::Default), "Straße");
+        assert_eq!(capitalize_in("izmir", CaseLocale::Turkic), "İzmir");
+        assert_eq!(capitalize_in("", CaseLocale::Default), "");
+        assert_eq!(uppercase_in("straße", CaseLocale::Default), "STRASSE");
+        assert_eq!(uppercase_in("izmir", CaseLocale::Default), "IZMIR");
+
+        assert!(is_all_uppercase("NASA") && is_all_uppercase("U.S."));
+        assert!(!is_all_uppercase("I") && !is_all_uppercase("Nasa") && !is_all_uppercase("42"));
+    }
+
+    #[test]
+    fn transfer_casing_test() {
+        let transfer = |cased: &str, text: &str| transfer_casing_in(cased, text, CaseLocale::Default);
+        assert_eq!(transfer("Whereis th elove", "where is the love"), "Where is the love");
+        assert_eq!(transfer("HELO Wrld", "hello world"), "HELLO World");
+        assert_eq!(transfer("iPhon", "iphone"), "iPhone");
+        // an inserted char is uppercase only when the chars of the word around it are
+        assert_eq!(transfer("the Qick", "the quick"), "the Quick");
+        assert_eq!(transfer("the QICK", "the quick"), "the QUICK");
+        assert_eq!(transfer("Straße", "strasse"), "Strasse");
+        assert_eq!(transfer("", "new"), "new");
+        assert_eq!(transfer("Gone", ""), "");
+        assert_eq!(transfer_casing_in("İZMR", "izmir", CaseLocale::Turkic), "İZMİR");
+    }
+}